@@ -0,0 +1,75 @@
+//! Statistics of the integrated device's audio output, i.e. the buffered audio
+//! and the underruns, shown in the diagnostics page
+
+use std::time::{Duration, Instant};
+
+use librespot_playback::{
+    audio_backend::{Sink, SinkResult},
+    convert::Converter,
+    decoder::AudioPacket,
+    NUM_CHANNELS, SAMPLE_RATE,
+};
+
+use crate::metrics;
+
+/// A sink recording the statistics of the audio output by another sink.
+/// As the output blocks once its buffer is full, the audio buffered ahead of the output
+/// is estimated by the duration of the written audio minus the time elapsed since starting
+/// the output.
+pub struct StatsSink {
+    sink: Box<dyn Sink>,
+    /// time when the output started playing the written audio
+    started: Option<Instant>,
+    /// duration (in seconds) of the audio written since the output started
+    written: f64,
+}
+
+impl StatsSink {
+    pub fn new(sink: Box<dyn Sink>) -> Self {
+        Self {
+            sink,
+            started: None,
+            written: 0.0,
+        }
+    }
+}
+
+impl Sink for StatsSink {
+    fn start(&mut self) -> SinkResult<()> {
+        self.started = None;
+        self.written = 0.0;
+        self.sink.start()
+    }
+
+    fn stop(&mut self) -> SinkResult<()> {
+        self.started = None;
+        metrics::record_audio_stopped();
+        self.sink.stop()
+    }
+
+    fn write(&mut self, packet: AudioPacket, converter: &mut Converter) -> SinkResult<()> {
+        let AudioPacket::Samples(samples) = &packet else {
+            return self.sink.write(packet, converter);
+        };
+
+        let now = Instant::now();
+        let started = match self.started {
+            Some(started) if now.duration_since(started).as_secs_f64() <= self.written => started,
+            _ => {
+                if self.started.is_some() {
+                    // the output played all the written audio before getting new audio
+                    metrics::record_audio_underrun();
+                }
+                self.started = Some(now);
+                self.written = 0.0;
+                now
+            }
+        };
+
+        self.written += samples.len() as f64 / f64::from(SAMPLE_RATE * u32::from(NUM_CHANNELS));
+        let buffered = self.written - now.duration_since(started).as_secs_f64();
+        metrics::record_audio_packet(Duration::from_secs_f64(buffered.max(0.0)));
+
+        self.sink.write(packet, converter)
+    }
+}
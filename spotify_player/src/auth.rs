@@ -1,5 +1,5 @@
 use anyhow::Result;
-use librespot_core::{authentication::Credentials, cache::Cache, config::SessionConfig, Session};
+use librespot_core::{authentication::Credentials, cache::Cache, config::SessionConfig};
 use librespot_oauth::{OAuthClientBuilder};
 use crate::config;
 
@@ -50,23 +50,20 @@ impl Default for AuthConfig {
 }
 
 impl AuthConfig {
-    /// Create a `librespot::Session` from authentication configs
-    pub fn session(&self) -> Session {
-        Session::new(self.session_config.clone(), Some(self.cache.clone()))
-    }
-
     pub fn new(configs: &config::Configs) -> Result<AuthConfig> {
-        let audio_cache_folder = if configs.app_config.device.audio_cache {
-            Some(configs.cache_folder.join("audio"))
-        } else {
-            None
-        };
+        // the size limit (in MB) of the audio cache, whose least recently used
+        // files are removed by `librespot` whenever the limit is exceeded
+        let audio_cache_size_limit = configs
+            .app_config
+            .device
+            .audio_cache_size_limit
+            .map(|limit| limit * 1024 * 1024);
 
         let cache = Cache::new(
             Some(configs.cache_folder.clone()),
             None,
-            audio_cache_folder,
-            None,
+            configs.audio_cache_folder(),
+            audio_cache_size_limit,
         )?;
 
         Ok(AuthConfig {
@@ -0,0 +1,58 @@
+//! Accessibility-oriented channel options of the integrated device's audio,
+//! i.e. a mono downmix and a left/right balance
+
+use librespot_playback::{
+    audio_backend::{Sink, SinkResult},
+    convert::Converter,
+    decoder::AudioPacket,
+    NUM_CHANNELS,
+};
+
+/// A sink downmixing the stereo audio samples to mono and/or balancing
+/// the left and right channels before writing them into another sink
+pub struct ChannelSink {
+    sink: Box<dyn Sink>,
+    mono: bool,
+    /// gains of the left and right channels
+    gains: [f64; 2],
+}
+
+impl ChannelSink {
+    /// Create a new sink, the balance ranging from `-1` (left only) to `1` (right only)
+    pub fn new(sink: Box<dyn Sink>, mono: bool, balance: f32) -> Self {
+        let balance = f64::from(balance.clamp(-1.0, 1.0));
+        Self {
+            sink,
+            mono,
+            gains: [(1.0 - balance).min(1.0), (1.0 + balance).min(1.0)],
+        }
+    }
+}
+
+impl Sink for ChannelSink {
+    fn start(&mut self) -> SinkResult<()> {
+        self.sink.start()
+    }
+
+    fn stop(&mut self) -> SinkResult<()> {
+        self.sink.stop()
+    }
+
+    fn write(&mut self, packet: AudioPacket, converter: &mut Converter) -> SinkResult<()> {
+        let AudioPacket::Samples(mut samples) = packet else {
+            return self.sink.write(packet, converter);
+        };
+
+        // samples are interleaved by channels
+        for frame in samples.chunks_exact_mut(NUM_CHANNELS as usize) {
+            if self.mono {
+                let value = frame.iter().sum::<f64>() / f64::from(NUM_CHANNELS);
+                frame.fill(value);
+            }
+            for (sample, gain) in frame.iter_mut().zip(self.gains) {
+                *sample *= gain;
+            }
+        }
+        self.sink.write(AudioPacket::Samples(samples), converter)
+    }
+}
@@ -9,24 +9,33 @@ use std::{
 use anyhow::{Context as _, Result};
 use rand::seq::SliceRandom;
 use tokio::net::UdpSocket;
+use tokio::sync::broadcast::error::RecvError;
 use tracing::Instrument;
 
 use crate::{
     cli::Request,
     client::{Client, PlayerRequest},
-    config::get_cache_folder_path,
+    config,
     state::{
-        AlbumId, ArtistId, Context, ContextId, Id, PlayableId, Playback, PlaybackMetadata,
-        PlaylistId, SharedState, TrackId,
+        AlbumId, ArtistId, Context, ContextId, EpisodeId, Id, PlayableId, Playback, PlaybackEvent,
+        PlaybackMetadata, Playlist, PlaylistId, SearchResults, SharedState, TrackId,
     },
 };
 use rspotify::prelude::{BaseClient, OAuthClient};
 
 use super::{
-    Command, Deserialize, GetRequest, IdOrName, ItemId, ItemType, Key, PlaylistCommand, Response,
-    Serialize, MAX_REQUEST_SIZE,
+    connection::SecureStream, CliError, Command, Deserialize, ErrorKind, GetRequest, IdOrName,
+    ItemId, ItemType, Key, PlaylistCommand, QueueCommand, Response, Serialize, SessionRequest,
+    SharedSession, MAX_REQUEST_SIZE, MAX_STREAM_REQUEST_SIZE,
 };
 
+/// The maximum duration to wait for a stream connection to send its request
+const STREAM_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[cfg(unix)]
+/// The path of the Unix socket bound by the application
+static UNIX_SOCKET_PATH: std::sync::OnceLock<std::path::PathBuf> = std::sync::OnceLock::new();
+
 pub async fn start_socket(client: Client, socket: UdpSocket, state: Option<SharedState>) {
     let mut buf = [0; MAX_REQUEST_SIZE];
 
@@ -52,20 +61,11 @@ pub async fn start_socket(client: Client, socket: UdpSocket, state: Option<Share
                 let span = tracing::info_span!("socket_request", request = ?request, dest_addr = ?dest_addr);
 
                 async {
-                    let response =
-                        match handle_socket_request(&client, state.as_ref(), request).await {
-                            Err(err) => {
-                                tracing::error!("Failed to handle socket request: {err:#}");
-                                let msg = format!("Bad request: {err:#}");
-                                Response::Err(msg.into_bytes())
-                            }
-                            Ok(data) => Response::Ok(data),
-                        };
+                    let response = get_socket_response(&client, state.as_ref(), request).await;
+                    log_response(&response);
                     send_response(response, &socket, dest_addr)
                         .await
                         .unwrap_or_default();
-
-                    tracing::info!("Successfully handled the socket request.",);
                 }
                 .instrument(span)
                 .await;
@@ -74,6 +74,308 @@ pub async fn start_socket(client: Client, socket: UdpSocket, state: Option<Share
     }
 }
 
+#[cfg(unix)]
+/// Binds a Unix socket to the given path, inside a folder only accessible by the user
+/// so that no other user can connect to the socket before its permissions are restricted.
+/// Fails if the permissions of the folder or the socket can't be restricted.
+pub fn bind_unix_socket(path: &std::path::Path) -> Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    let folder = path.parent().context("get the Unix socket's folder")?;
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(folder)
+        .with_context(|| format!("create the folder {}", folder.display()))?;
+    // the folder may have been created with other permissions
+    std::fs::set_permissions(folder, std::fs::Permissions::from_mode(0o700))
+        .with_context(|| format!("restrict the permissions of {}", folder.display()))?;
+
+    // remove the socket file left by a previously stopped application, if any
+    std::fs::remove_file(path).ok();
+    let listener = tokio::net::UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+        .with_context(|| format!("restrict the permissions of {}", path.display()))?;
+    Ok(listener)
+}
+
+#[cfg(unix)]
+/// Handles CLI requests sent to a Unix socket bound to the given path by [`bind_unix_socket`].
+///
+/// Each connection sends a single request and closes its write half,
+/// then reads the response until the connection is closed by the server.
+///
+/// Only the user can connect to the socket, which is served with the user's Spotify session.
+/// The socket file is removed when the application is terminated.
+pub async fn start_unix_socket(
+    client: Client,
+    listener: tokio::net::UnixListener,
+    path: std::path::PathBuf,
+    state: Option<SharedState>,
+) {
+    UNIX_SOCKET_PATH.set(path).ok();
+
+    loop {
+        let mut stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                tracing::warn!("Failed to accept a Unix socket connection: {err:#}");
+                continue;
+            }
+        };
+
+        tokio::task::spawn({
+            let client = client.clone();
+            let state = state.clone();
+            async move {
+                if let Some(request) = read_unix_socket_request(&mut stream).await {
+                    handle_stream_request(&client, state.as_ref(), stream, &request).await;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(unix)]
+/// Reads a request sent to the Unix socket until the connection's write half is closed
+async fn read_unix_socket_request(stream: &mut tokio::net::UnixStream) -> Option<Vec<u8>> {
+    use tokio::io::AsyncReadExt;
+
+    let mut req_buf = Vec::new();
+    let read = stream
+        .take(MAX_STREAM_REQUEST_SIZE as u64)
+        .read_to_end(&mut req_buf);
+    match tokio::time::timeout(STREAM_READ_TIMEOUT, read).await {
+        Ok(Ok(_)) => Some(req_buf),
+        Ok(Err(err)) => {
+            tracing::warn!("Failed to receive from the socket: {err:#}");
+            None
+        }
+        Err(_) => {
+            tracing::warn!("Timed out receiving a request from the socket");
+            None
+        }
+    }
+}
+
+#[cfg(unix)]
+/// Removes the file of the Unix socket started by the application, if any
+pub fn remove_unix_socket_file() {
+    if let Some(path) = UNIX_SOCKET_PATH.get() {
+        if let Err(err) = std::fs::remove_file(path) {
+            tracing::warn!(
+                "Failed to remove the Unix socket {}: {err:#}",
+                path.display()
+            );
+        }
+    }
+}
+
+/// Handles CLI requests sent from remote applications to a TCP socket.
+///
+/// The protocol is the same as the Unix socket's, except that each connection is encrypted and
+/// authenticated with the key shared via `remote_secret` (see [`SecureStream`]): the request, the response
+/// and each line of the watched data are sent as encrypted messages.
+pub async fn start_tcp_socket(
+    client: Client,
+    listener: tokio::net::TcpListener,
+    state: Option<SharedState>,
+    key: [u8; 32],
+) {
+    loop {
+        let (stream, addr) = match listener.accept().await {
+            Ok(v) => v,
+            Err(err) => {
+                tracing::warn!("Failed to accept a TCP socket connection: {err:#}");
+                continue;
+            }
+        };
+
+        tracing::info!("Got a remote connection from {addr}");
+        tokio::task::spawn({
+            let client = client.clone();
+            let state = state.clone();
+            async move {
+                let request = async {
+                    let mut stream = SecureStream::accept(stream, &key).await?;
+                    let request = stream
+                        .read_message()
+                        .await?
+                        .context("the connection was closed without sending a request")?;
+                    anyhow::Ok((stream, request))
+                };
+                match tokio::time::timeout(STREAM_READ_TIMEOUT, request).await {
+                    Ok(Ok((stream, request))) => {
+                        handle_stream_request(&client, state.as_ref(), stream, &request).await;
+                    }
+                    Ok(Err(err)) => {
+                        tracing::warn!("Rejected the remote connection from {addr}: {err:#}");
+                    }
+                    Err(_) => {
+                        tracing::warn!("Timed out receiving a request from {addr}");
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// A stream connection over which the application sends the responses to a request
+#[async_trait::async_trait]
+trait ResponseStream: Send + 'static {
+    /// Sends a response or a line of the watched data
+    async fn send(&mut self, data: &[u8]) -> Result<()>;
+}
+
+#[cfg(unix)]
+#[async_trait::async_trait]
+impl ResponseStream for tokio::net::UnixStream {
+    async fn send(&mut self, data: &[u8]) -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        self.write_all(data).await?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl ResponseStream for SecureStream<tokio::net::TcpStream> {
+    async fn send(&mut self, data: &[u8]) -> Result<()> {
+        self.write_message(data).await
+    }
+}
+
+/// Handles a request sent via a stream connection
+async fn handle_stream_request(
+    client: &Client,
+    state: Option<&SharedState>,
+    mut stream: impl ResponseStream,
+    req_buf: &[u8],
+) {
+    let request = match serde_json::from_slice::<Request>(req_buf) {
+        Ok(request) => request,
+        Err(err) => {
+            tracing::error!("Cannot deserialize the socket request: {err:#}");
+            return;
+        }
+    };
+
+    if let Request::FollowPlayback | Request::WatchKey(_) = request {
+        // the connection is kept open to stream data updates
+        let key = match request {
+            Request::WatchKey(key) => key,
+            _ => Key::Playback,
+        };
+        match state.cloned() {
+            Some(state) => {
+                tokio::task::spawn(watch_key(state, key, stream));
+            }
+            None => {
+                let response = Response::Err(
+                    ErrorKind::Other,
+                    b"the running application doesn't support watching data".to_vec(),
+                );
+                write_stream_response(&mut stream, &response).await;
+            }
+        }
+        return;
+    }
+
+    let span = tracing::info_span!("stream_socket_request", request = ?request);
+
+    async {
+        let response = get_socket_response(client, state, request).await;
+        log_response(&response);
+        write_stream_response(&mut stream, &response).await;
+    }
+    .instrument(span)
+    .await;
+}
+
+/// Logs the result of handling a socket request
+fn log_response(response: &Response) {
+    match response {
+        Response::Ok(_) => tracing::info!("Successfully handled the socket request."),
+        Response::Err(kind, err) => tracing::warn!(
+            "Failed to handle the socket request ({kind:?}): {}",
+            String::from_utf8_lossy(err)
+        ),
+    }
+}
+
+/// Sends the response of a request, after which the connection is closed
+async fn write_stream_response(stream: &mut impl ResponseStream, response: &Response) {
+    let result = async { stream.send(&serde_json::to_vec(response)?).await }.await;
+    if let Err(err) = result {
+        tracing::warn!("Failed to send the socket response: {err:#}");
+    }
+}
+
+/// Writes the key's data as a JSON line to the stream upon every data change
+/// until the stream is closed.
+///
+/// The data is read from the application's state upon the playback events published by the application.
+async fn watch_key(state: SharedState, key: Key, mut stream: impl ResponseStream) {
+    let mut playback_events = state.playback_events.subscribe();
+    let is_watched = |event: PlaybackEvent| match key {
+        Key::Queue => event == PlaybackEvent::QueueChange,
+        Key::UserLikedTracks => event == PlaybackEvent::LikedTracksChange,
+        _ => !matches!(
+            event,
+            PlaybackEvent::QueueChange | PlaybackEvent::LikedTracksChange
+        ),
+    };
+
+    loop {
+        let line = format!("{}\n", watched_key_value(&state, &key));
+        if stream.send(line.as_bytes()).await.is_err() {
+            // the client has disconnected
+            break;
+        }
+
+        // wait for the next change of the key's data
+        loop {
+            match playback_events.recv().await {
+                Ok(event) if !is_watched(event) => {}
+                // the data is re-sent if some events are missed
+                Ok(_) | Err(RecvError::Lagged(_)) => break,
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+}
+
+/// Get the JSON data of a watchable key from the application's state
+pub(super) fn watched_key_value(state: &SharedState, key: &Key) -> serde_json::Value {
+    let value = match key {
+        Key::Queue => serde_json::to_value(&state.player.read().queue),
+        Key::UserLikedTracks => {
+            let data = state.data.read();
+            let mut tracks = data.user_data.saved_tracks.values().collect::<Vec<_>>();
+            // most recently liked tracks first, like the Spotify API
+            tracks.sort_by(|x, y| y.added_at.cmp(&x.added_at));
+            serde_json::to_value(tracks)
+        }
+        _ => serde_json::to_value(state.player.read().current_playback()),
+    };
+    value.unwrap_or_default()
+}
+
+pub(super) async fn get_socket_response(
+    client: &Client,
+    state: Option<&SharedState>,
+    request: Request,
+) -> Response {
+    match handle_socket_request(client, state, request).await {
+        Err(err) => {
+            tracing::error!("Failed to handle socket request: {err:#}");
+            let msg = format!("Bad request: {err:#}");
+            Response::Err(ErrorKind::from_error(&err), msg.into_bytes())
+        }
+        Ok(data) => Response::Ok(data),
+    }
+}
+
 async fn send_response(
     response: Response,
     socket: &UdpSocket,
@@ -105,6 +407,18 @@ async fn current_playback(
     }
 }
 
+/// Get the currently playing track's ID, if any
+async fn current_track_id(
+    client: &Client,
+    state: Option<&SharedState>,
+) -> Result<Option<TrackId<'static>>> {
+    let playback = current_playback(client, state).await?;
+    match playback.and_then(|p| p.item) {
+        Some(rspotify::model::PlayableItem::Track(track)) => Ok(track.id),
+        _ => Ok(None),
+    }
+}
+
 async fn handle_socket_request(
     client: &Client,
     state: Option<&SharedState>,
@@ -123,57 +437,156 @@ async fn handle_socket_request(
             handle_playback_request(client, state, command).await?;
             Ok(Vec::new())
         }
-        Request::Connect(data) => {
-            let id = match data {
-                IdOrName::Id(id) => id,
-                IdOrName::Name(name) => {
-                    let devices = client.device().await?;
-                    match devices
-                        .into_iter()
-                        .find(|d| d.name == name)
-                        .and_then(|d| d.id)
-                    {
-                        Some(id) => id,
-                        None => {
-                            anyhow::bail!("No device with name={name} found");
-                        }
-                    }
-                }
-            };
-
-            client.transfer_playback(&id, None).await?;
+        Request::Connect {
+            device,
+            keep_playing,
+        } => {
+            let id = get_device_id(client, device).await?;
+            client
+                .transfer_playback(&id, keep_playing.then_some(true))
+                .await?;
+            client.apply_device_volume(&id).await?;
             Ok(Vec::new())
         }
-        Request::Like { unlike } => {
-            let playback = current_playback(client, state).await?;
-
-            // get currently playing track from the playback
-            let track = match playback {
-                None => None,
-                Some(ref playback) => match playback.item {
-                    Some(rspotify::model::PlayableItem::Track(ref track)) => Some(track),
-                    _ => None,
-                },
+        Request::Transfer { device } => {
+            let id = get_device_id(client, device).await?;
+            client.transfer_playback_preserving_state(&id).await?;
+            Ok(Vec::new())
+        }
+        Request::Like { unlike, id } => {
+            let id = match id {
+                Some(id) => Some(id),
+                None => current_track_id(client, state).await?,
             };
 
-            if let Some(id) = track.and_then(|t| t.id.clone()) {
-                if unlike {
-                    client.current_user_saved_tracks_delete([id]).await?;
-                } else {
-                    client.current_user_saved_tracks_add([id]).await?;
+            match (id, state) {
+                // update the running application's library data along with the user's library
+                (Some(id), Some(state)) => {
+                    if unlike {
+                        client
+                            .delete_from_library(state, crate::state::ItemId::Track(id))
+                            .await?;
+                    } else {
+                        let track = client.track(id).await?;
+                        client
+                            .add_to_library(state, crate::state::Item::Track(track))
+                            .await?;
+                    }
                 }
+                (Some(id), None) => {
+                    if unlike {
+                        client.current_user_saved_tracks_delete([id]).await?;
+                    } else {
+                        client.current_user_saved_tracks_add([id]).await?;
+                    }
+                }
+                (None, _) => {}
             }
 
             Ok(Vec::new())
         }
         Request::Playlist(command) => {
-            let resp = handle_playlist_request(client, command).await?;
+            let resp = handle_playlist_request(client, state, command).await?;
+            Ok(resp.into_bytes())
+        }
+        Request::CreatePlaylist { name, public } => {
+            let playlist = create_playlist(client, &name, public, false, "").await?;
+            Ok(serde_json::to_vec(&playlist)?)
+        }
+        Request::Queue(command) => {
+            let resp = handle_queue_request(client, command).await?;
             Ok(resp.into_bytes())
         }
-        Request::Search { query } => {
-            let resp = handle_search_request(client, query).await?;
+        Request::Search { query, item_type } => {
+            let resp = handle_search_request(client, query, item_type).await?;
             Ok(resp)
         }
+        Request::Lyrics { id, timestamped } => {
+            let id = match id {
+                Some(id) => id,
+                None => current_track_id(client, state)
+                    .await?
+                    .ok_or_else(|| CliError::new(ErrorKind::NotFound, "no playing track found!"))?,
+            };
+            let Some(lyrics) = client.lyrics(id.clone()).await? else {
+                anyhow::bail!(CliError::new(
+                    ErrorKind::NotFound,
+                    format!("No lyrics found for track {}", id.id())
+                ));
+            };
+
+            let lines = lyrics
+                .lines
+                .into_iter()
+                .map(|(t, line)| {
+                    if timestamped {
+                        // use the LRC format's timestamps, i.e. [mm:ss.xx]
+                        let ms = t.num_milliseconds();
+                        format!(
+                            "[{:02}:{:02}.{:02}] {line}",
+                            ms / 60_000,
+                            (ms / 1000) % 60,
+                            (ms % 1000) / 10
+                        )
+                    } else {
+                        line
+                    }
+                })
+                .collect::<Vec<_>>();
+            Ok(lines.join("\n").into_bytes())
+        }
+        #[cfg(feature = "streaming")]
+        Request::Bitrate(bitrate) => {
+            let state = state.context("switching the bitrate requires a running application")?;
+            client.set_bitrate(state, bitrate).await?;
+            Ok(format!("Switched the streaming bitrate to {bitrate} kbps").into_bytes())
+        }
+        Request::FollowPlayback | Request::WatchKey(_) => {
+            anyhow::bail!("watching data is only supported via a stream socket")
+        }
+        Request::Session(request) => handle_session_request(client, request).await,
+        Request::SearchAndQueue { query } => match client.search_and_queue(&query).await? {
+            Some(track) => Ok(format!(
+                "Added {} by {} to the queue",
+                track.name,
+                track.artists_info()
+            )
+            .into_bytes()),
+            None => anyhow::bail!(CliError::new(
+                ErrorKind::NotFound,
+                format!("No track found for query {query}")
+            )),
+        },
+    }
+}
+
+/// Handles a request of a UI attached to the application, which shares the application's Spotify session
+async fn handle_session_request(client: &Client, request: SessionRequest) -> Result<Vec<u8>> {
+    match request {
+        SessionRequest::Attach => {
+            let session = SharedSession {
+                device: client.integrated_device().await,
+            };
+            Ok(serde_json::to_vec(&session)?)
+        }
+        SessionRequest::Api(request) => {
+            let text = client.send_api_request(request).await?;
+            Ok(serde_json::to_vec(&text)?)
+        }
+        SessionRequest::Lyrics(id) => {
+            let lines = client.lyrics(id).await?.map(|lyrics| {
+                lyrics
+                    .lines
+                    .into_iter()
+                    .map(|(t, line)| (t.num_milliseconds(), line))
+                    .collect::<Vec<_>>()
+            });
+            Ok(serde_json::to_vec(&lines)?)
+        }
+        SessionRequest::RadioTrackIds(seed_uri) => {
+            let ids = client.radio_track_ids(seed_uri).await?;
+            Ok(serde_json::to_vec(&ids)?)
+        }
     }
 }
 
@@ -218,6 +631,27 @@ async fn handle_get_key_request(
     })
 }
 
+/// Get a device's ID from its `IdOrName` representation
+async fn get_device_id(client: &Client, device: IdOrName) -> Result<String> {
+    match device {
+        IdOrName::Id(id) => Ok(id),
+        IdOrName::Name(name) => {
+            let devices = client.device().await?;
+            match devices
+                .into_iter()
+                .find(|d| d.name == name)
+                .and_then(|d| d.id)
+            {
+                Some(id) => Ok(id),
+                None => anyhow::bail!(CliError::new(
+                    ErrorKind::NoDevice,
+                    format!("No device with name={name} found")
+                )),
+            }
+        }
+    }
+}
+
 /// Get a Spotify item's ID from its `IdOrName` representation
 async fn get_spotify_id(client: &Client, typ: ItemType, id_or_name: IdOrName) -> Result<ItemId> {
     // For `IdOrName::Name`, we search for the first item matching the name and return its Spotify id.
@@ -234,7 +668,10 @@ async fn get_spotify_id(client: &Client, typ: ItemType, id_or_name: IdOrName) ->
                 match results {
                     rspotify::model::SearchResult::Playlists(page) => {
                         if page.items.is_empty() {
-                            anyhow::bail!("Cannot find playlist with name='{name}'");
+                            anyhow::bail!(CliError::new(
+                                ErrorKind::NotFound,
+                                format!("Cannot find playlist with name='{name}'")
+                            ));
                         }
                         ItemId::Playlist(page.items[0].id.clone())
                     }
@@ -254,7 +691,10 @@ async fn get_spotify_id(client: &Client, typ: ItemType, id_or_name: IdOrName) ->
                         if !page.items.is_empty() && page.items[0].id.is_some() {
                             ItemId::Album(page.items[0].id.clone().unwrap())
                         } else {
-                            anyhow::bail!("Cannot find album with name='{name}'");
+                            anyhow::bail!(CliError::new(
+                                ErrorKind::NotFound,
+                                format!("Cannot find album with name='{name}'")
+                            ));
                         }
                     }
                     _ => unreachable!(),
@@ -271,7 +711,10 @@ async fn get_spotify_id(client: &Client, typ: ItemType, id_or_name: IdOrName) ->
                 match results {
                     rspotify::model::SearchResult::Artists(page) => {
                         if page.items.is_empty() {
-                            anyhow::bail!("Cannot find artist with name='{name}'");
+                            anyhow::bail!(CliError::new(
+                                ErrorKind::NotFound,
+                                format!("Cannot find artist with name='{name}'")
+                            ));
                         }
                         ItemId::Artist(page.items[0].id.clone())
                     }
@@ -291,7 +734,10 @@ async fn get_spotify_id(client: &Client, typ: ItemType, id_or_name: IdOrName) ->
                         if !page.items.is_empty() && page.items[0].id.is_some() {
                             ItemId::Track(page.items[0].id.clone().unwrap())
                         } else {
-                            anyhow::bail!("Cannot find track with name='{name}'");
+                            anyhow::bail!(CliError::new(
+                                ErrorKind::NotFound,
+                                format!("Cannot find track with name='{name}'")
+                            ));
                         }
                     }
                     _ => unreachable!(),
@@ -317,8 +763,18 @@ async fn handle_get_item_request(
     })
 }
 
-async fn handle_search_request(client: &Client, query: String) -> Result<Vec<u8>> {
-    let search_result = client.search(&query).await?;
+async fn handle_search_request(
+    client: &Client,
+    query: String,
+    item_type: Option<SearchItemType>,
+) -> Result<Vec<u8>> {
+    let search_result: SearchResults = match item_type {
+        Some(item_type) => client
+            .search_specific_type(&query, item_type.into())
+            .await?
+            .into(),
+        None => client.search(&query).await?,
+    };
 
     Ok(serde_json::to_vec(&search_result)?)
 }
@@ -398,6 +854,10 @@ async fn handle_playback_request(
             let track = client.track(id).await?;
             PlayerRequest::StartPlayback(Playback::URIs(vec![track.id.into()], None), None)
         }
+        Command::StartEpisode(id) => {
+            let id = EpisodeId::from_id(id)?.into_static();
+            PlayerRequest::StartPlayback(Playback::URIs(vec![id.into()], None), None)
+        }
         Command::PlayPause => PlayerRequest::ResumePause,
         Command::Play => PlayerRequest::Resume,
         Command::Pause => PlayerRequest::Pause,
@@ -408,13 +868,13 @@ async fn handle_playback_request(
         Command::Volume { percent, is_offset } => {
             let volume = playback
                 .as_ref()
-                .context("no active playback found!")?
+                .ok_or_else(|| CliError::new(ErrorKind::NoDevice, "no active playback found!"))?
                 .volume
                 .context("playback has no volume!")?;
             let percent = if is_offset {
-                std::cmp::max(0, (volume as i8) + percent)
+                (i64::from(volume) + i64::from(percent)).clamp(0, 100)
             } else {
-                percent
+                i64::from(percent)
             };
             PlayerRequest::Volume(percent.try_into()?)
         }
@@ -425,13 +885,15 @@ async fn handle_playback_request(
             let progress = client
                 .current_playback2()
                 .await?
-                .context("no active playback found!")?
+                .ok_or_else(|| CliError::new(ErrorKind::NoDevice, "no active playback found!"))?
                 .progress
                 .context("playback has no progress!")?;
-            PlayerRequest::SeekTrack(
-                progress + chrono::Duration::try_milliseconds(position_offset_ms).unwrap(),
-            )
+            let position = chrono::Duration::try_milliseconds(position_offset_ms)
+                .and_then(|offset| progress.checked_add(&offset))
+                .with_context(|| format!("invalid seek offset {position_offset_ms}ms"))?;
+            PlayerRequest::SeekTrack(std::cmp::max(position, chrono::Duration::zero()))
         }
+        Command::ToggleMute => PlayerRequest::ToggleMute,
     };
 
     if let Some(state) = state {
@@ -446,6 +908,7 @@ async fn handle_playback_request(
                     Ok(playback) => {
                         // update application's states
                         state.player.write().buffered_playback = playback;
+                        state.publish_playback_events();
                         client.update_playback(&state);
                     }
                     Err(err) => {
@@ -465,7 +928,66 @@ async fn handle_playback_request(
     Ok(())
 }
 
-async fn handle_playlist_request(client: &Client, command: PlaylistCommand) -> Result<String> {
+async fn handle_queue_request(client: &Client, command: QueueCommand) -> Result<String> {
+    match command {
+        QueueCommand::List => {
+            let queue = client.current_user_queue().await?;
+            let template = config::FormatTemplate::new("{track} • {artists}");
+            let describe = |item: &rspotify::model::PlayableItem| {
+                crate::utils::format_with_placeholders(&template, |placeholder| {
+                    crate::utils::get_playable_placeholder_value(placeholder, item)
+                })
+            };
+
+            let mut lines = Vec::new();
+            if let Some(ref item) = queue.currently_playing {
+                lines.push(format!("Now playing: {}", describe(item)));
+            }
+            for (i, item) in queue.queue.iter().enumerate() {
+                lines.push(format!("{}. {}", i + 1, describe(item)));
+            }
+            Ok(lines.join("\n"))
+        }
+        QueueCommand::Add { uri } => {
+            let uri = crate::utils::parse_uri(&uri);
+            let id = match TrackId::from_uri(&uri) {
+                Ok(id) => PlayableId::Track(id.into_static()),
+                Err(_) => PlayableId::Episode(
+                    EpisodeId::from_uri(&uri)
+                        .context(format!("'{uri}' is not a track or episode URI"))?
+                        .into_static(),
+                ),
+            };
+            client.add_item_to_queue(id, None).await?;
+            Ok(format!("Added '{uri}' to the queue"))
+        }
+        QueueCommand::Clear => {
+            client.clear_queue().await?;
+            Ok("Cleared the queue".to_string())
+        }
+    }
+}
+
+/// Creates a playlist owned by the current user
+async fn create_playlist(
+    client: &Client,
+    name: &str,
+    public: bool,
+    collab: bool,
+    description: &str,
+) -> Result<Playlist> {
+    let uid = client.current_user().await?.id;
+    let playlist = client
+        .user_playlist_create(uid, name, Some(public), Some(collab), Some(description))
+        .await?;
+    Ok(playlist.into())
+}
+
+async fn handle_playlist_request(
+    client: &Client,
+    state: Option<&SharedState>,
+    command: PlaylistCommand,
+) -> Result<String> {
     let uid = client.current_user().await?.id;
 
     match command {
@@ -475,18 +997,10 @@ async fn handle_playlist_request(client: &Client, command: PlaylistCommand) -> R
             collab,
             description,
         } => {
-            let resp = client
-                .user_playlist_create(
-                    uid,
-                    name.as_str(),
-                    Some(public),
-                    Some(collab),
-                    Some(description.as_str()),
-                )
-                .await?;
+            let playlist = create_playlist(client, &name, public, collab, &description).await?;
             Ok(format!(
                 "Playlist '{}' with id '{}' was created.",
-                resp.name, resp.id
+                playlist.name, playlist.id
             ))
         }
         PlaylistCommand::Delete { id } => {
@@ -553,7 +1067,7 @@ async fn handle_playlist_request(client: &Client, command: PlaylistCommand) -> R
         }
         PlaylistCommand::Sync { id, delete } => {
             // Get import dir/file
-            let imports_dir = get_cache_folder_path()?.join("imports");
+            let imports_dir = config::get_config().cache_folder.join("imports");
 
             let mut result = String::new();
 
@@ -594,6 +1108,32 @@ async fn handle_playlist_request(client: &Client, command: PlaylistCommand) -> R
 
             Ok(result)
         }
+        PlaylistCommand::AddTrack {
+            playlist_id,
+            track_id,
+        } => {
+            client
+                .add_item_to_playlist(state, playlist_id.as_ref(), track_id.as_ref().into())
+                .await?;
+            Ok(format!(
+                "Track '{}' was added to playlist '{}'.",
+                track_id.id(),
+                playlist_id.id()
+            ))
+        }
+        PlaylistCommand::RemoveTrack {
+            playlist_id,
+            track_id,
+        } => {
+            client
+                .delete_track_from_playlist(state, playlist_id.as_ref(), track_id.as_ref())
+                .await?;
+            Ok(format!(
+                "Track '{}' was removed from playlist '{}'.",
+                track_id.id(),
+                playlist_id.id()
+            ))
+        }
     }
 }
 
@@ -639,7 +1179,8 @@ async fn playlist_import(
     };
 
     // Get import dir/file
-    let cache_dir = get_cache_folder_path()?;
+    let configs = config::get_config();
+    let cache_dir = &configs.cache_folder;
     let imports_dir = cache_dir.join("imports");
     let to_dir = imports_dir.join(import_to.id());
     let from_file = to_dir.join(import_from.id());
@@ -1,10 +1,144 @@
 use clap::{builder::EnumValueParser, value_parser, Arg, ArgAction, ArgGroup, Command};
+use clap_complete::engine::{ArgValueCandidates, CompletionCandidate};
 use clap_complete::Shell;
+use rspotify::prelude::Id;
 
-use super::{ContextType, ItemType, Key};
+use super::{ContextType, HistoryFormat, ItemType, Key, SearchItemType};
+use crate::{
+    config,
+    state::{load_data_from_file_cache, Device, FileCacheKey, PlaylistFolderItem},
+};
+
+/// Get the cache folder of the command line being completed, which is resolved
+/// from its `--cache-folder` and `--profile` arguments like the application's cache folder
+fn completion_cache_folder() -> Option<std::path::PathBuf> {
+    // the shell passes the command line being completed after `--`
+    let words = std::env::args_os().skip_while(|arg| arg != "--").skip(1);
+    let args = super::init_cli()
+        .ok()?
+        .ignore_errors(true)
+        .try_get_matches_from(words)
+        .ok()?;
+
+    let mut folder = std::path::PathBuf::from(args.get_one::<String>("cache-folder")?);
+    if let Some(profile) = args.get_one::<String>("profile") {
+        folder = config::get_profile_folder_path(&folder, profile);
+    }
+    Some(folder)
+}
+
+/// Load data cached by the application for dynamic shell completions
+fn load_cached_data<T: serde::de::DeserializeOwned>(key: FileCacheKey) -> Vec<T> {
+    completion_cache_folder()
+        .and_then(|folder| load_data_from_file_cache(key, &folder))
+        .unwrap_or_default()
+}
+
+/// Complete the names of devices cached by the application
+fn device_name_candidates() -> Vec<CompletionCandidate> {
+    load_cached_data::<Device>(FileCacheKey::Devices)
+        .into_iter()
+        .map(|d| CompletionCandidate::new(d.name))
+        .collect()
+}
+
+/// Complete the IDs of playlists cached by the application, described by their names
+fn playlist_id_candidates() -> Vec<CompletionCandidate> {
+    load_cached_data::<PlaylistFolderItem>(FileCacheKey::Playlists)
+        .into_iter()
+        .filter_map(|item| match item {
+            PlaylistFolderItem::Playlist(p) => {
+                Some(CompletionCandidate::new(p.id.id()).help(Some(p.name.into())))
+            }
+            PlaylistFolderItem::Folder(_) => None,
+        })
+        .collect()
+}
 
 pub fn init_connect_subcommand() -> Command {
-    add_id_or_name_group(Command::new("connect").about("Connect to a Spotify device"))
+    Command::new("connect")
+        .about("Connect to a Spotify device")
+        .arg(
+            Arg::new("device")
+                .help("Name of the device")
+                .add(ArgValueCandidates::new(device_name_candidates)),
+        )
+        .arg(Arg::new("id").long("id").short('i'))
+        .arg(Arg::new("name").long("name").short('n'))
+        .group(
+            ArgGroup::new("id_or_name")
+                .args(["device", "id", "name"])
+                .required(true),
+        )
+        .arg(
+            Arg::new("keep-playing")
+                .long("keep-playing")
+                .action(ArgAction::SetTrue)
+                .help("Ensure the playback is playing on the device after connecting"),
+        )
+}
+
+pub fn init_transfer_command() -> Command {
+    Command::new("transfer")
+        .about("Transfer the playback to a Spotify device, preserving the playback's progress and play/pause state")
+        .arg(
+            Arg::new("device")
+                .help("Name of the device")
+                .add(ArgValueCandidates::new(device_name_candidates)),
+        )
+        .arg(Arg::new("id").long("id").short('i'))
+        .arg(Arg::new("name").long("name").short('n'))
+        .group(
+            ArgGroup::new("id_or_name")
+                .args(["device", "id", "name"])
+                .required(true),
+        )
+}
+
+pub fn init_play_command() -> Command {
+    Command::new("play")
+        .about("Start a playback of a track, episode, album, playlist or artist by its URI or URL")
+        .arg(
+            Arg::new("uri")
+                .required(true)
+                .help("Spotify URI or URL of the item, e.g. spotify:album:{id} or https://open.spotify.com/album/{id}"),
+        )
+        .arg(
+            Arg::new("shuffle")
+                .short('s')
+                .long("shuffle")
+                .action(ArgAction::SetTrue)
+                .help("Shuffle tracks within the launched playback (not supported for a track)"),
+        )
+        .arg(
+            Arg::new("device")
+                .short('d')
+                .long("device")
+                .value_name("DEVICE")
+                .help("Name of the device to connect to before starting the playback"),
+        )
+}
+
+pub fn init_radio_command() -> Command {
+    Command::new("radio")
+        .about("Start a radio playback of tracks similar to a track, artist, album or playlist")
+        .arg(
+            Arg::new("uri")
+                .required(true)
+                .help("Spotify URI or URL of the item, e.g. spotify:artist:{id} or https://open.spotify.com/artist/{id}"),
+        )
+}
+
+pub fn init_devices_command() -> Command {
+    Command::new("devices")
+        .about("List the available Spotify devices")
+        .after_help("Each device is printed in a line consisting of the device's name, type and whether it's active, separated by tabs.")
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .action(ArgAction::SetTrue)
+                .help("Print the devices in JSON"),
+        )
 }
 
 pub fn init_get_subcommand() -> Command {
@@ -12,11 +146,21 @@ pub fn init_get_subcommand() -> Command {
         .about("Get Spotify data")
         .subcommand_required(true)
         .subcommand(
-            Command::new("key").about("Get data by key").arg(
-                Arg::new("key")
-                    .value_parser(EnumValueParser::<Key>::new())
-                    .required(true),
-            ),
+            Command::new("key")
+                .about("Get data by key")
+                .arg(
+                    Arg::new("key")
+                        .value_parser(EnumValueParser::<Key>::new())
+                        .required(true),
+                )
+                .arg(init_format_arg())
+                .arg(
+                    Arg::new("watch")
+                        .short('w')
+                        .long("watch")
+                        .action(ArgAction::SetTrue)
+                        .help("Print the data as a JSON line upon every data change (only for the `playback`, `queue` and `user-liked-tracks` keys). Requires a running application"),
+                ),
         )
         .subcommand(add_id_or_name_group(
             Command::new("item").about("Get a Spotify item's data").arg(
@@ -91,7 +235,7 @@ fn add_id_or_name_group(cmd: Command) -> Command {
 pub fn init_playback_subcommand() -> Command {
     Command::new("playback")
         .about("Interact with the playback")
-        .subcommand_required(true)
+        .arg_required_else_help(true)
         .subcommand(init_playback_start_subcommand())
         .subcommand(Command::new("play-pause").about("Toggle between play and pause"))
         .subcommand(Command::new("play").about("Resume the current playback if stopped"))
@@ -105,8 +249,9 @@ pub fn init_playback_subcommand() -> Command {
                 .about("Set the volume percentage")
                 .arg(
                     Arg::new("percent")
-                        .value_parser(value_parser!(i8).range(-100..=100))
-                        .required(true),
+                        .allow_hyphen_values(true)
+                        .required(true)
+                        .help("Volume percentage, an offset like +5 or -5, or `mute` to toggle mute"),
                 )
                 .arg(
                     Arg::new("offset")
@@ -117,31 +262,143 @@ pub fn init_playback_subcommand() -> Command {
         )
         .subcommand(
             Command::new("seek")
-                .about("Seek by an offset milliseconds")
+                .about("Seek by an offset")
                 .arg(
-                    Arg::new("position_offset_ms")
-                        .value_parser(value_parser!(i64))
-                        .required(true),
+                    Arg::new("position_offset")
+                        .value_name("OFFSET")
+                        .allow_hyphen_values(true)
+                        .required(true)
+                        .help("Offset in milliseconds or with a unit (ms, s or m), e.g. +15s or -30s"),
                 ),
         )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .conflicts_with("format")
+                .help("Print the updated playback in JSON"),
+        )
+        .arg(init_format_arg().global(true))
+        .arg(
+            Arg::new("follow")
+                .long("follow")
+                .action(ArgAction::SetTrue)
+                .help("Keep running, printing the playback upon every playback change (requires a running application on Unix systems)"),
+        )
+}
+
+fn init_format_arg() -> Arg {
+    Arg::new("format")
+        .long("format")
+        .value_name("FORMAT")
+        .help("Print the playback using a format string, e.g. \"{track} - {artists}\"")
+}
+
+pub fn init_queue_subcommand() -> Command {
+    Command::new("queue")
+        .about("Interact with the playback queue")
+        .subcommand_required(true)
+        .subcommand(Command::new("list").about("List the currently playing item and the queue"))
+        .subcommand(
+            Command::new("add").about("Add a track or an episode to the queue").arg(
+                Arg::new("uri")
+                    .help("Spotify URI of the track or episode, e.g. spotify:track:{id}")
+                    .required(true),
+            ),
+        )
+        .subcommand(
+            Command::new("clear")
+                .about("Clear the queue")
+                .after_help("Spotify doesn't support clearing the queue directly, so the current playback is restarted from the playing item and its progress."),
+        )
 }
 
 pub fn init_search_command() -> Command {
     Command::new("search")
         .about("Search spotify")
         .arg(Arg::new("query").help("Search query").required(true))
+        .arg(
+            Arg::new("queue")
+                .long("queue")
+                .short('q')
+                .action(ArgAction::SetTrue)
+                .help("Add the top matched track to the queue instead of printing the results"),
+        )
+        .arg(
+            Arg::new("type")
+                .long("type")
+                .value_parser(EnumValueParser::<SearchItemType>::new())
+                .conflicts_with("queue")
+                .help("Only search items of a type, printing one item per line"),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("queue")
+                .help("Print the results as a JSON array of items with their IDs and URIs"),
+        )
 }
 
 pub fn init_like_command() -> Command {
     Command::new("like")
-        .about("Like currently playing track")
+        .about("Like a track, default to the currently playing track")
         .arg(
             Arg::new("unlike")
                 .long("unlike")
                 .short('u')
                 .action(ArgAction::SetTrue)
-                .help("Unlike the currently playing track"),
+                .help("Unlike the track"),
         )
+        .arg(Arg::new("uri").help("ID or URI of the track to like"))
+}
+
+#[cfg(feature = "streaming")]
+pub fn init_bitrate_command() -> Command {
+    Command::new("bitrate")
+        .about(
+            "Switch the integrated client's streaming bitrate without restarting the application",
+        )
+        .arg(
+            Arg::new("bitrate")
+                .required(true)
+                .value_parser(["96", "160", "320"])
+                .help("Bitrate in kbps"),
+        )
+}
+
+pub fn init_lyrics_command() -> Command {
+    Command::new("lyrics")
+        .about("Print lyrics of a track, default to the currently playing track")
+        .arg(Arg::new("uri").help("ID or URI of the track"))
+        .arg(
+            Arg::new("timestamps")
+                .long("timestamps")
+                .action(ArgAction::SetTrue)
+                .help("Prefix each line with its timestamp in the LRC format, e.g. [01:23.45]"),
+        )
+}
+
+pub fn init_batch_command() -> Command {
+    Command::new("batch")
+        .about("Run CLI commands read from a file or stdin, one command per line")
+        .after_help("Commands are run sequentially with a single client session, and the batch stops at the first failed command. Empty lines and lines starting with `#` are ignored.")
+        .arg(
+            Arg::new("file")
+                .required(true)
+                .help("Path to the file containing the commands, or `-` to read from stdin"),
+        )
+}
+
+pub fn init_daemon_command() -> Command {
+    Command::new("daemon")
+        .about("Run the application headlessly, handling CLI commands until the process is stopped")
+}
+
+pub fn init_stream_command() -> Command {
+    Command::new("stream")
+        .about("Run only the integrated Spotify Connect device, without the UI, until the process is stopped")
 }
 
 pub fn init_authenticate_command() -> Command {
@@ -151,6 +408,7 @@ pub fn init_authenticate_command() -> Command {
 pub fn init_generate_command() -> Command {
     Command::new("generate")
         .about("Generate shell completion for the application CLI")
+        .after_help("For completions of cached device and playlist names, register the dynamic completion instead, e.g. `source <(COMPLETE=bash spotify_player)`.")
         .arg(
             Arg::new("shell")
                 .action(ArgAction::Set)
@@ -159,11 +417,135 @@ pub fn init_generate_command() -> Command {
         )
 }
 
+pub fn init_history_subcommand() -> Command {
+    Command::new("history")
+        .about("Interact with the listening history recorded by the application")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("export")
+                .about("Export the listening history")
+                .arg(Arg::new("since").long("since").value_name("DATE").help(
+                    "Only export items played since a date, in YYYY-MM-DD or RFC 3339 format",
+                ))
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(EnumValueParser::<HistoryFormat>::new())
+                        .default_value("json")
+                        .help("Output format"),
+                ),
+        )
+}
+
+pub fn init_pick_command() -> Command {
+    Command::new("pick")
+        .about("Pick a playlist or a liked track with a fuzzy picker and print its URI")
+        .arg(
+            Arg::new("item_type")
+                .value_parser(["playlists", "tracks"])
+                .required(true),
+        )
+        .arg(
+            Arg::new("query")
+                .long("query")
+                .short('q')
+                .help("Initial query of the picker"),
+        )
+}
+
+pub fn init_import_csv_command() -> Command {
+    Command::new("import-csv")
+        .about("Import the tracks of a CSV export from another music service, e.g. Apple Music, YouTube Music or TuneMyMusic, into new playlists or the liked tracks")
+        .arg(Arg::new("file").required(true).help("Path to the CSV file"))
+        .arg(
+            Arg::new("playlist")
+                .long("playlist")
+                .short('p')
+                .value_name("NAME")
+                .conflicts_with("liked")
+                .help("Name of the playlist to import into, default to the CSV file's playlist column or name"),
+        )
+        .arg(
+            Arg::new("liked")
+                .long("liked")
+                .action(ArgAction::SetTrue)
+                .help("Import into the liked tracks instead of playlists"),
+        )
+        .arg(
+            Arg::new("public")
+                .long("public")
+                .action(ArgAction::SetTrue)
+                .help("Create public playlists"),
+        )
+        .arg(
+            Arg::new("review")
+                .long("review")
+                .action(ArgAction::SetTrue)
+                .help("Review the uncertain matches with a picker instead of reporting them as unmatched"),
+        )
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .help("Match the tracks without importing them"),
+        )
+}
+
+#[cfg(feature = "scrobble")]
+pub fn init_lastfm_sync_command() -> Command {
+    Command::new("lastfm-sync")
+        .about("Save the tracks loved on Last.fm to the user's liked tracks")
+        .arg(
+            Arg::new("dry-run")
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .help("Print the matched tracks without saving them"),
+        )
+        .arg(
+            Arg::new("skip-uncertain")
+                .long("skip-uncertain")
+                .action(ArgAction::SetTrue)
+                .help("Skip the uncertain matches instead of reviewing them with a picker"),
+        )
+}
+
+pub fn init_cache_subcommand() -> Command {
+    let category_args = [
+        Arg::new("images")
+            .long("images")
+            .action(ArgAction::SetTrue)
+            .help("The cover image cache"),
+        Arg::new("audio")
+            .long("audio")
+            .action(ArgAction::SetTrue)
+            .help("The audio cache of the integrated streaming device"),
+        Arg::new("api")
+            .long("api")
+            .action(ArgAction::SetTrue)
+            .help("The cache of the user's library data and other Spotify API metadata"),
+    ];
+
+    Command::new("cache")
+        .about("Inspect and clear the application's caches")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("stats")
+                .about("Print the number of files and the size of each cache")
+                .args(category_args.clone()),
+        )
+        .subcommand(
+            Command::new("clear")
+                .about("Clear the caches, default to all caches if no cache is specified")
+                .args(category_args),
+        )
+}
+
 pub fn init_playlist_subcommand() -> Command {
     Command::new("playlist")
         .about("Playlist editing")
         .subcommand_required(true)
         .subcommand(Command::new("new").about("Create a new playlist")
+            .visible_alias("create")
             .arg(Arg::new("name")
                 .value_parser(clap::builder::NonEmptyStringValueParser::new()))
             .arg(Arg::new("description")
@@ -207,4 +589,20 @@ pub fn init_playlist_subcommand() -> Command {
                 .long("delete")
                 .action(clap::ArgAction::SetTrue)
                 .help("Deletes any previously imported tracks that are no longer in an imported playlist since last import.")))
+        .subcommand(Command::new("add-track").about("Add a track to a playlist. If the track is already in the playlist, it's moved to the end.")
+            .arg(Arg::new("playlist_id")
+                .required(true)
+                .help("ID or URI of the playlist")
+                .add(ArgValueCandidates::new(playlist_id_candidates)))
+            .arg(Arg::new("track_id")
+                .required(true)
+                .help("ID or URI of the track")))
+        .subcommand(Command::new("remove-track").about("Remove all occurrences of a track from a playlist")
+            .arg(Arg::new("playlist_id")
+                .required(true)
+                .help("ID or URI of the playlist")
+                .add(ArgValueCandidates::new(playlist_id_candidates)))
+            .arg(Arg::new("track_id")
+                .required(true)
+                .help("ID or URI of the track")))
 }
@@ -0,0 +1,283 @@
+use anyhow::{Context as _, Result};
+use serde::de::DeserializeOwned;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::config;
+
+use super::{CliError, ErrorKind, Request, Response, SessionRequest, MAX_STREAM_REQUEST_SIZE};
+
+/// The maximum size of a message received from a remote application, e.g. a response
+/// with the user's playlists
+const MAX_RESPONSE_SIZE: usize = 64 * 1024 * 1024;
+
+/// A running application, e.g. `spotify_player daemon`, serving the requests
+/// sent to its stream socket with its Spotify session
+#[derive(Clone)]
+pub enum RunningApplication {
+    /// An application listening on the Unix socket bound to the path
+    #[cfg(unix)]
+    Local(std::path::PathBuf),
+    /// An application listening on a remote TCP socket, sharing the key of `remote_secret`
+    Remote { address: String, key: [u8; 32] },
+}
+
+impl RunningApplication {
+    /// Finds the remote application if `remote_address` is configured,
+    /// or a running application whose Unix socket accepts connections
+    pub fn find(configs: &config::Configs) -> Result<Option<Self>> {
+        if let Some(address) = configs.app_config.remote_address.clone() {
+            let key = configs
+                .app_config
+                .remote_key()?
+                .context("`remote_secret` is required to connect to a remote application")?;
+            return Ok(Some(Self::Remote { address, key }));
+        }
+
+        #[cfg(unix)]
+        {
+            let path = configs.client_socket_path();
+            if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+                return Ok(Some(Self::Local(path)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Sends a request to the application, returning the application's response
+    pub async fn send_request(&self, request: &Request) -> Result<Response> {
+        let data = match self {
+            #[cfg(unix)]
+            Self::Local(path) => {
+                let mut stream = Self::send_local_request(path, request).await?;
+                let mut data = Vec::new();
+                stream.read_to_end(&mut data).await?;
+                data
+            }
+            Self::Remote { address, key } => {
+                let mut stream = Self::send_remote_request(address, key, request).await?;
+                stream
+                    .read_message()
+                    .await?
+                    .context("the connection to the remote application was closed")?
+            }
+        };
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    /// Sends a request of an attached UI to the application, returning the response's data
+    pub async fn session_request<T: DeserializeOwned>(&self, request: SessionRequest) -> Result<T> {
+        match self.send_request(&Request::Session(request)).await? {
+            Response::Ok(data) => Ok(serde_json::from_slice(&data)?),
+            Response::Err(kind, err) => {
+                Err(CliError::new(kind, String::from_utf8_lossy(&err)).into())
+            }
+        }
+    }
+
+    /// Sends a request to watch data, calling `on_data` with the data streamed by the application
+    /// upon every data change until the connection is closed
+    pub async fn watch(
+        &self,
+        request: &Request,
+        mut on_data: impl FnMut(&str) -> Result<()>,
+    ) -> Result<()> {
+        match self {
+            #[cfg(unix)]
+            Self::Local(path) => {
+                use tokio::io::AsyncBufReadExt;
+
+                let stream = Self::send_local_request(path, request).await?;
+                let mut lines = tokio::io::BufReader::new(stream).lines();
+                while let Some(line) = lines.next_line().await? {
+                    on_data(&line)?;
+                }
+            }
+            Self::Remote { address, key } => {
+                let mut stream = Self::send_remote_request(address, key, request).await?;
+                while let Some(message) = stream.read_message().await? {
+                    on_data(String::from_utf8_lossy(&message).trim_end())?;
+                }
+            }
+        }
+
+        anyhow::bail!("the connection to the running application was closed")
+    }
+
+    #[cfg(unix)]
+    async fn send_local_request(
+        path: &std::path::Path,
+        request: &Request,
+    ) -> Result<tokio::net::UnixStream> {
+        let mut stream = tokio::net::UnixStream::connect(path)
+            .await
+            .with_context(|| format!("connect to the Unix socket {}", path.display()))?;
+        stream.write_all(&serde_json::to_vec(request)?).await?;
+        // close the write half to indicate the end of the request
+        stream.shutdown().await?;
+        Ok(stream)
+    }
+
+    async fn send_remote_request(
+        address: &str,
+        key: &[u8; 32],
+        request: &Request,
+    ) -> Result<SecureStream<tokio::net::TcpStream>> {
+        let stream = tokio::net::TcpStream::connect(address)
+            .await
+            .with_context(|| format!("connect to the remote application at {address}"))?;
+        let mut stream = SecureStream::connect(stream, key)
+            .await
+            .with_context(|| format!("secure the connection to {address}"))?;
+        stream.write_message(&serde_json::to_vec(request)?).await?;
+        Ok(stream)
+    }
+}
+
+/// The error of a remote application closing the connection during the handshake,
+/// which it does if the connection can't be authenticated
+fn rejected_error() -> anyhow::Error {
+    CliError::new(
+        ErrorKind::Auth,
+        "the remote application closed the connection, check that both applications use the same `remote_secret`",
+    )
+    .into()
+}
+
+/// The Noise protocol securing a connection between two applications sharing a key:
+/// the ephemeral keys of the connection are exchanged and authenticated with the pre-shared key
+const NOISE_PARAMS: &str = "Noise_NNpsk0_25519_ChaChaPoly_BLAKE2s";
+/// The maximum size of a Noise message
+const MAX_NOISE_MESSAGE_SIZE: usize = 65535;
+/// The size of the authentication tag of an encrypted Noise message
+const NOISE_TAG_SIZE: usize = 16;
+
+/// An encrypted and authenticated connection between two applications sharing a key,
+/// secured with the [`NOISE_PARAMS`] Noise protocol.
+///
+/// Each Noise message is sent as a frame of its length (a 16-bit big-endian integer) followed by
+/// the message. A message sent over the connection is split into a Noise message of its length
+/// (a 32-bit big-endian integer) followed by the Noise messages of its chunks.
+pub struct SecureStream<S> {
+    stream: S,
+    noise: snow::TransportState,
+    max_message_size: usize,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> SecureStream<S> {
+    /// Secures a connection to an application sharing the key
+    pub async fn connect(mut stream: S, key: &[u8; 32]) -> Result<Self> {
+        let mut noise = snow::Builder::new(NOISE_PARAMS.parse()?)
+            .psk(0, key)
+            .build_initiator()?;
+        let mut buf = vec![0; MAX_NOISE_MESSAGE_SIZE];
+
+        // -> psk, e
+        let len = noise.write_message(&[], &mut buf)?;
+        write_frame(&mut stream, &buf[..len]).await?;
+        // <- e, ee
+        let frame = read_frame(&mut stream).await?.ok_or_else(rejected_error)?;
+        noise
+            .read_message(&frame, &mut buf)
+            .context("authenticate the remote application")?;
+
+        Ok(Self {
+            stream,
+            noise: noise.into_transport_mode()?,
+            max_message_size: MAX_RESPONSE_SIZE,
+        })
+    }
+
+    /// Secures a connection accepted from an application sharing the key,
+    /// which sends requests of at most `MAX_STREAM_REQUEST_SIZE` bytes
+    pub async fn accept(mut stream: S, key: &[u8; 32]) -> Result<Self> {
+        let mut noise = snow::Builder::new(NOISE_PARAMS.parse()?)
+            .psk(0, key)
+            .build_responder()?;
+        let mut buf = vec![0; MAX_NOISE_MESSAGE_SIZE];
+
+        // -> psk, e
+        let frame = read_frame(&mut stream)
+            .await?
+            .context("the connection was closed during the handshake")?;
+        noise.read_message(&frame, &mut buf).context(
+            "authenticate the connection, the applications may not share the same `remote_secret`",
+        )?;
+        // <- e, ee
+        let len = noise.write_message(&[], &mut buf)?;
+        write_frame(&mut stream, &buf[..len]).await?;
+
+        Ok(Self {
+            stream,
+            noise: noise.into_transport_mode()?,
+            max_message_size: MAX_STREAM_REQUEST_SIZE,
+        })
+    }
+
+    /// Encrypts and sends a message
+    pub async fn write_message(&mut self, message: &[u8]) -> Result<()> {
+        let mut buf = vec![0; MAX_NOISE_MESSAGE_SIZE];
+
+        let len = u32::try_from(message.len()).context("message is too large")?;
+        let n = self.noise.write_message(&len.to_be_bytes(), &mut buf)?;
+        write_frame(&mut self.stream, &buf[..n]).await?;
+        for chunk in message.chunks(MAX_NOISE_MESSAGE_SIZE - NOISE_TAG_SIZE) {
+            let n = self.noise.write_message(chunk, &mut buf)?;
+            write_frame(&mut self.stream, &buf[..n]).await?;
+        }
+        Ok(())
+    }
+
+    /// Receives and decrypts a message, returning `None` if the connection is closed
+    pub async fn read_message(&mut self) -> Result<Option<Vec<u8>>> {
+        let mut buf = vec![0; MAX_NOISE_MESSAGE_SIZE];
+
+        let Some(frame) = read_frame(&mut self.stream).await? else {
+            return Ok(None);
+        };
+        let n = self
+            .noise
+            .read_message(&frame, &mut buf)
+            .context("decrypt a message")?;
+        let len = <[u8; 4]>::try_from(&buf[..n]).context("invalid message length")?;
+        let len = u32::from_be_bytes(len) as usize;
+        if len > self.max_message_size {
+            anyhow::bail!("message is too large ({len} bytes)");
+        }
+
+        let mut message = Vec::with_capacity(len);
+        while message.len() < len {
+            let frame = read_frame(&mut self.stream)
+                .await?
+                .context("the connection was closed while receiving a message")?;
+            let n = self
+                .noise
+                .read_message(&frame, &mut buf)
+                .context("decrypt a message")?;
+            message.extend_from_slice(&buf[..n]);
+        }
+        Ok(Some(message))
+    }
+}
+
+/// Writes a Noise message as a frame of its length followed by the message
+async fn write_frame(stream: &mut (impl AsyncWrite + Unpin), message: &[u8]) -> Result<()> {
+    let len = u16::try_from(message.len()).context("Noise message is too large")?;
+    stream.write_all(&len.to_be_bytes()).await?;
+    stream.write_all(message).await?;
+    Ok(())
+}
+
+/// Reads a Noise message's frame, returning `None` if the connection is closed
+async fn read_frame(stream: &mut (impl AsyncRead + Unpin)) -> Result<Option<Vec<u8>>> {
+    let mut len = [0; 2];
+    match stream.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+
+    let mut message = vec![0; usize::from(u16::from_be_bytes(len))];
+    stream.read_exact(&mut message).await?;
+    Ok(Some(message))
+}
@@ -1,14 +1,31 @@
-use crate::{auth::AuthConfig, client};
+use crate::{
+    auth::AuthConfig,
+    client,
+    state::{load_listening_history, FileCacheKey, SearchResults},
+    utils::SpotifyLink,
+};
 
 use super::{
-    config, init_cli, start_socket, Command, ContextType, GetRequest, IdOrName, ItemType, Key,
-    PlaylistCommand, PlaylistId, Request, Response, MAX_REQUEST_SIZE,
+    config, init_cli, start_socket, CliError, Command, ContextType, ErrorKind, GetRequest,
+    HistoryFormat, IdOrName, ItemType, Key, PlaylistCommand, PlaylistId, QueueCommand, Request,
+    Response, RunningApplication, SearchItemType, Serialize, TrackId, MAX_REQUEST_SIZE,
 };
 use anyhow::{Context, Result};
 use clap::{ArgMatches, Id};
 use clap_complete::{generate, Shell};
+use rspotify::model::CurrentPlaybackContext;
+use rspotify::prelude::Id as _;
 use std::net::UdpSocket;
 
+/// Default format string of the playback printed by `playback --follow`
+const DEFAULT_FOLLOW_FORMAT: &str = "{status} {track} • {artists}";
+
+/// Output of the playback data printed by a CLI command
+enum PlaybackOutput {
+    Json,
+    Format(config::FormatTemplate),
+}
+
 fn receive_response(socket: &UdpSocket) -> Result<Response> {
     // read response from the server's socket, which can be split into
     // smaller chunks of data
@@ -72,7 +89,9 @@ fn handle_get_subcommand(args: &ArgMatches) -> Request {
 }
 
 fn handle_playback_subcommand(args: &ArgMatches) -> Result<Request> {
-    let (cmd, args) = args.subcommand().expect("playback subcommand is required");
+    let Some((cmd, args)) = args.subcommand() else {
+        anyhow::bail!("a playback subcommand or the `--follow` option is required");
+    };
     let command = match cmd {
         "start" => match args.subcommand() {
             Some(("track", args)) => Command::StartTrack(get_id_or_name(args)),
@@ -117,20 +136,16 @@ fn handle_playback_subcommand(args: &ArgMatches) -> Result<Request> {
         "shuffle" => Command::Shuffle,
         "repeat" => Command::Repeat,
         "volume" => {
-            let percent = args
-                .get_one::<i8>("percent")
+            let value = args
+                .get_one::<String>("percent")
                 .expect("percent arg is required");
-            let offset = args.get_flag("offset");
-            Command::Volume {
-                percent: *percent,
-                is_offset: offset,
-            }
+            parse_volume_command(value, args.get_flag("offset"))?
         }
         "seek" => {
-            let position_offset_ms = args
-                .get_one::<i64>("position_offset_ms")
-                .expect("position_offset_ms is required");
-            Command::Seek(*position_offset_ms)
+            let position_offset = args
+                .get_one::<String>("position_offset")
+                .expect("position_offset is required");
+            Command::Seek(parse_seek_offset(position_offset)?)
         }
         _ => unreachable!(),
     };
@@ -138,6 +153,48 @@ fn handle_playback_subcommand(args: &ArgMatches) -> Result<Request> {
     Ok(Request::Playback(command))
 }
 
+/// Parses a Spotify URI (e.g. `spotify:album:{id}`) or an `open.spotify.com` URL
+/// (e.g. `https://open.spotify.com/album/{id}?si=...`) into the referred item
+fn parse_spotify_uri_or_url(value: &str) -> Result<SpotifyLink> {
+    crate::utils::parse_spotify_link(value)?
+        .with_context(|| format!("invalid Spotify URI or URL '{value}'"))
+}
+
+/// Parses a volume command from a string, e.g. `50`, `+5`, `-5` or `mute`
+pub(super) fn parse_volume_command(value: &str, is_offset: bool) -> Result<Command> {
+    if value == "mute" {
+        return Ok(Command::ToggleMute);
+    }
+
+    let percent = value
+        .parse::<i8>()
+        .ok()
+        .filter(|p| (-100..=100).contains(p))
+        .with_context(|| format!("invalid volume percentage '{value}'"))?;
+    // a signed value is an offset to the current volume
+    let is_offset = is_offset || value.starts_with(['+', '-']);
+    Ok(Command::Volume { percent, is_offset })
+}
+
+/// Parses a seek offset in milliseconds from a string, e.g. `5000`, `+15s`, `-30s` or `1m`
+pub(super) fn parse_seek_offset(value: &str) -> Result<i64> {
+    let (number, unit_ms) = if let Some(number) = value.strip_suffix("ms") {
+        (number, 1)
+    } else if let Some(number) = value.strip_suffix('s') {
+        (number, 1000)
+    } else if let Some(number) = value.strip_suffix('m') {
+        (number, 60_000)
+    } else {
+        (value, 1)
+    };
+
+    number
+        .parse::<i64>()
+        .ok()
+        .and_then(|number| number.checked_mul(unit_ms))
+        .with_context(|| format!("invalid seek offset '{value}'"))
+}
+
 /// Tries to connect to a running client, if exists, by sending a connection request
 /// to the client via a UDP socket.
 /// If no running client found, create a new client running in a separate thread to
@@ -159,7 +216,7 @@ fn try_connect_to_client(socket: &UdpSocket, configs: &config::Configs) -> Resul
             // create a Spotify API client
             let client = client::Client::new(auth_config);
             rt.block_on(client.new_session(None, false))
-                .context("new session")?;
+                .map_err(|err| CliError::new(ErrorKind::Auth, format!("new session: {err:#}")))?;
 
             // create a client socket for handling CLI commands
             let client_socket = rt.block_on(tokio::net::UdpSocket::bind(("127.0.0.1", port)))?;
@@ -174,15 +231,164 @@ fn try_connect_to_client(socket: &UdpSocket, configs: &config::Configs) -> Resul
     Ok(())
 }
 
-pub fn handle_cli_subcommand(cmd: &str, args: &ArgMatches) -> Result<()> {
-    let configs = config::get_config();
+fn send_request_via_udp_socket(request_buf: &[u8], configs: &config::Configs) -> Result<Response> {
+    let socket = UdpSocket::bind("127.0.0.1:0")?;
+    try_connect_to_client(&socket, configs).context("try to connect to a client")?;
+
+    socket.send(request_buf)?;
+    receive_response(&socket)
+}
+
+/// Handles a CLI command, exiting the process with the command's exit code
+pub fn handle_cli_subcommand(cmd: &str, args: &ArgMatches) -> ! {
+    let json_errors = args.get_flag("json-errors");
+    match run_cli_subcommand(cmd, args) {
+        Ok(()) => std::process::exit(0),
+        Err(err) => {
+            let kind = ErrorKind::from_error(&err);
+            let message = format!("{err:#}");
+            if json_errors {
+                let error = serde_json::json!({
+                    "kind": kind,
+                    "exit_code": kind.exit_code(),
+                    "message": message,
+                });
+                eprintln!("{error}");
+            } else {
+                eprintln!("{message}");
+            }
+            std::process::exit(kind.exit_code());
+        }
+    }
+}
+
+/// Constructs a socket request based on a CLI command and its arguments
+fn build_request(cmd: &str, args: &ArgMatches) -> Result<Request> {
+    let request = match cmd {
+        "get" => handle_get_subcommand(args),
+        "playback" => handle_playback_subcommand(args)?,
+        "playlist" => handle_playlist_subcommand(args)?,
+        "queue" => handle_queue_subcommand(args),
+        "connect" => {
+            let device = match args.get_one::<String>("device") {
+                Some(name) => IdOrName::Name(name.to_owned()),
+                None => get_id_or_name(args),
+            };
+            Request::Connect {
+                device,
+                keep_playing: args.get_flag("keep-playing"),
+            }
+        }
+        "transfer" => {
+            let device = match args.get_one::<String>("device") {
+                Some(name) => IdOrName::Name(name.to_owned()),
+                None => get_id_or_name(args),
+            };
+            Request::Transfer { device }
+        }
+        "play" => {
+            let uri = args.get_one::<String>("uri").expect("uri is required");
+            let link = parse_spotify_uri_or_url(uri)?;
+            let shuffle = args.get_flag("shuffle");
+            let (context_type, id) = match link {
+                SpotifyLink::Track(id) => {
+                    if shuffle {
+                        anyhow::bail!("`--shuffle` is not supported for a track");
+                    }
+                    (None, id.id().to_string())
+                }
+                SpotifyLink::Episode(id) => {
+                    if shuffle {
+                        anyhow::bail!("`--shuffle` is not supported for an episode");
+                    }
+                    return Ok(Request::Playback(Command::StartEpisode(
+                        id.id().to_string(),
+                    )));
+                }
+                SpotifyLink::Playlist(id) => (Some(ContextType::Playlist), id.id().to_string()),
+                SpotifyLink::Album(id) => (Some(ContextType::Album), id.id().to_string()),
+                SpotifyLink::Artist(id) => (Some(ContextType::Artist), id.id().to_string()),
+                SpotifyLink::Show(_) => {
+                    anyhow::bail!("playing a show is not supported, use an episode's link instead")
+                }
+            };
+            let id_or_name = IdOrName::Id(id);
+            Request::Playback(match context_type {
+                Some(context_type) => Command::StartContext {
+                    context_type,
+                    id_or_name,
+                    shuffle,
+                },
+                None => Command::StartTrack(id_or_name),
+            })
+        }
+        "radio" => {
+            let uri = args.get_one::<String>("uri").expect("uri is required");
+            let (item_type, id) = match parse_spotify_uri_or_url(uri)? {
+                SpotifyLink::Track(id) => (ItemType::Track, id.id().to_string()),
+                SpotifyLink::Album(id) => (ItemType::Album, id.id().to_string()),
+                SpotifyLink::Artist(id) => (ItemType::Artist, id.id().to_string()),
+                SpotifyLink::Playlist(id) => (ItemType::Playlist, id.id().to_string()),
+                SpotifyLink::Episode(_) | SpotifyLink::Show(_) => anyhow::bail!(
+                    "a radio can only be started from a track, album, playlist or artist"
+                ),
+            };
+            Request::Playback(Command::StartRadio(item_type, IdOrName::Id(id)))
+        }
+        "devices" => Request::Get(GetRequest::Key(Key::Devices)),
+        #[cfg(feature = "streaming")]
+        "bitrate" => Request::Bitrate(
+            args.get_one::<String>("bitrate")
+                .expect("bitrate is required")
+                .parse()?,
+        ),
+        "lyrics" => {
+            let id = match args.get_one::<String>("uri") {
+                Some(uri) => Some(TrackId::from_id_or_uri(uri)?.into_static()),
+                None => None,
+            };
+            Request::Lyrics {
+                id,
+                timestamped: args.get_flag("timestamps"),
+            }
+        }
+        "like" => {
+            let id = match args.get_one::<String>("uri") {
+                Some(uri) => Some(TrackId::from_id_or_uri(uri)?.into_static()),
+                None => None,
+            };
+            Request::Like {
+                unlike: args.get_flag("unlike"),
+                id,
+            }
+        }
+        "search" => {
+            let query = args
+                .get_one::<String>("query")
+                .expect("query is required")
+                .to_owned();
+            if args.get_flag("queue") {
+                Request::SearchAndQueue { query }
+            } else {
+                let item_type = args.get_one::<SearchItemType>("type").copied();
+                Request::Search { query, item_type }
+            }
+        }
+        _ => unreachable!(),
+    };
+    Ok(request)
+}
+
+fn run_cli_subcommand(cmd: &str, args: &ArgMatches) -> Result<()> {
+    let configs = &config::get_config();
 
     // handle commands that don't require a client separately
     match cmd {
         "authenticate" => {
             let auth_config = AuthConfig::new(configs)?;
-            crate::auth::get_creds(&auth_config, true, false)?;
-            std::process::exit(0);
+            crate::auth::get_creds(&auth_config, true, false)
+                .map_err(|err| CliError::new(ErrorKind::Auth, format!("{err:#}")))?;
+            return Ok(());
         }
         "generate" => {
             let gen = *args
@@ -191,48 +397,721 @@ pub fn handle_cli_subcommand(cmd: &str, args: &ArgMatches) -> Result<()> {
             let mut cmd = init_cli()?;
             let name = cmd.get_name().to_string();
             generate(gen, &mut cmd, name, &mut std::io::stdout());
-            std::process::exit(0);
+            return Ok(());
+        }
+        "history" => {
+            handle_history_subcommand(args, configs)?;
+            return Ok(());
+        }
+        "cache" => {
+            handle_cache_subcommand(args, configs)?;
+            return Ok(());
         }
+        "pick" => return handle_pick_command(args, configs),
+        #[cfg(feature = "scrobble")]
+        "lastfm-sync" => return handle_lastfm_sync_command(args, configs),
+        "import-csv" => return handle_import_csv_command(args, configs),
+        "batch" => return handle_batch_command(args),
         _ => {}
     }
 
-    let socket = UdpSocket::bind("127.0.0.1:0")?;
-    try_connect_to_client(&socket, configs).context("try to connect to a client")?;
+    if cmd == "playback" && args.get_flag("follow") {
+        if args.subcommand().is_some() {
+            anyhow::bail!("`--follow` can't be used with a playback subcommand");
+        }
+        let output = if args.get_flag("json") {
+            PlaybackOutput::Json
+        } else {
+            let format_str = args
+                .get_one::<String>("format")
+                .map_or(DEFAULT_FOLLOW_FORMAT, String::as_str);
+            PlaybackOutput::Format(config::FormatTemplate::new(format_str))
+        };
+        return watch_data(configs, &Request::FollowPlayback, &output);
+    }
+
+    if let ("get", Some(("key", args))) = (cmd, args.subcommand()) {
+        if args.get_flag("watch") {
+            let key = args.get_one::<Key>("key").expect("key is required").clone();
+            if !matches!(key, Key::Playback | Key::Queue | Key::UserLikedTracks) {
+                return Err(CliError::new(
+                    ErrorKind::InvalidArgument,
+                    "`--watch` is only supported for the `playback`, `queue` and `user-liked-tracks` keys",
+                )
+                .into());
+            }
+            let output = match args.get_one::<String>("format") {
+                Some(format_str) if matches!(key, Key::Playback) => {
+                    PlaybackOutput::Format(config::FormatTemplate::new(format_str))
+                }
+                Some(_) => {
+                    return Err(CliError::new(
+                        ErrorKind::InvalidArgument,
+                        "`--format` is only supported for the `playback` key",
+                    )
+                    .into())
+                }
+                None => PlaybackOutput::Json,
+            };
+            return watch_data(configs, &Request::WatchKey(key), &output);
+        }
+    }
 
     // construct a socket request based on the CLI command and its arguments
-    let request = match cmd {
-        "get" => handle_get_subcommand(args),
-        "playback" => handle_playback_subcommand(args)?,
-        "playlist" => handle_playlist_subcommand(args)?,
-        "connect" => Request::Connect(get_id_or_name(args)),
-        "like" => Request::Like {
-            unlike: args.get_flag("unlike"),
+    let request = build_request(cmd, args)
+        .map_err(|err| CliError::new(ErrorKind::InvalidArgument, format!("{err:#}")))?;
+
+    // playback output options of the `get key playback` and `playback` commands
+    let playback_output = match cmd {
+        "get" => match args.subcommand() {
+            Some(("key", args)) => match args.get_one::<String>("format") {
+                Some(format_str) => {
+                    if !matches!(args.get_one::<Key>("key"), Some(Key::Playback)) {
+                        anyhow::bail!("`--format` is only supported for the `playback` key");
+                    }
+                    Some(PlaybackOutput::Format(config::FormatTemplate::new(
+                        format_str,
+                    )))
+                }
+                None => None,
+            },
+            _ => None,
         },
-        "search" => Request::Search {
-            query: args
-                .get_one::<String>("query")
-                .expect("query is required")
-                .to_owned(),
+        "playback" => match args.get_one::<String>("format") {
+            Some(format_str) => Some(PlaybackOutput::Format(config::FormatTemplate::new(
+                format_str,
+            ))),
+            None if args.get_flag("json") => Some(PlaybackOutput::Json),
+            None => None,
         },
+        _ => None,
+    };
+
+    if cmd == "play" {
+        if let Some(device) = args.get_one::<String>("device") {
+            // connect to the device before starting the playback
+            let connect_request = Request::Connect {
+                device: IdOrName::Name(device.to_owned()),
+                keep_playing: false,
+            };
+            if let Response::Err(kind, err) = send_request(&connect_request, configs)? {
+                return Err(CliError::new(kind, String::from_utf8_lossy(&err)).into());
+            }
+        }
+    }
+
+    let mut response = send_request(&request, configs)?;
+    if cmd == "playback" && playback_output.is_some() && matches!(response, Response::Ok(_)) {
+        // retrieve the playback updated by the playback command
+        response = send_request(&Request::Get(GetRequest::Key(Key::Playback)), configs)?;
+    }
+
+    // handle a response from the client's socket
+    match response {
+        Response::Err(kind, err) => Err(CliError::new(kind, String::from_utf8_lossy(&err)).into()),
+        Response::Ok(data) => {
+            if cmd == "search" && (args.get_flag("json") || args.contains_id("type")) {
+                print_search_items(&data, args.get_flag("json"))?;
+                return Ok(());
+            }
+            if cmd == "devices" && !args.get_flag("json") {
+                let devices: Vec<rspotify::model::Device> = serde_json::from_slice(&data)?;
+                for device in devices {
+                    let active = if device.is_active { "\tactive" } else { "" };
+                    println!("{}\t{:?}{active}", device.name, device._type);
+                }
+                return Ok(());
+            }
+
+            match playback_output {
+                None => println!("{}", String::from_utf8_lossy(&data).replace("\\n", "\n")),
+                Some(PlaybackOutput::Json) => println!("{}", String::from_utf8_lossy(&data)),
+                Some(PlaybackOutput::Format(template)) => {
+                    let playback: Option<CurrentPlaybackContext> = serde_json::from_slice(&data)?;
+                    // print nothing if there is no playback
+                    if let Some(playback) = playback {
+                        println!("{}", crate::utils::format_playback(&template, &playback));
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Picks an item of the user's data with a fuzzy picker and prints its URI
+fn handle_pick_command(args: &ArgMatches, configs: &config::Configs) -> Result<()> {
+    use crate::state::{Id, Playlist, Track};
+    use crate::ui::picker::{pick, PickerItem};
+
+    let item_type = args
+        .get_one::<String>("item_type")
+        .expect("item_type is required");
+    let key = match item_type.as_str() {
+        "playlists" => Key::UserPlaylists,
+        "tracks" => Key::UserLikedTracks,
         _ => unreachable!(),
     };
 
-    // send the request to the client's socket
-    let request_buf = serde_json::to_vec(&request)?;
-    assert!(request_buf.len() <= MAX_REQUEST_SIZE);
-    socket.send(&request_buf)?;
+    let data = match send_request(&Request::Get(GetRequest::Key(key)), configs)? {
+        Response::Ok(data) => data,
+        Response::Err(kind, err) => {
+            return Err(CliError::new(kind, String::from_utf8_lossy(&err)).into())
+        }
+    };
+    let items = match item_type.as_str() {
+        "playlists" => serde_json::from_slice::<Vec<Playlist>>(&data)?
+            .into_iter()
+            .map(|p| PickerItem {
+                uri: p.id.uri(),
+                label: p.to_string(),
+            })
+            .collect::<Vec<_>>(),
+        _ => serde_json::from_slice::<Vec<Track>>(&data)?
+            .into_iter()
+            .map(|t| PickerItem {
+                uri: t.id.uri(),
+                label: t.to_string(),
+            })
+            .collect(),
+    };
 
-    // receive and handle a response from the client's socket
-    match receive_response(&socket)? {
-        Response::Err(err) => {
-            eprintln!("{}", String::from_utf8_lossy(&err));
-            std::process::exit(1);
+    let query = args.get_one::<String>("query").map_or("", String::as_str);
+    match pick(&items, query, "Pick")? {
+        Some(uri) => {
+            println!("{uri}");
+            Ok(())
         }
-        Response::Ok(data) => {
-            println!("{}", String::from_utf8_lossy(&data).replace("\\n", "\n"));
-            std::process::exit(0);
+        None => Err(CliError::new(ErrorKind::Other, "no item was picked").into()),
+    }
+}
+
+/// Sends a request to the client, returning the response's data
+fn request_data(request: &Request, configs: &config::Configs) -> Result<Vec<u8>> {
+    match send_request(request, configs)? {
+        Response::Ok(data) => Ok(data),
+        Response::Err(kind, err) => Err(CliError::new(kind, String::from_utf8_lossy(&err)).into()),
+    }
+}
+
+/// Normalizes a track's or an artist's name for comparison, ignoring the case
+/// and the suffixes like `(Remastered 2011)` or `- Live`
+fn normalize_name(name: &str) -> String {
+    let name = name.split(" (").next().unwrap_or(name);
+    let name = name.split(" - ").next().unwrap_or(name);
+    name.trim().to_lowercase()
+}
+
+/// Searches for a track by its name and artist, returning the matched track if any.
+///
+/// A match is certain if both the track's name and an artist's name match. Otherwise,
+/// the user reviews the search results with a picker if `review` is true.
+fn search_matched_track(
+    configs: &config::Configs,
+    name: &str,
+    artist: &str,
+    review: bool,
+) -> Result<Option<crate::state::Track>> {
+    use crate::state::Id;
+    use crate::ui::picker::{pick, PickerItem};
+
+    let query = format!("track:{name} artist:{artist}");
+    let results: SearchResults = serde_json::from_slice(&request_data(
+        &Request::Search {
+            query,
+            item_type: Some(SearchItemType::Tracks),
+        },
+        configs,
+    )?)?;
+    let mut tracks = results.tracks;
+
+    let certain_match = tracks.iter().position(|t| {
+        normalize_name(&t.name) == normalize_name(name)
+            && t.artists
+                .iter()
+                .any(|a| normalize_name(&a.name) == normalize_name(artist))
+    });
+    if let Some(i) = certain_match {
+        return Ok(Some(tracks.swap_remove(i)));
+    }
+    if !review || tracks.is_empty() {
+        return Ok(None);
+    }
+
+    let items = tracks
+        .iter()
+        .map(|t| PickerItem {
+            uri: t.id.uri(),
+            label: t.to_string(),
+        })
+        .collect::<Vec<_>>();
+    let prompt = format!("{name} • {artist}");
+    let picked = pick(&items, "", &prompt)?;
+    Ok(picked.and_then(|uri| tracks.into_iter().find(|t| t.id.uri() == uri)))
+}
+
+/// Saves the tracks loved on Last.fm to the user's liked tracks
+#[cfg(feature = "scrobble")]
+fn handle_lastfm_sync_command(args: &ArgMatches, configs: &config::Configs) -> Result<()> {
+    use crate::state::{Id, Track};
+
+    let lastfm = configs.app_config.lastfm.as_ref().ok_or_else(|| {
+        CliError::new(
+            ErrorKind::InvalidArgument,
+            "`lastfm-sync` requires the `[lastfm]` configurations",
+        )
+    })?;
+    let loved_tracks = tokio::runtime::Runtime::new()?
+        .block_on(crate::scrobbler::get_loved_tracks(lastfm))
+        .map_err(|err| CliError::new(ErrorKind::Api, format!("{err:#}")))?;
+
+    let liked_tracks = serde_json::from_slice::<Vec<Track>>(&request_data(
+        &Request::Get(GetRequest::Key(Key::UserLikedTracks)),
+        configs,
+    )?)?;
+
+    let dry_run = args.get_flag("dry-run");
+    let review = !args.get_flag("skip-uncertain");
+    let (mut saved, mut skipped) = (0, 0);
+    for loved in loved_tracks {
+        let Some(track) = search_matched_track(configs, &loved.track, &loved.artist, review)?
+        else {
+            eprintln!("Skipped: {} • {}", loved.track, loved.artist);
+            skipped += 1;
+            continue;
+        };
+        if liked_tracks.iter().any(|t| t.id == track.id) {
+            continue;
+        }
+        println!("{}\t{track}", track.id.uri());
+        if !dry_run {
+            request_data(
+                &Request::Like {
+                    unlike: false,
+                    id: Some(track.id.clone()),
+                },
+                configs,
+            )?;
+        }
+        saved += 1;
+    }
+
+    let action = if dry_run { "Matched" } else { "Saved" };
+    eprintln!("{action} {saved} tracks, skipped {skipped} tracks");
+    Ok(())
+}
+
+/// An entry of a CSV export from another music service
+struct CsvEntry {
+    name: String,
+    artist: String,
+    playlist: Option<String>,
+}
+
+/// Parses the records of a CSV text (RFC 4180), supporting quoted fields with escaped quotes
+/// and line breaks
+fn parse_csv_records(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let (mut record, mut field) = (Vec::new(), String::new());
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            // a quote only starts a quoted field at the beginning of the field
+            ('"', false) if field.is_empty() => in_quotes = true,
+            ('"', true) => in_quotes = false,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            ('\r', false) => {}
+            _ => field.push(c),
         }
     }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.retain(|r| r.iter().any(|f| !f.trim().is_empty()));
+    records
+}
+
+/// Parses a CSV export of Apple Music, `YouTube` Music, `TuneMyMusic` or Exportify,
+/// detecting the columns by the header's names
+fn parse_csv_export(text: &str) -> Result<Vec<CsvEntry>> {
+    let mut records = parse_csv_records(text.trim_start_matches('\u{feff}')).into_iter();
+    let header = records
+        .next()
+        .context("empty CSV file")?
+        .into_iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect::<Vec<_>>();
+    let column = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|n| header.iter().position(|h| h == n))
+    };
+
+    let name_col = column(&["track name", "song title", "title", "name", "song", "track"])
+        .context("missing a track name column, e.g. `Track name` or `Title`")?;
+    let artist_col = column(&[
+        "artist name(s)",
+        "artist name",
+        "artist name 1",
+        "artist",
+        "artists",
+        "album artist",
+    ])
+    .context("missing an artist column, e.g. `Artist name` or `Artist`")?;
+    let playlist_col = column(&["playlist name", "playlist"]);
+
+    Ok(records
+        .filter_map(|record| {
+            let field = |i: usize| record.get(i).map(|f| f.trim().to_string());
+            let artist = field(artist_col)?;
+            Some(CsvEntry {
+                name: field(name_col)?,
+                // only the first one of the artists, which are separated by `,` or `;`
+                artist: artist
+                    .split([',', ';'])
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string(),
+                playlist: playlist_col.and_then(field).filter(|p| !p.is_empty()),
+            })
+        })
+        .filter(|e| !e.name.is_empty())
+        .collect())
+}
+
+/// Imports the tracks of a CSV export from another music service into new playlists
+/// or the user's liked tracks, printing the unmatched entries
+fn handle_import_csv_command(args: &ArgMatches, configs: &config::Configs) -> Result<()> {
+    let file = args.get_one::<String>("file").expect("file is required");
+    let text = std::fs::read_to_string(file).with_context(|| format!("read {file}"))?;
+    let entries = parse_csv_export(&text)
+        .map_err(|err| CliError::new(ErrorKind::InvalidArgument, format!("{err:#}")))?;
+
+    // the imported entries' destinations: the liked tracks (`None`) or playlists by name
+    let default_playlist = args
+        .get_one::<String>("playlist")
+        .cloned()
+        .unwrap_or_else(|| {
+            std::path::Path::new(file)
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        });
+    let liked = args.get_flag("liked");
+    let mut destinations: Vec<(Option<String>, Vec<&CsvEntry>)> = Vec::new();
+    for entry in &entries {
+        let destination = if liked {
+            None
+        } else if args.contains_id("playlist") {
+            Some(default_playlist.clone())
+        } else {
+            Some(
+                entry
+                    .playlist
+                    .clone()
+                    .unwrap_or_else(|| default_playlist.clone()),
+            )
+        };
+        match destinations.iter_mut().find(|(d, _)| *d == destination) {
+            Some((_, entries)) => entries.push(entry),
+            None => destinations.push((destination, vec![entry])),
+        }
+    }
+
+    let dry_run = args.get_flag("dry-run");
+    let review = args.get_flag("review");
+    let (mut imported, mut unmatched) = (0, Vec::new());
+    for (destination, entries) in destinations {
+        let mut tracks = Vec::new();
+        for entry in entries {
+            match search_matched_track(configs, &entry.name, &entry.artist, review)? {
+                Some(track)
+                    if !tracks
+                        .iter()
+                        .any(|t: &crate::state::Track| t.id == track.id) =>
+                {
+                    tracks.push(track);
+                }
+                Some(_) => {}
+                None => unmatched.push(entry),
+            }
+        }
+
+        let target = destination.as_deref().unwrap_or("liked tracks");
+        eprintln!("Importing {} tracks into {target}...", tracks.len());
+        imported += tracks.len();
+        if dry_run || tracks.is_empty() {
+            continue;
+        }
+
+        match destination {
+            None => {
+                for track in tracks {
+                    request_data(
+                        &Request::Like {
+                            unlike: false,
+                            id: Some(track.id),
+                        },
+                        configs,
+                    )?;
+                }
+            }
+            Some(name) => {
+                let playlist: crate::state::Playlist = serde_json::from_slice(&request_data(
+                    &Request::CreatePlaylist {
+                        name,
+                        public: args.get_flag("public"),
+                    },
+                    configs,
+                )?)?;
+                let playlist_id = playlist.id;
+                for track in tracks {
+                    request_data(
+                        &Request::Playlist(PlaylistCommand::AddTrack {
+                            playlist_id: playlist_id.clone(),
+                            track_id: track.id,
+                        }),
+                        configs,
+                    )?;
+                }
+            }
+        }
+    }
+
+    for entry in &unmatched {
+        println!("{}\t{}", entry.name, entry.artist);
+    }
+    let action = if dry_run { "Matched" } else { "Imported" };
+    eprintln!(
+        "{action} {imported} tracks, {} entries were not matched",
+        unmatched.len()
+    );
+    Ok(())
+}
+
+/// Runs CLI commands read from a file or stdin, one command per line
+fn handle_batch_command(args: &ArgMatches) -> Result<()> {
+    use std::io::BufRead;
+
+    let file = args.get_one::<String>("file").expect("file is required");
+    let reader: Box<dyn BufRead> = if file == "-" {
+        Box::new(std::io::stdin().lock())
+    } else {
+        let file = std::fs::File::open(file).with_context(|| format!("open {file}"))?;
+        Box::new(std::io::BufReader::new(file))
+    };
+
+    let cli = init_cli()?;
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line_no = i + 1;
+
+        let words = split_command_line(line).map_err(|err| {
+            CliError::new(
+                ErrorKind::InvalidArgument,
+                format!("line {line_no}: {err:#}"),
+            )
+        })?;
+        let matches = cli
+            .clone()
+            .try_get_matches_from(std::iter::once(cli.get_name().to_string()).chain(words))
+            .map_err(|err| {
+                CliError::new(
+                    ErrorKind::InvalidArgument,
+                    format!("line {line_no}: {}", err.render()),
+                )
+            })?;
+
+        match matches.subcommand() {
+            Some((cmd @ ("batch" | "daemon" | "stream"), _)) => {
+                anyhow::bail!(CliError::new(
+                    ErrorKind::InvalidArgument,
+                    format!("line {line_no}: `{cmd}` can't be run in a batch")
+                ));
+            }
+            Some((cmd, args)) => run_cli_subcommand(cmd, args)
+                .with_context(|| format!("line {line_no}: `{line}`"))?,
+            None => anyhow::bail!(CliError::new(
+                ErrorKind::InvalidArgument,
+                format!("line {line_no}: missing command")
+            )),
+        }
+    }
+
+    Ok(())
+}
+
+/// Splits a command line into words like a POSIX shell, supporting single quotes,
+/// double quotes and backslash escapes
+fn split_command_line(line: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            // inside double quotes, a backslash only escapes the characters special to the shell
+            (Some('"'), '\\') if !matches!(chars.peek(), Some('"' | '\\' | '$' | '`')) => {
+                word.get_or_insert_with(String::new).push(c);
+            }
+            (Some('"') | None, '\\') => {
+                let c = chars.next().context("trailing backslash")?;
+                word.get_or_insert_with(String::new).push(c);
+            }
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if quote.is_some() {
+        anyhow::bail!("unterminated quote");
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// An item of the search results printed by the `search` command
+#[derive(Serialize)]
+struct SearchItem {
+    #[serde(rename = "type")]
+    typ: &'static str,
+    id: String,
+    uri: String,
+    name: String,
+    description: String,
+}
+
+impl SearchItem {
+    fn new(
+        typ: &'static str,
+        id: &impl rspotify::model::Id,
+        name: &str,
+        description: String,
+    ) -> Self {
+        Self {
+            typ,
+            id: id.id().to_string(),
+            uri: id.uri(),
+            name: name.to_string(),
+            description,
+        }
+    }
+}
+
+/// Prints the search results either as a JSON array of items
+/// or as tab-separated `uri`, `name` and `description` lines, one item per line
+fn print_search_items(data: &[u8], json: bool) -> Result<()> {
+    let results: SearchResults = serde_json::from_slice(data)?;
+
+    let mut items = Vec::new();
+    items.extend(results.tracks.iter().map(|t| {
+        let description = format!("{} • {}", t.artists_info(), t.album_info());
+        SearchItem::new("track", &t.id, &t.name, description)
+    }));
+    items.extend(results.albums.iter().map(|a| {
+        let description = crate::utils::map_join(&a.artists, |a| &a.name, ", ");
+        SearchItem::new("album", &a.id, &a.name, description)
+    }));
+    items.extend(
+        results
+            .artists
+            .iter()
+            .map(|a| SearchItem::new("artist", &a.id, &a.name, String::new())),
+    );
+    items.extend(
+        results
+            .playlists
+            .iter()
+            .map(|p| SearchItem::new("playlist", &p.id, &p.name, p.owner.0.clone())),
+    );
+    items.extend(
+        results
+            .shows
+            .iter()
+            .map(|s| SearchItem::new("show", &s.id, &s.name, String::new())),
+    );
+    items.extend(results.episodes.iter().map(|e| {
+        let description = e.show.as_ref().map(|s| s.name.clone()).unwrap_or_default();
+        SearchItem::new("episode", &e.id, &e.name, description)
+    }));
+    // audiobook IDs are not `rspotify` IDs
+    items.extend(results.audiobooks.iter().map(|a| SearchItem {
+        typ: "audiobook",
+        id: a.id.clone(),
+        uri: format!("spotify:audiobook:{}", a.id),
+        name: a.name.clone(),
+        description: a.authors_info(),
+    }));
+
+    if json {
+        println!("{}", serde_json::to_string(&items)?);
+    } else {
+        for item in items {
+            println!("{}\t{}\t{}", item.uri, item.name, item.description);
+        }
+    }
+    Ok(())
+}
+
+/// Prints the data streamed by a running client upon every data change, e.g. the playback
+/// for `playback --follow` or a key's data for `get key --watch`
+fn watch_data(configs: &config::Configs, request: &Request, output: &PlaybackOutput) -> Result<()> {
+    let application = RunningApplication::find(configs)?.context(
+        "`--follow` and `--watch` require a running application, e.g. `spotify_player daemon`, on Unix systems or a remote application",
+    )?;
+
+    let print_data = |data: &str| -> Result<()> {
+        match output {
+            PlaybackOutput::Json => println!("{data}"),
+            PlaybackOutput::Format(template) => {
+                let playback: Option<CurrentPlaybackContext> = serde_json::from_str(data)?;
+                // print an empty line if there is no playback to clear the previous output
+                let text = playback
+                    .map(|p| crate::utils::format_playback(template, &p))
+                    .unwrap_or_default();
+                println!("{text}");
+            }
+        }
+        Ok(())
+    };
+    tokio::runtime::Runtime::new()?.block_on(application.watch(request, print_data))
+}
+
+/// Sends a request to a remote application or a running client,
+/// or to a new client if no running client found
+fn send_request(request: &Request, configs: &config::Configs) -> Result<Response> {
+    let request_buf = serde_json::to_vec(request)?;
+    assert!(request_buf.len() <= MAX_REQUEST_SIZE);
+
+    // prefer a remote application or the Unix socket of a running client,
+    // e.g. `spotify_player daemon`, if exists
+    if let Some(application) = RunningApplication::find(configs)? {
+        return tokio::runtime::Runtime::new()?.block_on(application.send_request(request));
+    }
+
+    send_request_via_udp_socket(&request_buf, configs)
 }
 
 fn handle_playlist_subcommand(args: &ArgMatches) -> Result<Request> {
@@ -314,8 +1193,328 @@ fn handle_playlist_subcommand(args: &ArgMatches) -> Result<Request> {
 
             PlaylistCommand::Sync { id: pid, delete }
         }
+        "add-track" | "remove-track" => {
+            let playlist_id = args
+                .get_one::<String>("playlist_id")
+                .expect("playlist_id arg is required");
+            let track_id = args
+                .get_one::<String>("track_id")
+                .expect("track_id arg is required");
+
+            let playlist_id = PlaylistId::from_id_or_uri(playlist_id)?.into_static();
+            let track_id = TrackId::from_id_or_uri(track_id)?.into_static();
+
+            if cmd == "add-track" {
+                PlaylistCommand::AddTrack {
+                    playlist_id,
+                    track_id,
+                }
+            } else {
+                PlaylistCommand::RemoveTrack {
+                    playlist_id,
+                    track_id,
+                }
+            }
+        }
         _ => unreachable!(),
     };
 
     Ok(Request::Playlist(command))
 }
+
+fn handle_queue_subcommand(args: &ArgMatches) -> Request {
+    let (cmd, args) = args.subcommand().expect("queue subcommand is required");
+    let command = match cmd {
+        "list" => QueueCommand::List,
+        "add" => {
+            let uri = args
+                .get_one::<String>("uri")
+                .expect("uri arg is required")
+                .to_owned();
+            QueueCommand::Add { uri }
+        }
+        "clear" => QueueCommand::Clear,
+        _ => unreachable!(),
+    };
+
+    Request::Queue(command)
+}
+
+fn handle_history_subcommand(args: &ArgMatches, configs: &config::Configs) -> Result<()> {
+    let (cmd, args) = args.subcommand().expect("history subcommand is required");
+    match cmd {
+        "export" => {
+            let since = args
+                .get_one::<String>("since")
+                .map(String::as_str)
+                .map(parse_date)
+                .transpose()?;
+            let format = *args
+                .get_one::<HistoryFormat>("format")
+                .expect("format should have a default value");
+
+            let entries = load_listening_history(&configs.cache_folder)?
+                .into_iter()
+                .filter(|e| match since {
+                    Some(since) => chrono::DateTime::parse_from_rfc3339(&e.played_at)
+                        .is_ok_and(|played_at| played_at >= since),
+                    None => true,
+                })
+                .collect::<Vec<_>>();
+
+            match format {
+                HistoryFormat::Json => println!("{}", serde_json::to_string(&entries)?),
+                HistoryFormat::Csv => {
+                    println!("played_at,uri,name,artists,album,duration_ms");
+                    for e in entries {
+                        println!(
+                            "{},{},{},{},{},{}",
+                            e.played_at,
+                            csv_field(&e.uri),
+                            csv_field(&e.name),
+                            csv_field(&e.artists),
+                            csv_field(&e.album),
+                            e.duration_ms
+                        );
+                    }
+                }
+            }
+        }
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
+
+/// A cache stored inside the application's cache folder
+#[derive(Clone, Copy)]
+enum CacheKind {
+    Images,
+    Audio,
+    Api,
+}
+
+impl CacheKind {
+    const ALL: [CacheKind; 3] = [CacheKind::Images, CacheKind::Audio, CacheKind::Api];
+
+    fn name(self) -> &'static str {
+        match self {
+            CacheKind::Images => "images",
+            CacheKind::Audio => "audio",
+            CacheKind::Api => "api",
+        }
+    }
+
+    /// Get the paths of the cache's files (including nested folders' files) and their sizes
+    fn files(self, configs: &config::Configs) -> Result<Vec<(std::path::PathBuf, u64)>> {
+        fn walk(dir: &std::path::Path, files: &mut Vec<(std::path::PathBuf, u64)>) -> Result<()> {
+            if !dir.exists() {
+                return Ok(());
+            }
+            for entry in std::fs::read_dir(dir)? {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+                if metadata.is_dir() {
+                    walk(&entry.path(), files)?;
+                } else {
+                    files.push((entry.path(), metadata.len()));
+                }
+            }
+            Ok(())
+        }
+
+        let mut files = Vec::new();
+        match self {
+            CacheKind::Images => walk(&configs.cache_folder.join("image"), &mut files)?,
+            CacheKind::Audio => {
+                // the audio cache has no files if it's disabled
+                if let Some(folder) = configs.audio_cache_folder() {
+                    walk(&folder, &mut files)?;
+                }
+            }
+            CacheKind::Api => {
+                for key in FileCacheKey::API_DATA {
+                    let path = key.file_path(&configs.cache_folder);
+                    if let Ok(metadata) = std::fs::metadata(&path) {
+                        files.push((path, metadata.len()));
+                    }
+                }
+            }
+        }
+        Ok(files)
+    }
+}
+
+fn handle_cache_subcommand(args: &ArgMatches, configs: &config::Configs) -> Result<()> {
+    let (cmd, args) = args.subcommand().expect("cache subcommand is required");
+
+    let mut kinds = CacheKind::ALL
+        .into_iter()
+        .filter(|kind| args.get_flag(kind.name()))
+        .collect::<Vec<_>>();
+    if kinds.is_empty() {
+        kinds = CacheKind::ALL.to_vec();
+    }
+
+    for kind in kinds {
+        let files = kind.files(configs)?;
+        let size = files.iter().map(|(_, size)| size).sum::<u64>();
+        match cmd {
+            "stats" => println!(
+                "{}\t{} files\t{}",
+                kind.name(),
+                files.len(),
+                format_size(size)
+            ),
+            "clear" => {
+                for (path, _) in &files {
+                    std::fs::remove_file(path)
+                        .with_context(|| format!("remove {}", path.display()))?;
+                }
+                println!(
+                    "Cleared the {} cache ({} files, {})",
+                    kind.name(),
+                    files.len(),
+                    format_size(size)
+                );
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    Ok(())
+}
+
+/// Formats a size in bytes to a human-readable string, e.g. `1.5 MiB`
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if size < 1024 {
+        return format!("{size} B");
+    }
+    let mut unit = 1024;
+    for name in UNITS {
+        if size < unit * 1024 || name == "TiB" {
+            return format!("{}.{} {name}", size / unit, (size % unit) * 10 / unit);
+        }
+        unit *= 1024;
+    }
+    unreachable!()
+}
+
+/// Parses a date in RFC 3339 or `YYYY-MM-DD` format, the latter is at midnight in the local timezone
+fn parse_date(s: &str) -> Result<chrono::DateTime<chrono::FixedOffset>> {
+    if let Ok(date) = chrono::DateTime::parse_from_rfc3339(s) {
+        return Ok(date);
+    }
+
+    let date = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .with_context(|| format!("invalid date '{s}', expected YYYY-MM-DD or RFC 3339 format"))?
+        .and_time(chrono::NaiveTime::MIN)
+        .and_local_timezone(chrono::Local)
+        .earliest()
+        .context("invalid date in the local timezone")?;
+    Ok(date.fixed_offset())
+}
+
+/// Quotes a CSV field, escaping the quotes inside the field
+fn csv_field(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_command_line_words() {
+        assert_eq!(
+            split_command_line("  playback   play-pause ").unwrap(),
+            ["playback", "play-pause"]
+        );
+        assert!(split_command_line("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn split_command_line_quotes() {
+        assert_eq!(
+            split_command_line(r#"search "daft punk" 'one more time'"#).unwrap(),
+            ["search", "daft punk", "one more time"]
+        );
+        assert_eq!(
+            split_command_line(r#"say "it's" 'a "quote"' """#).unwrap(),
+            ["say", "it's", r#"a "quote""#, ""]
+        );
+        assert_eq!(split_command_line("a'b'\"c\"d").unwrap(), ["abcd"]);
+    }
+
+    #[test]
+    fn split_command_line_escapes() {
+        assert_eq!(
+            split_command_line(r"search daft\ punk \'").unwrap(),
+            ["search", "daft punk", "'"]
+        );
+        assert_eq!(
+            split_command_line(r#""a\"b\\c\d" 'e\f'"#).unwrap(),
+            [r#"a"b\c\d"#, r"e\f"]
+        );
+    }
+
+    #[test]
+    fn split_command_line_errors() {
+        assert!(split_command_line(r#"search "daft punk"#).is_err());
+        assert!(split_command_line("search 'daft punk").is_err());
+        assert!(split_command_line(r"search daft\").is_err());
+    }
+
+    #[test]
+    fn parse_csv_records_fields() {
+        assert_eq!(
+            parse_csv_records("Title,Artist\nOne More Time,Daft Punk\n"),
+            [["Title", "Artist"], ["One More Time", "Daft Punk"]]
+        );
+        assert_eq!(
+            parse_csv_records("Title,Artist,Album\nHello,,\n"),
+            [["Title", "Artist", "Album"], ["Hello", "", ""]]
+        );
+    }
+
+    #[test]
+    fn parse_csv_records_quotes() {
+        assert_eq!(
+            parse_csv_records(r#""Hello, Goodbye","The ""Beatles""","""#),
+            [["Hello, Goodbye", r#"The "Beatles""#, ""]]
+        );
+        // a quote inside an unquoted field is kept as-is
+        assert_eq!(
+            parse_csv_records("12\" Remix,Artist"),
+            [["12\" Remix", "Artist"]]
+        );
+    }
+
+    #[test]
+    fn parse_csv_records_line_breaks() {
+        assert_eq!(
+            parse_csv_records("Title,Artist\r\nA,B\r\n\r\nC,D"),
+            [["Title", "Artist"], ["A", "B"], ["C", "D"]]
+        );
+        assert_eq!(
+            parse_csv_records("\"Line 1\nLine 2\",Artist\r\nA,B"),
+            [["Line 1\nLine 2", "Artist"], ["A", "B"]]
+        );
+    }
+
+    #[test]
+    fn parse_csv_export_columns() {
+        let entries = parse_csv_export(
+            "\u{feff}Track Name,Artist Name(s),Playlist name\nAround the World,\"Daft Punk, Someone\",Mix\n",
+        )
+        .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name, "Around the World");
+        assert_eq!(entries[0].artist, "Daft Punk");
+        assert_eq!(entries[0].playlist.as_deref(), Some("Mix"));
+
+        assert!(parse_csv_export("Name,Album\nA,B\n").is_err());
+    }
+}
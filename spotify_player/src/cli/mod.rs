@@ -1,15 +1,41 @@
 mod client;
 mod commands;
+mod connection;
 mod handlers;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+mod web_api;
+#[cfg(feature = "websocket")]
+mod websocket;
 
 use crate::config;
 use rspotify::model::{AlbumId, ArtistId, Id, PlaylistId, TrackId};
 use serde::{Deserialize, Serialize};
 
 const MAX_REQUEST_SIZE: usize = 4096;
+/// The maximum size of a request sent via a stream socket, e.g. a Web API request
+/// of an attached UI adding tracks to a playlist
+const MAX_STREAM_REQUEST_SIZE: usize = 256 * 1024;
+
+/// Checks whether a secret sent by a client, e.g. the web API's token, matches the expected secret,
+/// in constant time to not leak the expected secret via timing
+fn is_secret_matched(secret: &str, expected: &str) -> bool {
+    use subtle::ConstantTimeEq;
+
+    secret.as_bytes().ct_eq(expected.as_bytes()).into()
+}
 
 pub use client::start_socket;
+pub use client::start_tcp_socket;
+#[cfg(unix)]
+pub use client::{bind_unix_socket, remove_unix_socket_file, start_unix_socket};
+pub use connection::RunningApplication;
 pub use handlers::handle_cli_subcommand;
+#[cfg(feature = "mqtt")]
+pub use mqtt::start_mqtt_client;
+pub use web_api::WebApi;
+#[cfg(feature = "websocket")]
+pub use websocket::start_websocket_server;
 
 #[derive(Debug, Serialize, Deserialize, clap::ValueEnum, Clone)]
 pub enum Key {
@@ -38,6 +64,22 @@ pub enum ItemType {
     Track,
 }
 
+#[derive(Debug, Serialize, Deserialize, clap::ValueEnum, Clone, Copy)]
+pub enum SearchItemType {
+    Track,
+    Album,
+    Artist,
+    Playlist,
+    Show,
+    Episode,
+}
+
+#[derive(Debug, clap::ValueEnum, Clone, Copy)]
+pub enum HistoryFormat {
+    Json,
+    Csv,
+}
+
 /// Spotify item's ID
 enum ItemId {
     Playlist(PlaylistId<'static>),
@@ -82,6 +124,21 @@ pub enum PlaylistCommand {
         id: Option<PlaylistId<'static>>,
         delete: bool,
     },
+    AddTrack {
+        playlist_id: PlaylistId<'static>,
+        track_id: TrackId<'static>,
+    },
+    RemoveTrack {
+        playlist_id: PlaylistId<'static>,
+        track_id: TrackId<'static>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum QueueCommand {
+    List,
+    Add { uri: String },
+    Clear,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -92,6 +149,7 @@ pub enum Command {
         shuffle: bool,
     },
     StartTrack(IdOrName),
+    StartEpisode(String),
     StartLikedTracks {
         limit: usize,
         random: bool,
@@ -109,24 +167,174 @@ pub enum Command {
         is_offset: bool,
     },
     Seek(i64),
+    ToggleMute,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Request {
     Get(GetRequest),
     Playback(Command),
-    Connect(IdOrName),
-    Like { unlike: bool },
+    Connect {
+        device: IdOrName,
+        keep_playing: bool,
+    },
+    Transfer {
+        device: IdOrName,
+    },
+    Like {
+        unlike: bool,
+        id: Option<TrackId<'static>>,
+    },
     Playlist(PlaylistCommand),
-    Search { query: String },
+    /// Create a playlist, responding with the created playlist's data
+    CreatePlaylist {
+        name: String,
+        public: bool,
+    },
+    Queue(QueueCommand),
+    Search {
+        query: String,
+        item_type: Option<SearchItemType>,
+    },
+    SearchAndQueue {
+        query: String,
+    },
+    Lyrics {
+        id: Option<TrackId<'static>>,
+        timestamped: bool,
+    },
+    #[cfg(feature = "streaming")]
+    Bitrate(u16),
+    /// Keep the connection open, streaming the playback upon every playback change
+    FollowPlayback,
+    /// Keep the connection open, streaming the key's data upon every data change
+    WatchKey(Key),
+    Session(SessionRequest),
+}
+
+/// A request of a UI attached to a running application, served by the application's Spotify session
+#[derive(Debug, Serialize, Deserialize)]
+pub enum SessionRequest {
+    /// Get the application's integrated device
+    Attach,
+    /// Send a Spotify Web API request, returning the response's body
+    Api(ApiRequest),
+    /// Get the timestamped lines (in milliseconds) of a track's lyrics
+    Lyrics(TrackId<'static>),
+    /// Get the IDs of the radio tracks based on a seed URI
+    RadioTrackIds(String),
+}
+
+/// The Spotify session shared by a running application with the UIs attached to it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SharedSession {
+    /// the name and the ID of the application's integrated device, if any
+    pub device: Option<(String, String)>,
+}
+
+/// A Spotify Web API request of an attached UI. The request is sent by the application
+/// with its session's access token, which is never shared with the UI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiRequest {
+    pub method: ApiMethod,
+    /// the endpoint's path relative to the Web API's base URL, e.g. `me/player`
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub payload: Option<serde_json::Value>,
+    /// whether the request is authorized with the token of the user-provided client ID
+    pub use_user_client_id: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ApiMethod {
+    Get,
+    Post,
+    Put,
+    Delete,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Response {
     Ok(Vec<u8>),
-    Err(Vec<u8>),
+    Err(ErrorKind, Vec<u8>),
+}
+
+/// Cause of a CLI command's failure, which determines the command's exit code
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Other,
+    InvalidArgument,
+    Auth,
+    NoDevice,
+    NotFound,
+    Api,
 }
 
+/// An error with a known cause
+#[derive(Debug)]
+pub struct CliError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::Other => 1,
+            Self::InvalidArgument => 2,
+            Self::Auth => 3,
+            Self::NoDevice => 4,
+            Self::NotFound => 5,
+            Self::Api => 6,
+        }
+    }
+
+    /// Gets the kind of an error based on its causes
+    pub fn from_error(err: &anyhow::Error) -> Self {
+        use rspotify::{http::HttpError, ClientError};
+
+        for cause in err.chain() {
+            if let Some(err) = cause.downcast_ref::<CliError>() {
+                return err.kind;
+            }
+            if let Some(err) = cause.downcast_ref::<ClientError>() {
+                let ClientError::Http(err) = err else {
+                    return Self::Api;
+                };
+                let HttpError::StatusCode(response) = err.as_ref() else {
+                    return Self::Api;
+                };
+                return match response.status().as_u16() {
+                    401 | 403 => Self::Auth,
+                    // Spotify's player API responds with 404 if there is no active device
+                    404 if response.url().path().starts_with("/v1/me/player") => Self::NoDevice,
+                    404 => Self::NotFound,
+                    _ => Self::Api,
+                };
+            }
+        }
+        Self::Other
+    }
+}
+
+impl CliError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CliError {}
+
 impl From<ContextType> for ItemType {
     fn from(value: ContextType) -> Self {
         match value {
@@ -137,6 +345,19 @@ impl From<ContextType> for ItemType {
     }
 }
 
+impl From<SearchItemType> for rspotify::model::SearchType {
+    fn from(value: SearchItemType) -> Self {
+        match value {
+            SearchItemType::Track => Self::Track,
+            SearchItemType::Album => Self::Album,
+            SearchItemType::Artist => Self::Artist,
+            SearchItemType::Playlist => Self::Playlist,
+            SearchItemType::Show => Self::Show,
+            SearchItemType::Episode => Self::Episode,
+        }
+    }
+}
+
 impl ItemId {
     pub fn uri(&self) -> String {
         match self {
@@ -148,6 +369,14 @@ impl ItemId {
     }
 }
 
+/// Parse a profile's name, which is used as a folder's name
+fn parse_profile_name(name: &str) -> Result<String, String> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(std::path::is_separator) {
+        return Err(format!("invalid profile name: {name}"));
+    }
+    Ok(name.to_string())
+}
+
 pub fn init_cli() -> anyhow::Result<clap::Command> {
     let default_cache_folder = config::get_cache_folder_path()?;
     let default_config_folder = config::get_config_folder_path()?;
@@ -158,11 +387,24 @@ pub fn init_cli() -> anyhow::Result<clap::Command> {
         .author(env!("CARGO_PKG_AUTHORS"))
         .subcommand(commands::init_get_subcommand())
         .subcommand(commands::init_playback_subcommand())
+        .subcommand(commands::init_play_command())
+        .subcommand(commands::init_radio_command())
         .subcommand(commands::init_connect_subcommand())
+        .subcommand(commands::init_transfer_command())
+        .subcommand(commands::init_devices_command())
         .subcommand(commands::init_like_command())
+        .subcommand(commands::init_lyrics_command())
         .subcommand(commands::init_authenticate_command())
+        .subcommand(commands::init_daemon_command())
+        .subcommand(commands::init_batch_command())
+        .subcommand(commands::init_stream_command())
         .subcommand(commands::init_playlist_subcommand())
+        .subcommand(commands::init_queue_subcommand())
         .subcommand(commands::init_generate_command())
+        .subcommand(commands::init_history_subcommand())
+        .subcommand(commands::init_cache_subcommand())
+        .subcommand(commands::init_pick_command())
+        .subcommand(commands::init_import_csv_command())
         .subcommand(commands::init_search_command())
         .arg(
             clap::Arg::new("theme")
@@ -171,6 +413,41 @@ pub fn init_cli() -> anyhow::Result<clap::Command> {
                 .value_name("THEME")
                 .help("Application theme"),
         )
+        .arg(
+            clap::Arg::new("theme-mode")
+                .long("theme-mode")
+                .value_name("MODE")
+                .value_parser(["auto", "light", "dark"])
+                .help("Choose between the `light_theme` and `dark_theme` configs, `auto` detects the terminal's background"),
+        )
+        .arg(
+            clap::Arg::new("json-errors")
+                .long("json-errors")
+                .global(true)
+                .action(clap::ArgAction::SetTrue)
+                .help("Print errors of CLI commands in JSON"),
+        )
+        .arg(
+            clap::Arg::new("remote")
+                .long("remote")
+                .global(true)
+                .value_name("ADDR")
+                .help("Connect to a remote application listening at an address, e.g. 192.168.1.2:8081, to send CLI commands or attach the UI"),
+        )
+        .arg(
+            clap::Arg::new("web-api")
+                .long("web-api")
+                .value_name("ADDR")
+                .help("Address of the web API's HTTP server, e.g. 127.0.0.1:8888"),
+        )
+        .arg(
+            clap::Arg::new("profile")
+                .long("profile")
+                .global(true)
+                .value_name("NAME")
+                .value_parser(parse_profile_name)
+                .help("Name of the profile with its own config and cache folders (including the credentials), which are nested inside the config and cache folders"),
+        )
         .arg(
             clap::Arg::new("config-folder")
                 .short('c')
@@ -188,6 +465,16 @@ pub fn init_cli() -> anyhow::Result<clap::Command> {
                 .help("Path to the application's cache folder"),
         );
 
+    #[cfg(feature = "scrobble")]
+    let cmd = cmd.subcommand(commands::init_lastfm_sync_command());
+    #[cfg(feature = "streaming")]
+    let cmd = cmd.subcommand(commands::init_bitrate_command()).arg(
+        clap::Arg::new("device-profile")
+            .long("device-profile")
+            .value_name("NAME")
+            .help("Name of the integrated device's profile"),
+    );
+
     #[cfg(feature = "daemon")]
     let cmd = cmd.arg(
         clap::Arg::new("daemon")
@@ -0,0 +1,141 @@
+//! An MQTT integration publishing the playback state and handling playback commands,
+//! e.g. for Home Assistant
+
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    client::Client,
+    config,
+    state::{PlaybackEvent, SharedState},
+};
+
+use super::{
+    client::get_socket_response,
+    handlers::{parse_seek_offset, parse_volume_command},
+    Command, Request, Response,
+};
+
+/// Connects to the configured MQTT broker, then
+/// - publishes the current playback as a retained JSON message to `{topic}/playback` upon every playback change
+/// - handles playback commands sent to `{topic}/command`
+pub async fn start_mqtt_client(client: Client, state: SharedState) {
+    let configs = config::get_config();
+    let mqtt_configs = &configs.app_config.mqtt;
+    let playback_topic = format!("{}/playback", mqtt_configs.topic);
+    let command_topic = format!("{}/command", mqtt_configs.topic);
+
+    let mut options = MqttOptions::new(
+        &mqtt_configs.client_id,
+        &mqtt_configs.host,
+        mqtt_configs.port,
+    );
+    options.set_keep_alive(Duration::from_secs(30));
+    if let Some(ref username) = mqtt_configs.username {
+        options.set_credentials(username, mqtt_configs.password.clone().unwrap_or_default());
+    }
+
+    let (mqtt, mut event_loop) = AsyncClient::new(options, 10);
+
+    tokio::task::spawn({
+        let mqtt = mqtt.clone();
+        let state = state.clone();
+        async move {
+            publish_playback(&mqtt, &state, &playback_topic).await;
+        }
+    });
+
+    tracing::info!(
+        "Starting an MQTT client connecting to {}:{}",
+        mqtt_configs.host,
+        mqtt_configs.port
+    );
+
+    loop {
+        match event_loop.poll().await {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                // (re-)subscribe to the command topic upon every new connection
+                if let Err(err) = mqtt.try_subscribe(&command_topic, QoS::AtLeastOnce) {
+                    tracing::warn!(
+                        "Failed to subscribe to the MQTT topic {command_topic}: {err:#}"
+                    );
+                }
+            }
+            Ok(Event::Incoming(Packet::Publish(publish))) if publish.topic == command_topic => {
+                let payload = String::from_utf8_lossy(&publish.payload).into_owned();
+                tokio::task::spawn({
+                    let client = client.clone();
+                    let state = state.clone();
+                    async move {
+                        if let Err(err) = handle_command(&client, &state, payload.trim()).await {
+                            tracing::warn!(
+                                "Failed to handle the MQTT command {payload:?}: {err:#}"
+                            );
+                        }
+                    }
+                });
+            }
+            Ok(_) => {}
+            Err(err) => {
+                tracing::warn!("MQTT connection error: {err:#}");
+                // wait before reconnecting to the broker
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        }
+    }
+}
+
+/// Publishes the playback upon every playback change
+async fn publish_playback(mqtt: &AsyncClient, state: &SharedState, topic: &str) {
+    let mut playback_events = state.playback_events.subscribe();
+
+    loop {
+        let playback =
+            serde_json::to_value(state.player.read().current_playback()).unwrap_or_default();
+        if let Err(err) = mqtt
+            .publish(topic, QoS::AtLeastOnce, true, playback.to_string())
+            .await
+        {
+            tracing::warn!("Failed to publish the playback to MQTT: {err:#}");
+        }
+
+        // wait for the next playback change
+        loop {
+            match playback_events.recv().await {
+                Ok(PlaybackEvent::QueueChange | PlaybackEvent::LikedTracksChange) => {}
+                // the playback is re-published if some events are missed
+                Ok(_) | Err(RecvError::Lagged(_)) => break,
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+}
+
+/// Handles a playback command, e.g. `play`, `next`, `volume 50` or `seek -15s`
+async fn handle_command(client: &Client, state: &SharedState, payload: &str) -> Result<()> {
+    let (name, arg) = payload
+        .split_once(char::is_whitespace)
+        .map_or((payload, None), |(name, arg)| (name, Some(arg.trim())));
+
+    let command = match name {
+        "play" => Command::Play,
+        "pause" => Command::Pause,
+        "play-pause" => Command::PlayPause,
+        "next" => Command::Next,
+        "previous" => Command::Previous,
+        "shuffle" => Command::Shuffle,
+        "repeat" => Command::Repeat,
+        "mute" => Command::ToggleMute,
+        "volume" => parse_volume_command(arg.context("missing volume percentage")?, false)?,
+        "seek" => Command::Seek(parse_seek_offset(arg.context("missing seek offset")?)?),
+        _ => anyhow::bail!("unknown command"),
+    };
+
+    match get_socket_response(client, Some(state), Request::Playback(command)).await {
+        Response::Ok(_) => Ok(()),
+        Response::Err(_, err) => anyhow::bail!(String::from_utf8_lossy(&err).into_owned()),
+    }
+}
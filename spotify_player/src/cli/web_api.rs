@@ -0,0 +1,170 @@
+//! The web API exposing a subset of the CLI requests as a REST API,
+//! e.g. for home automation and web dashboards
+
+use crate::{
+    client::Client,
+    http_server::{Request as HttpRequest, Response as HttpResponse, Routes},
+    state::SharedState,
+};
+
+use super::{
+    client::get_socket_response, Command, ErrorKind, GetRequest, Key, QueueCommand, Request,
+    Response,
+};
+
+/// An endpoint of the web API
+enum Endpoint {
+    /// An endpoint handled by a single CLI request
+    Request(Request),
+    /// The user's library, combining multiple CLI requests
+    Library,
+}
+
+/// The web API's routes mounted on the application's HTTP server.
+///
+/// Each request must be authorized with the `web_api_token`, either via an
+/// `Authorization: Bearer <token>` header or a `token` query parameter.
+pub struct WebApi {
+    client: Client,
+    state: Option<SharedState>,
+    token: String,
+}
+
+impl WebApi {
+    pub fn new(client: Client, state: Option<SharedState>, token: String) -> Self {
+        Self {
+            client,
+            state,
+            token,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Routes for WebApi {
+    async fn handle(&self, request: &HttpRequest) -> Option<HttpResponse> {
+        let endpoint = parse_endpoint(request)?;
+        tracing::info!("Got a web API request: {} {}", request.method, request.path);
+        Some(
+            handle_request(
+                &self.client,
+                self.state.as_ref(),
+                &self.token,
+                request,
+                endpoint,
+            )
+            .await,
+        )
+    }
+}
+
+async fn handle_request(
+    client: &Client,
+    state: Option<&SharedState>,
+    token: &str,
+    request: &HttpRequest,
+    endpoint: Result<Endpoint, HttpResponse>,
+) -> HttpResponse {
+    let authorized = request
+        .headers
+        .get("authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or(request.query.get("token").map(String::as_str))
+        .is_some_and(|t| super::is_secret_matched(t, token));
+    if !authorized {
+        return HttpResponse::error(401, "Invalid or missing token");
+    }
+
+    let endpoint = match endpoint {
+        Ok(endpoint) => endpoint,
+        Err(response) => return response,
+    };
+
+    match endpoint {
+        Endpoint::Request(request) => {
+            to_http_response(get_socket_response(client, state, request).await)
+        }
+        Endpoint::Library => {
+            let mut library = serde_json::Map::new();
+            for (name, key) in [
+                ("playlists", Key::UserPlaylists),
+                ("albums", Key::UserSavedAlbums),
+                ("artists", Key::UserFollowedArtists),
+                ("tracks", Key::UserLikedTracks),
+            ] {
+                let request = Request::Get(GetRequest::Key(key));
+                match get_socket_response(client, state, request).await {
+                    Response::Ok(data) => {
+                        library.insert(
+                            name.to_string(),
+                            serde_json::from_slice(&data).unwrap_or_default(),
+                        );
+                    }
+                    response @ Response::Err(..) => return to_http_response(response),
+                }
+            }
+            json_response(serde_json::Value::Object(library).to_string().into_bytes())
+        }
+    }
+}
+
+/// Creates a response with a JSON body, or an empty response if there is no body
+fn json_response(body: Vec<u8>) -> HttpResponse {
+    if body.is_empty() {
+        HttpResponse::no_content()
+    } else {
+        HttpResponse::ok("application/json", body)
+    }
+}
+
+fn to_http_response(response: Response) -> HttpResponse {
+    match response {
+        Response::Ok(data) => json_response(data),
+        Response::Err(kind, err) => {
+            let status = match kind {
+                ErrorKind::NotFound | ErrorKind::NoDevice => 404,
+                _ => 400,
+            };
+            HttpResponse::error(status, String::from_utf8_lossy(&err))
+        }
+    }
+}
+
+/// Maps an HTTP request to a web API's endpoint, returning `None` if the request's path
+/// is not one of the web API's routes
+fn parse_endpoint(request: &HttpRequest) -> Option<Result<Endpoint, HttpResponse>> {
+    let get_param = |name: &str| {
+        request
+            .query
+            .get(name)
+            .ok_or_else(|| HttpResponse::error(400, format!("missing `{name}` query parameter")))
+    };
+
+    let request = match (request.method.as_str(), request.path.trim_end_matches('/')) {
+        ("GET", "/playback") => Request::Get(GetRequest::Key(Key::Playback)),
+        ("GET", "/queue") => Request::Get(GetRequest::Key(Key::Queue)),
+        ("GET", "/library") => return Some(Ok(Endpoint::Library)),
+        ("POST", "/play") => Request::Playback(Command::Play),
+        ("POST", "/pause") => Request::Playback(Command::Pause),
+        ("POST", "/play-pause") => Request::Playback(Command::PlayPause),
+        ("POST", "/next") => Request::Playback(Command::Next),
+        ("POST", "/previous") => Request::Playback(Command::Previous),
+        ("POST", "/seek") => {
+            let offset = get_param("offset_ms").and_then(|offset| {
+                offset
+                    .parse::<i64>()
+                    .map_err(|_| HttpResponse::error(400, "`offset_ms` must be an integer"))
+            });
+            match offset {
+                Ok(offset) => Request::Playback(Command::Seek(offset)),
+                Err(response) => return Some(Err(response)),
+            }
+        }
+        ("POST", "/queue") => match get_param("uri") {
+            Ok(uri) => Request::Queue(QueueCommand::Add { uri: uri.clone() }),
+            Err(response) => return Some(Err(response)),
+        },
+        _ => return None,
+    };
+    Some(Ok(Endpoint::Request(request)))
+}
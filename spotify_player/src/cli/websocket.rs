@@ -0,0 +1,136 @@
+//! A WebSocket server broadcasting playback events, e.g. for external UIs and status widgets
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use futures_util::{SinkExt, StreamExt};
+use parking_lot::Mutex;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::state::{PlaybackEvent, SharedState};
+
+use super::{client::watched_key_value, Key};
+
+/// interval between progress events while the playback is playing
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// senders of the connected clients' event channels
+type Subscribers = Arc<Mutex<Vec<flume::Sender<String>>>>;
+
+/// Accepts WebSocket connections and broadcasts the playback events as JSON messages:
+/// - `{"event": "track_change", "playback": ...}` upon a new playing item
+/// - `{"event": "playback_change", "playback": ...}` upon other playback changes, e.g. pause or volume
+/// - `{"event": "progress", "progress_ms": ..., "is_playing": ...}` every second while playing
+/// - `{"event": "queue_update", "queue": ...}` upon queue changes
+pub async fn start_websocket_server(listener: TcpListener, state: SharedState) {
+    let subscribers = Subscribers::default();
+    tokio::task::spawn(broadcast_events(state.clone(), subscribers.clone()));
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                tracing::warn!("Failed to accept a WebSocket connection: {err:#}");
+                continue;
+            }
+        };
+
+        tokio::task::spawn({
+            let state = state.clone();
+            let subscribers = subscribers.clone();
+            async move {
+                if let Err(err) = handle_connection(stream, &state, &subscribers).await {
+                    tracing::warn!("Failed to handle the WebSocket connection: {err:#}");
+                }
+            }
+        });
+    }
+}
+
+fn playback_event(event: &str, playback: &serde_json::Value) -> String {
+    serde_json::json!({ "event": event, "playback": playback }).to_string()
+}
+
+fn queue_event(queue: &serde_json::Value) -> String {
+    serde_json::json!({ "event": "queue_update", "queue": queue }).to_string()
+}
+
+/// Serves a WebSocket client, which receives the current playback and queue upon connection
+async fn handle_connection(
+    stream: TcpStream,
+    state: &SharedState,
+    subscribers: &Subscribers,
+) -> Result<()> {
+    let mut ws = tokio_tungstenite::accept_async(stream).await?;
+
+    let (tx, rx) = flume::unbounded();
+    let playback = watched_key_value(state, &Key::Playback);
+    let queue = watched_key_value(state, &Key::Queue);
+    ws.send(Message::text(playback_event("track_change", &playback)))
+        .await?;
+    ws.send(Message::text(queue_event(&queue))).await?;
+    subscribers.lock().push(tx);
+
+    loop {
+        tokio::select! {
+            msg = rx.recv_async() => {
+                let Ok(msg) = msg else { break };
+                ws.send(Message::text(msg)).await?;
+            }
+            msg = ws.next() => match msg {
+                None | Some(Ok(Message::Close(_))) => break,
+                Some(Err(err)) => return Err(err.into()),
+                // other messages, e.g. pings, are ignored
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Broadcasts the playback events to the connected clients
+async fn broadcast_events(state: SharedState, subscribers: Subscribers) {
+    let mut playback_events = state.playback_events.subscribe();
+    let mut progress_interval = tokio::time::interval(PROGRESS_INTERVAL);
+
+    loop {
+        let msg = tokio::select! {
+            event = playback_events.recv() => match event {
+                Ok(PlaybackEvent::TrackChange) => {
+                    playback_event("track_change", &watched_key_value(&state, &Key::Playback))
+                }
+                Ok(PlaybackEvent::QueueChange) => {
+                    queue_event(&watched_key_value(&state, &Key::Queue))
+                }
+                Ok(PlaybackEvent::LikedTracksChange) => continue,
+                // the playback is re-sent if some events are missed
+                Ok(_) | Err(RecvError::Lagged(_)) => {
+                    playback_event("playback_change", &watched_key_value(&state, &Key::Playback))
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = progress_interval.tick() => {
+                let Some(playback) = state
+                    .player
+                    .read()
+                    .current_playback()
+                    .filter(|p| p.is_playing)
+                else {
+                    continue;
+                };
+                serde_json::json!({
+                    "event": "progress",
+                    "progress_ms": playback.progress.map(|p| p.num_milliseconds()),
+                    "is_playing": playback.is_playing,
+                })
+                .to_string()
+            }
+        };
+
+        // drop the senders of disconnected clients
+        subscribers.lock().retain(|tx| tx.send(msg.clone()).is_ok());
+    }
+}
@@ -1,10 +1,14 @@
 use anyhow::Context;
 use rspotify::model::Id;
+use tokio::sync::broadcast::error::RecvError;
 use tracing::Instrument;
 
 use crate::{
-    config,
-    state::{ContextId, ContextPageType, ContextPageUIState, PageState, PlayableId, SharedState},
+    config, metrics,
+    state::{
+        ContextId, ContextPageType, ContextPageUIState, PageState, PlayableId, PlaybackEvent,
+        SharedState,
+    },
 };
 
 use crate::utils::map_join;
@@ -31,10 +35,14 @@ pub async fn start_client_handler(
         let state = state.clone();
         let client = client.clone();
         let span = tracing::info_span!("client_request", request = ?request);
+        let request_type = request.name();
 
         tokio::task::spawn(
             async move {
-                if let Err(err) = client.handle_request(&state, request).await {
+                let timer = std::time::Instant::now();
+                let result = client.handle_request(&state, request).await;
+                metrics::record_client_request(request_type, timer.elapsed(), result.is_err());
+                if let Err(err) = result {
                     tracing::error!("Failed to handle client request: {err:#}");
                 }
             }
@@ -114,7 +122,21 @@ fn handle_page_change_event(
     client_pub: &flume::Sender<ClientRequest>,
     handler_state: &mut PlayerEventHandlerState,
 ) -> anyhow::Result<()> {
-    match state.ui.lock().current_page_mut() {
+    let mut ui = state.ui.lock();
+    let mut restore_view = false;
+    if let PageState::Context {
+        id,
+        context_page_type: ContextPageType::CurrentPlaying,
+        ..
+    } = ui.current_page()
+    {
+        // remember the view of the previously playing context before moving to the new one
+        if *id != state.player.read().playing_context_id() {
+            ui.save_context_view();
+        }
+    }
+
+    match ui.current_page_mut() {
         PageState::Context {
             id,
             context_page_type,
@@ -132,6 +154,7 @@ fn handle_page_change_event(
                 tracing::info!("Current context ID ({:?}) is different from the expected ID ({:?}), update the context state", id, expected_id);
 
                 *id = expected_id;
+                restore_view = true;
 
                 // update the UI page state based on the context's type
                 match id {
@@ -191,6 +214,10 @@ fn handle_page_change_event(
         _ => {}
     }
 
+    if restore_view {
+        ui.restore_context_view();
+    }
+
     Ok(())
 }
 
@@ -248,3 +275,67 @@ pub async fn start_player_event_watchers(
         }
     }
 }
+
+/// Writes the now-playing line formatted with `now_playing_format` into a file upon every change.
+///
+/// A named pipe is written directly, while a regular file is replaced atomically.
+pub async fn start_now_playing_writer(state: SharedState, path: std::path::PathBuf) {
+    let configs = config::get_config();
+    let mut playback_events = state.playback_events.subscribe();
+    let mut last_line = None;
+
+    loop {
+        let line = state
+            .player
+            .read()
+            .current_playback()
+            .map(|p| crate::utils::format_playback(&configs.app_config.now_playing_format, &p))
+            .unwrap_or_default();
+
+        if last_line.as_ref() != Some(&line) {
+            // writing to a named pipe blocks until there is a reader
+            let result = tokio::task::spawn_blocking({
+                let path = path.clone();
+                let line = line.clone();
+                move || write_now_playing(&path, &line)
+            })
+            .await;
+            match result {
+                Ok(Ok(())) => last_line = Some(line),
+                Ok(Err(err)) => tracing::warn!(
+                    "Failed to write the now-playing line into {}: {err:#}",
+                    path.display()
+                ),
+                Err(err) => tracing::error!("Failed to run the now-playing writer: {err:#}"),
+            }
+        }
+
+        // wait for the next playback change
+        loop {
+            match playback_events.recv().await {
+                Ok(PlaybackEvent::QueueChange | PlaybackEvent::LikedTracksChange) => {}
+                Ok(_) | Err(RecvError::Lagged(_)) => break,
+                Err(RecvError::Closed) => return,
+            }
+        }
+    }
+}
+
+fn write_now_playing(path: &std::path::Path, line: &str) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::fs::FileTypeExt;
+
+        if std::fs::metadata(path).is_ok_and(|m| m.file_type().is_fifo()) {
+            let mut pipe = std::fs::OpenOptions::new().write(true).open(path)?;
+            return writeln!(pipe, "{line}");
+        }
+    }
+
+    // write into a temporary file, then rename it to replace the file atomically
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    std::fs::write(&tmp_path, format!("{line}\n"))?;
+    std::fs::rename(&tmp_path, path)
+}
@@ -2,15 +2,20 @@ use std::ops::Deref;
 use std::{borrow::Cow, collections::HashMap, sync::Arc};
 
 use crate::state::Lyrics;
-use crate::{auth, config};
+use crate::{
+    auth,
+    config::{self, SearchCategory},
+    metrics,
+};
 use crate::{
     auth::AuthConfig,
     state::{
-        store_data_into_file_cache, Album, AlbumId, Artist, ArtistId, Category, Context, ContextId,
-        Device, FileCacheKey, Item, ItemId, MemoryCaches, Playback, PlaybackMetadata, Playlist,
-        PlaylistFolderItem, PlaylistId, SearchResults, SharedState, Show, ShowId, Track, TrackId,
-        UserId, TTL_CACHE_DURATION, USER_LIKED_TRACKS_ID, USER_RECENTLY_PLAYED_TRACKS_ID,
-        USER_TOP_TRACKS_ID,
+        append_listening_history_entry, store_data_into_file_cache, Album, AlbumId, Artist,
+        ArtistId, Audiobook, Category, Context, ContextId, Device, EpisodeId, FileCacheKey, Item,
+        ItemId, ListeningHistoryEntry, MemoryCaches, PageState, Playback, PlaybackEvent,
+        PlaybackMetadata, Playlist, PlaylistFolderItem, PlaylistId, SearchResults, SharedState,
+        Show, ShowId, Track, TrackId, UserId, TTL_CACHE_DURATION, USER_LIKED_TRACKS_ID,
+        USER_RECENTLY_PLAYED_TRACKS_ID, USER_TOP_TRACKS_ID,
     },
 };
 
@@ -34,6 +39,13 @@ pub use request::*;
 use serde::Deserialize;
 
 const SPOTIFY_API_ENDPOINT: &str = "https://api.spotify.com/v1";
+/// The dealer's URI of the player state updates pushed to the connected devices
+#[cfg(feature = "streaming")]
+const CONNECT_STATE_UPDATE_URI: &str = "hm://connect-state/v1/cluster";
+/// The duration to wait for the following playback updates before refreshing the playback
+#[cfg(feature = "streaming")]
+const PLAYBACK_UPDATES_DEBOUNCE_DURATION: std::time::Duration =
+    std::time::Duration::from_millis(100);
 const PLAYBACK_TYPES: [&rspotify::model::AdditionalType; 2] = [
     &rspotify::model::AdditionalType::Track,
     &rspotify::model::AdditionalType::Episode,
@@ -45,6 +57,8 @@ pub struct Client {
     http: reqwest::Client,
     spotify: Arc<spotify::Spotify>,
     auth_config: AuthConfig,
+    /// lock held while recovering a lost session, which the other callers wait for
+    session_recovery: Arc<tokio::sync::Mutex<()>>,
     #[cfg(feature = "streaming")]
     stream_conn: Arc<Mutex<Option<librespot_connect::Spirc>>>,
 }
@@ -67,6 +81,7 @@ impl Client {
             spotify: Arc::new(spotify::Spotify::new()),
             http: reqwest::Client::new(),
             auth_config,
+            session_recovery: Arc::new(tokio::sync::Mutex::new(())),
 
             #[cfg(feature = "streaming")]
             stream_conn: Arc::new(Mutex::new(None)),
@@ -114,6 +129,9 @@ impl Client {
                             tracing::warn!("Connection failed (device_id={id}): {err:#}");
                         } else {
                             tracing::info!("Connection succeeded (device_id={id})!");
+                            if let Err(err) = client.apply_device_volume(&id).await {
+                                tracing::warn!("Failed to apply the device's volume: {err:#}");
+                            }
                             // upon new connection, reset the buffered playback
                             state.player.write().buffered_playback = None;
                             client.update_playback(&state);
@@ -125,9 +143,25 @@ impl Client {
         });
     }
 
+    /// Create a new `librespot` session, whose autoplay follows the integrated device's autoplay toggle
+    fn librespot_session(&self, state: Option<&SharedState>) -> librespot_core::Session {
+        #[allow(unused_mut)]
+        let mut session_config = self.auth_config.session_config.clone();
+        #[cfg(feature = "streaming")]
+        if let Some(state) = state {
+            session_config.autoplay = Some(*state.autoplay.lock());
+        }
+        librespot_core::Session::new(session_config, Some(self.auth_config.cache.clone()))
+    }
+
     /// Create a new client session
     pub async fn new_session(&self, state: Option<&SharedState>, reauth: bool) -> Result<()> {
-        let session = self.auth_config.session();
+        if self.is_attached() {
+            anyhow::bail!(
+                "the Spotify session is owned by the application the client is attached to"
+            );
+        }
+        let session = self.librespot_session(state);
         let creds = auth::get_creds(&self.auth_config, reauth, true).context("get credentials")?;
         *self.session.lock().await = Some(session.clone());
 
@@ -165,17 +199,74 @@ impl Client {
         Ok(())
     }
 
-    /// Check if the current session is valid and if invalid, create a new session
+    /// Check if the current session is valid and if invalid, create a new session.
+    /// If the session is already being recovered, wait for the recovery instead.
     pub async fn check_valid_session(&self, state: &SharedState) -> Result<()> {
+        // the session of an attached client is recovered by the application it's attached to
+        if self.is_attached() || !self.session().await.is_invalid() {
+            return Ok(());
+        }
+
+        let _recovery = self.session_recovery.lock().await;
+        // the session may be recovered while waiting for the lock
         if self.session().await.is_invalid() {
             tracing::info!("Client's current session is invalid, creating a new session...");
-            self.new_session(Some(state), false)
+            metrics::record_session_reconnect();
+            self.recover_session(state)
                 .await
                 .context("create new client session")?;
         }
         Ok(())
     }
 
+    /// Create a new session to replace a lost session, retrying with an exponential backoff.
+    /// If the integrated device was playing upon losing the session,
+    /// its playback is resumed at the last position reported by Spotify.
+    async fn recover_session(&self, state: &SharedState) -> Result<()> {
+        const MAX_ATTEMPTS: u32 = 6;
+
+        let device_id = &self.auth_config.session_config.device_id;
+        let progress = {
+            let mut player = state.player.write();
+            player.reconnecting = true;
+            // the position extrapolated since the last report may not have been played,
+            // as the audio stops once the buffered audio runs out
+            player
+                .playback
+                .as_ref()
+                .filter(|p| p.is_playing && p.device.id.as_ref() == Some(device_id))
+                .and_then(|p| p.progress)
+        };
+
+        let mut delay = std::time::Duration::from_secs(1);
+        let mut attempt = 1;
+        let result = loop {
+            match self.new_session(Some(state), false).await {
+                Err(err) if attempt < MAX_ATTEMPTS => {
+                    tracing::warn!("Failed to create a new session (attempt {attempt}/{MAX_ATTEMPTS}), retrying in {delay:?}: {err:#}");
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                    attempt += 1;
+                }
+                result => break result,
+            }
+        };
+        state.player.write().reconnecting = false;
+        result?;
+
+        if let Some(progress) = progress {
+            tracing::info!(
+                "Resuming the interrupted playback at {}",
+                crate::utils::format_duration(&progress)
+            );
+            self.transfer_playback(device_id, Some(true))
+                .await
+                .context("resume the interrupted playback")?;
+            self.seek_track(progress, Some(device_id)).await?;
+        }
+        Ok(())
+    }
+
     /// Create a new streaming connection
     #[cfg(feature = "streaming")]
     pub async fn new_streaming_connection(
@@ -184,11 +275,16 @@ impl Client {
         session: librespot_core::Session,
         creds: librespot_core::authentication::Credentials,
     ) -> Result<()> {
+        if config::get_config().app_config.enable_playback_push_updates {
+            self.watch_playback_updates(state.clone(), &session)
+                .context("watch playback updates")?;
+        }
         let new_conn =
             crate::streaming::new_connection(self.clone(), state, session, creds).await?;
         let mut stream_conn = self.stream_conn.lock();
         // shutdown old streaming connection and replace it with a new connection
         if let Some(conn) = stream_conn.as_ref() {
+            metrics::record_stream_reconnect();
             if let Err(err) = conn.shutdown() {
                 log::error!("Failed to shutdown old streaming connection: {err:#}");
             }
@@ -197,6 +293,247 @@ impl Client {
         Ok(())
     }
 
+    /// Refresh the playback upon every player state update pushed by Spotify through the session's
+    /// dealer connection, so changes made by other Spotify clients are shown without polling.
+    ///
+    /// The updates are received until the session's dealer connection is closed.
+    #[cfg(feature = "streaming")]
+    fn watch_playback_updates(
+        &self,
+        state: SharedState,
+        session: &librespot_core::Session,
+    ) -> Result<()> {
+        use futures_util::{FutureExt, StreamExt};
+
+        let mut updates = session
+            .dealer()
+            .listen_for(CONNECT_STATE_UPDATE_URI, |_| Ok(()))?;
+        let client = self.clone();
+        tokio::task::spawn(async move {
+            while let Some(update) = updates.next().await {
+                if let Err(err) = update {
+                    tracing::warn!("Failed to receive a playback update: {err:#}");
+                    continue;
+                }
+                // a playback change usually comes with a burst of updates, which are handled at once
+                tokio::time::sleep(PLAYBACK_UPDATES_DEBOUNCE_DURATION).await;
+                while let Some(Some(_)) = updates.next().now_or_never() {}
+
+                if let Err(err) = client.retrieve_current_playback(&state, true).await {
+                    tracing::warn!("Failed to refresh the playback upon an update: {err:#}");
+                }
+            }
+            tracing::info!("Stopped watching playback updates of a closed session");
+        });
+        Ok(())
+    }
+
+    /// Switch the integrated device's streaming bitrate (in kbps) by restarting
+    /// the streaming connection, then resume the device's playback if any
+    #[cfg(feature = "streaming")]
+    pub async fn set_bitrate(&self, state: &SharedState, bitrate: u16) -> Result<()> {
+        if ![96, 160, 320].contains(&bitrate) {
+            anyhow::bail!("invalid bitrate {bitrate}, expected 96, 160 or 320");
+        }
+        if !state.is_streaming_enabled() {
+            anyhow::bail!("streaming is not enabled");
+        }
+
+        *state.bitrate.lock() = bitrate;
+        self.restart_integrated_client_preserving_playback(state)
+            .await?;
+        tracing::info!("Switched the streaming bitrate to {bitrate} kbps");
+        Ok(())
+    }
+
+    /// Toggle the integrated device's autoplay, which keeps playing similar tracks
+    /// when the playing context ends, by restarting the integrated client
+    #[cfg(feature = "streaming")]
+    pub async fn set_autoplay(&self, state: &SharedState, autoplay: bool) -> Result<()> {
+        if !state.is_streaming_enabled() {
+            anyhow::bail!("streaming is not enabled");
+        }
+
+        *state.autoplay.lock() = autoplay;
+        self.restart_integrated_client_preserving_playback(state)
+            .await?;
+        tracing::info!("Switched the autoplay to {autoplay}");
+        Ok(())
+    }
+
+    /// Toggle the integrated device's equalizer, which is only applied to the audio
+    /// while it's enabled, by restarting the integrated client
+    #[cfg(feature = "streaming")]
+    pub async fn set_equalizer(&self, state: &SharedState, enabled: bool) -> Result<()> {
+        let mut settings = (**state.equalizer.load()).clone();
+        settings.enabled = enabled;
+        settings.store();
+        state.equalizer.store(Arc::new(settings));
+
+        if state.is_streaming_enabled() {
+            self.restart_integrated_client_preserving_playback(state)
+                .await?;
+        }
+        tracing::info!("Switched the equalizer to {enabled}");
+        Ok(())
+    }
+
+    /// Toggle the integrated device's night mode, which is only applied to the audio
+    /// while it's enabled, by restarting the integrated client
+    #[cfg(feature = "streaming")]
+    pub async fn set_night_mode(&self, state: &SharedState, night_mode: bool) -> Result<()> {
+        *state.night_mode.lock() = night_mode;
+        if state.is_streaming_enabled() {
+            self.restart_integrated_client_preserving_playback(state)
+                .await?;
+        }
+        tracing::info!("Switched the night mode to {night_mode}");
+        Ok(())
+    }
+
+    /// Restart the integrated client to apply its new settings,
+    /// resuming its playback (if any) from the same position
+    #[cfg(feature = "streaming")]
+    async fn restart_integrated_client_preserving_playback(
+        &self,
+        state: &SharedState,
+    ) -> Result<()> {
+        let device_id = &self.auth_config.session_config.device_id;
+        let playback = state.player.read().buffered_playback.clone();
+        self.new_session(Some(state), false)
+            .await
+            .context("restart the integrated client")?;
+
+        if playback.is_some_and(|p| p.device_id.as_ref() == Some(device_id)) {
+            if let Err(err) = self.transfer_playback_preserving_state(device_id).await {
+                tracing::warn!(
+                    "Failed to resume the playback after restarting the integrated client: {err:#}"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Run a zeroconf discovery service, which lets other Spotify clients on the local network
+    /// connect to the integrated device with their credentials
+    #[cfg(feature = "streaming")]
+    pub async fn start_zeroconf_discovery(&self, state: SharedState) {
+        use futures_util::StreamExt;
+
+        let mut discovery = match crate::streaming::new_discovery(
+            &self.auth_config.session_config.device_id,
+            &state,
+        ) {
+            Ok(discovery) => discovery,
+            Err(err) => {
+                tracing::error!("Failed to start zeroconf discovery: {err:#}");
+                return;
+            }
+        };
+
+        while let Some(creds) = discovery.next().await {
+            tracing::info!(
+                "Got credentials of user {:?} from zeroconf discovery",
+                creds.username
+            );
+            let session = self.librespot_session(Some(&state));
+            if let Err(err) = self
+                .new_streaming_connection(state.clone(), session, creds)
+                .await
+            {
+                tracing::error!(
+                    "Failed to create a streaming connection from zeroconf discovery: {err:#}"
+                );
+            }
+        }
+    }
+
+    /// Fade out the integrated device's audio before pausing or skipping its playback.
+    /// The audio fades in again upon the next playing track or after a timeout.
+    #[cfg(feature = "streaming")]
+    async fn fade_out(
+        &self,
+        state: &SharedState,
+        request: &PlayerRequest,
+        playback: Option<&PlaybackMetadata>,
+    ) {
+        let configs = config::get_config();
+        let device = &configs.app_config.device;
+        let is_transition = matches!(
+            request,
+            PlayerRequest::NextTrack
+                | PlayerRequest::PreviousTrack
+                | PlayerRequest::Pause
+                | PlayerRequest::ResumePause
+        );
+        let is_playing = playback.is_some_and(|p| {
+            p.is_playing && p.device_id.as_ref() == Some(&self.auth_config.session_config.device_id)
+        });
+        if device.fade_duration_ms == 0 || device.bit_perfect || !is_transition || !is_playing {
+            return;
+        }
+
+        *state.fading_out.lock() = true;
+        tokio::time::sleep(std::time::Duration::from_millis(device.fade_duration_ms)).await;
+        tokio::task::spawn({
+            let state = state.clone();
+            async move {
+                // fade in again if the transition fails or takes too long
+                tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+                *state.fading_out.lock() = false;
+            }
+        });
+    }
+
+    /// Apply the configured volume and maximum volume of a Connect device
+    /// after transferring the playback to the device
+    pub async fn apply_device_volume(&self, device_id: &str) -> Result<()> {
+        let configs = config::get_config();
+        if configs.app_config.device_volumes.is_empty() {
+            return Ok(());
+        }
+
+        let devices = self.available_devices().await?;
+        let Some(device) = devices.iter().find(|d| d.id.as_deref() == Some(device_id)) else {
+            return Ok(());
+        };
+        let Some(volume_config) = configs.app_config.find_device_volume(&device.name) else {
+            return Ok(());
+        };
+
+        let current_volume = device.volume_percent.map(|v| v.min(100) as u8);
+        let volume = volume_config
+            .volume
+            .or(current_volume)
+            .map(|v| volume_config.cap(v));
+        if let Some(volume) = volume.filter(|v| Some(*v) != current_volume) {
+            tracing::info!("Setting the volume of device {} to {volume}%", device.name);
+            self.volume(volume, Some(device_id)).await?;
+        }
+        Ok(())
+    }
+
+    /// Seek the integrated device's playback directly, which replays the playing track's
+    /// already loaded (or cached) audio instantly, e.g. upon restarting the track,
+    /// without a round trip through the Spotify server.
+    /// Returns `false` if the playback isn't on the integrated device.
+    #[cfg(feature = "streaming")]
+    fn seek_integrated_device(
+        &self,
+        position: chrono::Duration,
+        device_id: Option<&str>,
+    ) -> Result<bool> {
+        if device_id != Some(self.auth_config.session_config.device_id.as_str()) {
+            return Ok(false);
+        }
+        let stream_conn = self.stream_conn.lock();
+        let Some(spirc) = stream_conn.as_ref() else {
+            return Ok(false);
+        };
+        spirc.set_position_ms(u32::try_from(position.num_milliseconds()).unwrap_or_default())?;
+        Ok(true)
+    }
+
     /// Handle a player request, return a new playback metadata on success
     pub async fn handle_player_request(
         &self,
@@ -210,10 +547,17 @@ impl Client {
                 // because `TransferPlayback` doesn't require an active playback
                 self.transfer_playback(&device_id, Some(force_play)).await?;
                 tracing::info!("Transferred playback to device with id={}", device_id);
+                if let Err(err) = self.apply_device_volume(&device_id).await {
+                    tracing::warn!("Failed to apply the device's volume: {err:#}");
+                }
                 return Ok(None);
             }
             PlayerRequest::StartPlayback(p, shuffle) => {
-                // Set the playback's shuffle state if specified in the request
+                let configs = config::get_config();
+                let defaults = configs.app_config.find_playback_default(p.context_type());
+                // Set the playback's shuffle state if specified in the request,
+                // falling back to the default shuffle state of the context's type
+                let shuffle = shuffle.or(defaults.and_then(|d| d.shuffle));
                 if let (Some(shuffle), Some(playback)) = (shuffle, playback.as_mut()) {
                     playback.shuffle_state = shuffle;
                 }
@@ -224,6 +568,9 @@ impl Client {
                 if let Some(ref playback) = playback {
                     self.shuffle(playback.shuffle_state, device_id).await?;
                 }
+                if let Some(repeat) = defaults.and_then(|d| d.repeat) {
+                    self.repeat(repeat, device_id).await?;
+                }
                 return Ok(None);
             }
             _ => {}
@@ -257,6 +604,10 @@ impl Client {
                 playback.is_playing = !playback.is_playing;
             }
             PlayerRequest::SeekTrack(position_ms) => {
+                #[cfg(feature = "streaming")]
+                if self.seek_integrated_device(position_ms, device_id)? {
+                    return Ok(Some(playback));
+                }
                 self.seek_track(position_ms, device_id).await?;
             }
             PlayerRequest::Repeat => {
@@ -270,12 +621,26 @@ impl Client {
 
                 playback.repeat_state = next_repeat_state;
             }
+            PlayerRequest::SetRepeat(repeat_state) => {
+                self.repeat(repeat_state, device_id).await?;
+
+                playback.repeat_state = repeat_state;
+            }
             PlayerRequest::Shuffle => {
                 self.shuffle(!playback.shuffle_state, device_id).await?;
 
                 playback.shuffle_state = !playback.shuffle_state;
             }
+            PlayerRequest::SetShuffle(shuffle) => {
+                self.shuffle(shuffle, device_id).await?;
+
+                playback.shuffle_state = shuffle;
+            }
             PlayerRequest::Volume(volume) => {
+                let volume = config::get_config()
+                    .app_config
+                    .find_device_volume(&playback.device_name)
+                    .map_or(volume, |v| v.cap(volume));
                 self.volume(volume, device_id).await?;
 
                 playback.volume = Some(u32::from(volume));
@@ -330,7 +695,10 @@ impl Client {
             }
             ClientRequest::GetLyrics { track_id } => {
                 let uri = track_id.uri();
-                if !state.data.read().caches.lyrics.contains_key(&uri) {
+                if !metrics::record_cache_lookup(
+                    "lyrics",
+                    state.data.read().caches.lyrics.contains_key(&uri),
+                ) {
                     let lyrics = self.lyrics(track_id).await?;
                     state
                         .data
@@ -344,25 +712,72 @@ impl Client {
             ClientRequest::RestartIntegratedClient => {
                 self.new_session(Some(state), false).await?;
             }
+            #[cfg(feature = "streaming")]
+            ClientRequest::SetBitrate(bitrate) => {
+                self.set_bitrate(state, bitrate).await?;
+            }
+            #[cfg(feature = "streaming")]
+            ClientRequest::SetAutoplay(autoplay) => {
+                self.set_autoplay(state, autoplay).await?;
+            }
+            #[cfg(feature = "streaming")]
+            ClientRequest::SetEqualizer(enabled) => {
+                self.set_equalizer(state, enabled).await?;
+            }
+            #[cfg(feature = "streaming")]
+            ClientRequest::SetNightMode(night_mode) => {
+                self.set_night_mode(state, night_mode).await?;
+            }
             ClientRequest::GetCurrentUser => {
                 let user = self.current_user().await?;
+                if let Err(err) = store_data_into_file_cache(
+                    FileCacheKey::User,
+                    &config::get_config().cache_folder,
+                    &user,
+                ) {
+                    tracing::error!("Failed to store user's data into the cache folder: {err:#}");
+                }
                 state.data.write().user_data.user = Some(user);
             }
             ClientRequest::Player(request) => {
                 let playback = state.player.read().buffered_playback.clone();
+                #[cfg(feature = "streaming")]
+                self.fade_out(state, &request, playback.as_ref()).await;
+                let seek_position = match request {
+                    PlayerRequest::SeekTrack(position) => Some(position),
+                    _ => None,
+                };
                 let playback = self.handle_player_request(request, playback).await?;
-                state.player.write().buffered_playback = playback;
+                let mut player = state.player.write();
+                player.buffered_playback = playback;
+                if let Some(position) = seek_position {
+                    player.seek_progress(position);
+                }
+                drop(player);
+                state.publish_playback_events();
                 self.update_playback(state);
             }
             ClientRequest::GetCurrentPlayback => {
                 self.retrieve_current_playback(state, true).await?;
             }
             ClientRequest::GetDevices => {
-                let devices = self.available_devices().await?;
-                state.player.write().devices = devices
+                let devices = self
+                    .available_devices()
+                    .await?
                     .into_iter()
                     .filter_map(Device::try_from_device)
-                    .collect();
+                    .collect::<Vec<_>>();
+                // cache the devices for CLI completions
+                if let Err(err) = store_data_into_file_cache(
+                    FileCacheKey::Devices,
+                    &config::get_config().cache_folder,
+                    &devices,
+                ) {
+                    tracing::error!(
+                        "Failed to store available devices into the cache folder: {err:#}"
+                    );
+                }
+                state.player.write().devices = devices;
             }
             ClientRequest::GetUserPlaylists => {
                 let playlists = self.current_user_playlists().await?;
@@ -415,7 +830,10 @@ impl Client {
             }
             ClientRequest::GetUserTopTracks => {
                 let uri = &USER_TOP_TRACKS_ID.uri;
-                if !state.data.read().caches.context.contains_key(uri) {
+                if !metrics::record_cache_lookup(
+                    "context",
+                    state.data.read().caches.context.contains_key(uri),
+                ) {
                     let tracks = self.current_user_top_tracks().await?;
                     state.data.write().caches.context.insert(
                         uri.to_owned(),
@@ -441,6 +859,10 @@ impl Client {
                 .context("store user's saved tracks into the cache folder")?;
 
                 let mut data = state.data.write();
+                let is_changed = data.user_data.saved_tracks.len() != tracks_hm.len()
+                    || tracks_hm
+                        .keys()
+                        .any(|uri| !data.user_data.saved_tracks.contains_key(uri));
                 data.user_data.saved_tracks = tracks_hm;
                 data.caches.context.insert(
                     USER_LIKED_TRACKS_ID.uri.clone(),
@@ -450,10 +872,17 @@ impl Client {
                     },
                     *TTL_CACHE_DURATION,
                 );
+                drop(data);
+                if is_changed {
+                    state.publish_event(PlaybackEvent::LikedTracksChange);
+                }
             }
             ClientRequest::GetUserRecentlyPlayedTracks => {
                 let uri = &USER_RECENTLY_PLAYED_TRACKS_ID.uri;
-                if !state.data.read().caches.context.contains_key(uri) {
+                if !metrics::record_cache_lookup(
+                    "context",
+                    state.data.read().caches.context.contains_key(uri),
+                ) {
                     let tracks = self.current_user_recently_played_tracks().await?;
                     state.data.write().caches.context.insert(
                         uri.to_owned(),
@@ -467,10 +896,18 @@ impl Client {
             }
             ClientRequest::GetContext(context) => {
                 let uri = context.uri();
-                if !state.data.read().caches.context.contains_key(&uri) {
-                    let context = match context {
+                if !metrics::record_cache_lookup(
+                    "context",
+                    state.data.read().caches.context.contains_key(&uri),
+                ) {
+                    // a playlist's tracks after the first page are loaded in the background
+                    let mut next_tracks_page = None;
+                    let mut context = match context {
                         ContextId::Playlist(playlist_id) => {
-                            self.playlist_context(playlist_id).await?
+                            let (context, next) =
+                                self.playlist_context_first_page(playlist_id).await?;
+                            next_tracks_page = next;
+                            context
                         }
                         ContextId::Album(album_id) => self.album_context(album_id).await?,
                         ContextId::Artist(artist_id) => self.artist_context(artist_id).await?,
@@ -482,16 +919,35 @@ impl Client {
                         ContextId::Show(show_id) => self.show_context(show_id).await?,
                     };
 
-                    state
-                        .data
-                        .write()
-                        .caches
-                        .context
-                        .insert(uri, context, *TTL_CACHE_DURATION);
+                    let view = state.ui.lock().context_views.get(&uri).cloned();
+                    if let (Some(view), Some(tracks)) = (view, context.tracks_mut()) {
+                        view.sort_tracks(tracks);
+                    }
+
+                    state.data.write().caches.context.insert(
+                        uri.clone(),
+                        context,
+                        *TTL_CACHE_DURATION,
+                    );
+                    if let Some(next) = next_tracks_page {
+                        self.load_playlist_tracks_in_background(state, uri, next);
+                    }
                 }
             }
             ClientRequest::Search(query) => {
-                if !state.data.read().caches.search.contains_key(&query) {
+                // a query can be replaced by a newer one before this request is handled,
+                // which happens when the query is searched live as the user types
+                let is_outdated = matches!(
+                    state.ui.lock().current_page(),
+                    PageState::Search { current_query, .. } if *current_query != query
+                );
+
+                if !is_outdated
+                    && !metrics::record_cache_lookup(
+                        "search",
+                        state.data.read().caches.search.contains_key(&query),
+                    )
+                {
                     let results = self.search(&query).await?;
 
                     state
@@ -502,12 +958,34 @@ impl Client {
                         .insert(query, results, *TTL_CACHE_DURATION);
                 }
             }
+            ClientRequest::SearchMore { query, category } => {
+                let offset = state
+                    .data
+                    .read()
+                    .caches
+                    .search
+                    .get(&query)
+                    .and_then(|results| results.next_offsets.get(&category).copied());
+
+                // more results can only be fetched for a cached search query
+                // whose category has a next page
+                if let Some(offset) = offset {
+                    let results = self.search_more(&query, category, offset).await?;
+
+                    if let Some(cached_results) = state.data.write().caches.search.get_mut(&query) {
+                        cached_results.extend_category(category, results);
+                    }
+                }
+            }
             ClientRequest::GetRadioTracks {
                 seed_uri: uri,
                 seed_name: name,
             } => {
                 let radio_uri = format!("radio:{uri}");
-                if !state.data.read().caches.context.contains_key(&radio_uri) {
+                if !metrics::record_cache_lookup(
+                    "context",
+                    state.data.read().caches.context.contains_key(&radio_uri),
+                ) {
                     let tracks = self.radio_tracks(uri).await?;
 
                     state.data.write().caches.context.insert(
@@ -520,11 +998,38 @@ impl Client {
                     );
                 }
             }
+            ClientRequest::SearchAndQueue(query) => match self.search_and_queue(&query).await? {
+                Some(track) => {
+                    state
+                        .player
+                        .write()
+                        .user_queued_items
+                        .insert(track.id.uri());
+                }
+                None => tracing::warn!("No track found for query {query}"),
+            },
+            ClientRequest::CopyUniversalLink(url) => {
+                let link = self.universal_link(&url).await?;
+                crate::event::execute_copy_command(link)?;
+            }
+            ClientRequest::PlayAudiobook(audiobook_id) => {
+                let chapters = self.audiobook_chapters(&audiobook_id).await?;
+                if chapters.is_empty() {
+                    anyhow::bail!("no playable chapter found for audiobook {audiobook_id}");
+                }
+                let request = ClientRequest::Player(PlayerRequest::StartPlayback(
+                    Playback::URIs(chapters, None),
+                    None,
+                ));
+                Box::pin(self.handle_request(state, request)).await?;
+            }
             ClientRequest::AddPlayableToQueue(playable_id) => {
+                let uri = playable_id.uri();
                 self.add_item_to_queue(playable_id, None).await?;
+                state.player.write().user_queued_items.insert(uri);
             }
             ClientRequest::AddPlayableToPlaylist(playlist_id, playable_id) => {
-                self.add_item_to_playlist(state, playlist_id, playable_id)
+                self.add_item_to_playlist(Some(state), playlist_id, playable_id)
                     .await?;
             }
             ClientRequest::AddAlbumToQueue(album_id) => {
@@ -532,13 +1037,15 @@ impl Client {
 
                 if let Context::Album { album: _, tracks } = album_context {
                     for track in tracks {
+                        let uri = track.id.uri();
                         self.add_item_to_queue(PlayableId::Track(track.id), None)
                             .await?;
+                        state.player.write().user_queued_items.insert(uri);
                     }
                 }
             }
             ClientRequest::DeleteTrackFromPlaylist(playlist_id, track_id) => {
-                self.delete_track_from_playlist(state, playlist_id, track_id)
+                self.delete_track_from_playlist(Some(state), playlist_id, track_id)
                     .await?;
             }
             ClientRequest::AddToLibrary(item) => {
@@ -549,7 +1056,13 @@ impl Client {
             }
             ClientRequest::GetCurrentUserQueue => {
                 let queue = self.current_user_queue().await?;
-                state.player.write().queue = Some(queue);
+                let mut player = state.player.write();
+                let is_changed = player.queue.as_ref() != Some(&queue);
+                player.queue = Some(queue);
+                drop(player);
+                if is_changed {
+                    state.publish_event(PlaybackEvent::QueueChange);
+                }
             }
             ClientRequest::ReorderPlaylistItems {
                 playlist_id,
@@ -592,6 +1105,16 @@ impl Client {
                 )
                 .await?;
             }
+            ClientRequest::Sequence(requests) => {
+                for request in requests {
+                    let is_player_request = matches!(request, ClientRequest::Player(_));
+                    Box::pin(self.handle_request(state, request)).await?;
+                    // the next requests may depend on the playback updated by a player request
+                    if is_player_request {
+                        self.retrieve_current_playback(state, false).await?;
+                    }
+                }
+            }
         }
 
         tracing::info!(
@@ -604,6 +1127,18 @@ impl Client {
 
     /// Get lyrics of a given track, return None if no lyrics is available
     pub async fn lyrics(&self, track_id: TrackId<'static>) -> Result<Option<Lyrics>> {
+        if let Some(attached) = self.attached.get() {
+            let lines: Option<Vec<(i64, String)>> = attached
+                .application
+                .session_request(crate::cli::SessionRequest::Lyrics(track_id))
+                .await?;
+            return Ok(lines.map(|lines| Lyrics {
+                lines: lines
+                    .into_iter()
+                    .map(|(t, line)| (chrono::Duration::milliseconds(t), line))
+                    .collect(),
+            }));
+        }
         let session = self.session().await;
         let id = librespot_core::spotify_id::SpotifyId::from_uri(&track_id.uri())?;
         match librespot_metadata::Lyrics::get(&session, &id).await {
@@ -671,6 +1206,22 @@ impl Client {
         Ok(first_page.items.into_iter().map(Playlist::from).collect())
     }
 
+    /// Get the name and the ID of the integrated device, which is the device of the application
+    /// the client is attached to if any
+    pub async fn integrated_device(&self) -> Option<(String, String)> {
+        if let Some(attached) = self.attached.get() {
+            return attached.device.clone();
+        }
+        if !cfg!(feature = "streaming") {
+            return None;
+        }
+        let session = self.session().await;
+        Some((
+            config::get_config().app_config.device.name.clone(),
+            session.device_id().to_string(),
+        ))
+    }
+
     /// Find an available device. If found, return the device's ID.
     async fn find_available_device(&self) -> Result<Option<String>> {
         let devices = self.available_devices().await?;
@@ -702,13 +1253,8 @@ impl Client {
         // 2. The device list is empty. This might be because user doesn't specify their own client ID.
         //    By default, the application uses Spotify web app's client ID, which doesn't have
         //    access to user's active devices.
-        #[cfg(feature = "streaming")]
-        {
-            let session = self.session().await;
-            devices.push((
-                configs.app_config.device.name.clone(),
-                session.device_id().to_string(),
-            ));
+        if let Some(device) = self.integrated_device().await {
+            devices.push(device);
         }
 
         if devices.is_empty() {
@@ -915,8 +1461,42 @@ impl Client {
         Ok(())
     }
 
+    /// Get a song.link/album.link universal link of a Spotify URL using the Odesli API,
+    /// which opens the linked item in any music service
+    async fn universal_link(&self, url: &str) -> Result<String> {
+        let response: serde_json::Value = self
+            .http
+            .get("https://api.song.link/v1-alpha.1/links")
+            .query(&[("url", url)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        response["pageUrl"]
+            .as_str()
+            .map(ToString::to_string)
+            .with_context(|| format!("no universal link found for {url}"))
+    }
+
     /// Get recommendation (radio) tracks based on a seed
     pub async fn radio_tracks(&self, seed_uri: String) -> Result<Vec<Track>> {
+        let track_ids = self.radio_track_ids(seed_uri).await?;
+
+        // Retrieve tracks based on IDs
+        let tracks = self
+            .tracks(track_ids, Some(rspotify::model::Market::FromToken))
+            .await?;
+        let tracks = tracks
+            .into_iter()
+            .filter_map(Track::try_from_full_track)
+            .collect();
+
+        Ok(tracks)
+    }
+
+    /// Get the IDs of recommendation (radio) tracks based on a seed
+    pub async fn radio_track_ids(&self, seed_uri: String) -> Result<Vec<TrackId<'static>>> {
         #[derive(Debug, Deserialize)]
         struct TrackData {
             original_gid: String,
@@ -926,6 +1506,13 @@ impl Client {
             tracks: Vec<TrackData>,
         }
 
+        if let Some(attached) = self.attached.get() {
+            return attached
+                .application
+                .session_request(crate::cli::SessionRequest::RadioTrackIds(seed_uri))
+                .await;
+        }
+
         let session = self.session().await;
 
         // Get an autoplay URI from the seed URI.
@@ -962,21 +1549,14 @@ impl Client {
         let track_ids = serde_json::from_slice::<RadioStationResponse>(&response.payload[0])?
             .tracks
             .into_iter()
-            .filter_map(|t| TrackId::from_id(t.original_gid).ok());
-
-        // Retrieve tracks based on IDs
-        let tracks = self
-            .tracks(track_ids, Some(rspotify::model::Market::FromToken))
-            .await?;
-        let tracks = tracks
-            .into_iter()
-            .filter_map(Track::try_from_full_track)
+            .filter_map(|t| TrackId::from_id(t.original_gid).ok())
             .collect();
 
-        Ok(tracks)
+        Ok(track_ids)
     }
 
-    /// Search for items (tracks, artists, albums, playlists) matching a given query
+    /// Search for items (tracks, artists, albums, playlists, shows, episodes, audiobooks)
+    /// matching a given query
     pub async fn search(&self, query: &str) -> Result<SearchResults> {
         let (
             track_result,
@@ -985,66 +1565,243 @@ impl Client {
             playlist_result,
             show_result,
             episode_result,
+            audiobook_result,
         ) = tokio::try_join!(
-            self.search_specific_type(query, rspotify::model::SearchType::Track),
-            self.search_specific_type(query, rspotify::model::SearchType::Artist),
-            self.search_specific_type(query, rspotify::model::SearchType::Album),
-            self.search_specific_type(query, rspotify::model::SearchType::Playlist),
-            self.search_specific_type(query, rspotify::model::SearchType::Show),
-            self.search_specific_type(query, rspotify::model::SearchType::Episode)
+            self.search_category(query, SearchCategory::Tracks, None),
+            self.search_category(query, SearchCategory::Artists, None),
+            self.search_category(query, SearchCategory::Albums, None),
+            self.search_category(query, SearchCategory::Playlists, None),
+            self.search_category(query, SearchCategory::Shows, None),
+            self.search_category(query, SearchCategory::Episodes, None),
+            self.search_category(query, SearchCategory::Audiobooks, None)
         )?;
 
-        let (tracks, artists, albums, playlists, shows, episodes) = (
-            match track_result {
-                rspotify::model::SearchResult::Tracks(p) => p
-                    .items
-                    .into_iter()
-                    .filter_map(Track::try_from_full_track)
-                    .collect(),
-                _ => anyhow::bail!("expect a track search result"),
-            },
-            match artist_result {
-                rspotify::model::SearchResult::Artists(p) => {
-                    p.items.into_iter().map(std::convert::Into::into).collect()
-                }
-                _ => anyhow::bail!("expect an artist search result"),
-            },
-            match album_result {
-                rspotify::model::SearchResult::Albums(p) => p
-                    .items
-                    .into_iter()
-                    .filter_map(Album::try_from_simplified_album)
-                    .collect(),
-                _ => anyhow::bail!("expect an album search result"),
-            },
-            match playlist_result {
-                rspotify::model::SearchResult::Playlists(p) => {
-                    p.items.into_iter().map(std::convert::Into::into).collect()
-                }
-                _ => anyhow::bail!("expect a playlist search result"),
-            },
-            match show_result {
-                rspotify::model::SearchResult::Shows(p) => {
-                    p.items.into_iter().map(std::convert::Into::into).collect()
-                }
-                _ => anyhow::bail!("expect a show search result"),
-            },
-            match episode_result {
-                rspotify::model::SearchResult::Episodes(p) => {
-                    p.items.into_iter().map(std::convert::Into::into).collect()
-                }
-                _ => anyhow::bail!("expect a episode search result"),
-            },
-        );
+        let mut results = SearchResults::default();
+        for result in [
+            track_result,
+            artist_result,
+            album_result,
+            playlist_result,
+            show_result,
+            episode_result,
+            audiobook_result,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            results.extend(result);
+        }
 
-        Ok(SearchResults {
-            tracks,
-            artists,
-            albums,
-            playlists,
-            shows,
-            episodes,
-        })
+        Ok(results)
+    }
+
+    /// Search for items of a category matching a given query, using the category's configured limit.
+    ///
+    /// Returns `None` without making any request if the category is not shown on the search page.
+    async fn search_category(
+        &self,
+        query: &str,
+        category: SearchCategory,
+        offset: Option<u32>,
+    ) -> Result<Option<SearchResults>> {
+        let Some(limit) = config::get_config().app_config.search.limit(category) else {
+            return Ok(None);
+        };
+
+        let Some(typ) = category.search_type() else {
+            // `rspotify` doesn't support searching audiobooks
+            return Ok(Some(self.search_audiobooks(query, limit, offset).await?));
+        };
+
+        Ok(Some(
+            self.spotify
+                .search(query, typ, None, None, Some(limit), offset)
+                .await?
+                .into(),
+        ))
+    }
+
+    /// Search for audiobooks matching a given query
+    async fn search_audiobooks(
+        &self,
+        query: &str,
+        limit: u32,
+        offset: Option<u32>,
+    ) -> Result<SearchResults> {
+        #[derive(Debug, Deserialize)]
+        struct AuthorData {
+            name: String,
+        }
+        #[derive(Debug, Deserialize)]
+        struct AudiobookData {
+            id: String,
+            name: String,
+            authors: Vec<AuthorData>,
+        }
+        #[derive(Debug, Deserialize)]
+        struct AudiobookSearchResponse {
+            // the API returns `null` for unavailable audiobooks
+            audiobooks: rspotify::model::Page<Option<AudiobookData>>,
+        }
+
+        let limit = limit.to_string();
+        let offset = offset.unwrap_or_default().to_string();
+        let payload = Query::from([
+            ("q", query),
+            ("type", "audiobook"),
+            ("market", "from_token"),
+            ("limit", limit.as_str()),
+            ("offset", offset.as_str()),
+        ]);
+
+        let response = self
+            .http_get::<AudiobookSearchResponse>(
+                &format!("{SPOTIFY_API_ENDPOINT}/search"),
+                &payload,
+                false,
+            )
+            .await?;
+
+        let page = response.audiobooks;
+        let mut results = SearchResults::default();
+        if page.next.is_some() {
+            results
+                .next_offsets
+                .insert(SearchCategory::Audiobooks, page.offset + page.limit);
+        }
+        results.audiobooks = page
+            .items
+            .into_iter()
+            .flatten()
+            .map(|a| Audiobook {
+                id: a.id,
+                name: a.name,
+                authors: a.authors.into_iter().map(|a| a.name).collect(),
+            })
+            .collect();
+        Ok(results)
+    }
+
+    /// Get the playable IDs of an audiobook's chapters, in order
+    async fn audiobook_chapters(&self, audiobook_id: &str) -> Result<Vec<PlayableId<'static>>> {
+        #[derive(Debug, Deserialize)]
+        struct ChapterData {
+            // chapters are played as episodes, so their URIs are episode URIs
+            uri: String,
+        }
+
+        let first_page = self
+            .http_get::<rspotify::model::Page<ChapterData>>(
+                &format!("{SPOTIFY_API_ENDPOINT}/audiobooks/{audiobook_id}/chapters"),
+                &Query::from([("market", "from_token"), ("limit", "50")]),
+                false,
+            )
+            .await?;
+        let chapters = self.all_paging_items(first_page, &Query::new()).await?;
+
+        Ok(chapters
+            .into_iter()
+            .filter_map(|c| {
+                let id = EpisodeId::from_uri(&c.uri).ok()?.into_static();
+                Some(PlayableId::Episode(id))
+            })
+            .collect())
+    }
+
+    /// Search a query and add the top matched track to the queue.
+    ///
+    /// Returns the queued track or `None` if no track matches the query.
+    pub async fn search_and_queue(&self, query: &str) -> Result<Option<Track>> {
+        let results: SearchResults = self
+            .search_specific_type(query, rspotify::model::SearchType::Track)
+            .await?
+            .into();
+        let Some(track) = results.tracks.into_iter().next() else {
+            return Ok(None);
+        };
+
+        self.add_item_to_queue(PlayableId::Track(track.id.clone()), None)
+            .await?;
+        Ok(Some(track))
+    }
+
+    /// Clear the user's queue.
+    ///
+    /// Spotify APIs don't support clearing the queue, so the current playback is restarted
+    /// from the playing item and its progress, which drops the queued items.
+    pub async fn clear_queue(&self) -> Result<()> {
+        let playback = self
+            .current_playback2()
+            .await?
+            .context("no active playback found!")?;
+        let id = playback
+            .item
+            .as_ref()
+            .and_then(rspotify::model::PlayableItem::id)
+            .context("playback has no playing item!")?
+            .into_static();
+        let device_id = playback.device.id.as_deref();
+
+        let new_playback = match crate::state::playback_context_id(&playback) {
+            Some(context_id) => {
+                Playback::Context(context_id, Some(rspotify::model::Offset::Uri(id.uri())))
+            }
+            None => Playback::URIs(vec![id], None),
+        };
+        self.start_playback(new_playback, device_id).await?;
+
+        if let Some(progress) = playback.progress {
+            self.seek_track(progress, device_id).await?;
+        }
+        if !playback.is_playing {
+            self.pause_playback(device_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Transfer the current playback to a device, preserving the playback's progress
+    /// and play/pause state.
+    ///
+    /// Spotify sometimes restarts the playing item upon a playback transfer,
+    /// so the progress and the play/pause state are restored after the transfer.
+    pub async fn transfer_playback_preserving_state(&self, device_id: &str) -> Result<()> {
+        let playback = self.current_playback2().await?;
+        let fetched_at = std::time::Instant::now();
+        let is_playing = playback.as_ref().is_some_and(|p| p.is_playing);
+
+        self.transfer_playback(device_id, Some(is_playing)).await?;
+        if let Err(err) = self.apply_device_volume(device_id).await {
+            tracing::warn!("Failed to apply the device's volume: {err:#}");
+        }
+
+        let Some(playback) = playback else {
+            return Ok(());
+        };
+        if let Some(mut progress) = playback.progress {
+            if is_playing {
+                // account for the time spent on transferring the playback
+                progress += chrono::TimeDelta::from_std(fetched_at.elapsed()).unwrap_or_default();
+            }
+            self.seek_track(progress, Some(device_id)).await?;
+        }
+        if !is_playing {
+            self.pause_playback(Some(device_id)).await?;
+        }
+        Ok(())
+    }
+
+    /// Search for more items of a specific category matching a given query,
+    /// starting from the given offset
+    pub async fn search_more(
+        &self,
+        query: &str,
+        category: SearchCategory,
+        offset: u32,
+    ) -> Result<SearchResults> {
+        Ok(self
+            .search_category(query, category, Some(offset))
+            .await?
+            .unwrap_or_default())
     }
 
     /// Search for items of a specific type matching a given query
@@ -1062,7 +1819,7 @@ impl Client {
     /// Add a playable item to a playlist
     pub async fn add_item_to_playlist(
         &self,
-        state: &SharedState,
+        state: Option<&SharedState>,
         playlist_id: PlaylistId<'_>,
         playable_id: PlayableId<'_>,
     ) -> Result<()> {
@@ -1078,7 +1835,9 @@ impl Client {
             .await?;
 
         // After adding a new track to a playlist, remove the cache of that playlist to force refetching new data
-        state.data.write().caches.context.remove(&playlist_id.uri());
+        if let Some(state) = state {
+            state.data.write().caches.context.remove(&playlist_id.uri());
+        }
 
         Ok(())
     }
@@ -1086,7 +1845,7 @@ impl Client {
     /// Remove a track from a playlist
     pub async fn delete_track_from_playlist(
         &self,
-        state: &SharedState,
+        state: Option<&SharedState>,
         playlist_id: PlaylistId<'_>,
         track_id: TrackId<'_>,
     ) -> Result<()> {
@@ -1099,14 +1858,16 @@ impl Client {
         .await?;
 
         // After making a delete request, update the playlist in-memory data stored inside the app caches.
-        if let Some(Context::Playlist { tracks, .. }) = state
-            .data
-            .write()
-            .caches
-            .context
-            .get_mut(&playlist_id.uri())
-        {
-            tracks.retain(|t| t.id != track_id);
+        if let Some(state) = state {
+            if let Some(Context::Playlist { tracks, .. }) = state
+                .data
+                .write()
+                .caches
+                .context
+                .get_mut(&playlist_id.uri())
+            {
+                tracks.retain(|t| t.id != track_id);
+            }
         }
 
         Ok(())
@@ -1153,7 +1914,7 @@ impl Client {
     }
 
     /// Add a Spotify item to current user's library.
-    async fn add_to_library(&self, state: &SharedState, item: Item) -> Result<()> {
+    pub async fn add_to_library(&self, state: &SharedState, item: Item) -> Result<()> {
         // Before adding new item, checks if that item already exists in the library to avoid adding a duplicated item.
         match item {
             Item::Track(track) => {
@@ -1170,6 +1931,7 @@ impl Client {
                         .user_data
                         .saved_tracks
                         .insert(track.id.uri(), track);
+                    state.publish_event(PlaybackEvent::LikedTracksChange);
                 }
             }
             Item::Album(album) => {
@@ -1234,12 +1996,13 @@ impl Client {
     }
 
     // Delete a Spotify item from user's library
-    async fn delete_from_library(&self, state: &SharedState, id: ItemId) -> Result<()> {
+    pub async fn delete_from_library(&self, state: &SharedState, id: ItemId) -> Result<()> {
         match id {
             ItemId::Track(id) => {
                 let uri = id.uri();
                 self.current_user_saved_tracks_delete([id]).await?;
                 state.data.write().user_data.saved_tracks.remove(&uri);
+                state.publish_event(PlaybackEvent::LikedTracksChange);
             }
             ItemId::Album(id) => {
                 state
@@ -1297,6 +2060,23 @@ impl Client {
 
     /// Get a playlist context data
     pub async fn playlist_context(&self, playlist_id: PlaylistId<'_>) -> Result<Context> {
+        let (mut context, mut next) = self.playlist_context_first_page(playlist_id).await?;
+        while let Some(url) = next {
+            let (mut tracks, next_page) = self.playlist_tracks_page(&url).await?;
+            if let Some(context_tracks) = context.tracks_mut() {
+                context_tracks.append(&mut tracks);
+            }
+            next = next_page;
+        }
+        Ok(context)
+    }
+
+    /// Get a playlist context data with the first page of the playlist's tracks,
+    /// returning the context and the URL of the tracks' next page if any
+    async fn playlist_context_first_page(
+        &self,
+        playlist_id: PlaylistId<'_>,
+    ) -> Result<(Context, Option<String>)> {
         let playlist_uri = playlist_id.uri();
         tracing::info!("Get playlist context: {}", playlist_uri);
 
@@ -1305,7 +2085,7 @@ impl Client {
         // let playlist = self
         //     .playlist(playlist_id, None, Some(Market::FromToken))
         //     .await?;
-        let playlist = self
+        let mut playlist = self
             .http_get::<rspotify::model::FullPlaylist>(
                 &format!("{SPOTIFY_API_ENDPOINT}/playlists/{}", playlist_id.id()),
                 &market_query(),
@@ -1313,19 +2093,104 @@ impl Client {
             )
             .await?;
 
-        // get the playlist's tracks
-        let first_page = playlist.tracks.clone();
-        let tracks = self
-            .all_paging_items(first_page, &market_query())
-            .await?
+        // get the playlist's first page of tracks
+        let first_page = std::mem::take(&mut playlist.tracks.items);
+        let next = playlist.tracks.next.clone();
+        let tracks = first_page
             .into_iter()
             .filter_map(Track::try_from_playlist_item)
             .collect::<Vec<_>>();
 
-        Ok(Context::Playlist {
-            playlist: playlist.into(),
-            tracks,
-        })
+        Ok((
+            Context::Playlist {
+                playlist: playlist.into(),
+                tracks,
+            },
+            next,
+        ))
+    }
+
+    /// Get a page of a playlist's tracks from the page's URL,
+    /// returning the tracks and the URL of the next page if any
+    async fn playlist_tracks_page(&self, url: &str) -> Result<(Vec<Track>, Option<String>)> {
+        let page = self
+            .http_get::<rspotify::model::Page<rspotify::model::PlaylistItem>>(
+                url,
+                &market_query(),
+                false,
+            )
+            .await?;
+        let next = if page.items.is_empty() {
+            None
+        } else {
+            page.next
+        };
+        let tracks = page
+            .items
+            .into_iter()
+            .filter_map(Track::try_from_playlist_item)
+            .collect();
+        Ok((tracks, next))
+    }
+
+    /// Load the remaining tracks of a cached playlist context in the background, starting from
+    /// the tracks' page at `next`, so that a large playlist can be browsed while its tracks
+    /// are being loaded. Each loaded page is appended to the cached context.
+    ///
+    /// The loading is cancelled once the playlist's page is left, and the partially loaded
+    /// context is removed from the cache, so the playlist is reloaded upon visiting it again.
+    fn load_playlist_tracks_in_background(&self, state: &SharedState, uri: String, next: String) {
+        let client = self.clone();
+        let state = state.clone();
+        tokio::task::spawn(async move {
+            let mut next = Some(next);
+            while let Some(url) = next {
+                let is_shown = matches!(
+                    state.ui.lock().current_page(),
+                    PageState::Context { id: Some(id), .. } if id.uri() == uri
+                );
+                if !is_shown {
+                    tracing::info!("Cancelled loading the tracks of {uri}");
+                    state.data.write().caches.context.remove(&uri);
+                    return;
+                }
+
+                let mut tracks = match client.playlist_tracks_page(&url).await {
+                    Ok((tracks, next_page)) => {
+                        next = next_page;
+                        tracks
+                    }
+                    Err(err) => {
+                        tracing::error!("Failed to load the tracks of {uri}: {err:#}");
+                        state.data.write().caches.context.remove(&uri);
+                        return;
+                    }
+                };
+
+                let view = state.ui.lock().context_views.get(&uri).cloned();
+                let mut data = state.data.write();
+                let Some(context_tracks) = data
+                    .caches
+                    .context
+                    .get_mut(&uri)
+                    .and_then(Context::tracks_mut)
+                else {
+                    // the context is no longer cached, e.g. after its cache entry expired
+                    return;
+                };
+                match view {
+                    // keep the context's tracks in the order of the page's view
+                    Some(view) => {
+                        if view.reversed {
+                            context_tracks.reverse();
+                        }
+                        context_tracks.append(&mut tracks);
+                        view.sort_tracks(context_tracks);
+                    }
+                    None => context_tracks.append(&mut tracks),
+                }
+            }
+        });
     }
 
     /// Get an album context data
@@ -1462,6 +2327,34 @@ impl Client {
                 .replace("\"name\":null", "\"name\":\"\"")
         }
 
+        let text = self.http_get_text(url, payload, use_user_client_id).await?;
+
+        Ok(serde_json::from_str(&process_spotify_api_response(&text))?)
+    }
+
+    /// Make a GET HTTP request to the Spotify server, returning the response's body
+    async fn http_get_text(
+        &self,
+        url: &str,
+        payload: &Query<'_>,
+        use_user_client_id: bool,
+    ) -> Result<String> {
+        if let Some(attached) = self.attached.get() {
+            let path = url
+                .strip_prefix(SPOTIFY_API_ENDPOINT)
+                .and_then(|path| path.strip_prefix('/'))
+                .with_context(|| format!("{url} is not a Spotify Web API endpoint"))?;
+            return attached
+                .api_request(
+                    crate::cli::ApiMethod::Get,
+                    path,
+                    payload,
+                    None,
+                    use_user_client_id,
+                )
+                .await;
+        }
+
         let access_token = if use_user_client_id {
             self.access_token_from_user_client_id().await
         } else {
@@ -1469,28 +2362,53 @@ impl Client {
         }
         .context("get access token")?;
 
-        tracing::debug!("{access_token} {url}");
+        tracing::debug!("{url}");
 
-        let response = self
-            .http
-            .get(url)
-            .query(payload)
-            .header(
-                reqwest::header::AUTHORIZATION,
-                format!("Bearer {access_token}"),
-            )
-            .send()
-            .await?;
+        let request = async {
+            let response = self
+                .http
+                .get(url)
+                .query(payload)
+                .header(
+                    reqwest::header::AUTHORIZATION,
+                    format!("Bearer {access_token}"),
+                )
+                .send()
+                .await?;
 
-        let status = response.status();
-        let text = process_spotify_api_response(&response.text().await?);
-        tracing::debug!("{text}");
+            let status = response.status();
+            let text = response.text().await?;
+            tracing::debug!("{text}");
 
-        if status != StatusCode::OK {
-            anyhow::bail!("failed to send a Spotify API request {url}: {text}");
-        }
+            if status != StatusCode::OK {
+                anyhow::bail!("failed to send a Spotify API request {url}: {text}");
+            }
+            Ok(text)
+        };
+        metrics::record_api_request("GET", url, request).await
+    }
 
-        Ok(serde_json::from_str(&text)?)
+    /// Send a Spotify Web API request of a UI attached to the application
+    /// with the session's access token
+    pub async fn send_api_request(&self, request: crate::cli::ApiRequest) -> Result<String> {
+        use crate::cli::ApiMethod;
+
+        let query = request
+            .query
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect::<Query>();
+        let payload = request.payload.unwrap_or_default();
+        Ok(match request.method {
+            ApiMethod::Get if request.use_user_client_id => {
+                let url = format!("{SPOTIFY_API_ENDPOINT}/{}", request.path);
+                self.http_get_text(&url, &query, true).await?
+            }
+            ApiMethod::Get => self.api_get(&request.path, &query).await?,
+            ApiMethod::Post => self.api_post(&request.path, &payload).await?,
+            ApiMethod::Put => self.api_put(&request.path, &payload).await?,
+            ApiMethod::Delete => self.api_delete(&request.path, &payload).await?,
+        })
     }
 
     /// Get all paging items starting from a pagination object of the first page
@@ -1550,12 +2468,19 @@ impl Client {
         state: &SharedState,
         reset_buffered_playback: bool,
     ) -> Result<()> {
-        let new_playback = {
+        let (new_playback, is_playing_changed) = {
             // update the playback state
             let playback = self.current_playback2().await?;
             let mut player = state.player.write();
 
+            let prev_is_playing = player.playback.as_ref().map(|p| p.is_playing);
+            let prev_progress = player.playback_progress();
             let prev_item = player.currently_playing();
+            // the previous item stops playing upon a new playback, so its entry
+            // in the listening history is created from its last progress
+            let prev_history_entry = prev_item
+                .zip(prev_progress)
+                .and_then(|(item, progress)| ListeningHistoryEntry::new(item, progress));
 
             let prev_name = match prev_item {
                 Some(rspotify::model::PlayableItem::Track(track)) => track.name.clone(),
@@ -1575,6 +2500,10 @@ impl Client {
             };
 
             let new_playback = prev_name != curr_name && !curr_name.is_empty();
+            let curr_is_playing = player.playback.as_ref().map(|p| p.is_playing);
+            let is_playing_changed = prev_is_playing.is_some()
+                && curr_is_playing.is_some()
+                && prev_is_playing != curr_is_playing;
             // check if we need to update the buffered playback
             let needs_update = match (&player.buffered_playback, &player.playback) {
                 (Some(bp), Some(p)) => bp.device_id != p.device.id || new_playback,
@@ -1599,8 +2528,24 @@ impl Client {
                 });
             }
 
-            new_playback
+            if new_playback {
+                if let Some(entry) = prev_history_entry {
+                    Self::record_listening_history_entry(state, &entry);
+                }
+            }
+
+            (new_playback, is_playing_changed)
         };
+        state.publish_playback_events();
+
+        if new_playback || is_playing_changed {
+            Self::handle_event_hooks(state, new_playback, is_playing_changed);
+        }
+
+        #[cfg(feature = "notify")]
+        if is_playing_changed && !new_playback {
+            self.notify_playing_changed(state);
+        }
 
         if !new_playback {
             return Ok(());
@@ -1610,6 +2555,121 @@ impl Client {
         Ok(())
     }
 
+    /// Execute the user-defined event hook commands upon playback changes
+    fn handle_event_hooks(state: &SharedState, new_playback: bool, is_playing_changed: bool) {
+        let configs = config::get_config();
+        let Some(playback) = state.player.read().playback.clone() else {
+            return;
+        };
+
+        if new_playback {
+            if let Some(ref cmd) = configs.app_config.on_track_change {
+                Self::execute_event_hook("track_change", cmd, &playback);
+            }
+        }
+        if is_playing_changed {
+            let (event, hook) = if playback.is_playing {
+                ("play", &configs.app_config.on_play)
+            } else {
+                ("pause", &configs.app_config.on_pause)
+            };
+            if let Some(cmd) = hook {
+                Self::execute_event_hook(event, cmd, &playback);
+            }
+        }
+    }
+
+    /// Execute an event hook command in a shell, passing the playback's metadata
+    /// via `SPOTIFY_PLAYER_*` environment variables
+    fn execute_event_hook(
+        event: &'static str,
+        cmd: &str,
+        playback: &rspotify::model::CurrentPlaybackContext,
+    ) {
+        let (shell, shell_arg) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+
+        let mut command = std::process::Command::new(shell);
+        command
+            .arg(shell_arg)
+            .arg(cmd)
+            .env("SPOTIFY_PLAYER_EVENT", event)
+            .env(
+                "SPOTIFY_PLAYER_STATUS",
+                if playback.is_playing {
+                    "playing"
+                } else {
+                    "paused"
+                },
+            )
+            .env("SPOTIFY_PLAYER_DEVICE", &playback.device.name)
+            .env(
+                "SPOTIFY_PLAYER_POSITION_MS",
+                playback
+                    .progress
+                    .map(|p| p.num_milliseconds())
+                    .unwrap_or_default()
+                    .to_string(),
+            );
+
+        if let Some(ref item) = playback.item {
+            for (placeholder, var) in [
+                ("{track}", "SPOTIFY_PLAYER_TRACK"),
+                ("{artists}", "SPOTIFY_PLAYER_ARTISTS"),
+                ("{album}", "SPOTIFY_PLAYER_ALBUM"),
+            ] {
+                if let Some(value) = crate::utils::get_playable_placeholder_value(placeholder, item)
+                {
+                    command.env(var, value);
+                }
+            }
+
+            let (uri, duration) = match item {
+                rspotify::model::PlayableItem::Track(track) => {
+                    (track.id.as_ref().map(Id::uri), track.duration)
+                }
+                rspotify::model::PlayableItem::Episode(episode) => {
+                    (Some(episode.id.uri()), episode.duration)
+                }
+            };
+            if let Some(uri) = uri {
+                command.env("SPOTIFY_PLAYER_URI", uri);
+            }
+            command.env(
+                "SPOTIFY_PLAYER_DURATION_MS",
+                duration.num_milliseconds().to_string(),
+            );
+        }
+
+        // run the command in a separate thread to not block the playback's update
+        std::thread::spawn(move || match command.output() {
+            Ok(output) if !output.status.success() => {
+                tracing::warn!(
+                    "Event hook command for {event} event failed: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                );
+            }
+            Err(err) => {
+                tracing::warn!("Failed to execute event hook command: {err:#}");
+            }
+            Ok(_) => {}
+        });
+    }
+
+    /// Append an entry of a played item to the listening history, if enabled
+    fn record_listening_history_entry(state: &SharedState, entry: &ListeningHistoryEntry) {
+        let configs = config::get_config();
+        if !configs.app_config.enable_listening_history || *state.private_session.lock() {
+            return;
+        }
+        if let Err(err) = append_listening_history_entry(&configs.cache_folder, entry) {
+            tracing::error!("Failed to append to the listening history: {err:#}");
+        }
+    }
+
     // Handle new track event
     async fn handle_new_playback_event(&self, state: &SharedState) -> Result<()> {
         let configs = config::get_config();
@@ -1622,6 +2682,8 @@ impl Client {
             track_or_episode.clone()
         };
 
+        metrics::record_track_played();
+
         let url = match curr_item {
             rspotify::model::PlayableItem::Track(ref track) => {
                 crate::utils::get_track_album_image_url(track)
@@ -1633,36 +2695,18 @@ impl Client {
             }
         };
 
-        let filename = (match curr_item {
-            rspotify::model::PlayableItem::Track(ref track) => {
-                format!(
-                    "{}-{}-cover-{}.jpg",
-                    track.album.name,
-                    track.album.artists.first().unwrap().name,
-                    // first 6 characters of the album's id
-                    &track.album.id.as_ref().unwrap().id()[..6]
-                )
-            }
-            rspotify::model::PlayableItem::Episode(ref episode) => {
-                format!(
-                    "{}-{}-cover-{}.jpg",
-                    episode.show.name,
-                    episode.show.publisher,
-                    // first 6 characters of the show's id
-                    &episode.show.id.as_ref().id()[..6]
-                )
-            }
-        })
-        .replace('/', ""); // remove invalid characters from the file's name
-        let path = configs.cache_folder.join("image").join(filename);
+        let path = crate::utils::get_cover_image_path(&curr_item);
 
         if configs.app_config.enable_cover_image_cache {
-            self.retrieve_image(url, &path, true).await?;
+            self.retrieve_image(url, path.as_deref(), true).await?;
         }
 
         #[cfg(feature = "image")]
-        if !state.data.read().caches.images.contains_key(url) {
-            let bytes = self.retrieve_image(url, &path, false).await?;
+        if !metrics::record_cache_lookup(
+            "images",
+            state.data.read().caches.images.contains_key(url),
+        ) {
+            let bytes = self.retrieve_image(url, path.as_deref(), false).await?;
 
             #[cfg(not(feature = "pixelate"))]
             let image =
@@ -1685,16 +2729,9 @@ impl Client {
         }
 
         // notify user about the playback's change if any
-        #[cfg(all(feature = "notify", feature = "streaming"))]
-        if configs.app_config.enable_notify
-            && (!configs.app_config.notify_streaming_only || self.stream_conn.lock().is_some())
-        {
-            Self::notify_new_playback(&curr_item, &path)?;
-        }
-
-        #[cfg(all(feature = "notify", not(feature = "streaming")))]
-        if configs.app_config.enable_notify {
-            Self::notify_new_playback(&curr_item, &path)?;
+        #[cfg(feature = "notify")]
+        if self.should_notify(state, config::NotifyEvent::TrackChange) {
+            Self::notify_new_playback(&curr_item, path.as_deref(), None)?;
         }
 
         Ok(())
@@ -1735,71 +2772,86 @@ impl Client {
     }
 
     #[cfg(feature = "notify")]
-    /// Create a notification for a new playback
+    /// Check if a notification should be sent for a playback event
+    fn should_notify(&self, state: &SharedState, event: config::NotifyEvent) -> bool {
+        let configs = config::get_config();
+        if !configs.app_config.enable_notify || !configs.app_config.notify_events.contains(&event) {
+            return false;
+        }
+
+        if *state.private_session.lock() {
+            return false;
+        }
+
+        #[cfg(feature = "streaming")]
+        if configs.app_config.notify_streaming_only && self.stream_conn.lock().is_none() {
+            return false;
+        }
+
+        true
+    }
+
+    #[cfg(feature = "notify")]
+    /// Notify user about the playback being resumed or paused
+    fn notify_playing_changed(&self, state: &SharedState) {
+        let (item, is_playing) = {
+            let player = state.player.read();
+            let Some(item) = player.currently_playing().cloned() else {
+                return;
+            };
+            (item, player.playback.as_ref().is_some_and(|p| p.is_playing))
+        };
+
+        let (event, status) = if is_playing {
+            (config::NotifyEvent::Play, "Playing")
+        } else {
+            (config::NotifyEvent::Pause, "Paused")
+        };
+        if !self.should_notify(state, event) {
+            return;
+        }
+        if let Err(err) = Self::notify_new_playback(
+            &item,
+            crate::utils::get_cover_image_path(&item).as_deref(),
+            Some(status),
+        ) {
+            tracing::warn!("Failed to notify about the playback's change: {err:#}");
+        }
+    }
+
+    #[cfg(feature = "notify")]
+    /// Create a notification for a new playback.
+    /// If `status` is specified, it's prepended to the notification's summary.
     fn notify_new_playback(
         playable: &rspotify::model::PlayableItem,
-        cover_img_path: &std::path::Path,
+        cover_img_path: Option<&std::path::Path>,
+        status: Option<&str>,
     ) -> Result<()> {
         let mut n = notify_rust::Notification::new();
 
-        let re = regex::Regex::new(r"\{.*?\}").unwrap();
-        // Generate a text described a track from a format string.
-        // For example, a format string "{track} - {artists}" will generate
+        // Generate a text described a track from a format template.
+        // For example, a format template "{track} - {artists}" will generate
         // a text consisting of the track's name followed by a dash then artists' names.
-        let get_text_from_format_str = |format_str: &str| {
-            let mut text = String::new();
-
-            let mut ptr = 0;
-            for m in re.find_iter(format_str) {
-                let s = m.start();
-                let e = m.end();
-
-                if ptr < s {
-                    text += &format_str[ptr..s];
-                }
-                ptr = e;
-                match m.as_str() {
-                    "{track}" => {
-                        let name = match playable {
-                            rspotify::model::PlayableItem::Track(ref track) => &track.name,
-                            rspotify::model::PlayableItem::Episode(ref episode) => &episode.name,
-                        };
-                        text += name;
-                    }
-                    "{artists}" => {
-                        if let rspotify::model::PlayableItem::Track(ref track) = playable {
-                            text += &crate::utils::map_join(&track.artists, |a| &a.name, ", ");
-                        }
-                    }
-                    "{album}" => match playable {
-                        rspotify::model::PlayableItem::Track(ref track) => {
-                            text += &track.album.name;
-                        }
-                        rspotify::model::PlayableItem::Episode(ref episode) => {
-                            text += &episode.show.name;
-                        }
-                    },
-                    &_ => {}
-                }
-            }
-            if ptr < format_str.len() {
-                text += &format_str[ptr..];
-            }
-
-            text
+        let get_text_from_format_template = |template: &config::FormatTemplate| {
+            crate::utils::format_with_placeholders(template, |placeholder| {
+                crate::utils::get_playable_placeholder_value(placeholder, playable)
+            })
         };
 
         let configs = config::get_config();
 
+        let mut summary = get_text_from_format_template(&configs.app_config.notify_format.summary);
+        if let Some(status) = status {
+            summary = format!("{status}: {summary}");
+        }
+
         n.appname("spotify_player")
-            .summary(&get_text_from_format_str(
-                &configs.app_config.notify_format.summary,
-            ))
-            .body(&get_text_from_format_str(
+            .summary(&summary)
+            .body(&get_text_from_format_template(
                 &configs.app_config.notify_format.body,
             ));
-        if cover_img_path.exists() {
-            n.icon(cover_img_path.to_str().context("valid cover_img_path")?);
+        if let Some(path) = cover_img_path.filter(|path| path.exists()) {
+            n.icon(path.to_str().context("valid cover_img_path")?);
         }
         if configs.app_config.notify_timeout_in_secs > 0 {
             n.timeout(std::time::Duration::from_secs(
@@ -1811,15 +2863,15 @@ impl Client {
         Ok(())
     }
 
-    /// Retrieve an image from a `url` or a cached `path`.
+    /// Retrieve an image from a `url` or a cached `path` (if any).
     /// If `saved` is specified, the retrieved image is saved to the cached `path`.
     async fn retrieve_image(
         &self,
         url: &str,
-        path: &std::path::Path,
+        path: Option<&std::path::Path>,
         saved: bool,
     ) -> Result<Vec<u8>> {
-        if path.exists() {
+        if let Some(path) = path.filter(|path| path.exists()) {
             tracing::debug!("Retrieving image from file: {}", path.display());
             return Ok(std::fs::read(path)?);
         }
@@ -1835,7 +2887,7 @@ impl Client {
             .bytes()
             .await?;
 
-        if saved {
+        if let Some(path) = path.filter(|_| saved) {
             tracing::info!("Saving the retrieved image into {}", path.display());
             let mut file = std::fs::File::create(path)?;
             file.write_all(&bytes)?;
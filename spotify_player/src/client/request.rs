@@ -1,3 +1,4 @@
+use crate::config::SearchCategory;
 use crate::state::{
     AlbumId, Category, ContextId, Item, ItemId, PlayableId, Playback, PlaylistId, TrackId,
 };
@@ -12,7 +13,9 @@ pub enum PlayerRequest {
     ResumePause,
     SeekTrack(chrono::Duration),
     Repeat,
+    SetRepeat(rspotify::model::RepeatState),
     Shuffle,
+    SetShuffle(bool),
     Volume(u8),
     ToggleMute,
     TransferPlayback(String, bool),
@@ -40,6 +43,15 @@ pub enum ClientRequest {
         seed_name: String,
     },
     Search(String),
+    SearchMore {
+        query: String,
+        category: SearchCategory,
+    },
+    SearchAndQueue(String),
+    /// Copy a song.link/album.link universal link of a Spotify URL
+    CopyUniversalLink(String),
+    /// Play an audiobook's chapters from the beginning
+    PlayAudiobook(String),
     AddPlayableToQueue(PlayableId<'static>),
     AddAlbumToQueue(AlbumId<'static>),
     AddPlayableToPlaylist(PlaylistId<'static>, PlayableId<'static>),
@@ -60,10 +72,69 @@ pub enum ClientRequest {
     },
     #[cfg(feature = "streaming")]
     RestartIntegratedClient,
+    #[cfg(feature = "streaming")]
+    SetBitrate(u16),
+    #[cfg(feature = "streaming")]
+    SetAutoplay(bool),
+    #[cfg(feature = "streaming")]
+    SetEqualizer(bool),
+    #[cfg(feature = "streaming")]
+    SetNightMode(bool),
     CreatePlaylist {
         playlist_name: String,
         public: bool,
         collab: bool,
         desc: String,
     },
+    /// Requests handled one after another, e.g. the requests of a macro
+    Sequence(Vec<ClientRequest>),
+}
+
+impl ClientRequest {
+    /// Gets the name of the request's variant, e.g. to label the request's metrics
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::GetCurrentUser => "GetCurrentUser",
+            Self::GetDevices => "GetDevices",
+            Self::GetBrowseCategories => "GetBrowseCategories",
+            Self::GetBrowseCategoryPlaylists(_) => "GetBrowseCategoryPlaylists",
+            Self::GetUserPlaylists => "GetUserPlaylists",
+            Self::GetUserSavedAlbums => "GetUserSavedAlbums",
+            Self::GetUserSavedShows => "GetUserSavedShows",
+            Self::GetUserFollowedArtists => "GetUserFollowedArtists",
+            Self::GetUserSavedTracks => "GetUserSavedTracks",
+            Self::GetUserTopTracks => "GetUserTopTracks",
+            Self::GetUserRecentlyPlayedTracks => "GetUserRecentlyPlayedTracks",
+            Self::GetContext(_) => "GetContext",
+            Self::GetCurrentPlayback => "GetCurrentPlayback",
+            Self::GetRadioTracks { .. } => "GetRadioTracks",
+            Self::Search(_) => "Search",
+            Self::SearchMore { .. } => "SearchMore",
+            Self::SearchAndQueue(_) => "SearchAndQueue",
+            Self::CopyUniversalLink(_) => "CopyUniversalLink",
+            Self::PlayAudiobook(_) => "PlayAudiobook",
+            Self::AddPlayableToQueue(_) => "AddPlayableToQueue",
+            Self::AddAlbumToQueue(_) => "AddAlbumToQueue",
+            Self::AddPlayableToPlaylist(..) => "AddPlayableToPlaylist",
+            Self::DeleteTrackFromPlaylist(..) => "DeleteTrackFromPlaylist",
+            Self::ReorderPlaylistItems { .. } => "ReorderPlaylistItems",
+            Self::AddToLibrary(_) => "AddToLibrary",
+            Self::DeleteFromLibrary(_) => "DeleteFromLibrary",
+            Self::Player(_) => "Player",
+            Self::GetCurrentUserQueue => "GetCurrentUserQueue",
+            Self::GetLyrics { .. } => "GetLyrics",
+            #[cfg(feature = "streaming")]
+            Self::RestartIntegratedClient => "RestartIntegratedClient",
+            #[cfg(feature = "streaming")]
+            Self::SetBitrate(_) => "SetBitrate",
+            #[cfg(feature = "streaming")]
+            Self::SetAutoplay(_) => "SetAutoplay",
+            #[cfg(feature = "streaming")]
+            Self::SetEqualizer(_) => "SetEqualizer",
+            #[cfg(feature = "streaming")]
+            Self::SetNightMode(_) => "SetNightMode",
+            Self::CreatePlaylist { .. } => "CreatePlaylist",
+            Self::Sequence(_) => "Sequence",
+        }
+    }
 }
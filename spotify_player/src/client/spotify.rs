@@ -3,13 +3,65 @@ use librespot_core::session::Session;
 use maybe_async::maybe_async;
 use rspotify::{
     clients::{BaseClient, OAuthClient},
-    http::HttpClient,
+    http::{BaseHttpClient, HttpClient, Query},
     sync::Mutex,
-    ClientResult, Config, Credentials, OAuth, Token,
+    ClientError, ClientResult, Config, Credentials, OAuth, Token,
 };
+use serde_json::Value;
 use std::{fmt, sync::Arc};
 
-use crate::{auth::SPOTIFY_CLIENT_ID, config, token};
+use crate::{
+    auth::SPOTIFY_CLIENT_ID,
+    cli::{ApiMethod, ApiRequest, RunningApplication, SessionRequest, SharedSession},
+    config, metrics, token,
+};
+
+/// A running application whose Spotify session is shared with the client
+#[derive(Clone)]
+pub struct AttachedApplication {
+    pub application: RunningApplication,
+    /// the name and the ID of the application's integrated device, if any
+    pub device: Option<(String, String)>,
+}
+
+impl AttachedApplication {
+    /// Send a Spotify Web API request via the application, returning the response's body
+    pub async fn api_request(
+        &self,
+        method: ApiMethod,
+        path: &str,
+        query: &Query<'_>,
+        payload: Option<&Value>,
+        use_user_client_id: bool,
+    ) -> Result<String> {
+        let request = ApiRequest {
+            method,
+            path: path.to_string(),
+            query: query
+                .iter()
+                .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+                .collect(),
+            payload: payload.cloned(),
+            use_user_client_id,
+        };
+        self.application
+            .session_request(SessionRequest::Api(request))
+            .await
+    }
+
+    /// Send a Spotify Web API request of `rspotify` via the application
+    async fn rspotify_request(
+        &self,
+        method: ApiMethod,
+        path: &str,
+        query: &Query<'_>,
+        payload: Option<&Value>,
+    ) -> ClientResult<String> {
+        self.api_request(method, path, query, payload, false)
+            .await
+            .map_err(|err| ClientError::Io(std::io::Error::other(format!("{err:#}"))))
+    }
+}
 
 #[derive(Clone, Default)]
 /// A Spotify client to interact with Spotify API server
@@ -26,6 +78,8 @@ pub struct Spotify {
     /// (<https://developer.spotify.com/documentation/web-api/reference/get-a-users-available-devices>)
     user_client_id: String,
     pub(crate) session: Arc<tokio::sync::Mutex<Option<Session>>>,
+    /// The running application the client is attached to, if any
+    pub(crate) attached: std::sync::OnceLock<AttachedApplication>,
 }
 
 #[allow(clippy::missing_fields_in_debug)] // Seems like not all fields are necessary in debug
@@ -57,9 +111,28 @@ impl Spotify {
                 .get_client_id()
                 .expect("get client_id"),
             session: Arc::new(tokio::sync::Mutex::new(None)),
+            attached: std::sync::OnceLock::new(),
         }
     }
 
+    /// Attach the client to a running application, using the application's Spotify session
+    /// instead of creating a new session
+    pub async fn attach(&self, application: RunningApplication) -> Result<()> {
+        let session: SharedSession = application.session_request(SessionRequest::Attach).await?;
+        self.attached
+            .set(AttachedApplication {
+                application,
+                device: session.device,
+            })
+            .ok();
+        Ok(())
+    }
+
+    /// Check if the client is attached to a running application
+    pub fn is_attached(&self) -> bool {
+        self.attached.get().is_some()
+    }
+
     pub async fn session(&self) -> Session {
         self.session
             .lock()
@@ -119,9 +192,17 @@ impl BaseClient for Spotify {
     }
 
     async fn refetch_token(&self) -> ClientResult<Option<Token>> {
-        let session = self.session().await;
         let old_token = self.token.lock().await.unwrap().clone();
 
+        if self.is_attached() {
+            // the session's token isn't shared with an attached client,
+            // whose API requests are sent by the application it's attached to
+            tracing::error!("Failed to get a new token: the client is attached to an application");
+            return Ok(old_token);
+        }
+
+        let session = self.session().await;
+
         if session.is_invalid() {
             tracing::error!("Failed to get a new token: invalid session");
             return Ok(old_token);
@@ -135,6 +216,58 @@ impl BaseClient for Spotify {
             }
         }
     }
+
+    // The below methods override the default ones to record the metrics
+    // of the Spotify API requests sent by `rspotify`, and to send the requests
+    // of an attached client via the application it's attached to
+
+    async fn api_get(&self, url: &str, payload: &Query<'_>) -> ClientResult<String> {
+        if let Some(attached) = self.attached.get() {
+            return attached
+                .rspotify_request(ApiMethod::Get, url, payload, None)
+                .await;
+        }
+        let url = self.api_url(url);
+        let headers = self.auth_headers().await?;
+        let request = self.http.get(&url, Some(&headers), payload);
+        Ok(metrics::record_api_request("GET", &url, request).await?)
+    }
+
+    async fn api_post(&self, url: &str, payload: &Value) -> ClientResult<String> {
+        if let Some(attached) = self.attached.get() {
+            return attached
+                .rspotify_request(ApiMethod::Post, url, &Query::new(), Some(payload))
+                .await;
+        }
+        let url = self.api_url(url);
+        let headers = self.auth_headers().await?;
+        let request = self.http.post(&url, Some(&headers), payload);
+        Ok(metrics::record_api_request("POST", &url, request).await?)
+    }
+
+    async fn api_put(&self, url: &str, payload: &Value) -> ClientResult<String> {
+        if let Some(attached) = self.attached.get() {
+            return attached
+                .rspotify_request(ApiMethod::Put, url, &Query::new(), Some(payload))
+                .await;
+        }
+        let url = self.api_url(url);
+        let headers = self.auth_headers().await?;
+        let request = self.http.put(&url, Some(&headers), payload);
+        Ok(metrics::record_api_request("PUT", &url, request).await?)
+    }
+
+    async fn api_delete(&self, url: &str, payload: &Value) -> ClientResult<String> {
+        if let Some(attached) = self.attached.get() {
+            return attached
+                .rspotify_request(ApiMethod::Delete, url, &Query::new(), Some(payload))
+                .await;
+        }
+        let url = self.api_url(url);
+        let headers = self.auth_headers().await?;
+        let request = self.http.delete(&url, Some(&headers), payload);
+        Ok(metrics::record_api_request("DELETE", &url, request).await?)
+    }
 }
 
 /// Implement `OAuthClient` trait for `Spotify` struct
@@ -1,6 +1,6 @@
 use crate::state::{
-    Album, Artist, DataReadGuard, Episode, Playlist, PlaylistFolder, PlaylistFolderItem, Show,
-    Track,
+    Album, Artist, Audiobook, DataReadGuard, Episode, Playlist, PlaylistFolder, PlaylistFolderItem,
+    Show, Track,
 };
 use serde::Deserialize;
 
@@ -11,6 +11,7 @@ pub enum Command {
 
     NextTrack,
     PreviousTrack,
+    RestartTrack,
     ResumePause,
     PlayRandom,
     Repeat,
@@ -25,6 +26,7 @@ pub enum Command {
 
     Quit,
     OpenCommandHelp,
+    OpenDiagnostics,
     ClosePopup,
 
     SelectNextOrScrollDown,
@@ -33,6 +35,8 @@ pub enum Command {
     PageSelectPreviousOrScrollUp,
     SelectFirstOrScrollToTop,
     SelectLastOrScrollToBottom,
+    SelectNextMatch,
+    SelectPreviousMatch,
 
     JumpToCurrentTrackInContext,
     ChooseSelected,
@@ -41,6 +45,19 @@ pub enum Command {
 
     #[cfg(feature = "streaming")]
     RestartIntegratedClient,
+    #[cfg(feature = "streaming")]
+    CycleBitrate,
+    #[cfg(feature = "streaming")]
+    SwitchAudioOutput,
+    #[cfg(feature = "streaming")]
+    SwitchDeviceProfile,
+    #[cfg(feature = "streaming")]
+    OpenEqualizer,
+    #[cfg(feature = "streaming")]
+    ToggleNightMode,
+    #[cfg(feature = "streaming")]
+    ToggleAutoplay,
+    TogglePrivateSession,
 
     FocusNextWindow,
     FocusPreviousWindow,
@@ -66,6 +83,7 @@ pub enum Command {
     LyricsPage,
     LibraryPage,
     SearchPage,
+    LibrarySearchPage,
     BrowsePage,
     PreviousPage,
     OpenSpotifyLinkFromClipboard,
@@ -84,10 +102,25 @@ pub enum Command {
     MovePlaylistItemDown,
 
     CreatePlaylist,
+
+    ClearSearchHistory,
+    LoadMoreSearchResults,
+    ToggleSearchLibraryFilter,
+    JumpPalette,
+    CommandPalette,
+    EditKeymap,
+    SaveSearch,
+    BrowseSavedSearches,
+    DeleteSavedSearch,
+    SearchAndQueue,
+    RunSavedSearch {
+        index: usize,
+    },
 }
 
 #[derive(Clone, Copy, Debug, Deserialize)]
 pub enum Action {
+    Play,
     GoToArtist,
     GoToAlbum,
     GoToRadio,
@@ -104,6 +137,7 @@ pub enum Action {
     ShowActionsOnShow,
     ToggleLiked,
     CopyLink,
+    CopyUniversalLink,
     Follow,
     Unfollow,
 }
@@ -119,6 +153,7 @@ pub enum ActionContext {
     // TODO: support actions for playlist folders
     PlaylistFolder(PlaylistFolder),
     Show(Show),
+    Audiobook(Audiobook),
 }
 
 #[derive(Debug, PartialEq, Clone, Deserialize, Default, Copy)]
@@ -163,6 +198,12 @@ impl From<Show> for ActionContext {
     }
 }
 
+impl From<Audiobook> for ActionContext {
+    fn from(v: Audiobook) -> Self {
+        Self::Audiobook(v)
+    }
+}
+
 impl From<Episode> for ActionContext {
     fn from(v: Episode) -> Self {
         Self::Episode(v)
@@ -189,6 +230,7 @@ impl ActionContext {
             // TODO: support actions for playlist folders
             Self::PlaylistFolder(_) => vec![],
             Self::Show(show) => construct_show_actions(show, data),
+            Self::Audiobook(_) => construct_audiobook_actions(),
         }
     }
 }
@@ -202,6 +244,7 @@ pub fn construct_track_actions(track: &Track, data: &DataReadGuard) -> Vec<Actio
         Action::ShowActionsOnAlbum,
         Action::ShowActionsOnArtist,
         Action::CopyLink,
+        Action::CopyUniversalLink,
         Action::AddToPlaylist,
         Action::AddToQueue,
     ];
@@ -222,9 +265,10 @@ pub fn construct_album_actions(album: &Album, data: &DataReadGuard) -> Vec<Actio
         Action::GoToRadio,
         Action::ShowActionsOnArtist,
         Action::CopyLink,
+        Action::CopyUniversalLink,
         Action::AddToQueue,
     ];
-    if data.user_data.saved_albums.iter().any(|a| a.id == album.id) {
+    if data.user_data.is_saved_album(album) {
         actions.push(Action::DeleteFromLibrary);
     } else {
         actions.push(Action::AddToLibrary);
@@ -236,12 +280,7 @@ pub fn construct_album_actions(album: &Album, data: &DataReadGuard) -> Vec<Actio
 pub fn construct_artist_actions(artist: &Artist, data: &DataReadGuard) -> Vec<Action> {
     let mut actions = vec![Action::GoToRadio, Action::CopyLink];
 
-    if data
-        .user_data
-        .followed_artists
-        .iter()
-        .any(|a| a.id == artist.id)
-    {
+    if data.user_data.is_followed_artist(artist) {
         actions.push(Action::Unfollow);
     } else {
         actions.push(Action::Follow);
@@ -269,17 +308,22 @@ pub fn construct_playlist_actions(playlist: &Playlist, data: &DataReadGuard) ->
 /// constructs a list of actions on a show
 pub fn construct_show_actions(show: &Show, data: &DataReadGuard) -> Vec<Action> {
     let mut actions = vec![Action::CopyLink];
-    if data.user_data.saved_shows.iter().any(|s| s.id == show.id) {
-        actions.push(Action::DeleteFromLibrary);
+    if data.user_data.is_saved_show(show) {
+        actions.push(Action::Unfollow);
     } else {
-        actions.push(Action::AddToLibrary);
+        actions.push(Action::Follow);
     }
     actions
 }
 
 /// constructs a list of actions on an episode
 pub fn construct_episode_actions(episode: &Episode, _data: &DataReadGuard) -> Vec<Action> {
-    let mut actions = vec![Action::CopyLink, Action::AddToPlaylist, Action::AddToQueue];
+    let mut actions = vec![
+        Action::Play,
+        Action::CopyLink,
+        Action::AddToPlaylist,
+        Action::AddToQueue,
+    ];
     if episode.show.is_some() {
         actions.push(Action::ShowActionsOnShow);
         actions.push(Action::GoToShow);
@@ -287,16 +331,123 @@ pub fn construct_episode_actions(episode: &Episode, _data: &DataReadGuard) -> Ve
     actions
 }
 
+/// constructs a list of actions on an audiobook
+pub fn construct_audiobook_actions() -> Vec<Action> {
+    vec![Action::Play, Action::CopyLink]
+}
+
 impl Command {
+    /// gets all the commands that don't require an argument
+    pub fn all() -> Vec<Self> {
+        vec![
+            Self::NextTrack,
+            Self::PreviousTrack,
+            Self::RestartTrack,
+            Self::ResumePause,
+            Self::PlayRandom,
+            Self::Repeat,
+            Self::ToggleFakeTrackRepeatMode,
+            Self::Shuffle,
+            Self::Mute,
+            Self::SeekForward,
+            Self::SeekBackward,
+            Self::Quit,
+            Self::OpenCommandHelp,
+            Self::OpenDiagnostics,
+            Self::ClosePopup,
+            Self::SelectNextOrScrollDown,
+            Self::SelectPreviousOrScrollUp,
+            Self::PageSelectNextOrScrollDown,
+            Self::PageSelectPreviousOrScrollUp,
+            Self::SelectFirstOrScrollToTop,
+            Self::SelectLastOrScrollToBottom,
+            Self::SelectNextMatch,
+            Self::SelectPreviousMatch,
+            Self::JumpToCurrentTrackInContext,
+            Self::ChooseSelected,
+            Self::RefreshPlayback,
+            #[cfg(feature = "streaming")]
+            Self::RestartIntegratedClient,
+            #[cfg(feature = "streaming")]
+            Self::CycleBitrate,
+            #[cfg(feature = "streaming")]
+            Self::SwitchAudioOutput,
+            #[cfg(feature = "streaming")]
+            Self::SwitchDeviceProfile,
+            #[cfg(feature = "streaming")]
+            Self::OpenEqualizer,
+            #[cfg(feature = "streaming")]
+            Self::ToggleNightMode,
+            #[cfg(feature = "streaming")]
+            Self::ToggleAutoplay,
+            Self::TogglePrivateSession,
+            Self::FocusNextWindow,
+            Self::FocusPreviousWindow,
+            Self::SwitchTheme,
+            Self::SwitchDevice,
+            Self::Search,
+            Self::Queue,
+            Self::ShowActionsOnSelectedItem,
+            Self::ShowActionsOnCurrentTrack,
+            Self::AddSelectedItemToQueue,
+            Self::JumpToHighlightTrackInContext,
+            Self::BrowseUserPlaylists,
+            Self::BrowseUserFollowedArtists,
+            Self::BrowseUserSavedAlbums,
+            Self::CurrentlyPlayingContextPage,
+            Self::TopTrackPage,
+            Self::RecentlyPlayedTrackPage,
+            Self::LikedTrackPage,
+            Self::LyricsPage,
+            Self::LibraryPage,
+            Self::SearchPage,
+            Self::LibrarySearchPage,
+            Self::BrowsePage,
+            Self::PreviousPage,
+            Self::OpenSpotifyLinkFromClipboard,
+            Self::SortTrackByTitle,
+            Self::SortTrackByArtists,
+            Self::SortTrackByAlbum,
+            Self::SortTrackByDuration,
+            Self::SortTrackByAddedDate,
+            Self::ReverseTrackOrder,
+            Self::SortLibraryAlphabetically,
+            Self::SortLibraryByRecent,
+            Self::MovePlaylistItemUp,
+            Self::MovePlaylistItemDown,
+            Self::CreatePlaylist,
+            Self::ClearSearchHistory,
+            Self::LoadMoreSearchResults,
+            Self::ToggleSearchLibraryFilter,
+            Self::JumpPalette,
+            Self::CommandPalette,
+            Self::EditKeymap,
+            Self::SaveSearch,
+            Self::BrowseSavedSearches,
+            Self::DeleteSavedSearch,
+            Self::SearchAndQueue,
+        ]
+    }
+
+    /// checks if the command is only handled by the search popup, whose key sequences
+    /// can also be bound to another command outside of the popup
+    pub fn is_search_popup_command(self) -> bool {
+        matches!(self, Self::SelectNextMatch | Self::SelectPreviousMatch)
+    }
+
     pub fn desc(self) -> String {
         if let Self::VolumeChange { offset } = self {
             return format!("change playback volume by {offset}");
         }
+        if let Self::RunSavedSearch { index } = self {
+            return format!("run the saved search at position {index}");
+        }
 
         match self {
             Self::None => "do nothing",
             Self::NextTrack => "next track",
             Self::PreviousTrack => "previous track",
+            Self::RestartTrack => "restart the current track",
             Self::ResumePause => "resume/pause based on the current playback",
             Self::PlayRandom => "play a random track in the current context",
             Self::Repeat => "cycle the repeat mode",
@@ -309,6 +460,19 @@ impl Command {
             Self::ClosePopup => "close a popup",
             #[cfg(feature = "streaming")]
             Self::RestartIntegratedClient => "restart the integrated client",
+            #[cfg(feature = "streaming")]
+            Self::CycleBitrate => "cycle the integrated client's streaming bitrate (96, 160 and 320 kbps)",
+            #[cfg(feature = "streaming")]
+            Self::SwitchAudioOutput => "open a popup for switching the integrated client's audio backend and output device",
+            #[cfg(feature = "streaming")]
+            Self::SwitchDeviceProfile => "open a popup for switching the integrated client's device profile",
+            #[cfg(feature = "streaming")]
+            Self::OpenEqualizer => "open a popup for adjusting the integrated client's equalizer",
+            #[cfg(feature = "streaming")]
+            Self::ToggleNightMode => "toggle the integrated client's night mode, which compresses the audio's dynamic range",
+            #[cfg(feature = "streaming")]
+            Self::ToggleAutoplay => "toggle the integrated client's autoplay of similar tracks when the playing context ends",
+            Self::TogglePrivateSession => "toggle the private session, which suppresses the scrobbling, the listening history and the notifications",
             Self::SelectNextOrScrollDown => "select the next item in a list/table or scroll down (supports vim-style count: 5j)",
             Self::SelectPreviousOrScrollUp => {
                 "select the previous item in a list/table or scroll up (supports vim-style count: 10k)"
@@ -325,6 +489,12 @@ impl Command {
             Self::SelectLastOrScrollToBottom => {
                 "select the last item in a list/table or scroll to the bottom"
             }
+            Self::SelectNextMatch => {
+                "select the next item matching the search popup's query after finishing typing it"
+            }
+            Self::SelectPreviousMatch => {
+                "select the previous item matching the search popup's query after finishing typing it"
+            }
             Self::ChooseSelected => "choose the selected item and act on it",
             Self::JumpToCurrentTrackInContext => "jump to the current track in the context",
             Self::RefreshPlayback => "manually refresh the current playback",
@@ -347,9 +517,11 @@ impl Command {
             Self::LyricsPage => "go to the lyrics page of the current track",
             Self::LibraryPage => "go to the user library page",
             Self::SearchPage => "go to the search page",
+            Self::LibrarySearchPage => "go to the search page for searching in the user's library",
             Self::BrowsePage => "go to the browse page",
             Self::Queue => "go to the queue page",
             Self::OpenCommandHelp => "go to the command help page",
+            Self::OpenDiagnostics => "go to the diagnostics page",
             Self::PreviousPage => "go to the previous page",
             Self::OpenSpotifyLinkFromClipboard => "open a Spotify link from clipboard",
             Self::SortTrackByTitle => "sort the track table (if any) by track's title",
@@ -365,7 +537,19 @@ impl Command {
             Self::MovePlaylistItemUp => "move playlist item up one position",
             Self::MovePlaylistItemDown => "move playlist item down one position",
             Self::CreatePlaylist => "create a new playlist",
-            Self::VolumeChange { offset: _ } => unreachable!(),
+            Self::ClearSearchHistory => "clear the search history",
+            Self::LoadMoreSearchResults => "load more results in the focused search result window",
+            Self::ToggleSearchLibraryFilter => {
+                "toggle showing only search results that are in the user's library"
+            }
+            Self::JumpPalette => "open a palette for jumping to a page, playlist, album or artist",
+            Self::CommandPalette => "open a palette for searching and running a command",
+            Self::EditKeymap => "open an editor for rebinding a command",
+            Self::SaveSearch => "save the current search query with a name",
+            Self::BrowseSavedSearches => "open a popup for browsing and running saved searches",
+            Self::DeleteSavedSearch => "delete the selected search in the saved searches popup",
+            Self::SearchAndQueue => "search a query and add the top matched track to the queue",
+            Self::VolumeChange { offset: _ } | Self::RunSavedSearch { index: _ } => unreachable!(),
         }
         .to_string()
     }
@@ -2,16 +2,21 @@ use crate::{
     command::{Action, ActionTarget, Command, CommandOrAction},
     key::{Key, KeySequence},
 };
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
 /// Application's keymap configurations
 pub struct KeymapConfig {
+    /// the leader key, which replaces `<leader>` in the key sequences
+    #[serde(default)]
+    pub leader: Option<Key>,
     #[serde(default)]
     pub keymaps: Vec<Keymap>,
     #[serde(default)]
     pub actions: Vec<ActionMap>,
+    #[serde(default)]
+    pub macros: Vec<Macro>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -30,10 +35,37 @@ pub struct ActionMap {
     pub action: Action,
 }
 
+#[derive(Clone, Debug, Deserialize)]
+/// A named macro that runs a list of steps in order, which can be mapped to a key sequence
+pub struct Macro {
+    pub name: String,
+    #[serde(default)]
+    pub key_sequence: Option<KeySequence>,
+    pub steps: Vec<MacroStep>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+/// A step of a macro
+pub enum MacroStep {
+    /// runs a command
+    Command(Command),
+    /// plays a playlist of the user, found by its name
+    PlayPlaylist(String),
+    /// searches a query in the search page
+    Search(String),
+    /// sets the playback's shuffle state
+    SetShuffle(bool),
+    #[cfg(feature = "scripting")]
+    /// runs a user script, found by its name in the `scripts` folder
+    RunScript(String),
+}
+
 impl Default for KeymapConfig {
     fn default() -> Self {
         KeymapConfig {
+            leader: None,
             actions: vec![],
+            macros: vec![],
             keymaps: vec![
                 Keymap {
                     key_sequence: "n".into(),
@@ -188,6 +220,10 @@ impl Default for KeymapConfig {
                     key_sequence: "g s".into(),
                     command: Command::SearchPage,
                 },
+                Keymap {
+                    key_sequence: "g S".into(),
+                    command: Command::LibrarySearchPage,
+                },
                 Keymap {
                     key_sequence: "g b".into(),
                     command: Command::BrowsePage,
@@ -328,6 +364,50 @@ impl Default for KeymapConfig {
                     key_sequence: "g c".into(),
                     command: Command::JumpToCurrentTrackInContext,
                 },
+                Keymap {
+                    key_sequence: "g H".into(),
+                    command: Command::ClearSearchHistory,
+                },
+                Keymap {
+                    key_sequence: "M".into(),
+                    command: Command::LoadMoreSearchResults,
+                },
+                Keymap {
+                    key_sequence: "C-l".into(),
+                    command: Command::ToggleSearchLibraryFilter,
+                },
+                Keymap {
+                    key_sequence: "g p".into(),
+                    command: Command::JumpPalette,
+                },
+                Keymap {
+                    key_sequence: ":".into(),
+                    command: Command::CommandPalette,
+                },
+                Keymap {
+                    key_sequence: "C-w".into(),
+                    command: Command::SaveSearch,
+                },
+                Keymap {
+                    key_sequence: "u s".into(),
+                    command: Command::BrowseSavedSearches,
+                },
+                Keymap {
+                    key_sequence: "C-d".into(),
+                    command: Command::DeleteSavedSearch,
+                },
+                Keymap {
+                    key_sequence: "g q".into(),
+                    command: Command::SearchAndQueue,
+                },
+                Keymap {
+                    key_sequence: "n".into(),
+                    command: Command::SelectNextMatch,
+                },
+                Keymap {
+                    key_sequence: "N".into(),
+                    command: Command::SelectPreviousMatch,
+                },
             ],
         }
     }
@@ -351,19 +431,34 @@ impl KeymapConfig {
                 );
             }
             Ok(content) => {
-                let mut parsed = toml::from_str::<Self>(&content)?;
+                let mut parsed = super::deserialize_config_file::<Self>(&content, &file_path, &[])?;
+                parsed.resolve_leader()?;
+
+                // conflicting bindings don't make the keymaps unusable, so they are only reported
+                let file_conflicts = parsed.find_conflicts();
+                for conflict in &file_conflicts {
+                    tracing::warn!(
+                        "Conflicting key binding in the keymap config file (path={file_path:?}): {conflict}"
+                    );
+                }
+
+                self.leader = parsed.leader;
                 std::mem::swap(&mut self.keymaps, &mut parsed.keymaps);
                 std::mem::swap(&mut self.actions, &mut parsed.actions);
+                self.macros = parsed.macros;
 
                 // a dumb approach (with quadratic complexity) to merge two different keymap arrays
                 // while keeping the invariant:
-                // - each `KeySequence` is mapped to only one `Command`.
+                // - each `KeySequence` is mapped to only one `Command`, apart from the commands
+                // handled by the search popup only. Mapping a key sequence to `None` removes
+                // both of its default mappings.
                 parsed.keymaps.into_iter().for_each(|keymap| {
-                    if !self
-                        .keymaps
-                        .iter()
-                        .any(|k| k.key_sequence == keymap.key_sequence)
-                    {
+                    if !self.keymaps.iter().any(|k| {
+                        k.key_sequence == keymap.key_sequence
+                            && (k.command == Command::None
+                                || k.command.is_search_popup_command()
+                                    == keymap.command.is_search_popup_command())
+                    }) {
                         self.keymaps.push(keymap);
                     }
                 });
@@ -376,6 +471,240 @@ impl KeymapConfig {
                         self.actions.push(action);
                     }
                 });
+
+                // the user's bindings can still conflict with the default bindings
+                for conflict in self.find_conflicts() {
+                    if !file_conflicts.contains(&conflict) {
+                        tracing::warn!(
+                            "Conflicting key binding between the keymap config file (path={file_path:?}) and the default keymaps: {conflict}"
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// gets the bound key sequences, each with a description of what it is bound to
+    /// and whether it is bound in the search popup only
+    fn bindings(&self) -> Vec<(&KeySequence, String, bool)> {
+        self.keymaps
+            .iter()
+            .filter(|keymap| keymap.command != Command::None)
+            .map(|keymap| {
+                (
+                    &keymap.key_sequence,
+                    format!("command `{:?}`", keymap.command),
+                    keymap.command.is_search_popup_command(),
+                )
+            })
+            .chain(self.actions.iter().map(|action| {
+                (
+                    &action.key_sequence,
+                    format!("action `{:?}`", action.action),
+                    false,
+                )
+            }))
+            .chain(self.macros.iter().filter_map(|m| {
+                m.key_sequence
+                    .as_ref()
+                    .map(|key_sequence| (key_sequence, format!("macro `{}`", m.name), false))
+            }))
+            .collect()
+    }
+
+    /// finds the conflicting bindings, i.e. key sequences bound more than once and
+    /// key sequences which can't be completed because a prefix of them is bound
+    pub fn find_conflicts(&self) -> Vec<String> {
+        let bindings = self.bindings();
+        let mut conflicts = vec![];
+        for (i, (key_sequence, target, in_search_popup)) in bindings.iter().enumerate() {
+            for (other_key_sequence, other_target, _) in bindings[..i]
+                .iter()
+                .filter(|(_, _, other_in_search_popup)| other_in_search_popup == in_search_popup)
+            {
+                if key_sequence == other_key_sequence {
+                    conflicts.push(format!(
+                        "\"{key_sequence}\" is bound to both {other_target} and {target}"
+                    ));
+                } else if other_key_sequence.is_prefix(key_sequence) {
+                    conflicts.push(format!(
+                        "\"{key_sequence}\" ({target}) is shadowed by \"{other_key_sequence}\" ({other_target})"
+                    ));
+                } else if key_sequence.is_prefix(other_key_sequence) {
+                    conflicts.push(format!(
+                        "\"{other_key_sequence}\" ({other_target}) is shadowed by \"{key_sequence}\" ({target})"
+                    ));
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// finds the bindings that binding `key_sequence` to `command` would conflict with.
+    ///
+    /// Returns the bindings of `key_sequence` itself, which the new binding replaces, and the
+    /// bindings which the new binding would shadow or be shadowed by, which prevent saving it.
+    pub fn find_conflicts_with(
+        &self,
+        key_sequence: &KeySequence,
+        command: Command,
+    ) -> (Vec<String>, Vec<String>) {
+        let command_target = format!("command `{command:?}`");
+        let mut replaced = vec![];
+        let mut shadowed = vec![];
+        for (other_key_sequence, target, in_search_popup) in self.bindings() {
+            // the command's other bindings are unbound
+            if target == command_target || in_search_popup != command.is_search_popup_command() {
+                continue;
+            }
+            let conflict = format!("\"{other_key_sequence}\" ({target})");
+            if other_key_sequence == key_sequence {
+                replaced.push(conflict);
+            } else if key_sequence.is_prefix(other_key_sequence)
+                || other_key_sequence.is_prefix(key_sequence)
+            {
+                shadowed.push(conflict);
+            }
+        }
+        (replaced, shadowed)
+    }
+
+    /// rebinds `command` to `key_sequence` by updating the keymap config file in `path` folder.
+    ///
+    /// The command's other key sequences are unbound, and so are the other bindings
+    /// of `key_sequence` in the file. The comments and the formatting of the file are kept.
+    /// Binding a key sequence which would shadow or be shadowed by another binding
+    /// (e.g. `g` and `g a`) is rejected.
+    pub fn rebind_command(
+        &self,
+        path: &std::path::Path,
+        command: Command,
+        key_sequence: &KeySequence,
+    ) -> Result<()> {
+        let (_, shadowed) = self.find_conflicts_with(key_sequence, command);
+        if !shadowed.is_empty() {
+            anyhow::bail!(
+                "\"{key_sequence}\" conflicts with {}, which must be unbound first",
+                shadowed.join(", ")
+            );
+        }
+
+        let file_path = path.join(super::KEYMAP_CONFIG_FILE);
+        let content = match std::fs::read_to_string(&file_path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err.into()),
+        };
+        let mut document = content
+            .parse::<toml_edit::DocumentMut>()
+            .with_context(|| format!("invalid keymap config file (path={file_path:?})"))?;
+
+        let is_key_sequence = |table: &toml_edit::Table| is_key_sequence_of(table, key_sequence);
+        let command_name = format!("{command:?}");
+
+        let keymaps = document
+            .entry("keymaps")
+            .or_insert_with(|| toml_edit::ArrayOfTables::new().into());
+        // an inline array of keymaps is converted into an array of tables
+        if keymaps.is_array() {
+            *keymaps = std::mem::take(keymaps)
+                .into_array_of_tables()
+                .map_err(|_| {
+                    anyhow::anyhow!(
+                        "`keymaps` in the keymap config file must be an array of tables"
+                    )
+                })?
+                .into();
+        }
+        let Some(keymaps) = keymaps.as_array_of_tables_mut() else {
+            anyhow::bail!("`keymaps` in the keymap config file must be an array of tables");
+        };
+        keymaps.retain(|keymap| {
+            let name = keymap.get("command").and_then(toml_edit::Item::as_str);
+            let in_search_popup = name
+                .and_then(parse_command)
+                .is_some_and(Command::is_search_popup_command);
+            let replaced =
+                is_key_sequence(keymap) && in_search_popup == command.is_search_popup_command();
+            !replaced && name != Some(command_name.as_str())
+        });
+        // unbind the command's key sequences, which can come from the default keymaps
+        for keymap in &self.keymaps {
+            if keymap.command != command || keymap.key_sequence == *key_sequence {
+                continue;
+            }
+            keymaps.push(keymap_table(&keymap.key_sequence, "None".into()));
+            // `None` also removes the key sequence's mapping handled in the other scope,
+            // which is kept by mapping it again
+            for other in &self.keymaps {
+                if other.key_sequence == keymap.key_sequence
+                    && other.command != Command::None
+                    && other.command.is_search_popup_command() != command.is_search_popup_command()
+                    && !keymaps.iter().any(|k| {
+                        is_key_sequence_of(k, &other.key_sequence)
+                            && k.get("command")
+                                .and_then(toml_edit::Item::as_str)
+                                .and_then(parse_command)
+                                == Some(other.command)
+                    })
+                {
+                    keymaps.push(keymap_table(
+                        &other.key_sequence,
+                        command_value(other.command),
+                    ));
+                }
+            }
+        }
+        keymaps.push(keymap_table(key_sequence, command_name.as_str().into()));
+
+        if let Some(actions) = document
+            .get_mut("actions")
+            .and_then(toml_edit::Item::as_array_of_tables_mut)
+        {
+            actions.retain(|action| !is_key_sequence(action));
+        }
+        if let Some(macros) = document
+            .get_mut("macros")
+            .and_then(toml_edit::Item::as_array_of_tables_mut)
+        {
+            for m in macros.iter_mut() {
+                if is_key_sequence(m) {
+                    m.remove("key_sequence");
+                }
+            }
+        }
+
+        std::fs::write(file_path, document.to_string())?;
+        Ok(())
+    }
+
+    /// replaces `<leader>` in the key sequences with the leader key
+    fn resolve_leader(&mut self) -> Result<()> {
+        let leader = self.leader;
+        let key_sequences = self
+            .keymaps
+            .iter_mut()
+            .map(|k| &mut k.key_sequence)
+            .chain(self.actions.iter_mut().map(|a| &mut a.key_sequence))
+            .chain(
+                self.macros
+                    .iter_mut()
+                    .filter_map(|m| m.key_sequence.as_mut()),
+            );
+        for key_sequence in key_sequences {
+            if !key_sequence.keys.contains(&Key::Leader) {
+                continue;
+            }
+            let Some(leader) = leader else {
+                anyhow::bail!(
+                    "`<leader>` is used in the key sequence \"{key_sequence}\" but `leader` is not set"
+                );
+            };
+            for key in &mut key_sequence.keys {
+                if *key == Key::Leader {
+                    *key = leader;
+                }
             }
         }
         Ok(())
@@ -397,18 +726,40 @@ impl KeymapConfig {
             .collect()
     }
 
-    /// checks if there is any command or action that has a given `prefix` key sequence as its prefix
+    /// checks if there is any command, action or macro whose key sequence has a given `prefix`
     pub fn has_matched_prefix(&self, prefix: &KeySequence) -> bool {
         let keymaps = self.find_matched_prefix_keymaps(prefix);
         let actions = self.find_matched_prefix_actions(prefix);
-        !keymaps.is_empty() || !actions.is_empty()
+        let has_macro = self
+            .macros
+            .iter()
+            .filter_map(|m| m.key_sequence.as_ref())
+            .any(|key_sequence| prefix.is_prefix(key_sequence));
+        !keymaps.is_empty() || !actions.is_empty() || has_macro
     }
 
     /// finds a command from a mapped key sequence
     pub fn find_command_from_key_sequence(&self, key_sequence: &KeySequence) -> Option<Command> {
         self.keymaps
             .iter()
-            .find(|&keymap| keymap.key_sequence == *key_sequence && keymap.command != Command::None)
+            .find(|&keymap| {
+                keymap.key_sequence == *key_sequence
+                    && keymap.command != Command::None
+                    && !keymap.command.is_search_popup_command()
+            })
+            .map(|keymap| keymap.command)
+    }
+
+    /// finds a command handled by the search popup only from a mapped key sequence
+    pub fn find_search_popup_command_from_key_sequence(
+        &self,
+        key_sequence: &KeySequence,
+    ) -> Option<Command> {
+        self.keymaps
+            .iter()
+            .find(|&keymap| {
+                keymap.key_sequence == *key_sequence && keymap.command.is_search_popup_command()
+            })
             .map(|keymap| keymap.command)
     }
 
@@ -423,6 +774,13 @@ impl KeymapConfig {
             .map(|action| (action.action, action.target))
     }
 
+    /// finds a macro from a mapped key sequence
+    pub fn find_macro_from_key_sequence(&self, key_sequence: &KeySequence) -> Option<&Macro> {
+        self.macros
+            .iter()
+            .find(|m| m.key_sequence.as_ref() == Some(key_sequence))
+    }
+
     /// finds a command or action from a mapped key sequence
     pub fn find_command_or_action_from_key_sequence(
         &self,
@@ -438,6 +796,44 @@ impl KeymapConfig {
     }
 }
 
+/// creates a keymap of the keymap config file
+fn keymap_table(key_sequence: &KeySequence, command: toml_edit::Value) -> toml_edit::Table {
+    let mut keymap = toml_edit::Table::new();
+    keymap.insert("key_sequence", toml_edit::value(key_sequence.to_string()));
+    keymap.insert("command", toml_edit::value(command));
+    keymap
+}
+
+/// checks if a mapping of the keymap config file is mapped from `key_sequence`
+fn is_key_sequence_of(mapping: &toml_edit::Table, key_sequence: &KeySequence) -> bool {
+    mapping
+        .get("key_sequence")
+        .and_then(toml_edit::Item::as_str)
+        .and_then(KeySequence::from_str)
+        .is_some_and(|k| k == *key_sequence)
+}
+
+/// creates the value of a command in the keymap config file
+fn command_value(command: Command) -> toml_edit::Value {
+    let (name, argument, value) = match command {
+        Command::VolumeChange { offset } => ("VolumeChange", "offset", i64::from(offset)),
+        Command::RunSavedSearch { index } => ("RunSavedSearch", "index", index as i64),
+        command => return format!("{command:?}").into(),
+    };
+    let mut arguments = toml_edit::InlineTable::new();
+    arguments.insert(argument, value.into());
+    let mut command = toml_edit::InlineTable::new();
+    command.insert(name, arguments.into());
+    command.into()
+}
+
+/// parses a command without arguments from its name in the keymap config file
+fn parse_command(name: &str) -> Option<Command> {
+    use serde::de::IntoDeserializer;
+
+    Command::deserialize(IntoDeserializer::<serde::de::value::Error>::into_deserializer(name)).ok()
+}
+
 impl Keymap {
     pub fn include_in_help_screen(&self) -> bool {
         !matches!(&self.command, Command::None)
@@ -475,3 +871,132 @@ impl From<&str> for KeySequence {
         Self::from_str(s).unwrap_or_else(|| panic!("invalid key sequence {s}"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_keymaps(content: &str) -> KeymapConfig {
+        toml::from_str(content).unwrap()
+    }
+
+    fn command_of(config: &KeymapConfig, key_sequence: &str) -> Option<Command> {
+        let key_sequence = KeySequence::from(key_sequence);
+        config
+            .keymaps
+            .iter()
+            .find(|k| k.key_sequence == key_sequence)
+            .map(|k| k.command)
+    }
+
+    #[test]
+    fn find_conflicts_of_bindings() {
+        let config = parse_keymaps(
+            r#"
+            keymaps = [
+                { command = "NextTrack", key_sequence = "n" },
+                { command = "PreviousTrack", key_sequence = "n" },
+                { command = "ResumePause", key_sequence = "g" },
+                { command = "Repeat", key_sequence = "g a" },
+                { command = "SelectNextMatch", key_sequence = "n" },
+                { command = "None", key_sequence = "g a" },
+            ]
+            macros = [{ name = "skip", key_sequence = "g", steps = [] }]
+            "#,
+        );
+
+        assert_eq!(
+            config.find_conflicts(),
+            [
+                r#""n" is bound to both command `NextTrack` and command `PreviousTrack`"#,
+                r#""g a" (command `Repeat`) is shadowed by "g" (command `ResumePause`)"#,
+                r#""g" is bound to both command `ResumePause` and macro `skip`"#,
+                r#""g a" (command `Repeat`) is shadowed by "g" (macro `skip`)"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn find_no_conflicts() {
+        // the search popup's bindings don't conflict with the other bindings
+        let config = parse_keymaps(
+            r#"
+            keymaps = [
+                { command = "NextTrack", key_sequence = "n" },
+                { command = "SelectNextMatch", key_sequence = "n" },
+                { command = "ResumePause", key_sequence = "g a" },
+                { command = "Repeat", key_sequence = "g b" },
+            ]
+            "#,
+        );
+        assert!(config.find_conflicts().is_empty());
+    }
+
+    #[test]
+    fn find_conflicts_with_binding() {
+        let config = parse_keymaps(
+            r#"
+            keymaps = [
+                { command = "NextTrack", key_sequence = "n" },
+                { command = "Repeat", key_sequence = "r" },
+                { command = "ResumePause", key_sequence = "g a" },
+            ]
+            "#,
+        );
+
+        let (replaced, shadowed) = config.find_conflicts_with(&"r".into(), Command::NextTrack);
+        assert_eq!(replaced, [r#""r" (command `Repeat`)"#]);
+        assert!(shadowed.is_empty());
+
+        let (replaced, shadowed) = config.find_conflicts_with(&"g".into(), Command::Shuffle);
+        assert!(replaced.is_empty());
+        assert_eq!(shadowed, [r#""g a" (command `ResumePause`)"#]);
+
+        // the command's own bindings don't conflict
+        let (replaced, shadowed) = config.find_conflicts_with(&"n".into(), Command::NextTrack);
+        assert!(replaced.is_empty() && shadowed.is_empty());
+    }
+
+    #[test]
+    fn rebind_command_in_config_file() {
+        let content = r#"[[keymaps]]
+command = "Shuffle"
+key_sequence = "s" # shuffle the playback
+
+[[keymaps]]
+command = "NextTrack"
+key_sequence = "n"
+
+[[keymaps]]
+command = "Repeat"
+key_sequence = "r"
+"#;
+        let path = std::env::temp_dir().join(format!(
+            "spotify-player-rebind-command-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&path).unwrap();
+        let file_path = path.join(crate::config::KEYMAP_CONFIG_FILE);
+        std::fs::write(&file_path, content).unwrap();
+        let config = parse_keymaps(content);
+
+        // a binding shadowing another binding is rejected without updating the file
+        assert!(config
+            .rebind_command(&path, Command::Shuffle, &"n a".into())
+            .is_err());
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), content);
+
+        config
+            .rebind_command(&path, Command::NextTrack, &"r".into())
+            .unwrap();
+        let updated = std::fs::read_to_string(&file_path).unwrap();
+        std::fs::remove_dir_all(&path).unwrap();
+
+        assert!(updated.contains("# shuffle the playback"));
+        let updated = parse_keymaps(&updated);
+        assert_eq!(command_of(&updated, "s"), Some(Command::Shuffle));
+        assert_eq!(command_of(&updated, "n"), Some(Command::None));
+        assert_eq!(command_of(&updated, "r"), Some(Command::NextTrack));
+        assert_eq!(updated.keymaps.len(), 3);
+    }
+}
@@ -3,35 +3,45 @@ mod theme;
 
 const DEFAULT_CONFIG_FOLDER: &str = ".config/spotify-player";
 const DEFAULT_CACHE_FOLDER: &str = ".cache/spotify-player";
+const PROFILES_FOLDER: &str = "profiles";
 const APP_CONFIG_FILE: &str = "app.toml";
 const THEME_CONFIG_FILE: &str = "theme.toml";
 const KEYMAP_CONFIG_FILE: &str = "keymap.toml";
+/// The minimum length of `web_api_token`, so that the token can't be easily guessed
+const MIN_WEB_API_TOKEN_LENGTH: usize = 16;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use config_parser2::{config_parser_impl, ConfigParse, ConfigParser};
 use librespot_core::config::SessionConfig;
 use reqwest::Url;
 use serde::{Deserialize, Serialize};
 use std::{
     path::{Path, PathBuf},
-    sync::OnceLock,
+    sync::Arc,
 };
 
 use keymap::KeymapConfig;
 use theme::ThemeConfig;
 
-pub use theme::Theme;
+pub use keymap::{Macro, MacroStep};
+pub use theme::{Theme, Window};
 
 use crate::auth::SPOTIFY_CLIENT_ID;
 
-static CONFIGS: OnceLock<Configs> = OnceLock::new();
+static CONFIGS: arc_swap::ArcSwapOption<Configs> = arc_swap::ArcSwapOption::const_empty();
+
+/// A function loading the application's configurations from the configuration files
+pub type ConfigLoader = Box<dyn Fn() -> Result<Configs> + Send + Sync>;
 
 #[derive(Debug)]
 pub struct Configs {
     pub app_config: AppConfig,
     pub keymap_config: KeymapConfig,
     pub theme_config: ThemeConfig,
+    pub config_folder: std::path::PathBuf,
     pub cache_folder: std::path::PathBuf,
+    /// the name of the profile whose configurations are used, if any
+    pub profile: Option<String>,
 }
 
 impl Configs {
@@ -40,31 +50,88 @@ impl Configs {
             app_config: AppConfig::new(config_folder)?,
             keymap_config: KeymapConfig::new(config_folder)?,
             theme_config: ThemeConfig::new(config_folder)?,
+            config_folder: config_folder.to_path_buf(),
             cache_folder: cache_folder.to_path_buf(),
+            profile: None,
+        })
+    }
+
+    /// Path to the Unix socket used by a running application to handle CLI commands,
+    /// inside a folder only accessible by the user
+    pub fn client_socket_path(&self) -> std::path::PathBuf {
+        self.cache_folder.join("socket").join("spotify-player.sock")
+    }
+
+    /// Path to the integrated device's audio cache folder, `None` if the audio cache is disabled
+    pub fn audio_cache_folder(&self) -> Option<std::path::PathBuf> {
+        let device = &self.app_config.device;
+        if !device.audio_cache {
+            return None;
+        }
+        Some(match &device.audio_cache_folder {
+            Some(folder) => folder.into(),
+            None => self.cache_folder.join("audio"),
         })
     }
 }
 
 #[derive(Debug, Deserialize, Serialize, ConfigParse)]
+#[serde(default)]
 #[allow(clippy::struct_excessive_bools)]
 /// Application configurations
 pub struct AppConfig {
     pub theme: String,
+    pub theme_mode: Option<ThemeMode>,
+    pub light_theme: Option<String>,
+    pub dark_theme: Option<String>,
     pub client_id: String,
     pub client_id_command: Option<Command>,
 
     pub client_port: u16,
 
+    pub web_api_address: Option<String>,
+    pub web_api_token: Option<String>,
+
+    pub remote_listen_address: Option<String>,
+    pub remote_listen_public: bool,
+    pub remote_address: Option<String>,
+    pub remote_secret: Option<String>,
+
+    pub metrics_address: Option<String>,
+    pub overlay_address: Option<String>,
+    #[cfg(feature = "websocket")]
+    pub websocket_address: Option<String>,
+
     pub login_redirect_uri: String,
 
     pub player_event_hook_command: Option<Command>,
+    pub on_track_change: Option<String>,
+    pub on_play: Option<String>,
+    pub on_pause: Option<String>,
+    pub webhooks: Vec<WebhookConfig>,
 
-    pub playback_format: String,
+    pub now_playing_file: Option<String>,
+    pub now_playing_format: FormatTemplate,
+
+    pub journal_folder: Option<String>,
+    pub journal_file_name: String,
+
+    pub set_terminal_title: bool,
+    pub tmux_status: bool,
+    pub terminal_title_format: FormatTemplate,
+
+    pub playback_format: FormatTemplate,
     pub playback_metadata_fields: Vec<String>,
+    /// the format of durations, e.g. the tracks' durations and the playback's progress
+    pub duration_format: String,
+    /// the `strftime` format of dates, e.g. the albums' release dates
+    pub date_format: String,
     #[cfg(feature = "notify")]
     pub notify_format: NotifyFormat,
     #[cfg(feature = "notify")]
     pub notify_timeout_in_secs: u64,
+    #[cfg(feature = "notify")]
+    pub notify_events: Vec<NotifyEvent>,
 
     pub tracks_playback_limit: usize,
 
@@ -115,9 +182,31 @@ pub struct AppConfig {
 
     pub enable_cover_image_cache: bool,
 
+    pub enable_listening_history: bool,
+
+    #[cfg(feature = "streaming")]
+    pub enable_playback_push_updates: bool,
+
     pub default_device: String,
 
     pub device: DeviceConfig,
+    pub device_profiles: Vec<DeviceProfile>,
+    pub device_profile: Option<String>,
+    pub device_volumes: Vec<DeviceVolumeConfig>,
+
+    pub playback_defaults: Vec<PlaybackDefault>,
+
+    #[cfg(feature = "mqtt")]
+    pub enable_mqtt: bool,
+    #[cfg(feature = "mqtt")]
+    pub mqtt: MqttConfig,
+
+    #[cfg(feature = "scrobble")]
+    pub scrobble_log_file: Option<String>,
+    #[cfg(feature = "scrobble")]
+    pub lastfm: Option<LastfmConfig>,
+    #[cfg(feature = "scrobble")]
+    pub listenbrainz: Option<ListenBrainzConfig>,
 
     #[cfg(all(feature = "streaming", feature = "notify"))]
     pub notify_streaming_only: bool,
@@ -125,6 +214,19 @@ pub struct AppConfig {
     pub seek_duration_secs: u16,
 
     pub sort_artist_albums_by_type: bool,
+
+    pub search_history_size: usize,
+    pub live_search_delay_in_ms: Option<u64>,
+
+    pub key_sequence_timeout_in_ms: u64,
+
+    pub search: SearchConfig,
+
+    pub mouse: MouseConfig,
+
+    pub scrolling: ScrollConfig,
+
+    pub confirmations: ConfirmationConfig,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -134,6 +236,16 @@ pub enum Position {
 }
 config_parser_impl!(Position);
 
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+/// The mode choosing between the light and dark themes
+pub enum ThemeMode {
+    Light,
+    Dark,
+    /// chooses the theme based on the terminal's background
+    Auto,
+}
+config_parser_impl!(ThemeMode);
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
 pub enum BorderType {
     Hidden,
@@ -151,6 +263,81 @@ pub enum ProgressBarType {
 }
 config_parser_impl!(ProgressBarType);
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A part of a parsed format string
+pub enum FormatToken {
+    Text(String),
+    /// a `{placeholder}`, including its braces
+    Placeholder(String),
+    Newline,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(from = "String", into = "String")]
+/// A format string with `{placeholder}`s, which is parsed into tokens once upon loading the
+/// configurations instead of upon every rendering
+pub struct FormatTemplate {
+    source: String,
+    tokens: Vec<FormatToken>,
+}
+config_parser_impl!(FormatTemplate);
+
+impl FormatTemplate {
+    pub fn new(source: impl Into<String>) -> Self {
+        let source = source.into();
+        let mut tokens = vec![];
+        let mut text = String::new();
+
+        let mut rest = source.as_str();
+        while let Some(c) = rest.chars().next() {
+            // a placeholder ends at the first `}` and can't span multiple lines
+            // or contain another `{`
+            let placeholder_end = rest
+                .char_indices()
+                .skip(1)
+                .find(|&(_, c)| matches!(c, '{' | '}' | '\n'))
+                .filter(|&(_, end)| c == '{' && end == '}')
+                .map(|(i, _)| i);
+
+            let (token, len) = match (c, placeholder_end) {
+                (_, Some(end)) => (FormatToken::Placeholder(rest[..=end].to_string()), end + 1),
+                ('\n', None) => (FormatToken::Newline, 1),
+                _ => {
+                    text.push(c);
+                    rest = &rest[c.len_utf8()..];
+                    continue;
+                }
+            };
+            if !text.is_empty() {
+                tokens.push(FormatToken::Text(std::mem::take(&mut text)));
+            }
+            tokens.push(token);
+            rest = &rest[len..];
+        }
+        if !text.is_empty() {
+            tokens.push(FormatToken::Text(text));
+        }
+
+        Self { source, tokens }
+    }
+
+    pub fn tokens(&self) -> &[FormatToken] {
+        &self.tokens
+    }
+}
+
+impl From<String> for FormatTemplate {
+    fn from(source: String) -> Self {
+        Self::new(source)
+    }
+}
+
+impl From<FormatTemplate> for String {
+    fn from(template: FormatTemplate) -> Self {
+        template.source
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, ConfigParse, Clone)]
 pub struct Command {
     pub command: String,
@@ -158,6 +345,49 @@ pub struct Command {
     pub args: Vec<String>,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone)]
+/// A webhook receiving playback events
+pub struct WebhookConfig {
+    pub url: String,
+    /// the events sent to the webhook, which defaults to all events
+    #[serde(default = "WebhookEvent::all")]
+    pub events: Vec<WebhookEvent>,
+}
+config_parser_impl!(WebhookConfig);
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+/// Playback events sent to webhooks
+pub enum WebhookEvent {
+    TrackChange,
+    Play,
+    Pause,
+    DeviceChange,
+    VolumeChange,
+}
+
+impl WebhookEvent {
+    fn all() -> Vec<Self> {
+        vec![
+            Self::TrackChange,
+            Self::Play,
+            Self::Pause,
+            Self::DeviceChange,
+            Self::VolumeChange,
+        ]
+    }
+
+    /// Name of the event in the webhooks' payloads
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::TrackChange => "track_change",
+            Self::Play => "play",
+            Self::Pause => "pause",
+            Self::DeviceChange => "device_change",
+            Self::VolumeChange => "volume_change",
+        }
+    }
+}
+
 impl Command {
     /// Execute a command, returning stdout if succeeded or stderr if failed
     pub fn execute(&self, extra_args: Option<Vec<String>>) -> anyhow::Result<String> {
@@ -179,6 +409,7 @@ impl Command {
 }
 
 #[derive(Debug, Deserialize, Serialize, ConfigParse, Clone)]
+#[serde(default)]
 /// Application device configurations
 pub struct DeviceConfig {
     pub name: String,
@@ -186,29 +417,324 @@ pub struct DeviceConfig {
     pub volume: u8,
     pub bitrate: u16,
     pub audio_cache: bool,
+    pub audio_cache_folder: Option<String>,
+    pub audio_cache_size_limit: Option<u64>,
     pub normalization: bool,
+    pub gapless: bool,
+    pub read_ahead_secs: u64,
     pub autoplay: bool,
+    pub zeroconf: bool,
+    pub zeroconf_port: u16,
+    pub system_volume_sync: bool,
+    pub audio_backend: Option<String>,
+    pub audio_device: Option<String>,
+    pub equalizer: bool,
+    pub equalizer_preset: EqualizerPreset,
+    pub equalizer_gains: Vec<f32>,
+    pub night_mode: bool,
+    pub fade_duration_ms: u64,
+    pub mono: bool,
+    pub balance: f32,
+    pub skip_silence: bool,
+    pub bit_perfect: bool,
+    pub sample_rate: Option<u32>,
+    pub resampler_quality: ResamplerQuality,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+/// A named profile of the integrated device, overriding the device configurations
+pub struct DeviceProfile {
+    /// the profile's name, which is also the device's name
+    pub name: String,
+    pub device_type: Option<String>,
+    pub audio_backend: Option<String>,
+    pub audio_device: Option<String>,
+}
+config_parser_impl!(DeviceProfile);
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+/// Volume configurations of a Connect device
+pub struct DeviceVolumeConfig {
+    /// the device's name
+    pub name: String,
+    /// the volume (in percentage) set upon transferring the playback to the device
+    pub volume: Option<u8>,
+    /// the maximum volume (in percentage) of the device
+    pub max_volume: Option<u8>,
+}
+config_parser_impl!(DeviceVolumeConfig);
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+/// Default shuffle and repeat states of the playbacks started in a type of context
+pub struct PlaybackDefault {
+    pub context_type: PlaybackContextType,
+    pub shuffle: Option<bool>,
+    pub repeat: Option<rspotify::model::RepeatState>,
+}
+config_parser_impl!(PlaybackDefault);
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+/// Types of the contexts in which a playback is started
+pub enum PlaybackContextType {
+    Playlist,
+    Album,
+    Artist,
+    Show,
+    /// a list of tracks, e.g. the liked tracks or the search results
+    Tracks,
+}
+config_parser_impl!(PlaybackContextType);
+
+impl DeviceVolumeConfig {
+    /// Limit a volume to the device's maximum volume
+    pub fn cap(&self, volume: u8) -> u8 {
+        self.max_volume.map_or(volume, |max| volume.min(max))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+/// Presets of the equalizer's gains
+pub enum EqualizerPreset {
+    Flat,
+    BassBoost,
+    Vocal,
+}
+config_parser_impl!(EqualizerPreset);
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+/// Qualities of the resampler converting the audio to the output sample rate
+pub enum ResamplerQuality {
+    /// a linear interpolation
+    Low,
+    /// a windowed sinc interpolation over 16 samples
+    Medium,
+    /// a windowed sinc interpolation over 64 samples
+    High,
+}
+config_parser_impl!(ResamplerQuality);
+
+impl EqualizerPreset {
+    /// Get the gain (in dB) of each equalizer's band, from the lowest to the highest frequency
+    pub fn gains(self) -> [f32; 10] {
+        match self {
+            Self::Flat => [0.0; 10],
+            Self::BassBoost => [6.0, 5.0, 4.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            Self::Vocal => [-2.0, -2.0, -1.0, 0.0, 2.0, 4.0, 4.0, 2.0, 0.0, -1.0],
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Flat => "Flat",
+            Self::BassBoost => "Bass Boost",
+            Self::Vocal => "Vocal",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ConfigParse, Clone)]
+#[serde(default)]
+#[cfg(feature = "mqtt")]
+/// Application MQTT configurations
+pub struct MqttConfig {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub topic: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ConfigParse, Clone)]
+#[cfg(feature = "scrobble")]
+/// Last.fm scrobbling configurations
+pub struct LastfmConfig {
+    pub api_key: String,
+    pub api_secret: String,
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, ConfigParse, Clone)]
+#[cfg(feature = "scrobble")]
+/// `ListenBrainz` scrobbling configurations
+pub struct ListenBrainzConfig {
+    pub token: String,
+    /// URL of the `ListenBrainz` API, which defaults to the official server's API
+    pub api_url: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, ConfigParse, Clone)]
+#[serde(default)]
 #[cfg(feature = "notify")]
 pub struct NotifyFormat {
-    pub summary: String,
-    pub body: String,
+    pub summary: FormatTemplate,
+    pub body: FormatTemplate,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[cfg(feature = "notify")]
+/// Playback events that trigger a notification
+pub enum NotifyEvent {
+    TrackChange,
+    Play,
+    Pause,
+}
+#[cfg(feature = "notify")]
+config_parser_impl!(NotifyEvent);
+
 #[derive(Debug, Deserialize, Serialize, ConfigParse, Clone)]
+#[serde(default)]
 // Application layout configurations
 pub struct LayoutConfig {
     pub library: LibraryLayoutConfig,
     pub playback_window_position: Position,
     pub playback_window_height: usize,
+    pub popup: PopupLayoutConfig,
+}
+
+#[derive(Debug, Deserialize, Serialize, ConfigParse, Clone)]
+#[serde(default)]
+pub struct PopupLayoutConfig {
+    /// the geometry of the popups without their own geometry
+    pub default: PopupGeometry,
+    pub action_list: Option<PopupGeometry>,
+    pub search: Option<PopupGeometry>,
+    pub device_list: Option<PopupGeometry>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+/// The size and the position of a popup, which is placed inside the main layout
+pub struct PopupGeometry {
+    pub anchor: PopupAnchor,
+    pub width: LayoutSize,
+    /// the popup's number of rows, the popup's own height if not specified
+    pub height: Option<u16>,
+}
+config_parser_impl!(PopupGeometry);
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+/// The edge or the corner of the main layout which a popup is placed at
+pub enum PopupAnchor {
+    Top,
+    TopLeft,
+    TopRight,
+    Bottom,
+    BottomLeft,
+    BottomRight,
 }
 
 #[derive(Debug, Deserialize, Serialize, ConfigParse, Clone)]
+#[serde(default)]
 pub struct LibraryLayoutConfig {
     pub playlist_percent: u16,
     pub album_percent: u16,
+    /// the library page's windows in the display order, overriding `playlist_percent`
+    /// and `album_percent` if not empty
+    pub windows: Vec<LibraryWindowConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LibraryWindowConfig {
+    pub window: LibraryWindow,
+    pub size: LayoutSize,
+}
+config_parser_impl!(LibraryWindowConfig);
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum LibraryWindow {
+    Playlists,
+    Albums,
+    Artists,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+/// The size of a window along its layout's direction
+pub enum LayoutSize {
+    /// a percentage of the layout's size
+    Percent(u16),
+    /// a fixed number of columns (or rows)
+    Length(u16),
+    /// the remaining space, shared with other `Fill` windows
+    Fill,
+}
+
+#[derive(Debug, Deserialize, Serialize, ConfigParse, Clone)]
+#[serde(default)]
+/// Mouse configurations
+pub struct MouseConfig {
+    /// whether to handle mouse events
+    pub enabled: bool,
+    /// the maximum time (in ms) between two clicks of a double-click
+    pub double_click_interval_in_ms: u64,
+    /// the number of items moved by a scroll of the mouse wheel
+    pub scroll_lines: usize,
+    /// whether double-clicking an item chooses it, e.g. plays a track
+    pub double_click_to_choose: bool,
+    /// whether right-clicking an item opens its action popup
+    pub right_click_to_show_actions: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, ConfigParse, Clone)]
+#[serde(default)]
+/// List scrolling configurations
+pub struct ScrollConfig {
+    /// the number of items kept visible above and below the selected item
+    pub scroll_off: usize,
+    /// whether moving down from the last item selects the first item and vice versa
+    pub wrap_around: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, ConfigParse, Clone)]
+#[serde(default)]
+#[allow(clippy::struct_excessive_bools)]
+/// Configurations of the destructive actions requiring a confirmation
+pub struct ConfirmationConfig {
+    pub delete_playlist: bool,
+    pub remove_track: bool,
+    pub remove_album: bool,
+    pub unfollow: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A destructive action, which may require a confirmation
+pub enum DestructiveAction {
+    /// deleting a playlist from the library
+    DeletePlaylist,
+    /// removing a track from a playlist or the liked tracks
+    RemoveTrack,
+    /// removing an album from the library
+    RemoveAlbum,
+    /// unfollowing an artist or a show
+    Unfollow,
+}
+
+#[derive(Debug, Deserialize, Serialize, ConfigParse, Clone)]
+#[serde(default)]
+/// Search page configurations
+pub struct SearchConfig {
+    /// result categories shown on the search page, in the display order
+    pub categories: Vec<SearchCategoryConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SearchCategoryConfig {
+    pub category: SearchCategory,
+    /// the number of items fetched per search request
+    pub limit: u32,
+}
+config_parser_impl!(SearchCategoryConfig);
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SearchCategory {
+    Tracks,
+    Albums,
+    Artists,
+    Playlists,
+    Shows,
+    Episodes,
+    Audiobooks,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
@@ -264,17 +790,33 @@ impl Default for AppConfig {
     fn default() -> Self {
         Self {
             theme: "dracula".to_owned(),
+            theme_mode: None,
+            light_theme: None,
+            dark_theme: None,
             // official Spotify web app's client id
             client_id: "65b708073fc0480ea92a077233ca87bd".to_string(),
             client_id_command: None,
 
             client_port: 8080,
 
+            web_api_address: None,
+            web_api_token: None,
+
+            remote_listen_address: None,
+            remote_listen_public: false,
+            remote_address: None,
+            remote_secret: None,
+
+            metrics_address: None,
+            overlay_address: None,
+            #[cfg(feature = "websocket")]
+            websocket_address: None,
+
             login_redirect_uri: "http://127.0.0.1:8989/login".to_string(),
 
             tracks_playback_limit: 50,
 
-            playback_format: String::from(
+            playback_format: FormatTemplate::new(
                 "{status} {track} • {artists} {liked}\n{album}\n{metadata}",
             ),
             playback_metadata_fields: vec![
@@ -283,15 +825,30 @@ impl Default for AppConfig {
                 "volume".to_string(),
                 "device".to_string(),
             ],
+            duration_format: String::from("%m:%S"),
+            date_format: String::from("%Y-%m-%d"),
             #[cfg(feature = "notify")]
-            notify_format: NotifyFormat {
-                summary: String::from("{track} • {artists}"),
-                body: String::from("{album}"),
-            },
+            notify_format: NotifyFormat::default(),
             #[cfg(feature = "notify")]
             notify_timeout_in_secs: 0,
+            #[cfg(feature = "notify")]
+            notify_events: vec![NotifyEvent::TrackChange],
 
             player_event_hook_command: None,
+            on_track_change: None,
+            on_play: None,
+            on_pause: None,
+            webhooks: Vec::new(),
+
+            now_playing_file: None,
+            now_playing_format: FormatTemplate::new("{status} {track} • {artists}"),
+
+            journal_folder: None,
+            journal_file_name: "%Y-%m-%d.md".to_string(),
+
+            set_terminal_title: false,
+            tmux_status: false,
+            terminal_title_format: FormatTemplate::new("{status} {track} • {artists}"),
 
             proxy: None,
             ap_port: None,
@@ -343,9 +900,31 @@ impl Default for AppConfig {
 
             enable_cover_image_cache: true,
 
+            enable_listening_history: true,
+
+            #[cfg(feature = "streaming")]
+            enable_playback_push_updates: true,
+
             default_device: "spotify-player".to_string(),
 
             device: DeviceConfig::default(),
+            device_profiles: Vec::new(),
+            device_profile: None,
+            device_volumes: Vec::new(),
+
+            playback_defaults: Vec::new(),
+
+            #[cfg(feature = "mqtt")]
+            enable_mqtt: false,
+            #[cfg(feature = "mqtt")]
+            mqtt: MqttConfig::default(),
+
+            #[cfg(feature = "scrobble")]
+            scrobble_log_file: None,
+            #[cfg(feature = "scrobble")]
+            lastfm: None,
+            #[cfg(feature = "scrobble")]
+            listenbrainz: None,
 
             #[cfg(all(feature = "streaming", feature = "notify"))]
             notify_streaming_only: false,
@@ -353,6 +932,18 @@ impl Default for AppConfig {
             seek_duration_secs: 5,
 
             sort_artist_albums_by_type: false,
+
+            search_history_size: 50,
+            live_search_delay_in_ms: None,
+
+            key_sequence_timeout_in_ms: 1000,
+
+            search: SearchConfig::default(),
+
+            mouse: MouseConfig::default(),
+            scrolling: ScrollConfig::default(),
+
+            confirmations: ConfirmationConfig::default(),
         }
     }
 }
@@ -365,8 +956,52 @@ impl Default for DeviceConfig {
             volume: 70,
             bitrate: 320,
             audio_cache: false,
+            audio_cache_folder: None,
+            audio_cache_size_limit: None,
             normalization: false,
+            gapless: true,
+            read_ahead_secs: 30,
             autoplay: false,
+            zeroconf: false,
+            zeroconf_port: 0,
+            system_volume_sync: false,
+            audio_backend: None,
+            audio_device: None,
+            equalizer: false,
+            equalizer_preset: EqualizerPreset::Flat,
+            equalizer_gains: Vec::new(),
+            night_mode: false,
+            fade_duration_ms: 0,
+            mono: false,
+            balance: 0.0,
+            skip_silence: false,
+            bit_perfect: false,
+            sample_rate: None,
+            resampler_quality: ResamplerQuality::High,
+        }
+    }
+}
+
+#[cfg(feature = "mqtt")]
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_string(),
+            port: 1883,
+            client_id: "spotify-player".to_string(),
+            topic: "spotify-player".to_string(),
+            username: None,
+            password: None,
+        }
+    }
+}
+
+#[cfg(feature = "notify")]
+impl Default for NotifyFormat {
+    fn default() -> Self {
+        Self {
+            summary: FormatTemplate::new("{track} • {artists}"),
+            body: FormatTemplate::new("{album}"),
         }
     }
 }
@@ -374,12 +1009,41 @@ impl Default for DeviceConfig {
 impl Default for LayoutConfig {
     fn default() -> Self {
         Self {
-            library: LibraryLayoutConfig {
-                playlist_percent: 40,
-                album_percent: 40,
-            },
+            library: LibraryLayoutConfig::default(),
             playback_window_position: Position::Top,
             playback_window_height: 4,
+            popup: PopupLayoutConfig::default(),
+        }
+    }
+}
+
+impl Default for LibraryLayoutConfig {
+    fn default() -> Self {
+        Self {
+            playlist_percent: 40,
+            album_percent: 40,
+            windows: vec![],
+        }
+    }
+}
+
+impl Default for PopupLayoutConfig {
+    fn default() -> Self {
+        Self {
+            default: PopupGeometry::default(),
+            action_list: None,
+            search: None,
+            device_list: None,
+        }
+    }
+}
+
+impl Default for PopupGeometry {
+    fn default() -> Self {
+        Self {
+            anchor: PopupAnchor::Bottom,
+            width: LayoutSize::Percent(100),
+            height: None,
         }
     }
 }
@@ -389,11 +1053,203 @@ impl LayoutConfig {
         if self.library.album_percent + self.library.playlist_percent > 99 {
             anyhow::bail!("Invalid library layout: summation of album_percent and playlist_percent cannot be greater than 99!");
         }
+        let windows = &self.library.windows;
+        if let Some((_, w)) = windows
+            .iter()
+            .enumerate()
+            .find(|&(i, w)| windows[..i].iter().any(|x| x.window == w.window))
+        {
+            anyhow::bail!(
+                "Invalid library layout: {:?} window is specified more than once!",
+                w.window
+            );
+        }
+        let popup = &self.popup;
+        if [&popup.default]
+            .into_iter()
+            .chain(&popup.action_list)
+            .chain(&popup.search)
+            .chain(&popup.device_list)
+            .any(|g| matches!(g.width, LayoutSize::Percent(p) if p > 100))
+        {
+            anyhow::bail!(
+                "Invalid popup layout: the width's percentage cannot be greater than 100!"
+            );
+        }
+        Ok(())
+    }
+}
+
+impl LibraryLayoutConfig {
+    /// gets the library page's windows in the display order
+    pub fn windows(&self) -> Vec<LibraryWindowConfig> {
+        if !self.windows.is_empty() {
+            return self.windows.clone();
+        }
+        vec![
+            LibraryWindowConfig {
+                window: LibraryWindow::Playlists,
+                size: LayoutSize::Percent(self.playlist_percent),
+            },
+            LibraryWindowConfig {
+                window: LibraryWindow::Albums,
+                size: LayoutSize::Percent(self.album_percent),
+            },
+            LibraryWindowConfig {
+                window: LibraryWindow::Artists,
+                size: LayoutSize::Percent(100 - (self.album_percent + self.playlist_percent)),
+            },
+        ]
+    }
+}
+
+impl From<LayoutSize> for ratatui::layout::Constraint {
+    fn from(size: LayoutSize) -> Self {
+        match size {
+            LayoutSize::Percent(percent) => Self::Percentage(percent),
+            LayoutSize::Length(length) => Self::Length(length),
+            LayoutSize::Fill => Self::Fill(1),
+        }
+    }
+}
+
+impl Default for MouseConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            double_click_interval_in_ms: 400,
+            scroll_lines: 1,
+            double_click_to_choose: true,
+            right_click_to_show_actions: true,
+        }
+    }
+}
+
+impl Default for ScrollConfig {
+    fn default() -> Self {
+        Self {
+            scroll_off: 0,
+            wrap_around: false,
+        }
+    }
+}
+
+impl Default for ConfirmationConfig {
+    fn default() -> Self {
+        Self {
+            delete_playlist: true,
+            remove_track: false,
+            remove_album: false,
+            unfollow: false,
+        }
+    }
+}
+
+impl ConfirmationConfig {
+    /// whether a destructive action requires a confirmation
+    pub fn is_required(&self, action: DestructiveAction) -> bool {
+        match action {
+            DestructiveAction::DeletePlaylist => self.delete_playlist,
+            DestructiveAction::RemoveTrack => self.remove_track,
+            DestructiveAction::RemoveAlbum => self.remove_album,
+            DestructiveAction::Unfollow => self.unfollow,
+        }
+    }
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            categories: [
+                SearchCategory::Tracks,
+                SearchCategory::Albums,
+                SearchCategory::Artists,
+                SearchCategory::Playlists,
+                SearchCategory::Shows,
+                SearchCategory::Episodes,
+            ]
+            .into_iter()
+            .map(|category| SearchCategoryConfig {
+                category,
+                limit: 20,
+            })
+            .collect(),
+        }
+    }
+}
+
+impl SearchCategory {
+    /// gets the `rspotify` search type of the category,
+    /// or `None` if the category is not supported by `rspotify`
+    pub fn search_type(self) -> Option<rspotify::model::SearchType> {
+        use rspotify::model::SearchType;
+
+        match self {
+            Self::Tracks => Some(SearchType::Track),
+            Self::Albums => Some(SearchType::Album),
+            Self::Artists => Some(SearchType::Artist),
+            Self::Playlists => Some(SearchType::Playlist),
+            Self::Shows => Some(SearchType::Show),
+            Self::Episodes => Some(SearchType::Episode),
+            Self::Audiobooks => None,
+        }
+    }
+}
+
+impl SearchConfig {
+    /// gets the number of items to fetch for a search category,
+    /// or `None` if the category is not shown
+    pub fn limit(&self, category: SearchCategory) -> Option<u32> {
+        self.categories
+            .iter()
+            .find(|c| c.category == category)
+            .map(|c| c.limit)
+    }
+
+    fn check_values(&self) -> anyhow::Result<()> {
+        if let Some(c) = self
+            .categories
+            .iter()
+            .find(|c| !(1..=50).contains(&c.limit))
+        {
+            anyhow::bail!(
+                "Invalid search config: the limit of {:?} category must be between 1 and 50!",
+                c.category
+            );
+        }
+        let categories = &self.categories;
+        if let Some((_, c)) = categories
+            .iter()
+            .enumerate()
+            .find(|&(i, c)| categories[..i].iter().any(|x| x.category == c.category))
+        {
+            anyhow::bail!(
+                "Invalid search config: {:?} category is specified more than once!",
+                c.category
+            );
+        }
         Ok(())
     }
 }
 
 impl AppConfig {
+    /// gets the name of the application's theme, which is either `light_theme` or `dark_theme`
+    /// (defaulting to `theme`) if `theme_mode` is set, otherwise `theme`
+    pub fn theme_name(&self) -> &str {
+        let is_light = match self.theme_mode {
+            None => return &self.theme,
+            Some(ThemeMode::Light) => true,
+            Some(ThemeMode::Dark) => false,
+            Some(ThemeMode::Auto) => crate::terminal_background::is_light().unwrap_or_default(),
+        };
+        let theme = if is_light {
+            &self.light_theme
+        } else {
+            &self.dark_theme
+        };
+        theme.as_deref().unwrap_or(&self.theme)
+    }
+
     pub fn new(path: &Path) -> Result<Self> {
         let mut config = Self::default();
         if !config.parse_config_file(path)? {
@@ -401,18 +1257,62 @@ impl AppConfig {
         }
 
         config.layout.check_values()?;
+        config.search.check_values()?;
+        if chrono::format::StrftimeItems::new(&config.date_format)
+            .any(|item| matches!(item, chrono::format::Item::Error))
+        {
+            anyhow::bail!("Invalid date format: {}", config.date_format);
+        }
+        if chrono::format::StrftimeItems::new(&config.journal_file_name)
+            .any(|item| matches!(item, chrono::format::Item::Error))
+        {
+            anyhow::bail!("Invalid journal file name: {}", config.journal_file_name);
+        }
+        if config.device.sample_rate == Some(0) {
+            anyhow::bail!("Invalid sample rate: 0");
+        }
+        if config.device.bit_perfect && config.device.sample_rate.is_some() {
+            anyhow::bail!("Invalid device configurations: `sample_rate` requires resampling the audio, which can't be enabled with `bit_perfect`");
+        }
+        if config
+            .web_api_token
+            .as_ref()
+            .is_some_and(|token| token.trim().len() < MIN_WEB_API_TOKEN_LENGTH)
+        {
+            anyhow::bail!("Invalid web API token: `web_api_token` must be at least {MIN_WEB_API_TOKEN_LENGTH} characters long, e.g. generated by `openssl rand -base64 32`");
+        }
+        config.remote_key()?;
         Ok(config)
     }
 
+    /// gets the key shared with remote applications, decoded from `remote_secret`
+    pub fn remote_key(&self) -> Result<Option<[u8; 32]>> {
+        use base64::Engine as _;
+
+        let Some(secret) = &self.remote_secret else {
+            return Ok(None);
+        };
+        base64::engine::general_purpose::STANDARD
+            .decode(secret.trim())
+            .ok()
+            .and_then(|key| <[u8; 32]>::try_from(key).ok())
+            .map(Some)
+            .context("Invalid remote secret: `remote_secret` must be a 32-byte key encoded in base64, e.g. generated by `openssl rand -base64 32`")
+    }
+
     // parses configurations from an application config file in `path` folder,
     // then updates the current configurations accordingly.
     // returns false if no config file found and true otherwise
     fn parse_config_file(&mut self, path: &Path) -> Result<bool> {
         let file_path = path.join(APP_CONFIG_FILE);
-        match std::fs::read_to_string(file_path) {
-            Ok(content) => self
-                .parse(toml::from_str::<toml::Value>(&content)?)
-                .map(|()| true),
+        match std::fs::read_to_string(&file_path) {
+            Ok(content) => {
+                // validate the file against the configurations' schema first, whose errors
+                // point to the locations of invalid values
+                deserialize_config_file::<Self>(&content, &file_path, &disabled_feature_keys())?;
+                self.parse(toml::from_str::<toml::Value>(&content)?)
+                    .map(|()| true)
+            }
             Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(false),
             Err(error) => Err(error.into()),
         }
@@ -446,6 +1346,25 @@ impl AppConfig {
         }
     }
 
+    /// Find a device profile by its name
+    pub fn find_device_profile(&self, name: &str) -> Option<&DeviceProfile> {
+        self.device_profiles.iter().find(|p| p.name == name)
+    }
+
+    /// Find the volume configurations of a Connect device by its name
+    pub fn find_device_volume(&self, name: &str) -> Option<&DeviceVolumeConfig> {
+        self.device_volumes.iter().find(|v| v.name == name)
+    }
+
+    pub fn find_playback_default(
+        &self,
+        context_type: PlaybackContextType,
+    ) -> Option<&PlaybackDefault> {
+        self.playback_defaults
+            .iter()
+            .find(|d| d.context_type == context_type)
+    }
+
     /// Returns stdout of `client_id_command` if set, otherwise it returns the the value of `client_id`
     pub fn get_client_id(&self) -> Result<String> {
         match self.client_id_command {
@@ -455,6 +1374,82 @@ impl AppConfig {
     }
 }
 
+/// gets the keys of the app config file used by the features disabled at build time,
+/// which are ignored without a warning
+fn disabled_feature_keys() -> Vec<&'static str> {
+    let mut keys = vec![];
+    if !cfg!(feature = "websocket") {
+        keys.push("websocket_address");
+    }
+    if !cfg!(feature = "notify") {
+        keys.extend([
+            "notify_format",
+            "notify_timeout_in_secs",
+            "notify_events",
+            "enable_notify",
+        ]);
+    }
+    if !cfg!(feature = "image") {
+        keys.extend([
+            "cover_img_length",
+            "cover_img_width",
+            "cover_img_scale",
+            "cover_img_scale_x",
+            "cover_img_scale_y",
+            "image_protocol",
+        ]);
+    }
+    if !cfg!(feature = "pixelate") {
+        keys.push("cover_img_pixels");
+    }
+    if !cfg!(feature = "media-control") {
+        keys.push("enable_media_control");
+    }
+    if !cfg!(feature = "streaming") {
+        keys.extend(["enable_streaming", "enable_playback_push_updates"]);
+    }
+    if !cfg!(feature = "system-volume-sync") {
+        keys.push("system_volume_sync");
+    }
+    if !cfg!(all(feature = "streaming", feature = "notify")) {
+        keys.push("notify_streaming_only");
+    }
+    if !cfg!(feature = "mqtt") {
+        keys.extend(["enable_mqtt", "mqtt"]);
+    }
+    if !cfg!(feature = "scrobble") {
+        keys.extend(["scrobble_log_file", "lastfm", "listenbrainz"]);
+    }
+    keys
+}
+
+/// deserializes the content of a config file in `file_path`.
+///
+/// Invalid values are errors pointing to their locations, while unknown keys (e.g. misspelled
+/// or outdated keys) are ignored and reported as warnings, apart from `ignored_keys`.
+fn deserialize_config_file<T: serde::de::DeserializeOwned>(
+    content: &str,
+    file_path: &Path,
+    ignored_keys: &[&str],
+) -> Result<T> {
+    let mut unknown_keys = vec![];
+    let value = toml::Deserializer::parse(content)
+        .and_then(|deserializer| {
+            serde_ignored::deserialize(deserializer, |key| unknown_keys.push(key.to_string()))
+        })
+        .with_context(|| format!("invalid config file (path={file_path:?})"))?;
+
+    for key in unknown_keys {
+        let top_level_key = key.split('.').next().unwrap_or_default();
+        if !ignored_keys.contains(&top_level_key) {
+            tracing::warn!(
+                "Unknown key `{key}` in the config file (path={file_path:?}) is ignored"
+            );
+        }
+    }
+    Ok(value)
+}
+
 /// gets the application's configuration folder path
 pub fn get_config_folder_path() -> Result<PathBuf> {
     match dirs_next::home_dir() {
@@ -471,11 +1466,88 @@ pub fn get_cache_folder_path() -> Result<PathBuf> {
     }
 }
 
-pub fn get_config() -> &'static Configs {
-    CONFIGS.get().expect("configs is already initialized")
+/// gets the folder of a profile inside the application's config or cache folder
+pub fn get_profile_folder_path(folder: &Path, profile: &str) -> PathBuf {
+    folder.join(PROFILES_FOLDER).join(profile)
+}
+
+/// Get the application's current configurations. The returned configurations stay the same
+/// until they're dropped, even if the configurations are reloaded meanwhile.
+pub fn get_config() -> Arc<Configs> {
+    CONFIGS.load_full().expect("configs is already initialized")
 }
+
+/// Set the application's configurations, which replace the previous configurations upon
+/// reloading them
 pub fn set_config(configs: Configs) {
-    CONFIGS
-        .set(configs)
-        .expect("configs should be initialized only once");
+    CONFIGS.store(Some(Arc::new(configs)));
+}
+
+/// Get the paths to the configuration files inside a configuration folder
+pub fn config_file_paths(config_folder: &Path) -> [PathBuf; 3] {
+    [APP_CONFIG_FILE, THEME_CONFIG_FILE, KEYMAP_CONFIG_FILE].map(|file| config_folder.join(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> FormatToken {
+        FormatToken::Text(s.to_string())
+    }
+
+    fn placeholder(s: &str) -> FormatToken {
+        FormatToken::Placeholder(s.to_string())
+    }
+
+    #[test]
+    fn format_template_tokens() {
+        let template = FormatTemplate::new("{status} {track} • {artists}");
+        assert_eq!(
+            template.tokens(),
+            [
+                placeholder("{status}"),
+                text(" "),
+                placeholder("{track}"),
+                text(" • "),
+                placeholder("{artists}"),
+            ]
+        );
+        assert!(FormatTemplate::new("").tokens().is_empty());
+    }
+
+    #[test]
+    fn format_template_newlines() {
+        let template = FormatTemplate::new("{track}\n\n{album}\n");
+        assert_eq!(
+            template.tokens(),
+            [
+                placeholder("{track}"),
+                FormatToken::Newline,
+                FormatToken::Newline,
+                placeholder("{album}"),
+                FormatToken::Newline,
+            ]
+        );
+    }
+
+    #[test]
+    fn format_template_unclosed_placeholders() {
+        // an unclosed placeholder is text, and a placeholder can't span multiple lines
+        assert_eq!(
+            FormatTemplate::new("{track • {artists}").tokens(),
+            [text("{track • "), placeholder("{artists}")]
+        );
+        assert_eq!(
+            FormatTemplate::new("{track\n}").tokens(),
+            [text("{track"), FormatToken::Newline, text("}")]
+        );
+        assert_eq!(FormatTemplate::new("a } {").tokens(), [text("a } {")]);
+    }
+
+    #[test]
+    fn format_template_source() {
+        let source = "{status} {track}\n{album}";
+        assert_eq!(String::from(FormatTemplate::new(source)), source);
+    }
 }
@@ -4,6 +4,8 @@ use anyhow::Result;
 use ratatui::style;
 use serde::Deserialize;
 
+use super::BorderType;
+
 #[derive(Clone, Debug, Deserialize)]
 /// Application theme configurations.
 pub struct ThemeConfig {
@@ -11,6 +13,34 @@ pub struct ThemeConfig {
     pub themes: Vec<Theme>,
 }
 
+#[derive(Clone, Debug, Deserialize)]
+/// Theme configurations as written in the theme config file, whose themes
+/// may extend other themes
+struct RawThemeConfig {
+    #[serde(default)]
+    themes: Vec<toml::Table>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+/// The theme config file's schema, which is used to validate the file before
+/// resolving the themes' inheritance
+struct ThemeConfigSchema {
+    #[serde(default)]
+    themes: Vec<ThemeSchema>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+/// A theme as written in the theme config file
+struct ThemeSchema {
+    name: String,
+    extends: Option<String>,
+    palette: Option<Palette>,
+    component_style: Option<ComponentStyle>,
+    window_style: Option<WindowStyles>,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct Theme {
     pub name: String,
@@ -18,6 +48,37 @@ pub struct Theme {
     palette: Palette,
     #[serde(default)]
     component_style: ComponentStyle,
+    #[serde(default)]
+    window_style: WindowStyles,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// A kind of the application's windows, whose block can be styled separately
+pub enum Window {
+    Playback,
+    /// the windows of the library page
+    Library,
+    /// the windows of pages other than the library page
+    Page,
+    Popup,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+struct WindowStyles {
+    #[serde(default)]
+    playback: WindowStyle,
+    #[serde(default)]
+    library: WindowStyle,
+    #[serde(default)]
+    page: WindowStyle,
+    #[serde(default)]
+    popup: WindowStyle,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+struct WindowStyle {
+    border_type: Option<BorderType>,
+    show_title: Option<bool>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -76,10 +137,14 @@ struct ComponentStyle {
     playlist_desc: Option<Style>,
     table_header: Option<Style>,
     selection: Option<Style>,
+    selection_inactive: Option<Style>,
     secondary_row: Option<Style>,
     like: Option<Style>,
     lyrics_played: Option<Style>,
     lyrics_playing: Option<Style>,
+    popup_block_title: Option<Style>,
+    popup_border: Option<Style>,
+    playback_border: Option<Style>,
 }
 
 #[derive(Default, Clone, Debug, Deserialize)]
@@ -152,11 +217,20 @@ impl ThemeConfig {
                 );
             }
             Ok(content) => {
-                let config = toml::from_str::<Self>(&content)?;
+                super::deserialize_config_file::<ThemeConfigSchema>(&content, &file_path, &[])?;
+                let config = toml::from_str::<RawThemeConfig>(&content)?;
+                let themes = config
+                    .themes
+                    .iter()
+                    .map(|theme| {
+                        let table = resolve_theme_table(theme, &config.themes, &mut vec![])?;
+                        Ok(toml::Value::Table(table).try_into::<Theme>()?)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
 
                 // merge user-defined themes and the application default themes
                 // Skip any theme whose name conflicts with already existed theme in the current application's themes
-                config.themes.into_iter().for_each(|theme| {
+                themes.into_iter().for_each(|theme| {
                     if !self.themes.iter().any(|t| t.name == theme.name) {
                         self.themes.push(theme);
                     }
@@ -167,7 +241,83 @@ impl ThemeConfig {
     }
 }
 
+/// resolves a theme's table by merging it into the (resolved) table of the theme it extends.
+/// `visited` stores the names of the themes being resolved to detect cyclic inheritance.
+fn resolve_theme_table(
+    theme: &toml::Table,
+    themes: &[toml::Table],
+    visited: &mut Vec<String>,
+) -> Result<toml::Table> {
+    let name = theme
+        .get("name")
+        .and_then(|n| n.as_str())
+        .unwrap_or_default();
+    if visited.iter().any(|v| v == name) {
+        anyhow::bail!(
+            "cyclic theme inheritance: {} -> {name}",
+            visited.join(" -> ")
+        );
+    }
+    visited.push(name.to_string());
+
+    let mut table = theme.clone();
+    let Some(base) = table.remove("extends") else {
+        return Ok(table);
+    };
+    let base = base
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("`extends` of a theme must be a theme's name"))?
+        .to_string();
+
+    let base_table = match themes
+        .iter()
+        .find(|t| t.get("name").and_then(|n| n.as_str()) == Some(&base))
+    {
+        Some(t) => resolve_theme_table(t, themes, visited)?,
+        // the default theme has the default palette and component styles
+        None if base == "default" => toml::Table::new(),
+        None => anyhow::bail!("theme {base} to extend is not found"),
+    };
+    Ok(merge_tables(base_table, table))
+}
+
+/// merges the `overrides` table into the `base` table recursively
+fn merge_tables(mut base: toml::Table, overrides: toml::Table) -> toml::Table {
+    for (key, value) in overrides {
+        let value = match (base.remove(&key), value) {
+            (Some(toml::Value::Table(b)), toml::Value::Table(o)) => {
+                toml::Value::Table(merge_tables(b, o))
+            }
+            (_, value) => value,
+        };
+        base.insert(key, value);
+    }
+    base
+}
+
 impl Theme {
+    fn window_style(&self, window: Window) -> &WindowStyle {
+        match window {
+            Window::Playback => &self.window_style.playback,
+            Window::Library => &self.window_style.library,
+            Window::Page => &self.window_style.page,
+            Window::Popup => &self.window_style.popup,
+        }
+    }
+
+    /// gets the border type of a window, defaulting to the `border_type` config option
+    pub fn border_type(&self, window: Window) -> BorderType {
+        self.window_style(window)
+            .border_type
+            .clone()
+            .unwrap_or_else(|| super::get_config().app_config.border_type.clone())
+    }
+
+    /// checks whether the title of a window is shown
+    pub fn show_title(&self, window: Window) -> bool {
+        self.window_style(window).show_title.unwrap_or(true)
+    }
+
     pub fn app(&self) -> style::Style {
         let mut style = style::Style::default();
         if let Some(ref c) = self.palette.background {
@@ -189,7 +339,11 @@ impl Theme {
                 )
                 .style(&self.palette)
         } else {
-            style::Style::default()
+            self.component_style
+                .selection_inactive
+                .as_ref()
+                .unwrap_or(&Style::default())
+                .style(&self.palette)
         }
     }
 
@@ -209,6 +363,27 @@ impl Theme {
             .style(&self.palette)
     }
 
+    pub fn popup_block_title(&self) -> style::Style {
+        match self.component_style.popup_block_title {
+            Some(ref style) => style.style(&self.palette),
+            None => self.block_title(),
+        }
+    }
+
+    pub fn popup_border(&self) -> style::Style {
+        match self.component_style.popup_border {
+            Some(ref style) => style.style(&self.palette),
+            None => self.border(),
+        }
+    }
+
+    pub fn playback_border(&self) -> style::Style {
+        match self.component_style.playback_border {
+            Some(ref style) => style.style(&self.palette),
+            None => self.border(),
+        }
+    }
+
     pub fn playback_status(&self) -> style::Style {
         self.component_style
             .playback_status
@@ -561,7 +736,7 @@ impl From<style::Color> for Color {
 impl Default for ThemeConfig {
     fn default() -> Self {
         Self {
-            themes: vec![Theme::default()],
+            themes: vec![Theme::default(), Theme::terminal()],
         }
     }
 }
@@ -572,6 +747,49 @@ impl Default for Theme {
             name: "default".to_owned(),
             palette: Palette::default(),
             component_style: ComponentStyle::default(),
+            window_style: WindowStyles::default(),
+        }
+    }
+}
+
+impl Theme {
+    /// the built-in `terminal` theme, which only uses the terminal's 16 ANSI colors, so it matches
+    /// the terminal's color scheme (e.g. a base16 scheme). Bright colors other than `BrightBlack`
+    /// are avoided because many base16 schemes map them to the same colors as the normal ones.
+    fn terminal() -> Self {
+        let bold = || [StyleModifier::Bold];
+        Self {
+            name: "terminal".to_owned(),
+            palette: Palette::default(),
+            component_style: ComponentStyle {
+                block_title: Some(Style::default().fg(StyleColor::Blue).modifiers(bold())),
+                border: Some(Style::default().fg(StyleColor::BrightBlack)),
+                playback_status: Some(Style::default().fg(StyleColor::Cyan).modifiers(bold())),
+                playback_track: Some(Style::default().modifiers(bold())),
+                playback_artists: Some(Style::default().fg(StyleColor::Magenta)),
+                playback_album: Some(Style::default().fg(StyleColor::Yellow)),
+                playback_metadata: Some(Style::default().fg(StyleColor::BrightBlack)),
+                playback_progress_bar: Some(
+                    Style::default()
+                        .fg(StyleColor::Green)
+                        .bg(StyleColor::BrightBlack),
+                ),
+                playback_progress_bar_unfilled: Some(Style::default().fg(StyleColor::BrightBlack)),
+                current_playing: Some(Style::default().fg(StyleColor::Green).modifiers(bold())),
+                page_desc: Some(Style::default().fg(StyleColor::Cyan).modifiers(bold())),
+                playlist_desc: Some(Style::default().fg(StyleColor::BrightBlack)),
+                table_header: Some(Style::default().fg(StyleColor::Yellow).modifiers(bold())),
+                selection: None,
+                selection_inactive: None,
+                secondary_row: None,
+                like: Some(Style::default().fg(StyleColor::Red)),
+                lyrics_played: Some(Style::default().fg(StyleColor::BrightBlack)),
+                lyrics_playing: Some(Style::default().fg(StyleColor::Green).modifiers(bold())),
+                popup_block_title: Some(Style::default().fg(StyleColor::Magenta).modifiers(bold())),
+                popup_border: Some(Style::default().fg(StyleColor::Blue)),
+                playback_border: None,
+            },
+            window_style: WindowStyles::default(),
         }
     }
 }
@@ -601,3 +819,111 @@ impl Default for Palette {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_themes(content: &str) -> Vec<toml::Table> {
+        toml::from_str::<RawThemeConfig>(content).unwrap().themes
+    }
+
+    fn resolve(themes: &[toml::Table], i: usize) -> Result<toml::Table> {
+        resolve_theme_table(&themes[i], themes, &mut vec![])
+    }
+
+    #[test]
+    fn resolve_theme_extending_theme() {
+        let themes = parse_themes(
+            r#"
+            [[themes]]
+            name = "base"
+            [themes.palette]
+            foreground = "white"
+            background = "black"
+            [themes.component_style]
+            block_title = { fg = "Magenta", modifiers = ["Bold"] }
+
+            [[themes]]
+            name = "child"
+            extends = "base"
+            [themes.palette]
+            background = "blue"
+            [themes.component_style]
+            block_title = { fg = "Green" }
+            "#,
+        );
+
+        let table = resolve(&themes, 1).unwrap();
+        assert_eq!(table["name"].as_str(), Some("child"));
+        assert!(!table.contains_key("extends"));
+        assert_eq!(table["palette"]["foreground"].as_str(), Some("white"));
+        assert_eq!(table["palette"]["background"].as_str(), Some("blue"));
+        let block_title = &table["component_style"]["block_title"];
+        assert_eq!(block_title["fg"].as_str(), Some("Green"));
+        assert_eq!(block_title["modifiers"][0].as_str(), Some("Bold"));
+        assert!(toml::Value::Table(table).try_into::<Theme>().is_ok());
+    }
+
+    #[test]
+    fn resolve_theme_extending_default_theme() {
+        let themes = parse_themes(
+            r#"
+            [[themes]]
+            name = "child"
+            extends = "default"
+            "#,
+        );
+        assert!(resolve(&themes, 0).is_ok());
+    }
+
+    #[test]
+    fn resolve_theme_errors() {
+        let themes = parse_themes(
+            r#"
+            [[themes]]
+            name = "missing"
+            extends = "unknown"
+
+            [[themes]]
+            name = "invalid"
+            extends = 1
+            "#,
+        );
+        assert!(resolve(&themes, 0).is_err());
+        assert!(resolve(&themes, 1).is_err());
+    }
+
+    #[test]
+    fn resolve_theme_cyclic_inheritance() {
+        let themes = parse_themes(
+            r#"
+            [[themes]]
+            name = "a"
+            extends = "b"
+
+            [[themes]]
+            name = "b"
+            extends = "a"
+
+            [[themes]]
+            name = "c"
+            extends = "c"
+
+            [[themes]]
+            name = "d"
+            extends = "a"
+            "#,
+        );
+
+        let err = resolve(&themes, 0).unwrap_err();
+        assert_eq!(err.to_string(), "cyclic theme inheritance: a -> b -> a");
+        let err = resolve(&themes, 2).unwrap_err();
+        assert_eq!(err.to_string(), "cyclic theme inheritance: c -> c");
+        let err = resolve(&themes, 3).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cyclic theme inheritance: d -> a -> b -> a"
+        );
+    }
+}
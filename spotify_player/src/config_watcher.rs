@@ -0,0 +1,79 @@
+//! Hot-reloading of the application's configurations upon changes of the configuration files
+
+use std::{path::PathBuf, time::Duration};
+
+use file_notify::Watcher;
+
+use crate::{
+    config,
+    state::{PopupState, SharedState},
+};
+
+/// delay before reloading the configurations, which groups the events of a single file save
+const RELOAD_DEBOUNCE_DELAY: Duration = Duration::from_millis(100);
+
+/// Starts the configuration watcher, which reloads the configurations with `load_configs`
+/// whenever a configuration file inside `config_folder` changes. If the new configurations
+/// are invalid, the previous configurations are kept and an error popup is shown.
+pub async fn start_config_watcher(
+    state: SharedState,
+    config_folder: PathBuf,
+    load_configs: config::ConfigLoader,
+) {
+    let (tx, rx) = flume::unbounded();
+    let mut watcher = match file_notify::recommended_watcher(move |event| {
+        tx.send(event).unwrap_or_default();
+    }) {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            tracing::error!("Failed to create the configuration watcher: {err:#}");
+            return;
+        }
+    };
+    // the folder is watched instead of the files, which are often replaced upon saving
+    if let Err(err) = watcher.watch(&config_folder, file_notify::RecursiveMode::NonRecursive) {
+        tracing::error!(
+            "Failed to watch the configuration folder {}: {err:#}",
+            config_folder.display()
+        );
+        return;
+    }
+
+    let paths = config::config_file_paths(&config_folder);
+    while let Ok(event) = rx.recv_async().await {
+        let is_config_changed = event.is_ok_and(|event| {
+            !event.kind.is_access()
+                && event
+                    .paths
+                    .iter()
+                    .any(|path| paths.iter().any(|p| p.file_name() == path.file_name()))
+        });
+        if !is_config_changed {
+            continue;
+        }
+        tokio::time::sleep(RELOAD_DEBOUNCE_DELAY).await;
+        rx.drain();
+
+        match load_configs() {
+            Ok(configs) => {
+                let theme = configs
+                    .theme_config
+                    .find_theme(configs.app_config.theme_name());
+                config::set_config(configs);
+                tracing::info!("Reloaded the configurations");
+
+                let mut ui = state.ui.lock();
+                if let Some(theme) = theme {
+                    ui.theme = theme;
+                }
+                if matches!(ui.popup, Some(PopupState::ConfigError(_))) {
+                    ui.popup = None;
+                }
+            }
+            Err(err) => {
+                tracing::warn!("Failed to reload the configurations: {err:#}");
+                state.ui.lock().popup = Some(PopupState::ConfigError(format!("{err:#}")));
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! A 10-band equalizer applied to the integrated device's audio before it's written
+//! into the audio backend's sink
+
+use librespot_playback::{
+    audio_backend::{Sink, SinkResult},
+    convert::Converter,
+    decoder::AudioPacket,
+    NUM_CHANNELS, SAMPLE_RATE,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::{
+    config::{self, EqualizerPreset},
+    state::{load_data_from_file_cache, store_data_into_file_cache, FileCacheKey, SharedState},
+};
+
+/// center frequencies (in Hz) of the equalizer's bands
+pub const BANDS: [f64; 10] = [
+    31.0, 62.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0,
+];
+/// maximum gain (in dB) of a band, the minimum gain being its opposite
+pub const MAX_GAIN: f32 = 12.0;
+/// quality factor of each band's filter, which is about one octave wide
+const Q: f64 = 1.41;
+
+/// Equalizer settings, which can be changed at runtime
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EqualizerSettings {
+    pub enabled: bool,
+    /// preset of the gains, `None` if the gains are customized
+    pub preset: Option<EqualizerPreset>,
+    /// gain (in dB) of each band
+    pub gains: [f32; 10],
+}
+
+/// Equalizer settings adjusted at runtime, stored in the cache folder along with
+/// the configured settings they were adjusted from
+#[derive(Serialize, Deserialize)]
+struct StoredEqualizerSettings {
+    configured: EqualizerSettings,
+    adjusted: EqualizerSettings,
+}
+
+impl EqualizerSettings {
+    /// Load the settings stored in the cache folder, default to the configured settings.
+    ///
+    /// The stored settings are discarded once the equalizer's configurations are changed,
+    /// so that the changed configurations take effect.
+    pub fn load() -> Self {
+        let configured = Self::configured();
+        match load_data_from_file_cache::<StoredEqualizerSettings>(
+            FileCacheKey::Equalizer,
+            &config::get_config().cache_folder,
+        ) {
+            Some(stored) if stored.configured == configured => stored.adjusted,
+            _ => configured,
+        }
+    }
+
+    /// Get the settings specified by the device's configurations
+    fn configured() -> Self {
+        let configs = config::get_config();
+        let device = &configs.app_config.device;
+        let mut settings = Self {
+            enabled: device.equalizer,
+            preset: Some(device.equalizer_preset),
+            gains: device.equalizer_preset.gains(),
+        };
+        if !device.equalizer_gains.is_empty() {
+            settings.preset = None;
+            for (gain, value) in settings.gains.iter_mut().zip(&device.equalizer_gains) {
+                *gain = value.clamp(-MAX_GAIN, MAX_GAIN);
+            }
+        }
+        settings
+    }
+
+    /// Store the settings into the cache folder
+    pub fn store(&self) {
+        let stored = StoredEqualizerSettings {
+            configured: Self::configured(),
+            adjusted: self.clone(),
+        };
+        if let Err(err) = store_data_into_file_cache(
+            FileCacheKey::Equalizer,
+            &config::get_config().cache_folder,
+            &stored,
+        ) {
+            tracing::error!("Failed to store the equalizer settings into the file cache: {err:#}");
+        }
+    }
+
+    /// Change a band's gain by an offset, which customizes the gains
+    pub fn change_gain(&mut self, band: usize, offset: f32) {
+        if let Some(gain) = self.gains.get_mut(band) {
+            *gain = (*gain + offset).clamp(-MAX_GAIN, MAX_GAIN);
+            self.preset = None;
+        }
+    }
+
+    /// Switch to the next preset
+    pub fn next_preset(&mut self) {
+        let preset = match self.preset {
+            Some(EqualizerPreset::Flat) => EqualizerPreset::BassBoost,
+            Some(EqualizerPreset::BassBoost) => EqualizerPreset::Vocal,
+            Some(EqualizerPreset::Vocal) | None => EqualizerPreset::Flat,
+        };
+        self.preset = Some(preset);
+        self.gains = preset.gains();
+    }
+}
+
+/// A peaking filter of a band, implemented as a biquad filter, see
+/// <https://www.w3.org/TR/audio-eq-cookbook/>
+struct PeakingFilter {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    /// last two input and output samples of each channel
+    history: [[f64; 4]; NUM_CHANNELS as usize],
+}
+
+impl PeakingFilter {
+    fn new(frequency: f64, gain: f32) -> Self {
+        let a = 10_f64.powf(f64::from(gain) / 40.0);
+        let w0 = 2.0 * std::f64::consts::PI * frequency / f64::from(SAMPLE_RATE);
+        let alpha = w0.sin() / (2.0 * Q);
+        let a0 = 1.0 + alpha / a;
+        Self {
+            b0: (1.0 + alpha * a) / a0,
+            b1: -2.0 * w0.cos() / a0,
+            b2: (1.0 - alpha * a) / a0,
+            a1: -2.0 * w0.cos() / a0,
+            a2: (1.0 - alpha / a) / a0,
+            history: Default::default(),
+        }
+    }
+
+    fn process(&mut self, channel: usize, x: f64) -> f64 {
+        let [x1, x2, y1, y2] = self.history[channel];
+        let y = self.b0 * x + self.b1 * x1 + self.b2 * x2 - self.a1 * y1 - self.a2 * y2;
+        self.history[channel] = [x, x1, y, y1];
+        y
+    }
+}
+
+/// A sink applying the equalizer to the audio samples before writing them into another sink.
+///
+/// The sink is only inserted into the audio pipeline while the equalizer is enabled. The settings
+/// adjusted at runtime are shared via the application's state without locking.
+pub struct EqualizerSink {
+    sink: Box<dyn Sink>,
+    state: SharedState,
+    /// settings of the current filters, to detect setting changes
+    settings: Option<Arc<EqualizerSettings>>,
+    filters: Vec<PeakingFilter>,
+    /// gain applied before the filters to avoid clipping when a band is boosted
+    pre_gain: f64,
+}
+
+impl EqualizerSink {
+    pub fn new(sink: Box<dyn Sink>, state: SharedState) -> Self {
+        Self {
+            sink,
+            state,
+            settings: None,
+            filters: Vec::new(),
+            pre_gain: 1.0,
+        }
+    }
+
+    /// Update the filters upon setting changes
+    fn update_filters(&mut self) {
+        if let Some(settings) = &self.settings {
+            if Arc::ptr_eq(settings, &self.state.equalizer.load()) {
+                return;
+            }
+        }
+        let settings = self.state.equalizer.load_full();
+
+        self.filters = if settings.enabled {
+            BANDS
+                .iter()
+                .zip(settings.gains)
+                .filter(|(_, gain)| gain.abs() > f32::EPSILON)
+                .map(|(frequency, gain)| PeakingFilter::new(*frequency, gain))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let max_gain = settings.gains.iter().copied().fold(0.0_f32, f32::max);
+        self.pre_gain = if settings.enabled {
+            10_f64.powf(-f64::from(max_gain) / 20.0)
+        } else {
+            1.0
+        };
+        self.settings = Some(settings);
+    }
+}
+
+impl Sink for EqualizerSink {
+    fn start(&mut self) -> SinkResult<()> {
+        // reset the filters' history of the previous stream
+        self.settings = None;
+        self.sink.start()
+    }
+
+    fn stop(&mut self) -> SinkResult<()> {
+        self.sink.stop()
+    }
+
+    fn write(&mut self, packet: AudioPacket, converter: &mut Converter) -> SinkResult<()> {
+        let AudioPacket::Samples(mut samples) = packet else {
+            return self.sink.write(packet, converter);
+        };
+
+        self.update_filters();
+        if !self.filters.is_empty() {
+            // samples are interleaved by channels
+            for (i, sample) in samples.iter_mut().enumerate() {
+                let channel = i % NUM_CHANNELS as usize;
+                let mut value = *sample * self.pre_gain;
+                for filter in &mut self.filters {
+                    value = filter.process(channel, value);
+                }
+                *sample = value.clamp(-1.0, 1.0);
+            }
+        }
+        self.sink.write(AudioPacket::Samples(samples), converter)
+    }
+}
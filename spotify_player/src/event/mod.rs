@@ -4,18 +4,21 @@ use crate::{
         self, construct_artist_actions, Action, ActionContext, ActionTarget, Command,
         CommandOrAction,
     },
-    config,
+    config::{self, DestructiveAction},
     key::{Key, KeySequence},
     state::{
         ActionListItem, Album, AlbumId, Artist, ArtistFocusState, ArtistId, ArtistPopupAction,
         BrowsePageUIState, Context, ContextId, ContextPageType, ContextPageUIState, DataReadGuard,
         Focusable, Id, Item, ItemId, LibraryFocusState, LibraryPageUIState, MutableWindowState,
-        PageState, PageType, PlayableId, Playback, PlaylistCreateCurrentField, PlaylistFolderItem, PlaylistId,
-        PlaylistPopupAction, PopupState, SearchFocusState, SearchPageUIState, SharedState, ShowId,
-        Track, TrackId, TrackOrder, UIStateGuard, USER_LIKED_TRACKS_ID,
+        PageState, PageType, PendingConfirmation, PlayableId, Playback, PlaylistCreateCurrentField, PlaylistFolderItem, PlaylistId,
+        PlaylistPopupAction, PopupState, SavedSearch, SearchFocusState, SearchMode, SearchPageUIState, SharedState,
+        ShowId, Track, TrackOrder, UIStateGuard, USER_LIKED_TRACKS_ID,
         USER_RECENTLY_PLAYED_TRACKS_ID, USER_TOP_TRACKS_ID,
     },
-    ui::{single_line_input::LineInput, Orientation},
+    ui::{
+        single_line_input::{InputEffect, LineInput},
+        Orientation,
+    },
     utils::parse_uri,
 };
 
@@ -23,20 +26,30 @@ use crate::utils::map_join;
 use anyhow::{Context as _, Result};
 use crossterm::event::KeyCode;
 
-use clipboard::{execute_copy_command, get_clipboard_content};
+pub use clipboard::execute_copy_command;
+use clipboard::get_clipboard_content;
 use ratatui::widgets::ListState;
 
 mod clipboard;
+mod mouse;
 mod page;
 mod popup;
 mod window;
 
+/// maximum value of a count prefix
+const MAX_COUNT_PREFIX: usize = 9999;
+/// maximum number of tracks skipped by a command with a count prefix,
+/// as each skipped track is a separate API request
+const MAX_SKIPPED_TRACKS: usize = 10;
+
 /// Start a terminal event handler (key pressed, mouse clicked, etc)
 pub fn start_event_handler(state: &SharedState, client_pub: &flume::Sender<ClientRequest>) {
     while let Ok(event) = crossterm::event::read() {
         let _enter = tracing::info_span!("terminal_event", event = ?event).entered();
         if let Err(err) = match event {
-            crossterm::event::Event::Mouse(event) => handle_mouse_event(event, client_pub, state),
+            crossterm::event::Event::Mouse(event) => {
+                mouse::handle_mouse_event(event, client_pub, state)
+            }
             crossterm::event::Event::Resize(columns, rows) => {
                 state.ui.lock().orientation = Orientation::from_size(columns, rows);
                 Ok(())
@@ -59,292 +72,6 @@ pub fn start_event_handler(state: &SharedState, client_pub: &flume::Sender<Clien
     }
 }
 
-// Handle a terminal mouse event
-fn handle_mouse_event(
-    event: crossterm::event::MouseEvent,
-    client_pub: &flume::Sender<ClientRequest>,
-    state: &SharedState,
-) -> Result<()> {
-    let mut ui = state.ui.lock();
-    let rect_contains = |r: ratatui::layout::Rect, col: u16, row: u16| -> bool {
-        col >= r.x && col < r.x + r.width && row >= r.y && row < r.y + r.height
-    };
-
-    match event.kind {
-        // Scroll to move selection down/up within focused pane
-        crossterm::event::MouseEventKind::ScrollDown => {
-            if let Some(selected) = ui.current_page_mut().selected() {
-                ui.current_page_mut().select(selected.saturating_add(1));
-            } else {
-                ui.current_page_mut().select(0);
-            }
-        }
-        crossterm::event::MouseEventKind::ScrollUp => {
-            if let Some(selected) = ui.current_page_mut().selected() {
-                ui.current_page_mut().select(selected.saturating_sub(1));
-            }
-        }
-        crossterm::event::MouseEventKind::Down(crossterm::event::MouseButton::Left) => {
-            tracing::debug!("Handling mouse event: {event:?}");
-            // Detect double click
-            let now = std::time::Instant::now();
-            let is_double = ui
-                .last_click
-                .map(|lc| lc.col == event.column && lc.row == event.row && now.duration_since(lc.when) <= std::time::Duration::from_millis(400))
-                .unwrap_or(false);
-            ui.last_click = Some(crate::state::LastClick {
-                when: now,
-                col: event.column,
-                row: event.row,
-            });
-
-            // Handle click on playback progress bar to seek
-            let pbar = ui.playback_progress_bar_rect;
-            if event.row == pbar.y && pbar.width > 0 {
-                let player = state.player.read();
-                let duration = match player.currently_playing() {
-                    Some(rspotify::model::PlayableItem::Track(track)) => Some(track.duration),
-                    Some(rspotify::model::PlayableItem::Episode(episode)) => Some(episode.duration),
-                    None => None,
-                };
-                if let Some(duration) = duration {
-                    // Clamp column within bar
-                    let rel_col = event
-                        .column
-                        .saturating_sub(pbar.x)
-                        .min(pbar.width.saturating_sub(1));
-                    let position_ms =
-                        (duration.num_milliseconds()) * i64::from(rel_col) / i64::from(pbar.width);
-                    drop(ui);
-                    client_pub.send(ClientRequest::Player(PlayerRequest::SeekTrack(
-                        chrono::Duration::try_milliseconds(position_ms).unwrap(),
-                    )))?;
-                    return Ok(());
-                }
-            }
-
-            // Hit-test panes and update focus/selection
-            let col = event.column;
-            let row = event.row;
-            // Copy rects to avoid immutable borrow while mutably borrowing page state
-            let rects = ui.rects;
-            match ui.current_page_mut() {
-                PageState::Library { state: lib } => {
-                    if rect_contains(rects.library_playlists, col, row) {
-                        lib.focus = LibraryFocusState::Playlists;
-                        let y = row.saturating_sub(rects.library_playlists.y);
-                        lib.playlist_list.select(Some(y as usize));
-                        if is_double {
-                            drop(lib);
-                            drop(ui);
-                            // Trigger choose selected on double click
-                            let mut ui2 = state.ui.lock();
-                            let _ = page::handle_key_sequence_for_page(&KeySequence { keys: vec![Key::None(KeyCode::Enter)] }, client_pub, state, &mut ui2);
-                            return Ok(());
-                        }
-                    } else if rect_contains(rects.library_albums, col, row) {
-                        lib.focus = LibraryFocusState::SavedAlbums;
-                        let y = row.saturating_sub(rects.library_albums.y);
-                        lib.saved_album_list.select(Some(y as usize));
-                        if is_double {
-                            drop(lib);
-                            drop(ui);
-                            let mut ui2 = state.ui.lock();
-                            let _ = page::handle_key_sequence_for_page(&KeySequence { keys: vec![Key::None(KeyCode::Enter)] }, client_pub, state, &mut ui2);
-                            return Ok(());
-                        }
-                    } else if rect_contains(rects.library_artists, col, row) {
-                        lib.focus = LibraryFocusState::FollowedArtists;
-                        let y = row.saturating_sub(rects.library_artists.y);
-                        lib.followed_artist_list.select(Some(y as usize));
-                        if is_double {
-                            drop(lib);
-                            drop(ui);
-                            let mut ui2 = state.ui.lock();
-                            let _ = page::handle_key_sequence_for_page(&KeySequence { keys: vec![Key::None(KeyCode::Enter)] }, client_pub, state, &mut ui2);
-                            return Ok(());
-                        }
-                    }
-                }
-                PageState::Search { state: search, .. } => {
-                    if rect_contains(rects.search_tracks, col, row) {
-                        search.focus = SearchFocusState::Tracks;
-                        let y = row.saturating_sub(rects.search_tracks.y);
-                        search.track_list.select(Some(y as usize));
-                        if is_double {
-                            drop(search);
-                            drop(ui);
-                            let mut ui2 = state.ui.lock();
-                            let _ = page::handle_key_sequence_for_page(&KeySequence { keys: vec![Key::None(KeyCode::Enter)] }, client_pub, state, &mut ui2);
-                            return Ok(());
-                        }
-                    } else if rect_contains(rects.search_albums, col, row) {
-                        search.focus = SearchFocusState::Albums;
-                        let y = row.saturating_sub(rects.search_albums.y);
-                        search.album_list.select(Some(y as usize));
-                        if is_double {
-                            drop(search);
-                            drop(ui);
-                            let mut ui2 = state.ui.lock();
-                            let _ = page::handle_key_sequence_for_page(&KeySequence { keys: vec![Key::None(KeyCode::Enter)] }, client_pub, state, &mut ui2);
-                            return Ok(());
-                        }
-                    } else if rect_contains(rects.search_artists, col, row) {
-                        search.focus = SearchFocusState::Artists;
-                        let y = row.saturating_sub(rects.search_artists.y);
-                        search.artist_list.select(Some(y as usize));
-                        if is_double {
-                            drop(search);
-                            drop(ui);
-                            let mut ui2 = state.ui.lock();
-                            let _ = page::handle_key_sequence_for_page(&KeySequence { keys: vec![Key::None(KeyCode::Enter)] }, client_pub, state, &mut ui2);
-                            return Ok(());
-                        }
-                    } else if rect_contains(rects.search_playlists, col, row) {
-                        search.focus = SearchFocusState::Playlists;
-                        let y = row.saturating_sub(rects.search_playlists.y);
-                        search.playlist_list.select(Some(y as usize));
-                        if is_double {
-                            drop(search);
-                            drop(ui);
-                            let mut ui2 = state.ui.lock();
-                            let _ = page::handle_key_sequence_for_page(&KeySequence { keys: vec![Key::None(KeyCode::Enter)] }, client_pub, state, &mut ui2);
-                            return Ok(());
-                        }
-                    } else if rect_contains(rects.search_shows, col, row) {
-                        search.focus = SearchFocusState::Shows;
-                        let y = row.saturating_sub(rects.search_shows.y);
-                        search.show_list.select(Some(y as usize));
-                        if is_double {
-                            drop(search);
-                            drop(ui);
-                            let mut ui2 = state.ui.lock();
-                            let _ = page::handle_key_sequence_for_page(&KeySequence { keys: vec![Key::None(KeyCode::Enter)] }, client_pub, state, &mut ui2);
-                            return Ok(());
-                        }
-                    } else if rect_contains(rects.search_episodes, col, row) {
-                        search.focus = SearchFocusState::Episodes;
-                        let y = row.saturating_sub(rects.search_episodes.y);
-                        search.episode_list.select(Some(y as usize));
-                        if is_double {
-                            drop(search);
-                            drop(ui);
-                            let mut ui2 = state.ui.lock();
-                            let _ = page::handle_key_sequence_for_page(&KeySequence { keys: vec![Key::None(KeyCode::Enter)] }, client_pub, state, &mut ui2);
-                            return Ok(());
-                        }
-                    }
-                }
-                PageState::Context { state: cstate, .. } => {
-                    if let Some(ContextPageUIState::Artist { focus, top_track_table, album_table, related_artist_list }) = cstate.as_mut() {
-                        if rect_contains(rects.context_artist_top_tracks, col, row) {
-                            *focus = ArtistFocusState::TopTracks;
-                            let y = row
-                                .saturating_sub(rects.context_artist_top_tracks.y)
-                                .saturating_sub(1); // adjust for table header
-                            let start = top_track_table.offset();
-                            top_track_table.select(Some(start + y as usize));
-                            if is_double {
-                                drop(ui);
-                                let mut ui2 = state.ui.lock();
-                                let _ = page::handle_key_sequence_for_page(&KeySequence { keys: vec![Key::None(KeyCode::Enter)] }, client_pub, state, &mut ui2);
-                                return Ok(());
-                            }
-                        } else if rect_contains(rects.context_artist_albums, col, row) {
-                            *focus = ArtistFocusState::Albums;
-                            let y = row
-                                .saturating_sub(rects.context_artist_albums.y)
-                                .saturating_sub(1); // adjust for table header
-                            let start = album_table.offset();
-                            album_table.select(Some(start + y as usize));
-                            if is_double {
-                                drop(ui);
-                                let mut ui2 = state.ui.lock();
-                                let _ = page::handle_key_sequence_for_page(&KeySequence { keys: vec![Key::None(KeyCode::Enter)] }, client_pub, state, &mut ui2);
-                                return Ok(());
-                            }
-                        } else if rect_contains(rects.context_artist_related_artists, col, row) {
-                            *focus = ArtistFocusState::RelatedArtists;
-                            let y = row.saturating_sub(rects.context_artist_related_artists.y);
-                            related_artist_list.select(Some(y as usize));
-                            if is_double {
-                                drop(ui);
-                                let mut ui2 = state.ui.lock();
-                                let _ = page::handle_key_sequence_for_page(&KeySequence { keys: vec![Key::None(KeyCode::Enter)] }, client_pub, state, &mut ui2);
-                                return Ok(());
-                            }
-                        }
-                    } else if let Some(state_variant) = cstate.as_mut() {
-                        // Generic tracks/episodes table
-                        if rect_contains(rects.context_tracks, col, row) {
-                            // All context tables render a header row, so subtract 1
-                            let header_offset: u16 = 1;
-                            let y = row
-                                .saturating_sub(rects.context_tracks.y)
-                                .saturating_sub(header_offset);
-                            // Add current table offset for proper mapping when scrolled
-                            match state_variant {
-                                ContextPageUIState::Tracks { track_table }
-                                | ContextPageUIState::Album { track_table }
-                                | ContextPageUIState::Playlist { track_table } => {
-                                    let start = track_table.offset();
-                                    track_table.select(Some(start + y as usize));
-                                }
-                                ContextPageUIState::Show { episode_table } => {
-                                    let start = episode_table.offset();
-                                    episode_table.select(Some(start + y as usize));
-                                }
-                                ContextPageUIState::Artist { .. } => {}
-                            }
-                            if is_double {
-                                drop(ui);
-                                let mut ui2 = state.ui.lock();
-                                let _ = page::handle_key_sequence_for_page(&KeySequence { keys: vec![Key::None(KeyCode::Enter)] }, client_pub, state, &mut ui2);
-                                return Ok(());
-                            }
-                        }
-                    }
-                }
-                PageState::Browse { state: _ } => {
-                    if rect_contains(rects.browse_list, col, row) {
-                        if let Some(MutableWindowState::List(list)) = ui.current_page_mut().focus_window_state_mut() {
-                            let y = row.saturating_sub(rects.browse_list.y);
-                            list.select(Some(y as usize));
-                        }
-                        if is_double {
-                            drop(ui);
-                            let mut ui2 = state.ui.lock();
-                            let _ = page::handle_key_sequence_for_page(&KeySequence { keys: vec![Key::None(KeyCode::Enter)] }, client_pub, state, &mut ui2);
-                            return Ok(());
-                        }
-                    }
-                }
-                PageState::Queue { scroll_offset: _ } => {
-                    if rect_contains(rects.queue_rect, col, row) {
-                        if let Some(MutableWindowState::Scroll(scroll)) = ui.current_page_mut().focus_window_state_mut() {
-                            let base = rects.queue_rect.y;
-                            // queue table has a header row
-                            *scroll = row.saturating_sub(base).saturating_sub(1) as usize;
-                        }
-                    }
-                }
-                PageState::CommandHelp { scroll_offset: _ } => {
-                    if rect_contains(rects.command_help_rect, col, row) {
-                        if let Some(MutableWindowState::Scroll(scroll)) = ui.current_page_mut().focus_window_state_mut() {
-                            let base = rects.command_help_rect.y;
-                            // help table has a header row
-                            *scroll = row.saturating_sub(base).saturating_sub(1) as usize;
-                        }
-                    }
-                }
-                PageState::Lyrics { .. } => {}
-            }
-        }
-        _ => {}
-    }
-    Ok(())
-}
-
 // Handle a terminal key pressed event
 fn handle_key_event(
     event: crossterm::event::KeyEvent,
@@ -353,13 +80,15 @@ fn handle_key_event(
 ) -> Result<()> {
     let key: Key = event.into();
     let mut ui = state.ui.lock();
+    ui.expire_input_key_sequence();
 
     let mut key_sequence = ui.input_key_sequence.clone();
     key_sequence.keys.push(key);
 
     // check if the current key sequence matches any keymap's prefix
     // if not, reset the key sequence
-    let keymap_config = &config::get_config().keymap_config;
+    let configs = config::get_config();
+    let keymap_config = &configs.keymap_config;
     if !keymap_config.has_matched_prefix(&key_sequence) {
         key_sequence = KeySequence { keys: vec![key] };
     }
@@ -387,7 +116,13 @@ fn handle_key_event(
             Some(CommandOrAction::Command(command)) => {
                 handle_global_command(command, client_pub, state, &mut ui)?
             }
-            None => false,
+            None => match keymap_config.find_macro_from_key_sequence(&key_sequence) {
+                Some(m) => {
+                    run_macro(m, client_pub, state, &mut ui)?;
+                    true
+                }
+                None => false,
+            },
         }
     };
 
@@ -403,7 +138,7 @@ fn handle_key_event(
                 let digit = c.to_digit(10).unwrap() as usize;
                 ui.input_key_sequence.keys = vec![];
                 ui.count_prefix = match ui.count_prefix {
-                    Some(count) => Some(count * 10 + digit),
+                    Some(count) => Some((count * 10 + digit).min(MAX_COUNT_PREFIX)),
                     None => {
                         if digit > 0 {
                             Some(digit)
@@ -415,6 +150,7 @@ fn handle_key_event(
             }
             _ => {
                 ui.input_key_sequence = key_sequence;
+                ui.input_key_time = Some(std::time::Instant::now());
                 ui.count_prefix = None;
             }
         }
@@ -460,6 +196,12 @@ pub fn handle_action_in_context(
                 ui.popup = None;
                 Ok(true)
             }
+            Action::CopyUniversalLink => {
+                let track_url = format!("https://open.spotify.com/track/{}", track.id.id());
+                client_pub.send(ClientRequest::CopyUniversalLink(track_url))?;
+                ui.popup = None;
+                Ok(true)
+            }
             Action::AddToPlaylist => {
                 client_pub.send(ClientRequest::GetUserPlaylists)?;
                 ui.popup = Some(PopupState::UserPlaylistList(
@@ -486,8 +228,13 @@ pub fn handle_action_in_context(
                 Ok(true)
             }
             Action::DeleteFromLiked => {
-                client_pub.send(ClientRequest::DeleteFromLibrary(ItemId::Track(track.id)))?;
-                ui.popup = None;
+                send_destructive_request(
+                    DestructiveAction::RemoveTrack,
+                    format!("Remove \"{}\" from the liked tracks?", track.name),
+                    ClientRequest::DeleteFromLibrary(ItemId::Track(track.id)),
+                    client_pub,
+                    ui,
+                )?;
                 Ok(true)
             }
             Action::GoToRadio => {
@@ -524,12 +271,20 @@ pub fn handle_action_in_context(
                     ..
                 } = ui.current_page()
                 {
-                    client_pub.send(ClientRequest::DeleteTrackFromPlaylist(
+                    let request = ClientRequest::DeleteTrackFromPlaylist(
                         playlist_id.clone_static(),
                         track.id,
-                    ))?;
+                    );
+                    send_destructive_request(
+                        DestructiveAction::RemoveTrack,
+                        format!("Remove \"{}\" from the playlist?", track.name),
+                        request,
+                        client_pub,
+                        ui,
+                    )?;
+                } else {
+                    ui.popup = None;
                 }
-                ui.popup = None;
                 Ok(true)
             }
             _ => Ok(false),
@@ -559,8 +314,13 @@ pub fn handle_action_in_context(
                 Ok(true)
             }
             Action::DeleteFromLibrary => {
-                client_pub.send(ClientRequest::DeleteFromLibrary(ItemId::Album(album.id)))?;
-                ui.popup = None;
+                send_destructive_request(
+                    DestructiveAction::RemoveAlbum,
+                    format!("Remove \"{}\" from the library?", album.name),
+                    ClientRequest::DeleteFromLibrary(ItemId::Album(album.id)),
+                    client_pub,
+                    ui,
+                )?;
                 Ok(true)
             }
             Action::CopyLink => {
@@ -569,6 +329,12 @@ pub fn handle_action_in_context(
                 ui.popup = None;
                 Ok(true)
             }
+            Action::CopyUniversalLink => {
+                let album_url = format!("https://open.spotify.com/album/{}", album.id.id());
+                client_pub.send(ClientRequest::CopyUniversalLink(album_url))?;
+                ui.popup = None;
+                Ok(true)
+            }
             Action::AddToQueue => {
                 client_pub.send(ClientRequest::AddAlbumToQueue(album.id))?;
                 ui.popup = None;
@@ -583,8 +349,13 @@ pub fn handle_action_in_context(
                 Ok(true)
             }
             Action::Unfollow => {
-                client_pub.send(ClientRequest::DeleteFromLibrary(ItemId::Artist(artist.id)))?;
-                ui.popup = None;
+                send_destructive_request(
+                    DestructiveAction::Unfollow,
+                    format!("Unfollow \"{}\"?", artist.name),
+                    ClientRequest::DeleteFromLibrary(ItemId::Artist(artist.id)),
+                    client_pub,
+                    ui,
+                )?;
                 Ok(true)
             }
             Action::CopyLink => {
@@ -629,10 +400,13 @@ pub fn handle_action_in_context(
                 Ok(true)
             }
             Action::DeleteFromLibrary => {
-                client_pub.send(ClientRequest::DeleteFromLibrary(ItemId::Playlist(
-                    playlist.id,
-                )))?;
-                ui.popup = None;
+                send_destructive_request(
+                    DestructiveAction::DeletePlaylist,
+                    format!("Delete \"{}\" from the library?", playlist.name),
+                    ClientRequest::DeleteFromLibrary(ItemId::Playlist(playlist.id)),
+                    client_pub,
+                    ui,
+                )?;
                 Ok(true)
             }
             _ => Ok(false),
@@ -644,19 +418,33 @@ pub fn handle_action_in_context(
                 ui.popup = None;
                 Ok(true)
             }
-            Action::AddToLibrary => {
+            // following a show is equivalent to saving it into the user's library
+            Action::AddToLibrary | Action::Follow => {
                 client_pub.send(ClientRequest::AddToLibrary(Item::Show(show)))?;
                 ui.popup = None;
                 Ok(true)
             }
-            Action::DeleteFromLibrary => {
-                client_pub.send(ClientRequest::DeleteFromLibrary(ItemId::Show(show.id)))?;
-                ui.popup = None;
+            Action::DeleteFromLibrary | Action::Unfollow => {
+                send_destructive_request(
+                    DestructiveAction::Unfollow,
+                    format!("Unfollow \"{}\"?", show.name),
+                    ClientRequest::DeleteFromLibrary(ItemId::Show(show.id)),
+                    client_pub,
+                    ui,
+                )?;
                 Ok(true)
             }
             _ => Ok(false),
         },
         ActionContext::Episode(episode) => match action {
+            Action::Play => {
+                client_pub.send(ClientRequest::Player(PlayerRequest::StartPlayback(
+                    Playback::URIs(vec![episode.id.into()], None),
+                    None,
+                )))?;
+                ui.popup = None;
+                Ok(true)
+            }
             Action::GoToShow => {
                 if let Some(show) = episode.show {
                     let context_id = ContextId::Show(
@@ -709,11 +497,52 @@ pub fn handle_action_in_context(
             }
             _ => Ok(false),
         },
+        ActionContext::Audiobook(audiobook) => match action {
+            Action::Play => {
+                client_pub.send(ClientRequest::PlayAudiobook(audiobook.id))?;
+                ui.popup = None;
+                Ok(true)
+            }
+            Action::CopyLink => {
+                let audiobook_url = format!("https://open.spotify.com/audiobook/{}", audiobook.id);
+                execute_copy_command(audiobook_url)?;
+                ui.popup = None;
+                Ok(true)
+            }
+            _ => Ok(false),
+        },
         // TODO: support actions for playlist folders
         ActionContext::PlaylistFolder(_) => Ok(false),
     }
 }
 
+/// Send the request of a destructive action, which is confirmed first
+/// if the action requires a confirmation
+fn send_destructive_request(
+    action: DestructiveAction,
+    prompt: String,
+    request: ClientRequest,
+    client_pub: &flume::Sender<ClientRequest>,
+    ui: &mut UIStateGuard,
+) -> Result<()> {
+    let configs = config::get_config();
+    let confirmations = &configs.app_config.confirmations;
+    if confirmations.is_required(action) && !ui.skipped_confirmations.contains(&action) {
+        ui.popup = Some(PopupState::Confirmation(
+            Box::new(PendingConfirmation {
+                action,
+                prompt,
+                request,
+            }),
+            ListState::default(),
+        ));
+    } else {
+        client_pub.send(request)?;
+        ui.popup = None;
+    }
+    Ok(())
+}
+
 fn handle_go_to_artist(artists: Vec<Artist>, ui: &mut UIStateGuard) {
     if artists.len() == 1 {
         let context_id = ContextId::Artist(artists[0].id.clone());
@@ -793,6 +622,140 @@ fn handle_global_action(
     Ok(false)
 }
 
+/// Open a Spotify link, which can be either a `https://open.spotify.com` URL or a `spotify:` URI.
+///
+/// A track or episode link is played, other links are opened in their context pages.
+/// Returns `false` if the given text doesn't contain any Spotify link.
+fn open_spotify_link(
+    link: &str,
+    client_pub: &flume::Sender<ClientRequest>,
+    ui: &mut UIStateGuard,
+) -> Result<bool> {
+    use crate::utils::SpotifyLink;
+
+    let Some(link) = crate::utils::parse_spotify_link(link)? else {
+        return Ok(false);
+    };
+
+    let context_id = match link {
+        // for track/episode link, play the item
+        SpotifyLink::Track(id) => {
+            client_pub.send(ClientRequest::Player(PlayerRequest::StartPlayback(
+                Playback::URIs(vec![id.into()], None),
+                None,
+            )))?;
+            return Ok(true);
+        }
+        SpotifyLink::Episode(id) => {
+            client_pub.send(ClientRequest::Player(PlayerRequest::StartPlayback(
+                Playback::URIs(vec![id.into()], None),
+                None,
+            )))?;
+            return Ok(true);
+        }
+        // for playlist/artist/album/show link, go to the corresponding context page
+        SpotifyLink::Playlist(id) => ContextId::Playlist(id),
+        SpotifyLink::Artist(id) => ContextId::Artist(id),
+        SpotifyLink::Album(id) => ContextId::Album(id),
+        SpotifyLink::Show(id) => ContextId::Show(id),
+    };
+    ui.new_page(PageState::Context {
+        id: None,
+        context_page_type: ContextPageType::Browsing(context_id),
+        state: None,
+    });
+    Ok(true)
+}
+
+/// Open a new search page to run a saved search
+fn run_saved_search(
+    search: SavedSearch,
+    client_pub: &flume::Sender<ClientRequest>,
+    ui: &mut UIStateGuard,
+) -> Result<()> {
+    let mut line_input = LineInput::default();
+    line_input.set_text(&search.query);
+    let mut page_state = SearchPageUIState::new(SearchMode::Spotify);
+    page_state.library_only = search.library_only;
+
+    ui.new_page(PageState::Search {
+        line_input,
+        current_query: search.query.clone(),
+        state: page_state,
+    });
+    client_pub.send(ClientRequest::Search(search.query))?;
+    Ok(())
+}
+
+/// Runs the steps of a macro in order.
+///
+/// The client requests of the steps are sent as a single sequence request,
+/// so that they are handled one after another.
+fn run_macro(
+    m: &config::Macro,
+    client_pub: &flume::Sender<ClientRequest>,
+    state: &SharedState,
+    ui: &mut UIStateGuard,
+) -> Result<()> {
+    tracing::info!("Running macro {}", m.name);
+
+    let (macro_pub, macro_sub) = flume::unbounded();
+    for step in &m.steps {
+        match step {
+            config::MacroStep::Command(command) => {
+                if !page::handle_command_for_page(*command, &macro_pub, state, ui)? {
+                    handle_global_command(*command, &macro_pub, state, ui)?;
+                }
+            }
+            config::MacroStep::PlayPlaylist(name) => {
+                let id = state
+                    .data
+                    .read()
+                    .user_data
+                    .playlists
+                    .iter()
+                    .find_map(|item| match item {
+                        PlaylistFolderItem::Playlist(p) if p.name == *name => Some(p.id.clone()),
+                        _ => None,
+                    })
+                    .with_context(|| format!("macro {}: no playlist named {name}", m.name))?;
+                macro_pub.send(ClientRequest::Player(PlayerRequest::StartPlayback(
+                    Playback::Context(ContextId::Playlist(id), None),
+                    None,
+                )))?;
+            }
+            config::MacroStep::Search(query) => {
+                let search = SavedSearch {
+                    name: query.clone(),
+                    query: query.clone(),
+                    library_only: false,
+                };
+                run_saved_search(search, &macro_pub, ui)?;
+            }
+            config::MacroStep::SetShuffle(shuffle) => {
+                macro_pub.send(ClientRequest::Player(PlayerRequest::SetShuffle(*shuffle)))?;
+            }
+            #[cfg(feature = "scripting")]
+            config::MacroStep::RunScript(name) => {
+                crate::scripting::spawn_script(name.clone(), client_pub.clone(), state.clone());
+            }
+        }
+    }
+
+    let requests = macro_sub.drain().collect::<Vec<_>>();
+    if !requests.is_empty() {
+        client_pub.send(ClientRequest::Sequence(requests))?;
+    }
+    Ok(())
+}
+
+/// Get the offset of seeking the playback `count` times by the configured seek duration
+fn seek_offset(count: usize) -> chrono::Duration {
+    let duration = config::get_config().app_config.seek_duration_secs;
+    chrono::Duration::try_seconds(i64::from(duration).saturating_mul(count as i64))
+        .unwrap_or(chrono::Duration::MAX)
+}
+
 /// Handle a global command that is not specific to any page/popup
 fn handle_global_command(
     command: Command,
@@ -800,15 +763,22 @@ fn handle_global_command(
     state: &SharedState,
     ui: &mut UIStateGuard,
 ) -> Result<bool> {
+    // the vim-style count prefix, e.g. `5n` to skip 5 tracks
+    let count = ui.count_prefix.unwrap_or(1);
+
     match command {
         Command::Quit => {
             ui.is_running = false;
         }
         Command::NextTrack => {
-            client_pub.send(ClientRequest::Player(PlayerRequest::NextTrack))?;
+            for _ in 0..count.min(MAX_SKIPPED_TRACKS) {
+                client_pub.send(ClientRequest::Player(PlayerRequest::NextTrack))?;
+            }
         }
         Command::PreviousTrack => {
-            client_pub.send(ClientRequest::Player(PlayerRequest::PreviousTrack))?;
+            for _ in 0..count.min(MAX_SKIPPED_TRACKS) {
+                client_pub.send(ClientRequest::Player(PlayerRequest::PreviousTrack))?;
+            }
         }
         Command::ResumePause => {
             client_pub.send(ClientRequest::Player(PlayerRequest::ResumePause))?;
@@ -828,7 +798,9 @@ fn handle_global_command(
         Command::VolumeChange { offset } => {
             if let Some(ref playback) = state.player.read().buffered_playback {
                 if let Some(volume) = playback.volume {
-                    let volume = std::cmp::min(volume as i32 + offset, 100_i32);
+                    let volume = (volume as i32)
+                        .saturating_add(offset.saturating_mul(count as i32))
+                        .clamp(0, 100);
                     client_pub.send(ClientRequest::Player(PlayerRequest::Volume(volume as u8)))?;
                 }
             }
@@ -838,26 +810,35 @@ fn handle_global_command(
         }
         Command::SeekForward => {
             if let Some(progress) = state.player.read().playback_progress() {
-                let duration = config::get_config().app_config.seek_duration_secs;
-                client_pub.send(ClientRequest::Player(PlayerRequest::SeekTrack(
-                    progress + chrono::Duration::try_seconds(i64::from(duration)).unwrap(),
-                )))?;
+                let position = progress
+                    .checked_add(&seek_offset(count))
+                    .unwrap_or(chrono::Duration::MAX);
+                client_pub.send(ClientRequest::Player(PlayerRequest::SeekTrack(position)))?;
             }
         }
+        Command::RestartTrack => {
+            client_pub.send(ClientRequest::Player(PlayerRequest::SeekTrack(
+                chrono::Duration::zero(),
+            )))?;
+        }
         Command::SeekBackward => {
             if let Some(progress) = state.player.read().playback_progress() {
-                let duration = config::get_config().app_config.seek_duration_secs;
-                client_pub.send(ClientRequest::Player(PlayerRequest::SeekTrack(
-                    std::cmp::max(
-                        chrono::Duration::zero(),
-                        progress - chrono::Duration::try_seconds(i64::from(duration)).unwrap(),
-                    ),
-                )))?;
+                let position = progress
+                    .checked_sub(&seek_offset(count))
+                    .unwrap_or(chrono::Duration::MIN)
+                    .max(chrono::Duration::zero());
+                client_pub.send(ClientRequest::Player(PlayerRequest::SeekTrack(position)))?;
             }
         }
         Command::OpenCommandHelp => {
             ui.new_page(PageState::CommandHelp { scroll_offset: 0 });
         }
+        Command::OpenDiagnostics => {
+            ui.new_page(PageState::Diagnostics {
+                entries: crate::utils::get_diagnostics(),
+                scroll_offset: 0,
+            });
+        }
         Command::RefreshPlayback => {
             client_pub.send(ClientRequest::GetCurrentPlayback)?;
         }
@@ -947,7 +928,14 @@ fn handle_global_command(
             ui.new_page(PageState::Search {
                 line_input: LineInput::default(),
                 current_query: String::new(),
-                state: SearchPageUIState::new(),
+                state: SearchPageUIState::new(SearchMode::Spotify),
+            });
+        }
+        Command::LibrarySearchPage => {
+            ui.new_page(PageState::Search {
+                line_input: LineInput::default(),
+                current_query: String::new(),
+                state: SearchPageUIState::new(SearchMode::Library),
             });
         }
         Command::BrowsePage => {
@@ -960,55 +948,15 @@ fn handle_global_command(
         }
         Command::PreviousPage => {
             if ui.history.len() > 1 {
+                ui.save_context_view();
                 ui.history.pop();
                 ui.popup = None;
+                ui.restore_context_view();
             }
         }
         Command::OpenSpotifyLinkFromClipboard => {
             let content = get_clipboard_content().context("get clipboard's content")?;
-            let re = regex::Regex::new(
-                r"https://open.spotify.com/(?P<type>.*?)/(?P<id>[[:alnum:]]*).*",
-            )?;
-            if let Some(cap) = re.captures(&content) {
-                let typ = cap.name("type").expect("valid capture").as_str();
-                let id = cap.name("id").expect("valid capture").as_str();
-                match typ {
-                    // for track link, play the song
-                    "track" => {
-                        let id = TrackId::from_id(id)?.into_static();
-                        client_pub.send(ClientRequest::Player(PlayerRequest::StartPlayback(
-                            Playback::URIs(vec![id.into()], None),
-                            None,
-                        )))?;
-                    }
-                    // for playlist/artist/album link, go to the corresponding context page
-                    "playlist" => {
-                        let id = PlaylistId::from_id(id)?.into_static();
-                        ui.new_page(PageState::Context {
-                            id: None,
-                            context_page_type: ContextPageType::Browsing(ContextId::Playlist(id)),
-                            state: None,
-                        });
-                    }
-                    "artist" => {
-                        let id = ArtistId::from_id(id)?.into_static();
-                        ui.new_page(PageState::Context {
-                            id: None,
-                            context_page_type: ContextPageType::Browsing(ContextId::Artist(id)),
-                            state: None,
-                        });
-                    }
-                    "album" => {
-                        let id = AlbumId::from_id(id)?.into_static();
-                        ui.new_page(PageState::Context {
-                            id: None,
-                            context_page_type: ContextPageType::Browsing(ContextId::Album(id)),
-                            state: None,
-                        });
-                    }
-                    e => anyhow::bail!("unsupported Spotify type {e}!"),
-                }
-            } else {
+            if !open_spotify_link(&content, client_pub, ui)? {
                 tracing::warn!("clipboard's content ({content}) is not a valid Spotify link!");
             }
         }
@@ -1049,6 +997,43 @@ fn handle_global_command(
         Command::RestartIntegratedClient => {
             client_pub.send(ClientRequest::RestartIntegratedClient)?;
         }
+        #[cfg(feature = "streaming")]
+        Command::CycleBitrate => {
+            let bitrate = match *state.bitrate.lock() {
+                96 => 160,
+                160 => 320,
+                _ => 96,
+            };
+            client_pub.send(ClientRequest::SetBitrate(bitrate))?;
+        }
+        #[cfg(feature = "streaming")]
+        Command::SwitchAudioOutput => {
+            let outputs = crate::streaming::list_audio_outputs();
+            ui.popup = Some(PopupState::AudioOutputList(outputs, ListState::default()));
+        }
+        #[cfg(feature = "streaming")]
+        Command::SwitchDeviceProfile => {
+            ui.popup = Some(PopupState::DeviceProfileList(ListState::default()));
+        }
+        #[cfg(feature = "streaming")]
+        Command::OpenEqualizer => {
+            ui.popup = Some(PopupState::Equalizer(ListState::default()));
+        }
+        #[cfg(feature = "streaming")]
+        Command::ToggleNightMode => {
+            let night_mode = !*state.night_mode.lock();
+            client_pub.send(ClientRequest::SetNightMode(night_mode))?;
+        }
+        #[cfg(feature = "streaming")]
+        Command::ToggleAutoplay => {
+            let autoplay = !*state.autoplay.lock();
+            client_pub.send(ClientRequest::SetAutoplay(autoplay))?;
+        }
+        Command::TogglePrivateSession => {
+            let mut private_session = state.private_session.lock();
+            *private_session = !*private_session;
+            tracing::info!("Toggled the private session to {}", *private_session);
+        }
         Command::FocusNextWindow => {
             if !ui.has_focused_popup() {
                 ui.current_page_mut().next();
@@ -1070,6 +1055,71 @@ fn handle_global_command(
                 current_field: PlaylistCreateCurrentField::Name,
             });
         }
+        Command::ClearSearchHistory => {
+            state.data.write().clear_search_history();
+        }
+        Command::JumpPalette => {
+            ui.popup = Some(PopupState::JumpPalette(
+                LineInput::default(),
+                ListState::default(),
+            ));
+        }
+        Command::CommandPalette => {
+            ui.popup = Some(PopupState::CommandPalette(
+                LineInput::default(),
+                ListState::default(),
+            ));
+        }
+        Command::EditKeymap => {
+            ui.popup = Some(PopupState::KeymapEditor(
+                LineInput::default(),
+                ListState::default(),
+            ));
+        }
+        Command::SaveSearch => {
+            let PageState::Search {
+                state: page_state,
+                current_query,
+                ..
+            } = ui.current_page()
+            else {
+                return Ok(false);
+            };
+            // library searches are stored under a different key from their queries
+            if page_state.mode == SearchMode::Spotify && !current_query.is_empty() {
+                let mut name = LineInput::default();
+                name.set_text(current_query);
+                let popup = PopupState::SavedSearchCreate {
+                    name,
+                    query: current_query.clone(),
+                    library_only: page_state.library_only,
+                };
+                ui.popup = Some(popup);
+            }
+        }
+        Command::SearchAndQueue => {
+            ui.popup = Some(PopupState::SearchAndQueue(LineInput::default()));
+        }
+        Command::BrowseSavedSearches => {
+            ui.popup = Some(PopupState::SavedSearchList(ListState::default()));
+        }
+        Command::RunSavedSearch { index } => {
+            let search = state.data.read().saved_searches.get(index).cloned();
+            if let Some(search) = search {
+                run_saved_search(search, client_pub, ui)?;
+            }
+        }
+        Command::ToggleSearchLibraryFilter => {
+            let PageState::Search {
+                state: page_state, ..
+            } = ui.current_page_mut()
+            else {
+                return Ok(false);
+            };
+            page_state.library_only = !page_state.library_only;
+            // the number of results in each window may change, so reset the selections
+            ui.current_page_mut().select(0);
+        }
         Command::JumpToCurrentTrackInContext => {
             let track_id = match state.player.read().currently_playing() {
                 Some(rspotify::model::PlayableItem::Track(track)) => {
@@ -0,0 +1,231 @@
+use super::*;
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
+
+/// Handle a terminal mouse event
+pub fn handle_mouse_event(
+    event: MouseEvent,
+    client_pub: &flume::Sender<ClientRequest>,
+    state: &SharedState,
+) -> Result<()> {
+    let configs = config::get_config();
+    let mouse_config = &configs.app_config.mouse;
+    if !mouse_config.enabled {
+        return Ok(());
+    }
+
+    tracing::debug!("Handling mouse event: {event:?}");
+    let mut ui = state.ui.lock();
+    let position = Position::new(event.column, event.row);
+
+    match event.kind {
+        MouseEventKind::ScrollDown | MouseEventKind::ScrollUp => {
+            let lines = mouse_config.scroll_lines;
+            let is_down = event.kind == MouseEventKind::ScrollDown;
+
+            if let Some(popup) = ui.popup.as_mut() {
+                if let Some(list_state) = popup.list_state_mut() {
+                    let selected = list_state.selected().unwrap_or_default();
+                    list_state.select(Some(if is_down {
+                        selected.saturating_add(lines)
+                    } else {
+                        selected.saturating_sub(lines)
+                    }));
+                }
+                return Ok(());
+            }
+
+            // scroll the window under the mouse, or the focused window if there is none
+            focus_page_window_at(&mut ui, position);
+            let page = ui.current_page_mut();
+            match page.selected() {
+                Some(selected) if is_down => page.select(selected.saturating_add(lines)),
+                Some(selected) => page.select(selected.saturating_sub(lines)),
+                None => page.select(0),
+            }
+        }
+        MouseEventKind::Down(button @ (MouseButton::Left | MouseButton::Right)) => {
+            if button == MouseButton::Left
+                && handle_click_on_progress_bar(position, client_pub, state, &ui)?
+            {
+                return Ok(());
+            }
+
+            let is_double_click = button == MouseButton::Left
+                && ui.last_click.is_some_and(|click| {
+                    click.col == event.column
+                        && click.row == event.row
+                        && click.when.elapsed()
+                            <= std::time::Duration::from_millis(
+                                mouse_config.double_click_interval_in_ms,
+                            )
+                });
+            ui.last_click = Some(crate::state::LastClick {
+                when: std::time::Instant::now(),
+                col: event.column,
+                row: event.row,
+            });
+
+            if ui.popup.is_some() {
+                if !select_popup_item_at(&mut ui, position) {
+                    return Ok(());
+                }
+                if is_double_click && mouse_config.double_click_to_choose {
+                    // choose the clicked item as if `enter` were pressed, which also works
+                    // for popups reading the raw key sequence such as the palettes
+                    let key_sequence = KeySequence {
+                        keys: vec![Key::None(KeyCode::Enter)],
+                    };
+                    popup::handle_key_sequence_for_popup(
+                        &key_sequence,
+                        client_pub,
+                        state,
+                        &mut ui,
+                    )?;
+                }
+                return Ok(());
+            }
+
+            let Some(id) = focus_page_window_at(&mut ui, position) else {
+                return Ok(());
+            };
+            ui.current_page_mut().select(id);
+
+            let command = match button {
+                MouseButton::Right if mouse_config.right_click_to_show_actions => {
+                    Command::ShowActionsOnSelectedItem
+                }
+                MouseButton::Left if is_double_click && mouse_config.double_click_to_choose => {
+                    Command::ChooseSelected
+                }
+                _ => return Ok(()),
+            };
+            page::handle_command_for_page(command, client_pub, state, &mut ui)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Seek the current playback to the clicked position on the playback progress bar,
+/// returning whether the progress bar is clicked
+fn handle_click_on_progress_bar(
+    position: Position,
+    client_pub: &flume::Sender<ClientRequest>,
+    state: &SharedState,
+    ui: &UIStateGuard,
+) -> Result<bool> {
+    let rect = ui.playback_progress_bar_rect;
+    if !rect.contains(position) {
+        return Ok(false);
+    }
+
+    let duration = match state.player.read().currently_playing() {
+        Some(rspotify::model::PlayableItem::Track(track)) => track.duration,
+        Some(rspotify::model::PlayableItem::Episode(episode)) => episode.duration,
+        None => return Ok(false),
+    };
+    let offset = i64::from(position.x - rect.x);
+    let position_ms = duration.num_milliseconds() * offset / i64::from(rect.width);
+    client_pub.send(ClientRequest::Player(PlayerRequest::SeekTrack(
+        chrono::Duration::try_milliseconds(position_ms).unwrap(),
+    )))?;
+    Ok(true)
+}
+
+/// Select the item of the popup's list at the given position,
+/// returning whether an item is selected
+fn select_popup_item_at(ui: &mut UIStateGuard, position: Position) -> bool {
+    let rect = ui.rects.popup_list;
+    if !rect.contains(position) {
+        return false;
+    }
+    let Some(list_state) = ui.popup.as_mut().and_then(PopupState::list_state_mut) else {
+        return false;
+    };
+    list_state.select(Some(list_state.offset() + usize::from(position.y - rect.y)));
+    true
+}
+
+/// Focus the current page's window at the given position, returning the position
+/// of the window's item at that position if any
+fn focus_page_window_at(ui: &mut UIStateGuard, position: Position) -> Option<usize> {
+    let rects = ui.rects;
+    let find_window = |windows: &[(Rect, usize)]| {
+        windows
+            .iter()
+            .position(|(rect, _)| rect.contains(position))
+            .map(|i| (i, windows[i]))
+    };
+
+    // the window's rectangle and the number of its header rows
+    let (rect, n_header_rows) = match ui.current_page_mut() {
+        PageState::Library { state } => {
+            let (i, window) = find_window(&[
+                (rects.library_playlists, 0),
+                (rects.library_albums, 0),
+                (rects.library_artists, 0),
+            ])?;
+            state.focus = [
+                LibraryFocusState::Playlists,
+                LibraryFocusState::SavedAlbums,
+                LibraryFocusState::FollowedArtists,
+            ][i];
+            window
+        }
+        PageState::Search { state, .. } => {
+            let (i, window) = find_window(&[
+                (rects.search_input, 0),
+                (rects.search_tracks, 0),
+                (rects.search_albums, 0),
+                (rects.search_artists, 0),
+                (rects.search_playlists, 0),
+                (rects.search_shows, 0),
+                (rects.search_episodes, 0),
+                (rects.search_audiobooks, 0),
+            ])?;
+            state.focus = [
+                SearchFocusState::Input,
+                SearchFocusState::Tracks,
+                SearchFocusState::Albums,
+                SearchFocusState::Artists,
+                SearchFocusState::Playlists,
+                SearchFocusState::Shows,
+                SearchFocusState::Episodes,
+                SearchFocusState::Audiobooks,
+            ][i];
+            window
+        }
+        PageState::Context {
+            state: Some(ContextPageUIState::Artist { focus, .. }),
+            ..
+        } => {
+            let (i, window) = find_window(&[
+                (rects.context_artist_top_tracks, 1),
+                (rects.context_artist_albums, 1),
+                (rects.context_artist_related_artists, 0),
+            ])?;
+            *focus = [
+                ArtistFocusState::TopTracks,
+                ArtistFocusState::Albums,
+                ArtistFocusState::RelatedArtists,
+            ][i];
+            window
+        }
+        PageState::Context { state: Some(_), .. } => find_window(&[(rects.context_tracks, 1)])?.1,
+        PageState::Browse { .. } => find_window(&[(rects.browse_list, 0)])?.1,
+        PageState::Queue { .. } => find_window(&[(rects.queue_rect, 1)])?.1,
+        PageState::CommandHelp { .. } => find_window(&[(rects.command_help_rect, 1)])?.1,
+        PageState::Context { state: None, .. }
+        | PageState::Lyrics { .. }
+        | PageState::Diagnostics { .. } => return None,
+    };
+
+    let row = usize::from(position.y - rect.y).checked_sub(n_header_rows)?;
+    let offset = match ui.current_page_mut().focus_window_state_mut()? {
+        MutableWindowState::List(state) => state.offset(),
+        MutableWindowState::Table(state) => state.offset(),
+        MutableWindowState::Scroll(_) => 0,
+    };
+    Some(offset + row)
+}
@@ -1,8 +1,14 @@
+use std::sync::OnceLock;
+
 use anyhow::Context as _;
 use command::CommandOrAction;
 
 use super::*;
 
+/// The sender of the queries typed in the search input to the live search task,
+/// which is started upon the first live search
+static LIVE_SEARCH_QUERIES: OnceLock<tokio::sync::watch::Sender<String>> = OnceLock::new();
+
 pub fn handle_key_sequence_for_page(
     key_sequence: &KeySequence,
     client_pub: &flume::Sender<ClientRequest>,
@@ -20,16 +26,9 @@ pub fn handle_key_sequence_for_page(
         .keymap_config
         .find_command_or_action_from_key_sequence(key_sequence)
     {
-        Some(CommandOrAction::Command(command)) => match page_type {
-            PageType::Search => anyhow::bail!("page search type should already be handled!"),
-            PageType::Library => handle_command_for_library_page(command, client_pub, ui, state),
-            PageType::Context => handle_command_for_context_page(command, client_pub, ui, state),
-            PageType::Browse => handle_command_for_browse_page(command, client_pub, ui, state),
-            // lyrics page doesn't support any commands
-            PageType::Lyrics => Ok(false),
-            PageType::Queue => Ok(handle_command_for_queue_page(command, ui)),
-            PageType::CommandHelp => Ok(handle_command_for_command_help_page(command, ui)),
-        },
+        Some(CommandOrAction::Command(command)) => {
+            handle_command_for_page(command, client_pub, state, ui)
+        }
         Some(CommandOrAction::Action(action, ActionTarget::SelectedItem)) => match page_type {
             PageType::Search => anyhow::bail!("page search type should already be handled!"),
             PageType::Library => handle_action_for_library_page(action, client_pub, ui, state),
@@ -43,6 +42,31 @@ pub fn handle_key_sequence_for_page(
     }
 }
 
+/// Handle a command for the current page
+pub fn handle_command_for_page(
+    command: Command,
+    client_pub: &flume::Sender<ClientRequest>,
+    state: &SharedState,
+    ui: &mut UIStateGuard,
+) -> Result<bool> {
+    match ui.current_page().page_type() {
+        PageType::Search => handle_command_or_action_for_search_page(
+            CommandOrAction::Command(command),
+            client_pub,
+            state,
+            ui,
+        ),
+        PageType::Library => handle_command_for_library_page(command, client_pub, ui, state),
+        PageType::Context => handle_command_for_context_page(command, client_pub, ui, state),
+        PageType::Browse => handle_command_for_browse_page(command, client_pub, ui, state),
+        // lyrics page doesn't support any commands
+        PageType::Lyrics => Ok(false),
+        PageType::Queue => Ok(handle_command_for_queue_page(command, ui)),
+        PageType::CommandHelp => Ok(handle_command_for_command_help_page(command, ui)),
+        PageType::Diagnostics => Ok(handle_command_for_diagnostics_page(command, ui, state)),
+    }
+}
+
 fn handle_action_for_library_page(
     action: Action,
     client_pub: &flume::Sender<ClientRequest>,
@@ -198,14 +222,35 @@ fn handle_key_sequence_for_search_page(
     state: &SharedState,
     ui: &mut UIStateGuard,
 ) -> Result<bool> {
-    let (focus_state, current_query, line_input) = match ui.current_page_mut() {
-        PageState::Search {
-            state,
+    // a Spotify link in the search input is opened directly instead of being searched
+    if let [Key::None(crossterm::event::KeyCode::Enter)] = key_sequence.keys.as_slice() {
+        if let PageState::Search {
+            state:
+                SearchPageUIState {
+                    focus: SearchFocusState::Input,
+                    mode: SearchMode::Spotify,
+                    ..
+                },
             line_input,
-            current_query,
-        } => (state.focus, current_query, line_input),
-        _ => anyhow::bail!("expect a search page"),
+            ..
+        } = ui.current_page()
+        {
+            let query = line_input.get_text();
+            if open_spotify_link(query.trim(), client_pub, ui)? {
+                return Ok(true);
+            }
+        }
+    }
+
+    let PageState::Search {
+        state: page_state,
+        line_input,
+        current_query,
+    } = ui.current_page_mut()
+    else {
+        anyhow::bail!("expect a search page");
     };
+    let focus_state = page_state.focus;
 
     // handle user's input
     if let SearchFocusState::Input = focus_state {
@@ -213,14 +258,77 @@ fn handle_key_sequence_for_search_page(
             return match &key_sequence.keys[0] {
                 Key::None(crossterm::event::KeyCode::Enter) => {
                     if !line_input.is_empty() {
-                        *current_query = line_input.get_text();
-                        client_pub.send(ClientRequest::Search(line_input.get_text()))?;
+                        let query = line_input.get_text();
+                        page_state.history_index = None;
+                        let mut data = state.data.write();
+                        data.add_search_query(query.clone());
+                        match page_state.mode {
+                            SearchMode::Spotify => {
+                                *current_query = query.clone();
+                                client_pub.send(ClientRequest::Search(query))?;
+                            }
+                            SearchMode::Library => {
+                                *current_query = data.search_library(&query);
+                            }
+                        }
+                    }
+                    Ok(true)
+                }
+                // recall past search queries
+                Key::None(crossterm::event::KeyCode::Up) => {
+                    let data = state.data.read();
+                    let history = &data.search_history;
+                    if !history.is_empty() {
+                        let id = match page_state.history_index {
+                            None => history.len() - 1,
+                            Some(id) => std::cmp::min(id, history.len()).saturating_sub(1),
+                        };
+                        page_state.history_index = Some(id);
+                        line_input.set_text(&history[id]);
+                    }
+                    Ok(true)
+                }
+                Key::None(crossterm::event::KeyCode::Down) => {
+                    let data = state.data.read();
+                    let history = &data.search_history;
+                    match page_state.history_index {
+                        Some(id) if id + 1 < history.len() => {
+                            page_state.history_index = Some(id + 1);
+                            line_input.set_text(&history[id + 1]);
+                        }
+                        Some(_) => {
+                            // moving past the most recent query restores an empty input
+                            page_state.history_index = None;
+                            line_input.set_text("");
+                        }
+                        None => {}
                     }
                     Ok(true)
                 }
                 k => match line_input.input(k) {
                     None => Ok(false),
-                    _ => Ok(true),
+                    Some(InputEffect::TextChanged) => {
+                        page_state.history_index = None;
+                        match page_state.mode {
+                            // library search doesn't make any API requests, so its results
+                            // can be updated as the user types
+                            SearchMode::Library => {
+                                *current_query =
+                                    state.data.write().search_library(&line_input.get_text());
+                            }
+                            SearchMode::Spotify => {
+                                if config::get_config()
+                                    .app_config
+                                    .live_search_delay_in_ms
+                                    .is_some()
+                                {
+                                    schedule_live_search(line_input.get_text(), client_pub, state);
+                                }
+                            }
+                        }
+                        Ok(true)
+                    }
+                    Some(_) => Ok(true),
                 },
             };
         }
@@ -233,11 +341,107 @@ fn handle_key_sequence_for_search_page(
         return Ok(false);
     };
 
+    handle_command_or_action_for_search_page(found_keymap, client_pub, state, ui)
+}
+
+/// Send a query to the live search task, which searches the query after a delay
+/// if no other query is typed by then, so that no request is made for every typed character
+fn schedule_live_search(
+    query: String,
+    client_pub: &flume::Sender<ClientRequest>,
+    state: &SharedState,
+) {
+    let queries = LIVE_SEARCH_QUERIES.get_or_init(|| {
+        let (queries, receiver) = tokio::sync::watch::channel(String::new());
+        tokio::task::spawn(run_live_search(receiver, client_pub.clone(), state.clone()));
+        queries
+    });
+    queries.send_replace(query);
+}
+
+/// Search the queries typed in the search input, dropping the queries superseded
+/// by a newer one within the live search delay
+async fn run_live_search(
+    mut queries: tokio::sync::watch::Receiver<String>,
+    client_pub: flume::Sender<ClientRequest>,
+    state: SharedState,
+) {
+    while queries.changed().await.is_ok() {
+        // wait until no new query is typed within the delay
+        loop {
+            let Some(delay) = config::get_config().app_config.live_search_delay_in_ms else {
+                break;
+            };
+            match tokio::time::timeout(std::time::Duration::from_millis(delay), queries.changed())
+                .await
+            {
+                Ok(Ok(())) => {}
+                Ok(Err(_)) => return,
+                Err(_) => break,
+            }
+        }
+        let query = queries.borrow_and_update().clone();
+
+        let mut ui = state.ui.lock();
+        let PageState::Search {
+            state: page_state,
+            line_input,
+            current_query,
+        } = ui.current_page_mut()
+        else {
+            continue;
+        };
+        if page_state.mode != SearchMode::Spotify
+            || query.is_empty()
+            || line_input.get_text() != query
+            || *current_query == query
+        {
+            continue;
+        }
+
+        *current_query = query.clone();
+        if let Err(err) = client_pub.send(ClientRequest::Search(query)) {
+            tracing::error!("Failed to send a live search request: {err:#}");
+        }
+    }
+}
+
+fn handle_command_or_action_for_search_page(
+    found_keymap: CommandOrAction,
+    client_pub: &flume::Sender<ClientRequest>,
+    state: &SharedState,
+    ui: &mut UIStateGuard,
+) -> Result<bool> {
+    let (focus_state, mode, library_only, current_query) = match ui.current_page() {
+        PageState::Search {
+            state,
+            current_query,
+            ..
+        } => (state.focus, state.mode, state.library_only, current_query),
+        _ => anyhow::bail!("expect a search page"),
+    };
+
+    if let CommandOrAction::Command(Command::LoadMoreSearchResults) = found_keymap {
+        let Some(category) = focus_state.category() else {
+            return Ok(false);
+        };
+        // library search results already include all the matched items
+        if mode == SearchMode::Spotify && !current_query.is_empty() {
+            client_pub.send(ClientRequest::SearchMore {
+                query: current_query.clone(),
+                category,
+            })?;
+        }
+        return Ok(true);
+    }
+
     let data = state.data.read();
-    let search_results = data.caches.search.get(current_query);
+    let search_results = data.search_results(current_query, library_only);
+    let search_results = search_results.as_deref();
 
     match focus_state {
-        SearchFocusState::Input => anyhow::bail!("user's search input should be handled before"),
+        // commands on the search input are not supported
+        SearchFocusState::Input => Ok(false),
         SearchFocusState::Tracks => {
             let tracks = search_results
                 .map(|s| s.tracks.iter().collect::<Vec<_>>())
@@ -350,6 +554,32 @@ fn handle_key_sequence_for_search_page(
                 CommandOrAction::Action(..) => Ok(false),
             }
         }
+        SearchFocusState::Audiobooks => {
+            let audiobooks = search_results
+                .map(|s| s.audiobooks.iter().collect::<Vec<_>>())
+                .unwrap_or_default();
+
+            match found_keymap {
+                CommandOrAction::Command(command) => {
+                    window::handle_command_for_audiobook_list_window(
+                        command,
+                        client_pub,
+                        &audiobooks,
+                        ui,
+                    )
+                }
+                CommandOrAction::Action(action, ActionTarget::SelectedItem) => {
+                    window::handle_action_for_selected_item(
+                        action,
+                        &audiobooks,
+                        &data,
+                        ui,
+                        client_pub,
+                    )
+                }
+                CommandOrAction::Action(..) => Ok(false),
+            }
+        }
     }
 }
 
@@ -503,6 +733,25 @@ fn handle_command_for_command_help_page(command: Command, ui: &mut UIStateGuard)
     handle_navigation_command(command, ui.current_page_mut(), scroll_offset, 10000, count)
 }
 
+fn handle_command_for_diagnostics_page(
+    command: Command,
+    ui: &mut UIStateGuard,
+    state: &SharedState,
+) -> bool {
+    let (scroll_offset, len) = match ui.current_page() {
+        PageState::Diagnostics {
+            entries,
+            scroll_offset,
+        } => (
+            *scroll_offset,
+            entries.len() + crate::utils::get_live_diagnostics(state).len(),
+        ),
+        _ => return false,
+    };
+    let count = ui.count_prefix;
+    handle_navigation_command(command, ui.current_page_mut(), scroll_offset, len, count)
+}
+
 pub fn handle_navigation_command(
     command: Command,
     page: &mut PageState,
@@ -517,13 +766,11 @@ pub fn handle_navigation_command(
     let configs = config::get_config();
     match command {
         Command::SelectNextOrScrollDown => {
-            let offset = count.unwrap_or(1);
-            page.select(std::cmp::min(id + offset, len - 1));
+            page.select(next_position(id, count.unwrap_or(1), len));
             true
         }
         Command::SelectPreviousOrScrollUp => {
-            let offset = count.unwrap_or(1);
-            page.select(id.saturating_sub(offset));
+            page.select(previous_position(id, count.unwrap_or(1), len));
             true
         }
         Command::PageSelectNextOrScrollDown => {
@@ -549,3 +796,23 @@ pub fn handle_navigation_command(
         _ => false,
     }
 }
+
+/// gets the position after moving down `offset` items from `id` in a list of `len` items,
+/// which moves from the last item to the first one if the `wrap_around` option is enabled
+pub fn next_position(id: usize, offset: usize, len: usize) -> usize {
+    if id + 1 >= len && config::get_config().app_config.scrolling.wrap_around {
+        0
+    } else {
+        std::cmp::min(id + offset, len.saturating_sub(1))
+    }
+}
+
+/// gets the position after moving up `offset` items from `id` in a list of `len` items,
+/// which moves from the first item to the last one if the `wrap_around` option is enabled
+pub fn previous_position(id: usize, offset: usize, len: usize) -> usize {
+    if id == 0 && config::get_config().app_config.scrolling.wrap_around {
+        len.saturating_sub(1)
+    } else {
+        id.saturating_sub(offset)
+    }
+}
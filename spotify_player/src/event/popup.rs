@@ -1,5 +1,6 @@
 use super::*;
 use crate::command::construct_artist_actions;
+use crate::state::{CommandPaletteItem, JumpPaletteItem, CONFIRMATION_CHOICES};
 use anyhow::Context;
 
 pub fn handle_key_sequence_for_popup(
@@ -18,6 +19,33 @@ pub fn handle_key_sequence_for_popup(
         PopupState::PlaylistCreate { .. } => {
             return handle_key_sequence_for_create_playlist_popup(key_sequence, client_pub, ui);
         }
+        PopupState::JumpPalette(..) => {
+            return handle_key_sequence_for_jump_palette_popup(key_sequence, client_pub, state, ui);
+        }
+        PopupState::CommandPalette(..) => {
+            return handle_key_sequence_for_command_palette_popup(
+                key_sequence,
+                client_pub,
+                state,
+                ui,
+            );
+        }
+        PopupState::KeymapEditor(..) => {
+            return handle_key_sequence_for_keymap_editor_popup(key_sequence, ui);
+        }
+        PopupState::KeymapRebind { .. } => {
+            return handle_key_sequence_for_keymap_rebind_popup(key_sequence, ui);
+        }
+        PopupState::SavedSearchCreate { .. } => {
+            return handle_key_sequence_for_create_saved_search_popup(key_sequence, state, ui);
+        }
+        PopupState::SearchAndQueue(..) => {
+            return handle_key_sequence_for_search_and_queue_popup(key_sequence, client_pub, ui);
+        }
+        #[cfg(feature = "streaming")]
+        PopupState::Equalizer(..) => {
+            return handle_key_sequence_for_equalizer_popup(key_sequence, client_pub, state, ui);
+        }
         PopupState::ActionList(item, ..) => {
             return handle_key_sequence_for_action_list_popup(
                 item.n_actions(),
@@ -45,6 +73,80 @@ pub fn handle_key_sequence_for_popup(
         PopupState::ActionList(..) => {
             anyhow::bail!("action list popup should be handled before")
         }
+        PopupState::JumpPalette(..) => {
+            anyhow::bail!("jump palette popup should be handled before")
+        }
+        PopupState::CommandPalette(..) => {
+            anyhow::bail!("command palette popup should be handled before")
+        }
+        PopupState::KeymapEditor(..) => {
+            anyhow::bail!("keymap editor popup should be handled before")
+        }
+        PopupState::KeymapRebind { .. } => {
+            anyhow::bail!("keymap rebind popup should be handled before")
+        }
+        PopupState::SavedSearchCreate { .. } => {
+            anyhow::bail!("create saved search popup should be handled before")
+        }
+        PopupState::SearchAndQueue(..) => {
+            anyhow::bail!("search and queue popup should be handled before")
+        }
+        #[cfg(feature = "streaming")]
+        PopupState::Equalizer(..) => {
+            anyhow::bail!("equalizer popup should be handled before")
+        }
+        // the error popup is closed by the `ClosePopup` command
+        PopupState::ConfigError(_) => Ok(false),
+        PopupState::Confirmation(..) => handle_command_for_list_popup(
+            command,
+            ui,
+            CONFIRMATION_CHOICES.len(),
+            |_, _| {},
+            |ui: &mut UIStateGuard, id: usize| -> Result<()> {
+                let Some(PopupState::Confirmation(confirmation, _)) = ui.popup.take() else {
+                    return Ok(());
+                };
+                // the choices are "No", "Yes" and "Yes to all"
+                if id == 2 {
+                    ui.skipped_confirmations.insert(confirmation.action);
+                }
+                if id > 0 {
+                    client_pub.send(confirmation.request)?;
+                }
+                Ok(())
+            },
+            |ui: &mut UIStateGuard| {
+                ui.popup = None;
+            },
+        ),
+        PopupState::SavedSearchList(_) => {
+            if command == Command::DeleteSavedSearch {
+                let id = ui.popup.as_ref().and_then(PopupState::list_selected);
+                let mut data = state.data.write();
+                data.delete_saved_search(id.unwrap_or_default());
+                // keep the selection within the remaining saved searches
+                let n_items = data.saved_searches.len();
+                if let Some(popup) = ui.popup.as_mut() {
+                    popup.list_select(id.map(|id| std::cmp::min(id, n_items.saturating_sub(1))));
+                }
+                return Ok(true);
+            }
+
+            let searches = state.data.read().saved_searches.clone();
+
+            handle_command_for_list_popup(
+                command,
+                ui,
+                searches.len(),
+                |_, _| {},
+                |ui: &mut UIStateGuard, id: usize| -> Result<()> {
+                    run_saved_search(searches[id].clone(), client_pub, ui)
+                },
+                |ui: &mut UIStateGuard| {
+                    ui.popup = None;
+                },
+            )
+        }
         PopupState::ArtistList(_, artists, _) => {
             let n_items = artists.len();
 
@@ -260,6 +362,53 @@ pub fn handle_key_sequence_for_popup(
                 },
             )
         }
+        #[cfg(feature = "streaming")]
+        PopupState::AudioOutputList(outputs, _) => {
+            let outputs = outputs.clone();
+
+            handle_command_for_list_popup(
+                command,
+                ui,
+                outputs.len(),
+                |_, _| {},
+                |ui: &mut UIStateGuard, id: usize| -> Result<()> {
+                    // the new audio output is applied upon restarting the integrated client
+                    *state.audio_output.lock() = outputs[id].clone();
+                    client_pub.send(ClientRequest::RestartIntegratedClient)?;
+                    ui.popup = None;
+                    Ok(())
+                },
+                |ui: &mut UIStateGuard| {
+                    ui.popup = None;
+                },
+            )
+        }
+        #[cfg(feature = "streaming")]
+        PopupState::DeviceProfileList(_) => {
+            let configs = config::get_config();
+            let profiles = &configs.app_config.device_profiles;
+
+            handle_command_for_list_popup(
+                command,
+                ui,
+                profiles.len() + 1,
+                |_, _| {},
+                |ui: &mut UIStateGuard, id: usize| -> Result<()> {
+                    // the first item is the default profile of the device configurations
+                    let profile = id.checked_sub(1).map(|id| profiles[id].clone());
+                    // the new profile is applied upon restarting the integrated client
+                    *state.audio_output.lock() =
+                        crate::streaming::AudioOutput::new(profile.as_ref());
+                    *state.device_profile.lock() = profile;
+                    client_pub.send(ClientRequest::RestartIntegratedClient)?;
+                    ui.popup = None;
+                    Ok(())
+                },
+                |ui: &mut UIStateGuard| {
+                    ui.popup = None;
+                },
+            )
+        }
         PopupState::DeviceList(_) => {
             let player = state.player.read();
 
@@ -331,6 +480,137 @@ fn handle_key_sequence_for_create_playlist_popup(
     Ok(false)
 }
 
+fn handle_key_sequence_for_create_saved_search_popup(
+    key_sequence: &KeySequence,
+    state: &SharedState,
+    ui: &mut UIStateGuard,
+) -> Result<bool> {
+    let Some(PopupState::SavedSearchCreate {
+        name,
+        query,
+        library_only,
+    }) = &mut ui.popup
+    else {
+        return Ok(false);
+    };
+    if key_sequence.keys.len() == 1 {
+        match &key_sequence.keys[0] {
+            Key::None(crossterm::event::KeyCode::Enter) => {
+                if !name.is_empty() {
+                    state.data.write().add_saved_search(SavedSearch {
+                        name: name.get_text(),
+                        query: query.clone(),
+                        library_only: *library_only,
+                    });
+                    ui.popup = None;
+                }
+                return Ok(true);
+            }
+            k => {
+                if name.input(k).is_some() {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+    Ok(false)
+}
+
+fn handle_key_sequence_for_search_and_queue_popup(
+    key_sequence: &KeySequence,
+    client_pub: &flume::Sender<ClientRequest>,
+    ui: &mut UIStateGuard,
+) -> Result<bool> {
+    let Some(PopupState::SearchAndQueue(query)) = &mut ui.popup else {
+        return Ok(false);
+    };
+    if key_sequence.keys.len() == 1 {
+        match &key_sequence.keys[0] {
+            Key::None(crossterm::event::KeyCode::Enter) => {
+                if !query.is_empty() {
+                    client_pub.send(ClientRequest::SearchAndQueue(query.get_text()))?;
+                    ui.popup = None;
+                }
+                return Ok(true);
+            }
+            k => {
+                if query.input(k).is_some() {
+                    return Ok(true);
+                }
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Handle a key sequence for the equalizer popup, in which `left`/`h` and `right`/`l` adjust
+/// the selected band's gain, `0` resets the band's gain, `p` switches to the next preset
+/// and `e` toggles the equalizer
+#[cfg(feature = "streaming")]
+fn handle_key_sequence_for_equalizer_popup(
+    key_sequence: &KeySequence,
+    client_pub: &flume::Sender<ClientRequest>,
+    state: &SharedState,
+    ui: &mut UIStateGuard,
+) -> Result<bool> {
+    use crossterm::event::KeyCode;
+
+    let band = ui
+        .popup
+        .as_ref()
+        .and_then(PopupState::list_selected)
+        .unwrap_or_default();
+    if let [Key::None(code)] = key_sequence.keys.as_slice() {
+        let mut settings = (**state.equalizer.load()).clone();
+        let is_changed = match code {
+            KeyCode::Left | KeyCode::Char('h') => {
+                settings.change_gain(band, -1.0);
+                true
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                settings.change_gain(band, 1.0);
+                true
+            }
+            KeyCode::Char('0') => {
+                let gain = settings.gains[band];
+                settings.change_gain(band, -gain);
+                true
+            }
+            KeyCode::Char('p') => {
+                settings.next_preset();
+                true
+            }
+            KeyCode::Char('e') => {
+                client_pub.send(ClientRequest::SetEqualizer(!settings.enabled))?;
+                return Ok(true);
+            }
+            _ => false,
+        };
+        if is_changed {
+            settings.store();
+            state.equalizer.store(std::sync::Arc::new(settings));
+            return Ok(true);
+        }
+    }
+
+    let Some(command) = config::get_config()
+        .keymap_config
+        .find_command_from_key_sequence(key_sequence)
+    else {
+        return Ok(false);
+    };
+    handle_command_for_list_popup(
+        command,
+        ui,
+        crate::equalizer::BANDS.len(),
+        |_, _| {},
+        |_, _| Ok(()),
+        |ui: &mut UIStateGuard| {
+            ui.popup = None;
+        },
+    )
+}
+
 fn handle_key_sequence_for_search_popup(
     key_sequence: &KeySequence,
     client_pub: &flume::Sender<ClientRequest>,
@@ -338,13 +618,17 @@ fn handle_key_sequence_for_search_popup(
     ui: &mut UIStateGuard,
 ) -> Result<bool> {
     // handle user's input that updates the search query
-    let Some(PopupState::Search { ref mut query }) = &mut ui.popup else {
+    let Some(PopupState::Search {
+        ref mut query,
+        ref mut is_editing,
+    }) = &mut ui.popup
+    else {
         return Ok(false);
     };
     if key_sequence.keys.len() == 1 {
         if let Key::None(c) = key_sequence.keys[0] {
             match c {
-                crossterm::event::KeyCode::Char(c) => {
+                crossterm::event::KeyCode::Char(c) if *is_editing => {
                     query.push(c);
                     ui.current_page_mut().select(0);
                     return Ok(true);
@@ -355,19 +639,249 @@ fn handle_key_sequence_for_search_popup(
                         ui.popup = None;
                     } else {
                         query.pop().unwrap();
+                        *is_editing = true;
                         ui.current_page_mut().select(0);
                     }
                     return Ok(true);
                 }
+                crossterm::event::KeyCode::Enter => {
+                    // finish editing the query, keeping the current page filtered,
+                    // and let the page's event handler choose the selected item
+                    *is_editing = false;
+                }
                 _ => {}
             }
         }
     }
 
+    // move between the matched items after finishing editing the query
+    if !*is_editing {
+        let command = config::get_config()
+            .keymap_config
+            .find_search_popup_command_from_key_sequence(key_sequence);
+        let command = match command {
+            Some(Command::SelectNextMatch) => Some(Command::SelectNextOrScrollDown),
+            Some(Command::SelectPreviousMatch) => Some(Command::SelectPreviousOrScrollUp),
+            _ => None,
+        };
+        if let Some(command) = command {
+            return page::handle_command_for_page(command, client_pub, state, ui);
+        }
+    }
+
     // key sequence not handle by the popup should be moved to the current page's event handler
     page::handle_key_sequence_for_page(key_sequence, client_pub, state, ui)
 }
 
+fn handle_key_sequence_for_jump_palette_popup(
+    key_sequence: &KeySequence,
+    client_pub: &flume::Sender<ClientRequest>,
+    state: &SharedState,
+    ui: &mut UIStateGuard,
+) -> Result<bool> {
+    let Some(PopupState::JumpPalette(input, list_state)) = &mut ui.popup else {
+        return Ok(false);
+    };
+
+    // handle user's input that updates the query
+    if key_sequence.keys.len() == 1 {
+        match input.input(&key_sequence.keys[0]) {
+            Some(InputEffect::TextChanged) => {
+                list_state.select(Some(0));
+                return Ok(true);
+            }
+            Some(_) => return Ok(true),
+            None => {}
+        }
+    }
+
+    let Some(command) = config::get_config()
+        .keymap_config
+        .find_command_from_key_sequence(key_sequence)
+    else {
+        return Ok(false);
+    };
+
+    let items = JumpPaletteItem::matched_items(&state.data.read(), &input.get_text());
+
+    handle_command_for_list_popup(
+        command,
+        ui,
+        items.len(),
+        |_, _| {},
+        |ui: &mut UIStateGuard, id: usize| -> Result<()> {
+            let context_id = match &items[id] {
+                JumpPaletteItem::Page(_, command) => {
+                    ui.popup = None;
+                    handle_global_command(*command, client_pub, state, ui)?;
+                    return Ok(());
+                }
+                JumpPaletteItem::Playlist(p) => ContextId::Playlist(p.id.clone()),
+                JumpPaletteItem::Album(a) => ContextId::Album(a.id.clone()),
+                JumpPaletteItem::Artist(a) => ContextId::Artist(a.id.clone()),
+            };
+            ui.new_page(PageState::Context {
+                id: None,
+                context_page_type: ContextPageType::Browsing(context_id),
+                state: None,
+            });
+            Ok(())
+        },
+        |ui: &mut UIStateGuard| {
+            ui.popup = None;
+        },
+    )
+}
+
+fn handle_key_sequence_for_command_palette_popup(
+    key_sequence: &KeySequence,
+    client_pub: &flume::Sender<ClientRequest>,
+    state: &SharedState,
+    ui: &mut UIStateGuard,
+) -> Result<bool> {
+    let Some(PopupState::CommandPalette(input, list_state)) = &mut ui.popup else {
+        return Ok(false);
+    };
+
+    // handle user's input that updates the query
+    if key_sequence.keys.len() == 1 {
+        match input.input(&key_sequence.keys[0]) {
+            Some(InputEffect::TextChanged) => {
+                list_state.select(Some(0));
+                return Ok(true);
+            }
+            Some(_) => return Ok(true),
+            None => {}
+        }
+    }
+
+    let Some(command) = config::get_config()
+        .keymap_config
+        .find_command_from_key_sequence(key_sequence)
+    else {
+        return Ok(false);
+    };
+
+    let items = CommandPaletteItem::matched_items(&input.get_text());
+
+    handle_command_for_list_popup(
+        command,
+        ui,
+        items.len(),
+        |_, _| {},
+        |ui: &mut UIStateGuard, id: usize| -> Result<()> {
+            // run the command as if its shortcut was pressed after closing the palette
+            ui.popup = None;
+            match &items[id] {
+                CommandPaletteItem::Command(command, _) => {
+                    if !page::handle_command_for_page(*command, client_pub, state, ui)? {
+                        handle_global_command(*command, client_pub, state, ui)?;
+                    }
+                }
+                CommandPaletteItem::Macro(m) => run_macro(m, client_pub, state, ui)?,
+            }
+            Ok(())
+        },
+        |ui: &mut UIStateGuard| {
+            ui.popup = None;
+        },
+    )
+}
+
+fn handle_key_sequence_for_keymap_editor_popup(
+    key_sequence: &KeySequence,
+    ui: &mut UIStateGuard,
+) -> Result<bool> {
+    let Some(PopupState::KeymapEditor(input, list_state)) = &mut ui.popup else {
+        return Ok(false);
+    };
+
+    // handle user's input that updates the query
+    if key_sequence.keys.len() == 1 {
+        match input.input(&key_sequence.keys[0]) {
+            Some(InputEffect::TextChanged) => {
+                list_state.select(Some(0));
+                return Ok(true);
+            }
+            Some(_) => return Ok(true),
+            None => {}
+        }
+    }
+
+    let Some(command) = config::get_config()
+        .keymap_config
+        .find_command_from_key_sequence(key_sequence)
+    else {
+        return Ok(false);
+    };
+
+    let items = CommandPaletteItem::matched_commands(&input.get_text());
+
+    handle_command_for_list_popup(
+        command,
+        ui,
+        items.len(),
+        |_, _| {},
+        |ui: &mut UIStateGuard, id: usize| -> Result<()> {
+            if let CommandPaletteItem::Command(command, _) = items[id] {
+                ui.popup = Some(PopupState::KeymapRebind {
+                    command,
+                    key_sequence: KeySequence { keys: vec![] },
+                });
+            }
+            Ok(())
+        },
+        |ui: &mut UIStateGuard| {
+            ui.popup = None;
+        },
+    )
+}
+
+/// Handle a key sequence for the keymap rebind popup, in which the pressed keys
+/// form the new key sequence of the command
+fn handle_key_sequence_for_keymap_rebind_popup(
+    key_sequence: &KeySequence,
+    ui: &mut UIStateGuard,
+) -> Result<bool> {
+    let Some(PopupState::KeymapRebind {
+        command,
+        key_sequence: new_key_sequence,
+    }) = &mut ui.popup
+    else {
+        return Ok(false);
+    };
+    let Some(key) = key_sequence.keys.last() else {
+        return Ok(false);
+    };
+
+    match key {
+        Key::None(KeyCode::Esc) => {
+            ui.popup = None;
+        }
+        Key::None(KeyCode::Backspace) => {
+            new_key_sequence.keys.pop();
+        }
+        Key::None(KeyCode::Enter) => {
+            if new_key_sequence.keys.is_empty() {
+                return Ok(true);
+            }
+            // the configuration watcher reloads the keymaps upon the file's change
+            let configs = config::get_config();
+            configs.keymap_config.rebind_command(
+                &configs.config_folder,
+                *command,
+                new_key_sequence,
+            )?;
+            ui.popup = None;
+        }
+        key if key.is_representable() => {
+            new_key_sequence.keys.push(*key);
+        }
+        _ => {}
+    }
+    Ok(true)
+}
+
 /// Handle a command for a context list popup in which each item represents a context
 ///
 /// # Arguments
@@ -432,20 +946,23 @@ fn handle_command_for_list_popup(
     on_choose_func: impl FnOnce(&mut UIStateGuard, usize) -> anyhow::Result<()>,
     on_close_func: impl FnOnce(&mut UIStateGuard),
 ) -> anyhow::Result<bool> {
+    let count = ui.count_prefix.unwrap_or(1);
     let popup = ui.popup.as_mut().with_context(|| "expect a popup")?;
     let current_id = popup.list_selected().unwrap_or_default();
 
     match command {
         Command::SelectPreviousOrScrollUp => {
-            if current_id > 0 {
-                popup.list_select(Some(current_id - 1));
-                on_select_func(ui, current_id - 1);
+            let id = page::previous_position(current_id, count, n_items);
+            if id != current_id {
+                popup.list_select(Some(id));
+                on_select_func(ui, id);
             }
         }
         Command::SelectNextOrScrollDown => {
-            if current_id + 1 < n_items {
-                popup.list_select(Some(current_id + 1));
-                on_select_func(ui, current_id + 1);
+            let id = page::next_position(current_id, count, n_items);
+            if id != current_id && id < n_items {
+                popup.list_select(Some(id));
+                on_select_func(ui, id);
             }
         }
         Command::ChooseSelected => {
@@ -533,5 +1050,8 @@ pub fn handle_item_action(
         ActionListItem::Episode(episode, actions) => {
             handle_action_in_context(actions[n], episode.into(), client_pub, &data, ui)
         }
+        ActionListItem::Audiobook(audiobook, actions) => {
+            handle_action_in_context(actions[n], audiobook.into(), client_pub, &data, ui)
+        }
     }
 }
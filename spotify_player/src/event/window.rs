@@ -5,7 +5,7 @@ use crate::{
         construct_album_actions, construct_artist_actions, construct_playlist_actions,
         construct_show_actions,
     },
-    state::{Episode, MutableWindowState, Show, UIStateGuard},
+    state::{Audiobook, Episode, MutableWindowState, Show, UIStateGuard},
 };
 use command::Action;
 use rand::Rng;
@@ -132,6 +132,11 @@ pub fn handle_command_for_focused_context_window(
             if let Some(tracks) = data.context_tracks_mut(context_id) {
                 tracks.sort_by(|x, y| order.compare(x, y));
             }
+            // remember the order to re-apply it when the context is fetched again
+            let uri = context_id.uri();
+            let view = ui.context_views.entry(uri).or_default();
+            view.order = Some(order);
+            view.reversed = false;
             return Ok(true);
         }
         // reverse ordering command
@@ -140,6 +145,9 @@ pub fn handle_command_for_focused_context_window(
             if let Some(tracks) = data.context_tracks_mut(context_id) {
                 tracks.reverse();
             }
+            let uri = context_id.uri();
+            let view = ui.context_views.entry(uri).or_default();
+            view.reversed = !view.reversed;
             return Ok(true);
         }
     }
@@ -609,6 +617,39 @@ pub fn handle_command_for_episode_list_window(
     Ok(true)
 }
 
+pub fn handle_command_for_audiobook_list_window(
+    command: Command,
+    client_pub: &flume::Sender<ClientRequest>,
+    audiobooks: &[&Audiobook],
+    ui: &mut UIStateGuard,
+) -> Result<bool> {
+    let id = ui.current_page_mut().selected().unwrap_or_default();
+    if id >= audiobooks.len() {
+        return Ok(false);
+    }
+
+    let count = ui.count_prefix;
+    if handle_navigation_command(command, ui.current_page_mut(), id, audiobooks.len(), count) {
+        return Ok(true);
+    }
+    match command {
+        Command::ChooseSelected => {
+            client_pub.send(ClientRequest::PlayAudiobook(audiobooks[id].id.clone()))?;
+        }
+        Command::ShowActionsOnSelectedItem => {
+            ui.popup = Some(PopupState::ActionList(
+                Box::new(ActionListItem::Audiobook(
+                    audiobooks[id].clone(),
+                    command::construct_audiobook_actions(),
+                )),
+                ListState::default(),
+            ));
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
 fn handle_command_for_episode_table_window(
     command: Command,
     client_pub: &flume::Sender<ClientRequest>,
@@ -0,0 +1,70 @@
+//! Fades of the integrated device's audio, which fade in upon starting the playback
+//! and fade out before pausing or skipping the playback
+
+use librespot_playback::{
+    audio_backend::{Sink, SinkResult},
+    convert::Converter,
+    decoder::AudioPacket,
+    NUM_CHANNELS, SAMPLE_RATE,
+};
+
+use crate::state::SharedState;
+
+/// A sink fading the audio samples in or out before writing them into another sink
+pub struct FadeSink {
+    sink: Box<dyn Sink>,
+    state: SharedState,
+    /// gain of the current frame, from 0 (silent) to 1
+    gain: f64,
+    /// change of the gain per frame
+    step: f64,
+}
+
+impl FadeSink {
+    pub fn new(sink: Box<dyn Sink>, state: SharedState, duration: std::time::Duration) -> Self {
+        Self {
+            sink,
+            state,
+            gain: 1.0,
+            step: 1.0 / (duration.as_secs_f64() * f64::from(SAMPLE_RATE)),
+        }
+    }
+}
+
+impl Sink for FadeSink {
+    fn start(&mut self) -> SinkResult<()> {
+        // fade in upon starting or resuming the playback
+        self.gain = 0.0;
+        self.sink.start()
+    }
+
+    fn stop(&mut self) -> SinkResult<()> {
+        self.sink.stop()
+    }
+
+    fn write(&mut self, packet: AudioPacket, converter: &mut Converter) -> SinkResult<()> {
+        let AudioPacket::Samples(mut samples) = packet else {
+            return self.sink.write(packet, converter);
+        };
+
+        let target = if *self.state.fading_out.lock() {
+            0.0
+        } else {
+            1.0
+        };
+        if self.gain < 1.0 || target < 1.0 {
+            // samples are interleaved by channels
+            for frame in samples.chunks_mut(NUM_CHANNELS as usize) {
+                self.gain = if self.gain < target {
+                    (self.gain + self.step).min(target)
+                } else {
+                    (self.gain - self.step).max(target)
+                };
+                for sample in frame {
+                    *sample *= self.gain;
+                }
+            }
+        }
+        self.sink.write(AudioPacket::Samples(samples), converter)
+    }
+}
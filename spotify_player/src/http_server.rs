@@ -0,0 +1,209 @@
+//! A minimal HTTP server shared by the application's HTTP endpoints (the web API,
+//! the overlay and the metrics), each of which is mounted on the server as a set of routes
+
+use std::{collections::HashMap, fmt::Write as _, sync::Arc};
+
+use anyhow::{Context as _, Result};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::Instrument;
+
+/// maximum size (in bytes) of a request's line and headers
+const MAX_REQUEST_SIZE: u64 = 8192;
+
+/// A parsed HTTP request
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub headers: HashMap<String, String>,
+}
+
+/// An HTTP response
+pub struct Response {
+    status: u16,
+    content_type: &'static str,
+    headers: Vec<(&'static str, &'static str)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    pub fn ok(content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            content_type,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn no_content() -> Self {
+        Self {
+            status: 204,
+            content_type: "text/plain",
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn error(status: u16, msg: impl Into<String>) -> Self {
+        Self {
+            status,
+            content_type: "text/plain",
+            headers: Vec::new(),
+            body: msg.into().into_bytes(),
+        }
+    }
+
+    /// Adds an extra header to the response
+    pub fn with_header(mut self, name: &'static str, value: &'static str) -> Self {
+        self.headers.push((name, value));
+        self
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            204 => "No Content",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            404 => "Not Found",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// A set of routes mounted on the HTTP server
+#[async_trait::async_trait]
+pub trait Routes: Send + Sync {
+    /// Handles a request, returning `None` if the request's path is not one of the routes
+    async fn handle(&self, request: &Request) -> Option<Response>;
+}
+
+/// Serves requests sent to the listener's address with the mounted routes.
+/// Requests not matching any of the routes are responded with `404 Not Found`.
+pub async fn start_http_server(listener: TcpListener, routes: Vec<Box<dyn Routes>>) {
+    let routes: Arc<[Box<dyn Routes>]> = routes.into();
+    loop {
+        let (stream, addr) = match listener.accept().await {
+            Ok(v) => v,
+            Err(err) => {
+                tracing::warn!("Failed to accept an HTTP connection: {err:#}");
+                continue;
+            }
+        };
+
+        let span = tracing::info_span!("http_request", addr = ?addr);
+        tokio::task::spawn({
+            let routes = routes.clone();
+            async move {
+                if let Err(err) = handle_connection(&routes, stream).await {
+                    tracing::warn!("Failed to handle the HTTP request: {err:#}");
+                }
+            }
+            .instrument(span)
+        });
+    }
+}
+
+async fn handle_connection(routes: &[Box<dyn Routes>], mut stream: TcpStream) -> Result<()> {
+    let (reader, mut writer) = stream.split();
+    let response = match read_request(reader).await {
+        Ok(request) => {
+            let mut response = None;
+            for r in routes {
+                response = r.handle(&request).await;
+                if response.is_some() {
+                    break;
+                }
+            }
+            response.unwrap_or_else(|| Response::error(404, "Unknown endpoint"))
+        }
+        Err(err) => Response::error(400, format!("Bad request: {err:#}")),
+    };
+
+    let mut header = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.reason(),
+        response.content_type,
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        write!(header, "{name}: {value}\r\n").unwrap();
+    }
+    header.push_str("\r\n");
+    writer.write_all(header.as_bytes()).await?;
+    writer.write_all(&response.body).await?;
+    writer.shutdown().await?;
+    Ok(())
+}
+
+/// Reads an HTTP request's line and headers. The request's body is ignored.
+async fn read_request(reader: impl tokio::io::AsyncRead + Unpin) -> Result<Request> {
+    let mut reader = BufReader::new(reader.take(MAX_REQUEST_SIZE));
+
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().context("missing request method")?.to_string();
+    let target = parts.next().context("missing request target")?;
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let (k, v) = s.split_once('=').unwrap_or((s, ""));
+            (percent_decode(k), percent_decode(v))
+        })
+        .collect();
+
+    let mut headers = HashMap::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            break;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    Ok(Request {
+        method,
+        path: path.to_string(),
+        query,
+        headers,
+    })
+}
+
+/// Decodes a percent-encoded URL component
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        decoded.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            b'+' => decoded.push(b' '),
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
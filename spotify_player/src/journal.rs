@@ -0,0 +1,109 @@
+//! Listening journal appending each played track to a daily markdown file,
+//! e.g. for Obsidian-style daily notes
+
+use std::{io::Write, path::Path};
+
+use anyhow::Result;
+use rspotify::{model::PlayableItem, prelude::Id};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    config,
+    state::{PlaybackEvent, SharedState},
+};
+
+/// Starts the journal writer, which appends each played track to the daily file inside `folder`
+pub async fn start_journal_writer(state: SharedState, folder: std::path::PathBuf) {
+    let mut playback_events = state.playback_events.subscribe();
+    let mut last_uri = None;
+    loop {
+        match playback_events.recv().await {
+            Ok(PlaybackEvent::TrackChange | PlaybackEvent::Play) => {}
+            Ok(_) => continue,
+            Err(RecvError::Lagged(n)) => {
+                tracing::warn!("Missed {n} playback events to journal");
+            }
+            Err(RecvError::Closed) => break,
+        }
+
+        let item = {
+            let player = state.player.read();
+            let is_playing = player.playback.as_ref().is_some_and(|p| p.is_playing);
+            // an item is only journaled once it starts playing outside a private session
+            player
+                .currently_playing()
+                .filter(|_| is_playing && !*state.private_session.lock())
+                .cloned()
+        };
+        let Some(item) = item else {
+            continue;
+        };
+        let uri = item.id().map(|id| id.uri());
+        if uri.is_none() || uri == last_uri {
+            continue;
+        }
+        last_uri = uri;
+
+        if let Err(err) = write_entry(&folder, &item) {
+            tracing::warn!("Failed to write the listening journal's entry: {err:#}");
+        }
+    }
+}
+
+/// Appends an item's entry to the current day's journal file, creating the file if needed
+fn write_entry(folder: &Path, item: &PlayableItem) -> Result<()> {
+    let configs = config::get_config();
+    let now = chrono::Local::now();
+
+    std::fs::create_dir_all(folder)?;
+    let path = folder.join(
+        now.format(&configs.app_config.journal_file_name)
+            .to_string(),
+    );
+    let is_new = !path.exists();
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    if is_new {
+        writeln!(file, "# Listening journal {}\n", now.format("%Y-%m-%d"))?;
+    }
+    writeln!(
+        file,
+        "{}",
+        format_entry(&now.format("%H:%M").to_string(), item)
+    )?;
+    Ok(())
+}
+
+/// Formats an item's entry as a markdown list item, e.g.
+/// `- 14:03 [Title](https://open.spotify.com/track/...) — Artist · *Album*`
+fn format_entry(time: &str, item: &PlayableItem) -> String {
+    let (name, artists, album, external_urls) = match item {
+        PlayableItem::Track(track) => (
+            &track.name,
+            track
+                .artists
+                .iter()
+                .map(|a| a.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            &track.album.name,
+            &track.external_urls,
+        ),
+        PlayableItem::Episode(episode) => (
+            &episode.name,
+            episode.show.publisher.clone(),
+            &episode.show.name,
+            &episode.external_urls,
+        ),
+    };
+
+    // escape the characters breaking a markdown link's text
+    let name = name.replace('[', "\\[").replace(']', "\\]");
+    let title = match external_urls.get("spotify") {
+        Some(url) => format!("[{name}]({url})"),
+        None => name,
+    };
+    format!("- {time} {title} — {artists} · *{album}*")
+}
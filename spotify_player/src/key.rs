@@ -7,6 +7,9 @@ pub enum Key {
     None(KeyCode),
     Ctrl(KeyCode),
     Alt(KeyCode),
+    /// the leader key, i.e. `<leader>` in a configured key sequence,
+    /// which is replaced by the configured leader key
+    Leader,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -61,8 +64,19 @@ impl Key {
         })
     }
 
+    /// checks if the key has a string representation, i.e. it can be used in a key sequence
+    pub fn is_representable(&self) -> bool {
+        match self {
+            Key::None(k) | Key::Ctrl(k) | Key::Alt(k) => key_code_to_string(*k).is_some(),
+            Key::Leader | Key::Unknown => false,
+        }
+    }
+
     /// creates a `Key` from its string representation
     pub fn from_str(s: &str) -> Option<Self> {
+        if s == "<leader>" {
+            return Some(Key::Leader);
+        }
         let chars = s.chars().collect::<Vec<_>>();
         if chars.len() > 2 && chars[1] == '-' && chars[2] != ' ' {
             // M-<c> for alt-<c> and C-<c> for ctrl-<c>
@@ -81,8 +95,8 @@ impl Key {
     }
 }
 
-fn key_code_to_string(k: KeyCode) -> String {
-    match k {
+fn key_code_to_string(k: KeyCode) -> Option<String> {
+    Some(match k {
         KeyCode::Char(c) => {
             if c == ' ' {
                 "space".to_string()
@@ -121,16 +135,21 @@ fn key_code_to_string(k: KeyCode) -> String {
         KeyCode::F(11) => "f11".to_string(),
         KeyCode::F(12) => "f12".to_string(),
 
-        _ => panic!("unknown key: {k:?}"),
-    }
+        _ => return None,
+    })
+}
+
+fn display_key_code(k: KeyCode) -> String {
+    key_code_to_string(k).unwrap_or_else(|| panic!("unknown key: {k:?}"))
 }
 
 impl std::fmt::Display for Key {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match *self {
-            Key::Ctrl(k) => write!(f, "C-{}", key_code_to_string(k)),
-            Key::Alt(k) => write!(f, "M-{}", key_code_to_string(k)),
-            Key::None(k) => write!(f, "{}", key_code_to_string(k)),
+            Key::Ctrl(k) => write!(f, "C-{}", display_key_code(k)),
+            Key::Alt(k) => write!(f, "M-{}", display_key_code(k)),
+            Key::None(k) => write!(f, "{}", display_key_code(k)),
+            Key::Leader => write!(f, "<leader>"),
             Key::Unknown => write!(f, "unknown key"),
         }
     }
@@ -1,19 +1,48 @@
+#[cfg(feature = "streaming")]
+mod audio_stats;
 mod auth;
+#[cfg(feature = "streaming")]
+mod channels;
 mod cli;
 mod client;
 mod command;
 mod config;
+mod config_watcher;
+#[cfg(feature = "streaming")]
+mod equalizer;
 mod event;
+#[cfg(feature = "streaming")]
+mod fade;
+mod http_server;
+mod journal;
 mod key;
-#[cfg(feature = "media-control")]
+#[cfg(all(feature = "media-control", not(target_os = "linux")))]
 mod media_control;
+mod metrics;
+#[cfg(all(feature = "media-control", target_os = "linux"))]
+mod mpris;
+#[cfg(feature = "streaming")]
+mod night_mode;
+#[cfg(feature = "streaming")]
+mod output;
+mod overlay;
 mod playlist_folders;
+#[cfg(feature = "streaming")]
+mod resample;
+#[cfg(feature = "scripting")]
+mod scripting;
+#[cfg(feature = "scrobble")]
+mod scrobbler;
+#[cfg(feature = "streaming")]
+mod silence;
 mod state;
 #[cfg(feature = "streaming")]
 mod streaming;
+mod terminal_background;
 mod token;
 mod ui;
 mod utils;
+mod webhook;
 
 use anyhow::{Context, Result};
 use std::io::Write;
@@ -70,68 +99,140 @@ fn init_logging(cache_folder: &std::path::Path) -> Result<()> {
     Ok(())
 }
 
-#[tokio::main]
-async fn start_app(state: &state::SharedState) -> Result<()> {
-    let configs = config::get_config();
+/// Set environment variables for `PulseAudio`
+#[cfg(feature = "pulseaudio-backend")]
+fn set_pulseaudio_env_vars(configs: &config::Configs) {
+    if std::env::var("PULSE_PROP_application.name").is_err() {
+        std::env::set_var("PULSE_PROP_application.name", "spotify-player");
+    }
+    if std::env::var("PULSE_PROP_application.icon_name").is_err() {
+        std::env::set_var("PULSE_PROP_application.icon_name", "spotify");
+    }
+    if std::env::var("PULSE_PROP_stream.description").is_err() {
+        std::env::set_var(
+            "PULSE_PROP_stream.description",
+            format!(
+                "Spotify Connect endpoint ({})",
+                configs.app_config.device.name
+            ),
+        );
+    }
+    if std::env::var("PULSE_PROP_media.software").is_err() {
+        std::env::set_var("PULSE_PROP_media.software", "Spotify");
+    }
+    if std::env::var("PULSE_PROP_media.role").is_err() {
+        std::env::set_var("PULSE_PROP_media.role", "music");
+    }
+}
 
-    if !state.is_daemon {
-        #[cfg(feature = "image")]
-        {
-            // initialize `viuer` supports for kitty, iterm2, and sixel
-            viuer::get_kitty_support();
-            viuer::is_iterm_supported();
-            #[cfg(feature = "sixel")]
-            viuer::is_sixel_supported();
+/// Load the application's configurations from the configuration files,
+/// which are overridden by the CLI arguments
+fn load_configs(
+    args: &clap::ArgMatches,
+    config_folder: &std::path::Path,
+    cache_folder: &std::path::Path,
+) -> Result<config::Configs> {
+    let mut configs = config::Configs::new(config_folder, cache_folder)?;
+    if let Some(theme) = args.get_one::<String>("theme") {
+        // override the theme config if user specifies a `theme` cli argument
+        theme.clone_into(&mut configs.app_config.theme);
+        configs.app_config.theme_mode = None;
+    }
+    if let Some(mode) = args.get_one::<String>("theme-mode") {
+        // override the theme mode config if user specifies a `theme-mode` cli argument
+        configs.app_config.theme_mode = Some(match mode.as_str() {
+            "light" => config::ThemeMode::Light,
+            "dark" => config::ThemeMode::Dark,
+            _ => config::ThemeMode::Auto,
+        });
+    }
+    if let Some(addr) = args.get_one::<String>("web-api") {
+        // override the web API address config if user specifies a `web-api` cli argument
+        configs.app_config.web_api_address = Some(addr.clone());
+    }
+    if let Some(addr) = args.get_one::<String>("remote") {
+        // override the remote address config if user specifies a `remote` cli argument
+        configs.app_config.remote_address = Some(addr.clone());
+    }
+    configs.profile = args.get_one::<String>("profile").cloned();
+    #[cfg(feature = "streaming")]
+    if let Some(profile) = args.get_one::<String>("device-profile") {
+        // override the device profile config if user specifies a `device-profile` cli argument
+        configs.app_config.device_profile = Some(profile.clone());
+    }
+    if let Some(profile) = &configs.app_config.device_profile {
+        if configs.app_config.find_device_profile(profile).is_none() {
+            anyhow::bail!("device profile {profile} is not found in `device_profiles`");
         }
     }
+    Ok(configs)
+}
 
-    // client channels
-    let (client_pub, client_sub) = flume::unbounded::<client::ClientRequest>();
-
-    #[cfg(feature = "pulseaudio-backend")]
-    {
-        // set environment variables for PulseAudio
-        if std::env::var("PULSE_PROP_application.name").is_err() {
-            std::env::set_var("PULSE_PROP_application.name", "spotify-player");
-        }
-        if std::env::var("PULSE_PROP_application.icon_name").is_err() {
-            std::env::set_var("PULSE_PROP_application.icon_name", "spotify");
-        }
-        if std::env::var("PULSE_PROP_stream.description").is_err() {
-            std::env::set_var(
-                "PULSE_PROP_stream.description",
-                format!(
-                    "Spotify Connect endpoint ({})",
-                    configs.app_config.device.name
-                ),
-            );
-        }
-        if std::env::var("PULSE_PROP_media.software").is_err() {
-            std::env::set_var("PULSE_PROP_media.software", "Spotify");
+/// Spawn the tasks needed for running the application UI, returning the UI task
+fn start_ui_tasks(
+    state: &state::SharedState,
+    client_pub: &flume::Sender<client::ClientRequest>,
+) -> tokio::task::JoinHandle<Result<()>> {
+    // terminal event handler task
+    tokio::task::spawn_blocking({
+        let client_pub = client_pub.clone();
+        let state = state.clone();
+        move || {
+            event::start_event_handler(&state, &client_pub);
         }
-        if std::env::var("PULSE_PROP_media.role").is_err() {
-            std::env::set_var("PULSE_PROP_media.role", "music");
+    });
+
+    // application UI task
+    tokio::task::spawn_blocking({
+        let state = state.clone();
+        move || ui::run(&state)
+    })
+}
+
+/// Stop the application upon a termination signal.
+/// The UI, if running, is stopped first to restore the terminal.
+#[cfg(unix)]
+async fn handle_termination_signals(state: state::SharedState) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let (mut terminate, mut interrupt) = match (
+        signal(SignalKind::terminate()),
+        signal(SignalKind::interrupt()),
+    ) {
+        (Ok(terminate), Ok(interrupt)) => (terminate, interrupt),
+        (Err(err), _) | (_, Err(err)) => {
+            tracing::warn!("Failed to listen to termination signals: {err:#}");
+            return;
         }
+    };
+    tokio::select! {
+        _ = terminate.recv() => {}
+        _ = interrupt.recv() => {}
     }
 
-    // create a Spotify API client
-    let auth_config = auth::AuthConfig::new(configs)?;
-    let client = client::Client::new(auth_config);
-    client
-        .new_session(Some(state), true)
-        .await
-        .context("initialize new Spotify session")?;
-
-    // initialize Spotify-related stuff
-    init_spotify(&client_pub, &client, state).context("Failed to initialize the Spotify data")?;
+    tracing::info!("Got a termination signal, stopping the application...");
+    if crossterm::terminal::is_raw_mode_enabled().unwrap_or_default() {
+        // the UI restores the terminal and removes the Unix socket file before exiting
+        state.ui.lock().is_running = false;
+    } else {
+        cli::remove_unix_socket_file();
+        std::process::exit(0);
+    }
+}
 
-    // Spawn application's tasks
+/// Spawn the tasks of the application's services, e.g. the sockets handling CLI commands
+fn start_service_tasks(
+    client: &client::Client,
+    state: &state::SharedState,
+) -> Vec<tokio::task::JoinHandle<()>> {
+    let configs = config::get_config();
     let mut tasks = Vec::new();
 
     // client socket task (for handling CLI commands)
     tasks.push(tokio::task::spawn({
         let client = client.clone();
         let state = state.clone();
+        let configs = configs.clone();
         async move {
             let port = configs.app_config.client_port;
             tracing::info!("Starting a client socket at 127.0.0.1:{port}");
@@ -146,6 +247,301 @@ async fn start_app(state: &state::SharedState) -> Result<()> {
         }
     }));
 
+    // Unix socket task (for handling CLI commands, e.g. from scripts or `spotify_player daemon`)
+    #[cfg(unix)]
+    tasks.push(tokio::task::spawn({
+        let client = client.clone();
+        let state = state.clone();
+        let configs = configs.clone();
+        async move {
+            let path = configs.client_socket_path();
+            if std::os::unix::net::UnixStream::connect(&path).is_ok() {
+                tracing::warn!(
+                    "Another running application is listening on the Unix socket {}",
+                    path.display()
+                );
+                return;
+            }
+            tracing::info!("Starting a client socket at {}", path.display());
+            match cli::bind_unix_socket(&path) {
+                Ok(listener) => {
+                    cli::start_unix_socket(client, listener, path, Some(state)).await;
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to create a Unix socket for handling CLI commands: {err:#}"
+                    );
+                }
+            }
+        }
+    }));
+
+    // remote TCP socket task (for handling CLI commands sent from other machines)
+    if let Some(addr) = configs.app_config.remote_listen_address.clone() {
+        tasks.push(tokio::task::spawn({
+            let client = client.clone();
+            let state = state.clone();
+            let configs = configs.clone();
+            async move {
+                let Ok(Some(key)) = configs.app_config.remote_key() else {
+                    tracing::warn!("Remote control requires `remote_secret` to be configured");
+                    return;
+                };
+                // the socket is only exposed to other machines if explicitly allowed
+                if !configs.app_config.remote_listen_public {
+                    let is_loopback = tokio::net::lookup_host(&addr)
+                        .await
+                        .map(|mut addrs| addrs.all(|socket_addr| socket_addr.ip().is_loopback()));
+                    match is_loopback {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            tracing::warn!("Refused to create a remote client socket at {addr}: listening on a non-loopback address requires `remote_listen_public` to be enabled");
+                            return;
+                        }
+                        Err(err) => {
+                            tracing::warn!("Failed to resolve the remote client socket address {addr}: {err:#}");
+                            return;
+                        }
+                    }
+                }
+                tracing::info!("Starting a remote client socket at {addr}");
+                match tokio::net::TcpListener::bind(&addr).await {
+                    Ok(listener) => {
+                        cli::start_tcp_socket(client, listener, Some(state), key).await
+                    }
+                    Err(err) => {
+                        tracing::warn!(
+                            "Failed to create a remote client socket at {addr}: {err:#}"
+                        );
+                    }
+                }
+            }
+        }));
+    }
+
+    // HTTP server tasks (for the web API, the overlay and the metrics endpoints).
+    // Endpoints configured with the same address are mounted on the same server.
+    let mut http_servers: Vec<(String, Vec<Box<dyn http_server::Routes>>)> = vec![];
+    let mut mount_routes = |addr: String, routes: Box<dyn http_server::Routes>| {
+        if let Some(i) = http_servers.iter().position(|(a, _)| *a == addr) {
+            http_servers[i].1.push(routes);
+        } else {
+            http_servers.push((addr, vec![routes]));
+        }
+    };
+    if let Some(addr) = configs.app_config.web_api_address.clone() {
+        match configs.app_config.web_api_token.clone() {
+            Some(token) => mount_routes(
+                addr,
+                Box::new(cli::WebApi::new(client.clone(), Some(state.clone()), token)),
+            ),
+            None => tracing::warn!("Web API requires `web_api_token` to be configured"),
+        }
+    }
+    if let Some(addr) = configs.app_config.overlay_address.clone() {
+        mount_routes(addr, Box::new(overlay::Overlay::new(state.clone())));
+    }
+    if let Some(addr) = configs.app_config.metrics_address.clone() {
+        mount_routes(addr, Box::new(metrics::MetricsRoutes));
+    }
+    for (addr, routes) in http_servers {
+        tasks.push(tokio::task::spawn(async move {
+            tracing::info!("Starting an HTTP server at {addr}");
+            match tokio::net::TcpListener::bind(&addr).await {
+                Ok(listener) => http_server::start_http_server(listener, routes).await,
+                Err(err) => {
+                    tracing::warn!("Failed to create an HTTP server at {addr}: {err:#}");
+                }
+            }
+        }));
+    }
+
+    // WebSocket task (for broadcasting playback events to external UIs)
+    #[cfg(feature = "websocket")]
+    if let Some(addr) = configs.app_config.websocket_address.clone() {
+        tasks.push(tokio::task::spawn({
+            let state = state.clone();
+            async move {
+                tracing::info!("Starting a WebSocket server at {addr}");
+                match tokio::net::TcpListener::bind(&addr).await {
+                    Ok(listener) => cli::start_websocket_server(listener, state).await,
+                    Err(err) => {
+                        tracing::warn!("Failed to create a WebSocket server at {addr}: {err:#}");
+                    }
+                }
+            }
+        }));
+    }
+
+    #[cfg(feature = "scrobble")]
+    {
+        // scrobbler task (for scrobbling the played tracks)
+        tasks.push(tokio::task::spawn(scrobbler::start_scrobbler(
+            state.clone(),
+        )));
+    }
+
+    #[cfg(feature = "mqtt")]
+    if configs.app_config.enable_mqtt {
+        // MQTT task (for publishing the playback and handling playback commands)
+        tasks.push(tokio::task::spawn({
+            let client = client.clone();
+            let state = state.clone();
+            async move {
+                cli::start_mqtt_client(client, state).await;
+            }
+        }));
+    }
+
+    // webhook task (for sending playback events to the configured webhooks),
+    // which always runs as webhooks can be added by reloading the configurations
+    tasks.push(tokio::task::spawn(webhook::start_webhook_sender(
+        state.clone(),
+    )));
+
+    #[cfg(feature = "streaming")]
+    if configs.app_config.device.zeroconf && state.is_streaming_enabled() {
+        // zeroconf discovery task (for advertising the integrated device on the local network)
+        tasks.push(tokio::task::spawn({
+            let client = client.clone();
+            let state = state.clone();
+            async move {
+                client.start_zeroconf_discovery(state).await;
+            }
+        }));
+    }
+
+    if let Some(folder) = configs.app_config.journal_folder.clone() {
+        // listening journal task (for appending the played tracks to daily markdown files)
+        tasks.push(tokio::task::spawn(journal::start_journal_writer(
+            state.clone(),
+            folder.into(),
+        )));
+    }
+
+    // now-playing writer task
+    if let Some(path) = configs.app_config.now_playing_file.clone() {
+        tasks.push(tokio::task::spawn({
+            let state = state.clone();
+            async move {
+                client::start_now_playing_writer(state, path.into()).await;
+            }
+        }));
+    }
+
+    tasks
+}
+
+/// Start the application. If the application fails while the UI is running,
+/// the UI is stopped to restore the terminal before the error is reported.
+fn start_app(
+    state: &state::SharedState,
+    config_folder: std::path::PathBuf,
+    config_loader: config::ConfigLoader,
+) -> Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    let mut ui_task = None;
+    let result = runtime.block_on(run_app(state, config_folder, config_loader, &mut ui_task));
+
+    if let (Err(_), Some(ui_task)) = (&result, ui_task) {
+        {
+            let mut ui = state.ui.lock();
+            ui.is_stopped_by_error = true;
+            ui.is_running = false;
+        }
+        if !matches!(runtime.block_on(ui_task), Ok(Ok(()))) {
+            // the UI failed without restoring the terminal
+            ui::restore_terminal()?;
+        }
+    }
+
+    // the blocking tasks, e.g. the terminal event handler waiting for terminal events, never finish
+    runtime.shutdown_background();
+    result
+}
+
+async fn run_app(
+    state: &state::SharedState,
+    config_folder: std::path::PathBuf,
+    config_loader: config::ConfigLoader,
+    ui_task: &mut Option<tokio::task::JoinHandle<Result<()>>>,
+) -> Result<()> {
+    let configs = config::get_config();
+
+    if !state.is_daemon {
+        #[cfg(feature = "image")]
+        {
+            // initialize `viuer` supports for kitty, iterm2, and sixel
+            viuer::get_kitty_support();
+            viuer::is_iterm_supported();
+            #[cfg(feature = "sixel")]
+            viuer::is_sixel_supported();
+        }
+    }
+
+    // client channels
+    let (client_pub, client_sub) = flume::unbounded::<client::ClientRequest>();
+
+    #[cfg(feature = "pulseaudio-backend")]
+    set_pulseaudio_env_vars(&configs);
+
+    #[cfg(unix)]
+    tokio::task::spawn(handle_termination_signals(state.clone()));
+
+    // create a Spotify API client
+    let auth_config = auth::AuthConfig::new(&configs)?;
+
+    // The UI is attached to a remote application or a running application, e.g. `spotify_player daemon`,
+    // if any, sharing the application's Spotify session instead of authenticating again.
+    let application = if state.is_daemon {
+        None
+    } else {
+        cli::RunningApplication::find(&configs)?
+    };
+
+    // With cached credentials, the UI is started before creating a new session, so the user's
+    // library loaded from the file caches is shown right away and refreshed in the background.
+    // Otherwise, the UI is started after the user authenticates the application in the terminal.
+    let is_ui_started =
+        !state.is_daemon && (application.is_some() || auth_config.cache.credentials().is_some());
+    if is_ui_started {
+        *ui_task = Some(start_ui_tasks(state, &client_pub));
+    }
+
+    let client = client::Client::new(auth_config);
+    match application {
+        Some(application) => {
+            tracing::info!("Attaching to the running application...");
+            client
+                .attach(application)
+                .await
+                .context("attach to the running application")?;
+        }
+        None => client
+            .new_session(Some(state), true)
+            .await
+            .context("initialize new Spotify session")?,
+    }
+
+    // initialize Spotify-related stuff
+    init_spotify(&client_pub, &client, state).context("Failed to initialize the Spotify data")?;
+
+    // Spawn application's tasks
+    let mut tasks = Vec::new();
+
+    // the application's services are provided by the application the UI is attached to
+    let client_is_attached = client.is_attached();
+    if !client_is_attached {
+        tasks.extend(start_service_tasks(&client, state));
+    }
+
+    // configuration watcher task (for hot-reloading the configurations)
+    tasks.push(tokio::task::spawn(config_watcher::start_config_watcher(
+        state.clone(),
+        config_folder,
+        config_loader,
+    )));
+
     // client event handler task
     tasks.push(tokio::task::spawn({
         let state = state.clone();
@@ -163,32 +559,22 @@ async fn start_app(state: &state::SharedState) -> Result<()> {
         }
     }));
 
-    if !state.is_daemon {
-        // spawn tasks needed for running the application UI
-
-        // terminal event handler task
-        tokio::task::spawn_blocking({
-            let client_pub = client_pub.clone();
-            let state = state.clone();
-            move || {
-                event::start_event_handler(&state, &client_pub);
-            }
-        });
-
-        // application UI task
-        tokio::task::spawn_blocking({
-            let state = state.clone();
-            move || ui::run(&state)
-        });
+    if !state.is_daemon && !is_ui_started {
+        *ui_task = Some(start_ui_tasks(state, &client_pub));
     }
 
     #[cfg(feature = "media-control")]
-    if configs.app_config.enable_media_control {
+    if configs.app_config.enable_media_control && !client_is_attached {
         // media control task
         tokio::task::spawn_blocking({
             let state = state.clone();
             move || {
-                if let Err(err) = media_control::start_event_watcher(&state, client_pub) {
+                #[cfg(target_os = "linux")]
+                let result = mpris::start_mpris_server(&state, client_pub);
+                #[cfg(not(target_os = "linux"))]
+                let result = media_control::start_event_watcher(&state, client_pub);
+
+                if let Err(err) = result {
                     tracing::error!(
                         "Failed to start the application's media control event watcher: err={err:#?}"
                     );
@@ -217,6 +603,43 @@ async fn start_app(state: &state::SharedState) -> Result<()> {
     Ok(())
 }
 
+/// Start the application's integrated Spotify Connect device, without the UI and CLI sockets
+#[cfg(feature = "streaming")]
+#[tokio::main]
+async fn start_streaming(state: &state::SharedState) -> Result<()> {
+    let configs = config::get_config();
+
+    if !state.is_streaming_enabled() {
+        anyhow::bail!("streaming is disabled by the `enable_streaming` config option");
+    }
+
+    let (client_pub, client_sub) = flume::unbounded::<client::ClientRequest>();
+
+    #[cfg(feature = "pulseaudio-backend")]
+    set_pulseaudio_env_vars(&configs);
+
+    // create a Spotify API client, which also starts the integrated device
+    let auth_config = auth::AuthConfig::new(&configs)?;
+    let client = client::Client::new(auth_config);
+    client
+        .new_session(Some(state), true)
+        .await
+        .context("initialize new Spotify session")?;
+
+    // keep the playback up-to-date to handle player events, e.g. hooks and listening history
+    tokio::task::spawn({
+        let state = state.clone();
+        async move {
+            client::start_player_event_watchers(state, client_pub).await;
+        }
+    });
+
+    // block until the process is stopped
+    client::start_client_handler(state.clone(), client, client_sub).await;
+
+    Ok(())
+}
+
 fn main() -> Result<()> {
     // librespot depends on hyper-rustls which requires a crypto provider to be set up.
     // TODO: see if this can be fixed upstream
@@ -224,25 +647,35 @@ fn main() -> Result<()> {
         .install_default()
         .unwrap();
 
+    // handle dynamic shell completions, if requested by the shell
+    clap_complete::CompleteEnv::with_factory(|| {
+        cli::init_cli().expect("the application's CLI should be initialized")
+    })
+    .complete();
+
     // parse command line arguments
     let args = cli::init_cli()?.get_matches();
 
-    // initialize the application's cache and config folders
-    let config_folder: std::path::PathBuf = args
+    // initialize the application's cache and config folders, which are nested
+    // inside the specified folders if a profile is selected
+    let profile = args.get_one::<String>("profile");
+    let mut config_folder: std::path::PathBuf = args
         .get_one::<String>("config-folder")
         .expect("config-folder should have default value")
         .into();
+    if let Some(profile) = profile {
+        config_folder = config::get_profile_folder_path(&config_folder, profile);
+    }
     if !config_folder.exists() {
         std::fs::create_dir_all(&config_folder)?;
     }
 
-    let cache_folder: std::path::PathBuf = args
+    let mut cache_folder: std::path::PathBuf = args
         .get_one::<String>("cache-folder")
         .expect("cache-folder should have a default value")
         .into();
-    let cache_audio_folder = cache_folder.join("audio");
-    if !cache_audio_folder.exists() {
-        std::fs::create_dir_all(&cache_audio_folder)?;
+    if let Some(profile) = profile {
+        cache_folder = config::get_profile_folder_path(&cache_folder, profile);
     }
     let cache_image_folder = cache_folder.join("image");
     if !cache_image_folder.exists() {
@@ -250,14 +683,19 @@ fn main() -> Result<()> {
     }
 
     // initialize the application configs
-    {
-        let mut configs = config::Configs::new(&config_folder, &cache_folder)?;
-        if let Some(theme) = args.get_one::<String>("theme") {
-            // override the theme config if user specifies a `theme` cli argument
-            theme.clone_into(&mut configs.app_config.theme);
+    config::set_config(load_configs(&args, &config_folder, &cache_folder)?);
+    if let Some(cache_audio_folder) = config::get_config().audio_cache_folder() {
+        if !cache_audio_folder.exists() {
+            std::fs::create_dir_all(&cache_audio_folder)?;
         }
-        config::set_config(configs);
     }
+    // the configurations are reloaded upon changes of the configuration files
+    let config_loader: config::ConfigLoader = Box::new({
+        let args = args.clone();
+        let config_folder = config_folder.clone();
+        let cache_folder = cache_folder.clone();
+        move || load_configs(&args, &config_folder, &cache_folder)
+    });
 
     match args.subcommand() {
         None => {
@@ -291,8 +729,47 @@ fn main() -> Result<()> {
                 is_daemon = false;
             }
 
+            if !is_daemon
+                && config::get_config().app_config.theme_mode == Some(config::ThemeMode::Auto)
+            {
+                // detect the terminal's background before the UI's theme is chosen
+                terminal_background::detect();
+            }
+
             let state = std::sync::Arc::new(state::State::new(is_daemon));
-            start_app(&state)
+            start_app(&state, config_folder, config_loader)
+        }
+        Some(("daemon", _)) => {
+            // initialize the application's log
+            init_logging(&cache_folder).context("failed to initialize application's logging")?;
+
+            // log the application's configurations
+            tracing::info!("Configurations: {:?}", config::get_config());
+
+            // unlike the `--daemon` flag, the process isn't detached from the terminal,
+            // which makes it easy to manage the daemon with a service manager like `systemd`
+            tracing::info!("Starting the application in daemon mode...");
+            let state = std::sync::Arc::new(state::State::new(true));
+            start_app(&state, config_folder, config_loader)
+        }
+        Some(("stream", _)) => {
+            // initialize the application's log
+            init_logging(&cache_folder).context("failed to initialize application's logging")?;
+
+            // log the application's configurations
+            tracing::info!("Configurations: {:?}", config::get_config());
+
+            #[cfg(feature = "streaming")]
+            {
+                tracing::info!("Starting the application's integrated streaming device...");
+                let state = std::sync::Arc::new(state::State::new(true));
+                start_streaming(&state)
+            }
+
+            #[cfg(not(feature = "streaming"))]
+            {
+                anyhow::bail!("the `stream` command requires the `streaming` feature to be enabled")
+            }
         }
         Some((cmd, args)) => cli::handle_cli_subcommand(cmd, args),
     }
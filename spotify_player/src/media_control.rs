@@ -1,6 +1,8 @@
 #![allow(unused_imports)]
 use souvlaki::MediaPosition;
-use souvlaki::{MediaControlEvent, MediaControls, MediaMetadata, MediaPlayback, PlatformConfig};
+use souvlaki::{
+    MediaControlEvent, MediaControls, MediaMetadata, MediaPlayback, PlatformConfig, SeekDirection,
+};
 
 use crate::utils;
 use crate::{
@@ -9,64 +11,142 @@ use crate::{
     utils::map_join,
 };
 
+/// Maximum difference between the playback's actual and expected positions,
+/// above which the position shown by the OS's media widget is updated
+const MAX_POSITION_DRIFT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// The media controls' last updated state
+#[derive(Default)]
+struct ControlState {
+    info: String,
+    is_playing: bool,
+    /// the playback's position at the last update and the time of the update
+    position: Option<(std::time::Duration, std::time::Instant)>,
+}
+
+impl ControlState {
+    /// Check if the playback's position differs from the position
+    /// expected from the last update, e.g. after a seek
+    fn is_position_changed(&self, position: Option<std::time::Duration>) -> bool {
+        match (self.position, position) {
+            (Some((last_position, updated_at)), Some(position)) => {
+                let expected = if self.is_playing {
+                    last_position + updated_at.elapsed()
+                } else {
+                    last_position
+                };
+                expected.abs_diff(position) > MAX_POSITION_DRIFT
+            }
+            (None, None) => false,
+            _ => true,
+        }
+    }
+}
+
+/// Get the cover image's URL of a playable item, preferring the cached cover image
+/// because the OS's media widgets can load it without network requests
+fn get_cover_url(item: &rspotify::model::PlayableItem) -> Option<String> {
+    if let Some(path) = utils::get_cover_image_path(item).filter(|path| path.exists()) {
+        return Some(format!("file://{}", path.display()));
+    }
+    match item {
+        rspotify::model::PlayableItem::Track(track) => utils::get_track_album_image_url(track),
+        rspotify::model::PlayableItem::Episode(episode) => {
+            utils::get_episode_show_image_url(episode)
+        }
+    }
+    .map(ToString::to_string)
+}
+
 fn update_control_metadata(
     state: &SharedState,
     controls: &mut MediaControls,
-    prev_info: &mut String,
+    prev_state: &mut ControlState,
 ) -> Result<(), souvlaki::Error> {
     let player = state.player.read();
 
-    match player.currently_playing() {
-        None => {}
-        Some(item) => {
-            let progress = player
-                .playback_progress()
-                .and_then(|p| Some(MediaPosition(p.to_std().ok()?)));
-
-            if player.playback.as_ref().expect("playback").is_playing {
-                controls.set_playback(MediaPlayback::Playing { progress })?;
-            } else {
-                controls.set_playback(MediaPlayback::Paused { progress })?;
-            }
-
-            match item {
-                rspotify::model::PlayableItem::Track(track) => {
-                    // only update metadata when the track information is changed
-                    let track_info = format!("{}/{}", track.name, track.album.name);
-                    if track_info != *prev_info {
-                        controls.set_metadata(MediaMetadata {
-                            title: Some(&track.name),
-                            album: Some(&track.album.name),
-                            artist: Some(&map_join(&track.artists, |a| &a.name, ", ")),
-                            duration: track.duration.to_std().ok(),
-                            cover_url: utils::get_track_album_image_url(track),
-                        })?;
+    let Some(item) = player.currently_playing() else {
+        return Ok(());
+    };
 
-                        *prev_info = track_info;
-                    }
-                }
-                rspotify::model::PlayableItem::Episode(episode) => {
-                    // only update metadata when the episode information is changed
-                    let episode_info = format!("{}/{}", episode.name, episode.show.name);
-                    if episode_info != *prev_info {
-                        controls.set_metadata(MediaMetadata {
-                            title: Some(&episode.name),
-                            album: Some(&episode.show.name),
-                            artist: Some(&episode.show.publisher),
-                            duration: episode.duration.to_std().ok(),
-                            cover_url: utils::get_episode_show_image_url(episode),
-                        })?;
+    let cover_url = get_cover_url(item);
+    // only update metadata when the item's information is changed
+    let (info, metadata) = match item {
+        rspotify::model::PlayableItem::Track(track) => (
+            format!("{}/{}/{cover_url:?}", track.name, track.album.name),
+            (
+                track.name.as_str(),
+                track.album.name.as_str(),
+                map_join(&track.artists, |a| &a.name, ", "),
+                track.duration,
+            ),
+        ),
+        rspotify::model::PlayableItem::Episode(episode) => (
+            format!("{}/{}/{cover_url:?}", episode.name, episode.show.name),
+            (
+                episode.name.as_str(),
+                episode.show.name.as_str(),
+                episode.show.publisher.clone(),
+                episode.duration,
+            ),
+        ),
+    };
+    let is_info_changed = info != prev_state.info;
+    if is_info_changed {
+        let (title, album, artist, duration) = metadata;
+        controls.set_metadata(MediaMetadata {
+            title: Some(title),
+            album: Some(album),
+            artist: Some(&artist),
+            duration: duration.to_std().ok(),
+            cover_url: cover_url.as_deref(),
+        })?;
+        prev_state.info = info;
+    }
 
-                        *prev_info = episode_info;
-                    }
-                }
-            }
+    // Only update the playback when its state is changed, so that the OS can
+    // smoothly advance the displayed position between updates.
+    let is_playing = player.playback.as_ref().is_some_and(|p| p.is_playing);
+    let position = player.playback_progress().and_then(|p| p.to_std().ok());
+    if is_info_changed
+        || is_playing != prev_state.is_playing
+        || prev_state.is_position_changed(position)
+    {
+        let progress = position.map(MediaPosition);
+        if is_playing {
+            controls.set_playback(MediaPlayback::Playing { progress })?;
+        } else {
+            controls.set_playback(MediaPlayback::Paused { progress })?;
         }
+        prev_state.is_playing = is_playing;
+        prev_state.position = position.map(|p| (p, std::time::Instant::now()));
     }
 
     Ok(())
 }
 
+/// Seek the current playback by an offset in a direction
+fn seek_by(
+    state: &SharedState,
+    client_pub: &flume::Sender<ClientRequest>,
+    direction: SeekDirection,
+    offset: std::time::Duration,
+) {
+    let Some(progress) = state.player.read().playback_progress() else {
+        return;
+    };
+    let Ok(offset) = chrono::Duration::from_std(offset) else {
+        return;
+    };
+    let position = match direction {
+        SeekDirection::Forward => progress + offset,
+        SeekDirection::Backward => std::cmp::max(chrono::Duration::zero(), progress - offset),
+    };
+    client_pub
+        .send(ClientRequest::Player(PlayerRequest::SeekTrack(position)))
+        .unwrap_or_default();
+}
+
 /// Start the application's media control event watcher
 pub fn start_event_watcher(
     state: &SharedState,
@@ -91,6 +171,7 @@ pub fn start_event_watcher(
     };
     let mut controls = MediaControls::new(config)?;
 
+    let event_state = state.clone();
     controls.attach(move |e| {
         tracing::info!("Got a media control event: {e:?}");
         match e {
@@ -99,7 +180,7 @@ pub fn start_event_watcher(
                     .send(ClientRequest::Player(PlayerRequest::Resume))
                     .unwrap_or_default();
             }
-            MediaControlEvent::Pause => {
+            MediaControlEvent::Pause | MediaControlEvent::Stop => {
                 client_pub
                     .send(ClientRequest::Player(PlayerRequest::Pause))
                     .unwrap_or_default();
@@ -116,6 +197,18 @@ pub fn start_event_watcher(
                         .unwrap_or_default();
                 }
             }
+            MediaControlEvent::Seek(direction) => {
+                let offset = crate::config::get_config().app_config.seek_duration_secs;
+                seek_by(
+                    &event_state,
+                    &client_pub,
+                    direction,
+                    std::time::Duration::from_secs(u64::from(offset)),
+                );
+            }
+            MediaControlEvent::SeekBy(direction, offset) => {
+                seek_by(&event_state, &client_pub, direction, offset);
+            }
             MediaControlEvent::Next => {
                 client_pub
                     .send(ClientRequest::Player(PlayerRequest::NextTrack))
@@ -142,9 +235,9 @@ pub fn start_event_watcher(
     // handler provided by the souvlaki library, which only handles an event every 1s.
     // [1]: https://github.com/Sinono3/souvlaki/blob/b4d47bb2797ffdd625c17192df640510466762e1/src/platform/linux/mod.rs#L450
     let refresh_duration = std::time::Duration::from_millis(1000);
-    let mut info = String::new();
+    let mut control_state = ControlState::default();
     loop {
-        update_control_metadata(state, &mut controls, &mut info)?;
+        update_control_metadata(state, &mut controls, &mut control_state)?;
         std::thread::sleep(refresh_duration);
 
         // this must be run repeatedly to ensure that
@@ -0,0 +1,386 @@
+//! Application metrics exposed in the Prometheus text format, e.g. for monitoring
+//! the application running as a daemon
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Write as _,
+    sync::atomic::{AtomicU64, Ordering},
+    sync::LazyLock,
+};
+
+use parking_lot::Mutex;
+
+use crate::http_server::{Request, Response, Routes};
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::default);
+
+/// maximum number of the recent reconnections shown in the diagnostics page
+const MAX_RECENT_RECONNECTS: usize = 5;
+
+#[derive(Default)]
+struct Metrics {
+    tracks_played: AtomicU64,
+    stream_reconnects: AtomicU64,
+    session_reconnects: AtomicU64,
+    /// statistics of the handled client requests, keyed by the request's type
+    client_requests: Mutex<BTreeMap<&'static str, RequestStats>>,
+    /// statistics of the sent Spotify API requests, keyed by the request's method and endpoint
+    api_requests: Mutex<BTreeMap<(&'static str, String), RequestStats>>,
+    /// numbers of hits and misses of the application's caches, keyed by the cache's name
+    cache_lookups: Mutex<BTreeMap<&'static str, (u64, u64)>>,
+    /// times and kinds of the recent reconnections, the latest last
+    recent_reconnects: Mutex<VecDeque<(chrono::DateTime<chrono::Local>, &'static str)>>,
+    /// number of the audio packets output by the integrated device
+    audio_packets: AtomicU64,
+    /// number of the times the integrated device's output ran out of audio
+    audio_underruns: AtomicU64,
+    /// duration (in milliseconds) of the audio buffered ahead of the integrated device's output
+    audio_buffered_ms: AtomicU64,
+}
+
+#[derive(Default, Clone)]
+struct RequestStats {
+    count: u64,
+    errors: u64,
+    duration_seconds: f64,
+}
+
+pub fn record_track_played() {
+    METRICS.tracks_played.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_stream_reconnect() {
+    METRICS.stream_reconnects.fetch_add(1, Ordering::Relaxed);
+    record_recent_reconnect("stream");
+}
+
+pub fn record_session_reconnect() {
+    METRICS.session_reconnects.fetch_add(1, Ordering::Relaxed);
+    record_recent_reconnect("session");
+}
+
+fn record_recent_reconnect(kind: &'static str) {
+    let mut reconnects = METRICS.recent_reconnects.lock();
+    if reconnects.len() == MAX_RECENT_RECONNECTS {
+        reconnects.pop_front();
+    }
+    reconnects.push_back((chrono::Local::now(), kind));
+}
+
+/// Records an audio packet output by the integrated device along with the duration
+/// of the audio buffered ahead of the output
+#[cfg(feature = "streaming")]
+pub fn record_audio_packet(buffered: std::time::Duration) {
+    METRICS.audio_packets.fetch_add(1, Ordering::Relaxed);
+    METRICS
+        .audio_buffered_ms
+        .store(buffered.as_millis() as u64, Ordering::Relaxed);
+}
+
+#[cfg(feature = "streaming")]
+pub fn record_audio_underrun() {
+    METRICS.audio_underruns.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Records that the integrated device's output is stopped, so no audio is buffered
+#[cfg(feature = "streaming")]
+pub fn record_audio_stopped() {
+    METRICS.audio_buffered_ms.store(0, Ordering::Relaxed);
+}
+
+impl RequestStats {
+    fn record(&mut self, duration: std::time::Duration, is_error: bool) {
+        self.count += 1;
+        self.duration_seconds += duration.as_secs_f64();
+        if is_error {
+            self.errors += 1;
+        }
+    }
+}
+
+/// Records a handled client request of a given type
+pub fn record_client_request(
+    request_type: &'static str,
+    duration: std::time::Duration,
+    is_error: bool,
+) {
+    METRICS
+        .client_requests
+        .lock()
+        .entry(request_type)
+        .or_default()
+        .record(duration, is_error);
+}
+
+/// Sends a Spotify API request, recording the request's statistics
+pub async fn record_api_request<T, E>(
+    method: &'static str,
+    url: &str,
+    request: impl std::future::Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let timer = std::time::Instant::now();
+    let result = request.await;
+    METRICS
+        .api_requests
+        .lock()
+        .entry((method, api_endpoint(url)))
+        .or_default()
+        .record(timer.elapsed(), result.is_err());
+    result
+}
+
+/// Gets the endpoint of a Spotify API request's URL, in which the IDs are replaced with `{id}`
+/// to not record every requested item separately
+fn api_endpoint(url: &str) -> String {
+    let path = url.split('?').next().unwrap_or_default();
+    let path = path.split_once("/v1/").map_or(path, |(_, path)| path);
+
+    let mut segments = Vec::new();
+    for segment in path.split('/') {
+        let is_id = segments.last() == Some(&"users")
+            || (segment.len() == 22 && segment.chars().all(|c| c.is_ascii_alphanumeric()));
+        segments.push(if is_id { "{id}" } else { segment });
+    }
+    segments.join("/")
+}
+
+/// Records a lookup of a cache, returning whether the lookup is a hit
+pub fn record_cache_lookup(cache: &'static str, hit: bool) -> bool {
+    let mut lookups = METRICS.cache_lookups.lock();
+    let (hits, misses) = lookups.entry(cache).or_default();
+    if hit {
+        *hits += 1;
+    } else {
+        *misses += 1;
+    }
+    hit
+}
+
+/// Gets the statistics of the integrated device's audio output, shown in the diagnostics page
+#[cfg(feature = "streaming")]
+pub fn audio_output_diagnostics() -> Vec<(String, String)> {
+    vec![
+        (
+            "Buffered audio".to_string(),
+            format!("{} ms", METRICS.audio_buffered_ms.load(Ordering::Relaxed)),
+        ),
+        (
+            "Output audio packets".to_string(),
+            METRICS.audio_packets.load(Ordering::Relaxed).to_string(),
+        ),
+        (
+            "Audio underruns".to_string(),
+            METRICS.audio_underruns.load(Ordering::Relaxed).to_string(),
+        ),
+    ]
+}
+
+/// Gets the hit rates of the application's caches and the recent reconnections,
+/// shown in the diagnostics page
+pub fn diagnostics() -> Vec<(String, String)> {
+    let mut entries = vec![];
+
+    for (cache, (hits, misses)) in METRICS.cache_lookups.lock().iter() {
+        let total = hits + misses;
+        if total > 0 {
+            entries.push((
+                format!("Cache hit rate ({cache})"),
+                format!(
+                    "{:.1}% ({hits}/{total})",
+                    *hits as f64 * 100.0 / total as f64
+                ),
+            ));
+        }
+    }
+
+    entries.extend([
+        (
+            "Stream reconnects".to_string(),
+            METRICS
+                .stream_reconnects
+                .load(Ordering::Relaxed)
+                .to_string(),
+        ),
+        (
+            "Session reconnects".to_string(),
+            METRICS
+                .session_reconnects
+                .load(Ordering::Relaxed)
+                .to_string(),
+        ),
+    ]);
+    for (time, kind) in METRICS.recent_reconnects.lock().iter().rev() {
+        entries.push((
+            "Recent reconnect".to_string(),
+            format!("{} ({kind})", time.format("%Y-%m-%d %H:%M:%S")),
+        ));
+    }
+
+    entries
+}
+
+/// Renders the number of requests, failed requests and time spent on them,
+/// given the requests' labels and statistics
+fn render_request_stats(
+    s: &mut String,
+    prefix: &str,
+    requests: &str,
+    stats: &[(String, RequestStats)],
+) {
+    let counters: [(String, String, fn(&RequestStats) -> String); 3] = [
+        (
+            format!("{prefix}s_total"),
+            format!("Number of {requests}"),
+            |stats| stats.count.to_string(),
+        ),
+        (
+            format!("{prefix}_errors_total"),
+            format!("Number of failed {requests}"),
+            |stats| stats.errors.to_string(),
+        ),
+        (
+            format!("{prefix}_duration_seconds_total"),
+            format!("Total time spent on {requests}"),
+            |stats| stats.duration_seconds.to_string(),
+        ),
+    ];
+    for (name, help, value) in counters {
+        writeln!(s, "# HELP {name} {help}\n# TYPE {name} counter").unwrap();
+        for (labels, stats) in stats {
+            writeln!(s, "{name}{{{labels}}} {}", value(stats)).unwrap();
+        }
+    }
+}
+
+/// Renders the metrics in the Prometheus text format
+fn render() -> String {
+    let mut s = String::new();
+
+    for (name, help, value) in [
+        (
+            "spotify_player_tracks_played_total",
+            "Number of played tracks and episodes",
+            &METRICS.tracks_played,
+        ),
+        (
+            "spotify_player_stream_reconnects_total",
+            "Number of reconnections of the integrated streaming device",
+            &METRICS.stream_reconnects,
+        ),
+        (
+            "spotify_player_session_reconnects_total",
+            "Number of reconnections of the Spotify session",
+            &METRICS.session_reconnects,
+        ),
+        (
+            "spotify_player_audio_underruns_total",
+            "Number of the times the integrated streaming device's output ran out of audio",
+            &METRICS.audio_underruns,
+        ),
+    ] {
+        let value = value.load(Ordering::Relaxed);
+        writeln!(
+            s,
+            "# HELP {name} {help}\n# TYPE {name} counter\n{name} {value}"
+        )
+        .unwrap();
+    }
+
+    let requests = METRICS
+        .client_requests
+        .lock()
+        .iter()
+        .map(|(name, stats)| (format!("request=\"{name}\""), stats.clone()))
+        .collect::<Vec<_>>();
+    render_request_stats(
+        &mut s,
+        "spotify_player_client_request",
+        "handled client requests",
+        &requests,
+    );
+
+    let requests = METRICS
+        .api_requests
+        .lock()
+        .iter()
+        .map(|((method, endpoint), stats)| {
+            (
+                format!("method=\"{method}\",endpoint=\"{endpoint}\""),
+                stats.clone(),
+            )
+        })
+        .collect::<Vec<_>>();
+    render_request_stats(
+        &mut s,
+        "spotify_player_api_request",
+        "sent Spotify API requests",
+        &requests,
+    );
+
+    let lookups = METRICS.cache_lookups.lock();
+    writeln!(
+        s,
+        "# HELP spotify_player_cache_lookups_total Number of lookups of the application's caches\n# TYPE spotify_player_cache_lookups_total counter"
+    )
+    .unwrap();
+    for (cache, (hits, misses)) in lookups.iter() {
+        writeln!(
+            s,
+            "spotify_player_cache_lookups_total{{cache=\"{cache}\",result=\"hit\"}} {hits}\nspotify_player_cache_lookups_total{{cache=\"{cache}\",result=\"miss\"}} {misses}"
+        )
+        .unwrap();
+    }
+
+    s
+}
+
+/// The metrics' routes mounted on the application's HTTP server, serving the metrics
+/// at the `/metrics` path
+pub struct MetricsRoutes;
+
+#[async_trait::async_trait]
+impl Routes for MetricsRoutes {
+    async fn handle(&self, request: &Request) -> Option<Response> {
+        (request.path == "/metrics").then(|| Response::ok("text/plain; version=0.0.4", render()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_endpoint_of_urls() {
+        assert_eq!(
+            api_endpoint(
+                "https://api.spotify.com/v1/me/player/currently-playing?market=from_token"
+            ),
+            "me/player/currently-playing"
+        );
+        assert_eq!(api_endpoint("https://api.spotify.com/v1/search"), "search");
+    }
+
+    #[test]
+    fn api_endpoint_replaces_ids() {
+        assert_eq!(
+            api_endpoint(
+                "https://api.spotify.com/v1/playlists/37i9dQZF1DXcBWIGoYBM5M/tracks?offset=50"
+            ),
+            "playlists/{id}/tracks"
+        );
+        assert_eq!(
+            api_endpoint("https://api.spotify.com/v1/artists/0TnOYISbd1XYRBk9myaseg/top-tracks"),
+            "artists/{id}/top-tracks"
+        );
+        // the users' IDs have various lengths and characters
+        assert_eq!(
+            api_endpoint("https://api.spotify.com/v1/users/john.doe-42/playlists"),
+            "users/{id}/playlists"
+        );
+        // segments of other lengths or with other characters aren't IDs
+        assert_eq!(
+            api_endpoint("https://api.spotify.com/v1/browse/featured-playlists"),
+            "browse/featured-playlists"
+        );
+    }
+}
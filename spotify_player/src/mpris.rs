@@ -0,0 +1,668 @@
+//! MPRIS D-Bus service implementing the `org.mpris.MediaPlayer2`, `org.mpris.MediaPlayer2.Player`,
+//! `org.mpris.MediaPlayer2.TrackList` and `org.mpris.MediaPlayer2.Playlists` interfaces,
+//! which allows external tools like `playerctl` to control the playback, browse the queue
+//! and start the user's playlists.
+//!
+//! See: https://specifications.freedesktop.org/mpris-spec/latest/
+
+use dbus::arg::{PropMap, RefArg, Variant};
+use dbus::blocking::stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged;
+use dbus::blocking::Connection;
+use dbus::channel::{MatchingReceiver, Sender};
+use dbus::message::{MatchRule, SignalArgs};
+use dbus::{Message, Path};
+use dbus_crossroads::{Crossroads, IfaceBuilder};
+use rspotify::model::{PlayableItem, RepeatState};
+use tokio::sync::broadcast::error::TryRecvError;
+
+use crate::{
+    client::{ClientRequest, PlayerRequest},
+    state::{
+        AlbumId, ArtistId, ContextId, EpisodeId, Id, PlayableId, Playback, PlaybackEvent,
+        PlaylistFolderItem, PlaylistId, SharedState, TrackId,
+    },
+    utils,
+};
+
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.spotify_player";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const ROOT_INTERFACE: &str = "org.mpris.MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+const TRACK_LIST_INTERFACE: &str = "org.mpris.MediaPlayer2.TrackList";
+const PLAYLISTS_INTERFACE: &str = "org.mpris.MediaPlayer2.Playlists";
+const NO_TRACK_PATH: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+/// `Player` interface's properties indicating whether the playback can be controlled,
+/// which depend on whether there is a playback
+const CAN_PROPERTIES: [&str; 5] = [
+    "CanGoNext",
+    "CanGoPrevious",
+    "CanPlay",
+    "CanPause",
+    "CanSeek",
+];
+
+/// Data of the MPRIS D-Bus object
+struct MprisData {
+    state: SharedState,
+    client_pub: flume::Sender<ClientRequest>,
+}
+
+impl MprisData {
+    fn send(&self, request: PlayerRequest) {
+        self.client_pub
+            .send(ClientRequest::Player(request))
+            .unwrap_or_default();
+    }
+}
+
+/// MPRIS properties used to detect changes and emit the corresponding D-Bus signals
+#[derive(Default, PartialEq)]
+struct Properties {
+    playback_status: &'static str,
+    has_playback: bool,
+    loop_status: &'static str,
+    shuffle: bool,
+    volume: f64,
+    tracks: Vec<Path<'static>>,
+    playlist_count: u32,
+    active_playlist: Option<Path<'static>>,
+}
+
+impl Properties {
+    fn new(state: &SharedState) -> Self {
+        let playback = state.player.read().current_playback();
+        Self {
+            playback_status: playback_status(state),
+            has_playback: playback.is_some(),
+            loop_status: loop_status(state),
+            shuffle: playback.as_ref().is_some_and(|p| p.shuffle_state),
+            volume: volume(state),
+            tracks: track_list(state)
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect(),
+            playlist_count: playlists(state).len() as u32,
+            active_playlist: active_playlist(state).map(|(path, _, _)| path),
+        }
+    }
+}
+
+/// Start the application's MPRIS D-Bus service
+pub fn start_mpris_server(
+    state: &SharedState,
+    client_pub: flume::Sender<ClientRequest>,
+) -> Result<(), dbus::Error> {
+    tracing::info!("Initializing application's MPRIS D-Bus service...");
+
+    let conn = Connection::new_session()?;
+    conn.request_name(BUS_NAME, false, true, false)?;
+
+    let mut cr = Crossroads::new();
+    let interfaces = [
+        cr.register(ROOT_INTERFACE, register_root_interface),
+        cr.register(PLAYER_INTERFACE, register_player_interface),
+        cr.register(TRACK_LIST_INTERFACE, register_track_list_interface),
+        cr.register(PLAYLISTS_INTERFACE, register_playlists_interface),
+    ];
+    cr.insert(
+        OBJECT_PATH,
+        &interfaces,
+        MprisData {
+            state: state.clone(),
+            client_pub,
+        },
+    );
+    conn.start_receive(
+        MatchRule::new_method_call(),
+        Box::new(move |msg, conn| {
+            cr.handle_message(msg, conn).unwrap_or_default();
+            true
+        }),
+    );
+
+    let mut playback_events = state.playback_events.subscribe();
+    let mut properties = Properties::new(state);
+    loop {
+        conn.process(std::time::Duration::from_millis(1000))?;
+
+        let mut is_changed = false;
+        loop {
+            match playback_events.try_recv() {
+                Ok(PlaybackEvent::Seek) => {
+                    let position = state.player.read().playback_progress().unwrap_or_default();
+                    if let Ok(msg) = Message::new_signal(OBJECT_PATH, PLAYER_INTERFACE, "Seeked") {
+                        conn.send(msg.append1(position.num_microseconds().unwrap_or_default()))
+                            .unwrap_or_default();
+                    }
+                }
+                Ok(PlaybackEvent::LikedTracksChange) => {}
+                Ok(_) | Err(TryRecvError::Lagged(_)) => is_changed = true,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Closed) => return Ok(()),
+            }
+        }
+
+        if is_changed {
+            let new_properties = Properties::new(state);
+            if new_properties != properties {
+                emit_changes(&conn, state, &properties, &new_properties);
+                properties = new_properties;
+            }
+        }
+    }
+}
+
+/// Emit D-Bus signals for changed MPRIS properties
+fn emit_changes(conn: &Connection, state: &SharedState, old: &Properties, new: &Properties) {
+    let mut changed = PropMap::new();
+    if old.playback_status != new.playback_status {
+        insert(
+            &mut changed,
+            "PlaybackStatus",
+            new.playback_status.to_string(),
+        );
+    }
+    if old.has_playback != new.has_playback {
+        for name in CAN_PROPERTIES {
+            insert(&mut changed, name, new.has_playback);
+        }
+    }
+    if old.loop_status != new.loop_status {
+        insert(&mut changed, "LoopStatus", new.loop_status.to_string());
+    }
+    if old.shuffle != new.shuffle {
+        insert(&mut changed, "Shuffle", new.shuffle);
+    }
+    if old.volume.to_bits() != new.volume.to_bits() {
+        insert(&mut changed, "Volume", new.volume);
+    }
+    if old.tracks.first() != new.tracks.first() {
+        insert(&mut changed, "Metadata", current_metadata(state));
+    }
+    emit_properties_changed(conn, PLAYER_INTERFACE, changed, Vec::new());
+
+    if old.tracks != new.tracks {
+        emit_properties_changed(
+            conn,
+            TRACK_LIST_INTERFACE,
+            PropMap::new(),
+            vec!["Tracks".to_string()],
+        );
+        let current_track = new
+            .tracks
+            .first()
+            .cloned()
+            .unwrap_or_else(|| Path::from(NO_TRACK_PATH));
+        if let Ok(msg) = Message::new_signal(OBJECT_PATH, TRACK_LIST_INTERFACE, "TrackListReplaced")
+        {
+            conn.send(msg.append2(new.tracks.clone(), current_track))
+                .unwrap_or_default();
+        }
+    }
+
+    let mut changed = PropMap::new();
+    if old.playlist_count != new.playlist_count {
+        insert(&mut changed, "PlaylistCount", new.playlist_count);
+    }
+    if old.active_playlist != new.active_playlist {
+        insert(&mut changed, "ActivePlaylist", active_playlist_value(state));
+    }
+    emit_properties_changed(conn, PLAYLISTS_INTERFACE, changed, Vec::new());
+}
+
+fn emit_properties_changed(
+    conn: &Connection,
+    interface: &str,
+    changed: PropMap,
+    invalidated: Vec<String>,
+) {
+    if changed.is_empty() && invalidated.is_empty() {
+        return;
+    }
+    let signal = PropertiesPropertiesChanged {
+        interface_name: interface.to_string(),
+        changed_properties: changed,
+        invalidated_properties: invalidated,
+    };
+    conn.send(signal.to_emit_message(&Path::from(OBJECT_PATH)))
+        .unwrap_or_default();
+}
+
+fn register_root_interface(b: &mut IfaceBuilder<MprisData>) {
+    b.method("Raise", (), (), |_, _, (): ()| Ok(()));
+    b.method("Quit", (), (), |_, _, (): ()| Ok(()));
+    b.property("CanQuit").get(|_, _| Ok(false));
+    b.property("CanRaise").get(|_, _| Ok(false));
+    b.property("HasTrackList").get(|_, _| Ok(true));
+    b.property("Identity")
+        .get(|_, _| Ok("Spotify Player".to_string()));
+    b.property("SupportedUriSchemes")
+        .get(|_, _| Ok(vec!["spotify".to_string()]));
+    b.property("SupportedMimeTypes")
+        .get(|_, _| Ok(Vec::<String>::new()));
+}
+
+fn register_player_interface(b: &mut IfaceBuilder<MprisData>) {
+    b.method("Next", (), (), |_, data, (): ()| {
+        data.send(PlayerRequest::NextTrack);
+        Ok(())
+    });
+    b.method("Previous", (), (), |_, data, (): ()| {
+        data.send(PlayerRequest::PreviousTrack);
+        Ok(())
+    });
+    b.method("Pause", (), (), |_, data, (): ()| {
+        data.send(PlayerRequest::Pause);
+        Ok(())
+    });
+    b.method("PlayPause", (), (), |_, data, (): ()| {
+        data.send(PlayerRequest::ResumePause);
+        Ok(())
+    });
+    // Spotify doesn't support stopping a playback, so the playback is paused instead
+    b.method("Stop", (), (), |_, data, (): ()| {
+        data.send(PlayerRequest::Pause);
+        Ok(())
+    });
+    b.method("Play", (), (), |_, data, (): ()| {
+        data.send(PlayerRequest::Resume);
+        Ok(())
+    });
+    b.method("Seek", ("Offset",), (), |_, data, (offset,): (i64,)| {
+        let progress = data.state.player.read().playback_progress();
+        if let Some(progress) = progress {
+            let position = progress + chrono::Duration::microseconds(offset);
+            data.send(PlayerRequest::SeekTrack(std::cmp::max(
+                position,
+                chrono::Duration::zero(),
+            )));
+        }
+        Ok(())
+    });
+    b.method(
+        "SetPosition",
+        ("TrackId", "Position"),
+        (),
+        |_, data, (track_id, position): (Path<'static>, i64)| {
+            // the position is ignored if the track isn't the playing track, as per the MPRIS spec
+            let is_current_track = track_list(&data.state)
+                .first()
+                .is_some_and(|(path, _)| *path == track_id);
+            if is_current_track && position >= 0 {
+                data.send(PlayerRequest::SeekTrack(chrono::Duration::microseconds(
+                    position,
+                )));
+            }
+            Ok(())
+        },
+    );
+    b.method("OpenUri", ("Uri",), (), |_, data, (uri,): (String,)| {
+        if let Some(playback) = uri_to_playback(&uri) {
+            data.send(PlayerRequest::StartPlayback(playback, None));
+        }
+        Ok(())
+    });
+    b.signal::<(i64,), _>("Seeked", ("Position",));
+
+    b.property("PlaybackStatus")
+        .get(|_, data| Ok(playback_status(&data.state).to_string()));
+    b.property("LoopStatus")
+        .get(|_, data| Ok(loop_status(&data.state).to_string()))
+        .set(|_, data, status: String| {
+            let repeat_state = match status.as_str() {
+                "Track" => RepeatState::Track,
+                "Playlist" => RepeatState::Context,
+                "None" => RepeatState::Off,
+                _ => return Err(dbus::MethodErr::invalid_arg(&status)),
+            };
+            if status != loop_status(&data.state) {
+                data.send(PlayerRequest::SetRepeat(repeat_state));
+            }
+            Ok(None)
+        });
+    // Spotify doesn't support changing the playback rate, so the rate is always `1.0`.
+    // Setting the rate to `0.0` pauses the playback, as per the MPRIS spec.
+    b.property("Rate")
+        .get(|_, _| Ok(1.0))
+        .set(|_, data, rate: f64| {
+            if rate <= 0.0 {
+                data.send(PlayerRequest::Pause);
+            }
+            Ok(None)
+        });
+    b.property("MinimumRate").get(|_, _| Ok(1.0));
+    b.property("MaximumRate").get(|_, _| Ok(1.0));
+    b.property("Shuffle")
+        .get(|_, data| {
+            let playback = data.state.player.read().current_playback();
+            Ok(playback.is_some_and(|p| p.shuffle_state))
+        })
+        .set(|_, data, shuffle: bool| {
+            let playback = data.state.player.read().current_playback();
+            if playback.is_some_and(|p| p.shuffle_state != shuffle) {
+                data.send(PlayerRequest::SetShuffle(shuffle));
+            }
+            Ok(None)
+        });
+    b.property("Metadata")
+        .get(|_, data| Ok(current_metadata(&data.state)));
+    b.property("Volume")
+        .get(|_, data| Ok(volume(&data.state)))
+        .set(|_, data, volume: f64| {
+            data.send(PlayerRequest::Volume(
+                (volume.clamp(0.0, 1.0) * 100.0).round() as u8,
+            ));
+            Ok(None)
+        });
+    b.property("Position").emits_changed_false().get(|_, data| {
+        let progress = data.state.player.read().playback_progress();
+        Ok(progress
+            .and_then(|p| p.num_microseconds())
+            .unwrap_or_default())
+    });
+    for name in CAN_PROPERTIES {
+        b.property(name)
+            .get(|_, data| Ok(data.state.player.read().playback.is_some()));
+    }
+    b.property("CanControl").get(|_, _| Ok(true));
+}
+
+fn register_track_list_interface(b: &mut IfaceBuilder<MprisData>) {
+    b.method(
+        "GetTracksMetadata",
+        ("TrackIds",),
+        ("Metadata",),
+        |_, data, (track_ids,): (Vec<Path<'static>>,)| {
+            let tracks = track_list(&data.state);
+            let metadata = track_ids
+                .iter()
+                .filter_map(|id| {
+                    tracks
+                        .iter()
+                        .find(|(path, _)| path == id)
+                        .map(|(path, item)| item_metadata(path.clone(), item))
+                })
+                .collect::<Vec<_>>();
+            Ok((metadata,))
+        },
+    );
+    // Spotify doesn't support editing the queue, so `CanEditTracks` is `false`
+    // and the below methods have no effect, as per the MPRIS spec
+    b.method(
+        "AddTrack",
+        ("Uri", "AfterTrack", "SetAsCurrent"),
+        (),
+        |_, _, _: (String, Path<'static>, bool)| Ok(()),
+    );
+    b.method(
+        "RemoveTrack",
+        ("TrackId",),
+        (),
+        |_, _, _: (Path<'static>,)| Ok(()),
+    );
+    b.method(
+        "GoTo",
+        ("TrackId",),
+        (),
+        |_, data, (track_id,): (Path<'static>,)| {
+            // Spotify doesn't support skipping to a queue's item,
+            // so a new playback of the item is started instead
+            let tracks = track_list(&data.state);
+            let id = tracks
+                .iter()
+                .skip(1)
+                .find(|(path, _)| *path == track_id)
+                .and_then(|(_, item)| item.id())
+                .map(|id| id.into_static());
+            if let Some(id) = id {
+                data.send(PlayerRequest::StartPlayback(
+                    Playback::URIs(vec![id], None),
+                    None,
+                ));
+            }
+            Ok(())
+        },
+    );
+    b.signal::<(Vec<Path<'static>>, Path<'static>), _>(
+        "TrackListReplaced",
+        ("Tracks", "CurrentTrack"),
+    );
+
+    b.property("Tracks")
+        .emits_changed_invalidation()
+        .get(|_, data| {
+            Ok(track_list(&data.state)
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect::<Vec<_>>())
+        });
+    b.property("CanEditTracks").get(|_, _| Ok(false));
+}
+
+fn register_playlists_interface(b: &mut IfaceBuilder<MprisData>) {
+    b.method(
+        "ActivatePlaylist",
+        ("PlaylistId",),
+        (),
+        |_, data, (playlist_id,): (Path<'static>,)| {
+            let id = playlists(&data.state)
+                .into_iter()
+                .find(|(path, _, _)| *path == playlist_id)
+                .and_then(|(path, _, _)| {
+                    let id = path.rsplit('/').next()?;
+                    PlaylistId::from_id(id).ok().map(PlaylistId::into_static)
+                });
+            if let Some(id) = id {
+                data.send(PlayerRequest::StartPlayback(
+                    Playback::Context(ContextId::Playlist(id), None),
+                    None,
+                ));
+            }
+            Ok(())
+        },
+    );
+    b.method(
+        "GetPlaylists",
+        ("Index", "MaxCount", "Order", "ReverseOrder"),
+        ("Playlists",),
+        |_, data, (index, max_count, order, reverse_order): (u32, u32, String, bool)| {
+            let mut playlists = playlists(&data.state);
+            // the user's playlists are in the user-defined order by default
+            if order == "Alphabetical" {
+                playlists.sort_by(|x, y| x.1.to_lowercase().cmp(&y.1.to_lowercase()));
+            }
+            if reverse_order {
+                playlists.reverse();
+            }
+            let playlists = playlists
+                .into_iter()
+                .skip(index as usize)
+                .take(max_count as usize)
+                .collect::<Vec<_>>();
+            Ok((playlists,))
+        },
+    );
+
+    b.property("PlaylistCount")
+        .get(|_, data| Ok(playlists(&data.state).len() as u32));
+    b.property("Orderings")
+        .get(|_, _| Ok(vec!["Alphabetical".to_string(), "UserDefined".to_string()]));
+    b.property("ActivePlaylist")
+        .get(|_, data| Ok(active_playlist_value(&data.state)));
+}
+
+fn insert(map: &mut PropMap, key: &str, value: impl RefArg + 'static) {
+    map.insert(key.to_string(), Variant(Box::new(value)));
+}
+
+fn playback_status(state: &SharedState) -> &'static str {
+    match state.player.read().current_playback() {
+        None => "Stopped",
+        Some(playback) if playback.is_playing => "Playing",
+        Some(_) => "Paused",
+    }
+}
+
+fn loop_status(state: &SharedState) -> &'static str {
+    match state
+        .player
+        .read()
+        .current_playback()
+        .map(|p| p.repeat_state)
+    {
+        Some(RepeatState::Track) => "Track",
+        Some(RepeatState::Context) => "Playlist",
+        Some(RepeatState::Off) | None => "None",
+    }
+}
+
+fn volume(state: &SharedState) -> f64 {
+    state
+        .player
+        .read()
+        .current_playback()
+        .and_then(|p| p.device.volume_percent)
+        .map_or(0.0, |v| f64::from(v) / 100.0)
+}
+
+/// Get the track list, i.e. the playing item followed by the queue's items,
+/// along with the D-Bus object path of each item
+fn track_list(state: &SharedState) -> Vec<(Path<'static>, PlayableItem)> {
+    let player = state.player.read();
+    player
+        .currently_playing()
+        .into_iter()
+        .chain(player.queue.iter().flat_map(|q| q.queue.iter()))
+        .enumerate()
+        .map(|(i, item)| {
+            let id = item.id().map(|id| id.id().to_string()).unwrap_or_default();
+            // an item can be queued multiple times, so the item's index is included
+            // in the path to ensure that the path is unique within the track list
+            (
+                Path::from(format!("/spotify_player/track/{i}_{id}")),
+                item.clone(),
+            )
+        })
+        .collect()
+}
+
+/// Get the metadata of the playing item
+fn current_metadata(state: &SharedState) -> PropMap {
+    match track_list(state).into_iter().next() {
+        Some((path, item)) => item_metadata(path, &item),
+        None => {
+            let mut metadata = PropMap::new();
+            insert(&mut metadata, "mpris:trackid", Path::from(NO_TRACK_PATH));
+            metadata
+        }
+    }
+}
+
+fn item_metadata(path: Path<'static>, item: &PlayableItem) -> PropMap {
+    let mut metadata = PropMap::new();
+    insert(&mut metadata, "mpris:trackid", path);
+    let (duration, external_urls) = match item {
+        PlayableItem::Track(track) => {
+            insert(&mut metadata, "xesam:title", track.name.clone());
+            insert(&mut metadata, "xesam:album", track.album.name.clone());
+            insert(
+                &mut metadata,
+                "xesam:artist",
+                track
+                    .artists
+                    .iter()
+                    .map(|a| a.name.clone())
+                    .collect::<Vec<_>>(),
+            );
+            if let Some(url) = utils::get_track_album_image_url(track) {
+                insert(&mut metadata, "mpris:artUrl", url.to_string());
+            }
+            (track.duration, &track.external_urls)
+        }
+        PlayableItem::Episode(episode) => {
+            insert(&mut metadata, "xesam:title", episode.name.clone());
+            insert(&mut metadata, "xesam:album", episode.show.name.clone());
+            insert(
+                &mut metadata,
+                "xesam:artist",
+                vec![episode.show.publisher.clone()],
+            );
+            if let Some(url) = utils::get_episode_show_image_url(episode) {
+                insert(&mut metadata, "mpris:artUrl", url.to_string());
+            }
+            (episode.duration, &episode.external_urls)
+        }
+    };
+    insert(
+        &mut metadata,
+        "mpris:length",
+        duration.num_microseconds().unwrap_or_default(),
+    );
+    if let Some(url) = external_urls.get("spotify") {
+        insert(&mut metadata, "xesam:url", url.clone());
+    }
+    metadata
+}
+
+/// Get the user's playlists, each of which is represented by its D-Bus object path, name and icon
+fn playlists(state: &SharedState) -> Vec<(Path<'static>, String, String)> {
+    state
+        .data
+        .read()
+        .user_data
+        .playlists
+        .iter()
+        .filter_map(|item| match item {
+            PlaylistFolderItem::Playlist(p) => Some((
+                Path::from(format!("/spotify_player/playlist/{}", p.id.id())),
+                p.name.clone(),
+                String::new(),
+            )),
+            PlaylistFolderItem::Folder(_) => None,
+        })
+        .collect()
+}
+
+/// Get the user's playlist being played, if any
+fn active_playlist(state: &SharedState) -> Option<(Path<'static>, String, String)> {
+    let Some(ContextId::Playlist(id)) = state.player.read().playing_context_id() else {
+        return None;
+    };
+    let path = Path::from(format!("/spotify_player/playlist/{}", id.id()));
+    playlists(state).into_iter().find(|(p, _, _)| *p == path)
+}
+
+/// Get the value of the `ActivePlaylist` property, whose first field indicates
+/// whether there is an active playlist
+fn active_playlist_value(state: &SharedState) -> (bool, (Path<'static>, String, String)) {
+    match active_playlist(state) {
+        Some(playlist) => (true, playlist),
+        None => (false, (Path::from("/"), String::new(), String::new())),
+    }
+}
+
+/// Convert a Spotify URI to a playback of the corresponding item
+fn uri_to_playback(uri: &str) -> Option<Playback> {
+    if let Ok(id) = TrackId::from_uri(uri) {
+        return Some(Playback::URIs(
+            vec![PlayableId::Track(id.into_static())],
+            None,
+        ));
+    }
+    if let Ok(id) = EpisodeId::from_uri(uri) {
+        return Some(Playback::URIs(
+            vec![PlayableId::Episode(id.into_static())],
+            None,
+        ));
+    }
+    let context_id = if let Ok(id) = PlaylistId::from_uri(uri) {
+        ContextId::Playlist(id.into_static())
+    } else if let Ok(id) = AlbumId::from_uri(uri) {
+        ContextId::Album(id.into_static())
+    } else if let Ok(id) = ArtistId::from_uri(uri) {
+        ContextId::Artist(id.into_static())
+    } else {
+        return None;
+    };
+    Some(Playback::Context(context_id, None))
+}
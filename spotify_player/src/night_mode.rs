@@ -0,0 +1,98 @@
+//! A night mode compressing the dynamic range of the integrated device's audio,
+//! which tames loud peaks and lifts quiet passages when listening at low volumes
+
+use librespot_playback::{
+    audio_backend::{Sink, SinkResult},
+    convert::Converter,
+    decoder::AudioPacket,
+    mixer::VolumeGetter,
+    NUM_CHANNELS, SAMPLE_RATE,
+};
+
+/// level (in dB) above which the audio is compressed
+const THRESHOLD: f64 = -20.0;
+/// ratio of the compression above the threshold
+const RATIO: f64 = 4.0;
+/// gain (in dB) applied after the compression to lift quiet passages
+const MAKEUP_GAIN: f64 = 9.0;
+/// attack time (in seconds) of the compression
+const ATTACK: f64 = 0.005;
+/// release time (in seconds) of the compression
+const RELEASE: f64 = 0.2;
+
+/// A sink compressing the audio samples before writing them into another sink,
+/// which is only inserted into the audio pipeline while the night mode is enabled
+pub struct NightModeSink {
+    sink: Box<dyn Sink>,
+    /// the software volume, which is applied to the samples before the sink
+    volume: Box<dyn VolumeGetter + Send>,
+    /// envelope of the audio's level before applying the volume
+    envelope: f64,
+    attack_coef: f64,
+    release_coef: f64,
+}
+
+impl NightModeSink {
+    pub fn new(sink: Box<dyn Sink>, volume: Box<dyn VolumeGetter + Send>) -> Self {
+        let sample_rate = f64::from(SAMPLE_RATE);
+        Self {
+            sink,
+            volume,
+            envelope: 0.0,
+            attack_coef: (-1.0 / (ATTACK * sample_rate)).exp(),
+            release_coef: (-1.0 / (RELEASE * sample_rate)).exp(),
+        }
+    }
+
+    /// Get the gain applied to a frame, whose level is the maximum level of its channels
+    fn frame_gain(&mut self, level: f64) -> f64 {
+        let coef = if level > self.envelope {
+            self.attack_coef
+        } else {
+            self.release_coef
+        };
+        self.envelope = coef * self.envelope + (1.0 - coef) * level;
+
+        let envelope = 20.0 * self.envelope.max(1e-9).log10();
+        let reduction = if envelope > THRESHOLD {
+            (envelope - THRESHOLD) * (1.0 - 1.0 / RATIO)
+        } else {
+            0.0
+        };
+        10_f64.powf((MAKEUP_GAIN - reduction) / 20.0)
+    }
+}
+
+impl Sink for NightModeSink {
+    fn start(&mut self) -> SinkResult<()> {
+        self.envelope = 0.0;
+        self.sink.start()
+    }
+
+    fn stop(&mut self) -> SinkResult<()> {
+        self.sink.stop()
+    }
+
+    fn write(&mut self, packet: AudioPacket, converter: &mut Converter) -> SinkResult<()> {
+        let AudioPacket::Samples(mut samples) = packet else {
+            return self.sink.write(packet, converter);
+        };
+
+        // the level is detected before applying the volume,
+        // so that loud peaks are also compressed at low volumes
+        let attenuation = self.volume.attenuation_factor();
+        if attenuation > 0.0 {
+            // samples are interleaved by channels
+            for frame in samples.chunks_mut(NUM_CHANNELS as usize) {
+                let level = frame.iter().fold(0.0_f64, |level, x| level.max(x.abs())) / attenuation;
+                let gain = self.frame_gain(level);
+                for sample in frame {
+                    *sample = (*sample * gain).clamp(-1.0, 1.0);
+                }
+            }
+        } else {
+            self.envelope = 0.0;
+        }
+        self.sink.write(AudioPacket::Samples(samples), converter)
+    }
+}
@@ -0,0 +1,349 @@
+//! Audio backends of the integrated device opening their output device at a configured
+//! sample rate, as `librespot`'s backends always open it at `SAMPLE_RATE`
+
+use librespot_playback::{
+    audio_backend::{Sink, SinkError, SinkResult},
+    convert::Converter,
+    decoder::AudioPacket,
+    NUM_CHANNELS,
+};
+
+/// A builder of a sink writing into an output device (`None` for the default device)
+/// opened at a sample rate
+pub type SinkBuilder = fn(Option<String>, u32) -> Box<dyn Sink>;
+
+/// Find the sink builder of an audio backend able to open its output device
+/// at a configured sample rate
+pub fn find(backend: &str) -> Option<SinkBuilder> {
+    match backend {
+        #[cfg(feature = "alsa-backend")]
+        "alsa" => Some(alsa_output::AlsaSink::open as SinkBuilder),
+        #[cfg(feature = "pulseaudio-backend")]
+        "pulseaudio" => Some(pulseaudio_output::PulseAudioSink::open as SinkBuilder),
+        #[cfg(feature = "rodio-backend")]
+        "rodio" => Some(cpal_output::CpalSink::open as SinkBuilder),
+        _ => None,
+    }
+}
+
+/// Get the samples of an audio packet
+fn packet_samples(packet: &AudioPacket) -> SinkResult<&[f64]> {
+    packet
+        .samples()
+        .map_err(|err| SinkError::OnWrite(err.to_string()))
+}
+
+#[cfg(feature = "alsa-backend")]
+mod alsa_output {
+    use super::{
+        packet_samples, AudioPacket, Converter, Sink, SinkError, SinkResult, NUM_CHANNELS,
+    };
+    use alsa::{
+        pcm::{Access, Format, HwParams, PCM},
+        Direction, ValueOr,
+    };
+
+    /// A sink writing 16-bit samples into an ALSA device
+    pub struct AlsaSink {
+        device: String,
+        sample_rate: u32,
+        pcm: Option<PCM>,
+    }
+
+    impl AlsaSink {
+        pub fn open(device: Option<String>, sample_rate: u32) -> Box<dyn Sink> {
+            Box::new(Self {
+                device: device.unwrap_or_else(|| "default".to_string()),
+                sample_rate,
+                pcm: None,
+            })
+        }
+
+        fn open_pcm(&self) -> Result<PCM, alsa::Error> {
+            let pcm = PCM::new(&self.device, Direction::Playback, false)?;
+            {
+                let hwp = HwParams::any(&pcm)?;
+                hwp.set_access(Access::RWInterleaved)?;
+                hwp.set_format(Format::s16())?;
+                hwp.set_channels(u32::from(NUM_CHANNELS))?;
+                // the sample rate is pinned, so ALSA's plugins mustn't resample the audio
+                hwp.set_rate_resample(false)?;
+                hwp.set_rate(self.sample_rate, ValueOr::Nearest)?;
+                pcm.hw_params(&hwp)?;
+            }
+            Ok(pcm)
+        }
+    }
+
+    impl Sink for AlsaSink {
+        fn start(&mut self) -> SinkResult<()> {
+            if self.pcm.is_some() {
+                return Ok(());
+            }
+            let pcm = self.open_pcm().map_err(|err| {
+                SinkError::ConnectionRefused(format!("failed to open {}: {err}", self.device))
+            })?;
+            let rate = pcm
+                .hw_params_current()
+                .and_then(|hwp| hwp.get_rate())
+                .map_err(|err| SinkError::InvalidParams(err.to_string()))?;
+            if rate != self.sample_rate {
+                return Err(SinkError::InvalidParams(format!(
+                    "{} doesn't support a sample rate of {} Hz",
+                    self.device, self.sample_rate
+                )));
+            }
+            self.pcm = Some(pcm);
+            Ok(())
+        }
+
+        fn stop(&mut self) -> SinkResult<()> {
+            if let Some(pcm) = self.pcm.take() {
+                pcm.drain()
+                    .map_err(|err| SinkError::StateChange(err.to_string()))?;
+            }
+            Ok(())
+        }
+
+        fn write(&mut self, packet: AudioPacket, converter: &mut Converter) -> SinkResult<()> {
+            let Some(pcm) = &self.pcm else {
+                return Err(SinkError::NotConnected(format!(
+                    "{} isn't open",
+                    self.device
+                )));
+            };
+            let samples = converter.f64_to_s16(packet_samples(&packet)?);
+            let io = pcm
+                .io_i16()
+                .map_err(|err| SinkError::OnWrite(err.to_string()))?;
+
+            let mut written = 0;
+            while written < samples.len() {
+                match io.writei(&samples[written..]) {
+                    Ok(frames) => written += frames * NUM_CHANNELS as usize,
+                    // recover from an underrun
+                    Err(err) => pcm
+                        .try_recover(err, false)
+                        .map_err(|err| SinkError::OnWrite(err.to_string()))?,
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "pulseaudio-backend")]
+mod pulseaudio_output {
+    use super::{
+        packet_samples, AudioPacket, Converter, Sink, SinkError, SinkResult, NUM_CHANNELS,
+    };
+    use libpulse_binding::{sample, stream::Direction};
+    use libpulse_simple_binding::Simple;
+
+    /// A sink writing 16-bit samples into a `PulseAudio` stream.
+    /// The server only switches the output device to the stream's sample rate
+    /// if its `avoid-resampling` option is enabled.
+    pub struct PulseAudioSink {
+        device: Option<String>,
+        sample_rate: u32,
+        stream: Option<Simple>,
+    }
+
+    impl PulseAudioSink {
+        pub fn open(device: Option<String>, sample_rate: u32) -> Box<dyn Sink> {
+            Box::new(Self {
+                device,
+                sample_rate,
+                stream: None,
+            })
+        }
+    }
+
+    impl Sink for PulseAudioSink {
+        fn start(&mut self) -> SinkResult<()> {
+            if self.stream.is_some() {
+                return Ok(());
+            }
+            let spec = sample::Spec {
+                format: sample::Format::S16NE,
+                channels: NUM_CHANNELS,
+                rate: self.sample_rate,
+            };
+            if !spec.is_valid() {
+                return Err(SinkError::InvalidParams(format!(
+                    "invalid sample rate: {} Hz",
+                    self.sample_rate
+                )));
+            }
+            let stream = Simple::new(
+                None,
+                "spotify_player",
+                Direction::Playback,
+                self.device.as_deref(),
+                "music",
+                &spec,
+                None,
+                None,
+            )
+            .map_err(|err| SinkError::ConnectionRefused(err.to_string()))?;
+            self.stream = Some(stream);
+            Ok(())
+        }
+
+        fn stop(&mut self) -> SinkResult<()> {
+            if let Some(stream) = self.stream.take() {
+                stream
+                    .drain()
+                    .map_err(|err| SinkError::StateChange(err.to_string()))?;
+            }
+            Ok(())
+        }
+
+        fn write(&mut self, packet: AudioPacket, converter: &mut Converter) -> SinkResult<()> {
+            let Some(stream) = &self.stream else {
+                return Err(SinkError::NotConnected(
+                    "PulseAudio stream isn't open".to_string(),
+                ));
+            };
+            let bytes = converter
+                .f64_to_s16(packet_samples(&packet)?)
+                .into_iter()
+                .flat_map(i16::to_ne_bytes)
+                .collect::<Vec<_>>();
+            stream
+                .write(&bytes)
+                .map_err(|err| SinkError::OnWrite(err.to_string()))
+        }
+    }
+}
+
+#[cfg(feature = "rodio-backend")]
+mod cpal_output {
+    use super::{
+        packet_samples, AudioPacket, Converter, Sink, SinkError, SinkResult, NUM_CHANNELS,
+    };
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+    use ringbuf::{
+        traits::{Consumer, Observer, Producer, Split},
+        HeapProd, HeapRb,
+    };
+    use std::time::{Duration, Instant};
+
+    /// duration of the audio buffered ahead of the output
+    const BUFFER_DURATION: Duration = Duration::from_millis(500);
+    /// interval of polling the buffer while it's full or being drained
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+    /// A sink writing 32-bit float samples into a `cpal` output stream, i.e. the output
+    /// used by `librespot`'s `rodio` backend, which resamples the audio to the device's
+    /// default sample rate.
+    ///
+    /// The samples are passed to the output's real-time callback via a preallocated
+    /// lock-free ring buffer, so the callback never allocates or blocks.
+    pub struct CpalSink {
+        device: Option<String>,
+        sample_rate: u32,
+        stream: Option<(cpal::Stream, HeapProd<f32>)>,
+    }
+
+    impl CpalSink {
+        pub fn open(device: Option<String>, sample_rate: u32) -> Box<dyn Sink> {
+            Box::new(Self {
+                device,
+                sample_rate,
+                stream: None,
+            })
+        }
+
+        fn build_stream(&self) -> anyhow::Result<(cpal::Stream, HeapProd<f32>)> {
+            let host = cpal::default_host();
+            let device = match &self.device {
+                Some(name) => host
+                    .output_devices()?
+                    .find(|d| d.name().is_ok_and(|n| n == *name))
+                    .ok_or_else(|| anyhow::anyhow!("output device {name} not found"))?,
+                None => host
+                    .default_output_device()
+                    .ok_or_else(|| anyhow::anyhow!("no default output device"))?,
+            };
+
+            let channels = u16::from(NUM_CHANNELS);
+            let supported = device.supported_output_configs()?.any(|c| {
+                c.channels() == channels
+                    && c.sample_format() == cpal::SampleFormat::F32
+                    && (c.min_sample_rate().0..=c.max_sample_rate().0).contains(&self.sample_rate)
+            });
+            if !supported {
+                anyhow::bail!(
+                    "the output device doesn't support a sample rate of {} Hz",
+                    self.sample_rate
+                );
+            }
+            let config = cpal::StreamConfig {
+                channels,
+                sample_rate: cpal::SampleRate(self.sample_rate),
+                buffer_size: cpal::BufferSize::Default,
+            };
+
+            let capacity = (f64::from(self.sample_rate)
+                * f64::from(channels)
+                * BUFFER_DURATION.as_secs_f64()) as usize;
+            let (producer, mut consumer) = HeapRb::<f32>::new(capacity).split();
+            let stream = device.build_output_stream(
+                &config,
+                move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                    let read = consumer.pop_slice(data);
+                    // output silence until the next audio packet is written
+                    data[read..].fill(0.0);
+                },
+                |err| tracing::error!("Failed to output the audio: {err}"),
+                None,
+            )?;
+            stream.play()?;
+            Ok((stream, producer))
+        }
+    }
+
+    impl Sink for CpalSink {
+        fn start(&mut self) -> SinkResult<()> {
+            if self.stream.is_none() {
+                let stream = self
+                    .build_stream()
+                    .map_err(|err| SinkError::ConnectionRefused(format!("{err:#}")))?;
+                self.stream = Some(stream);
+            }
+            Ok(())
+        }
+
+        fn stop(&mut self) -> SinkResult<()> {
+            if let Some((_, producer)) = &self.stream {
+                // play the buffered audio before closing the output stream,
+                // giving up if the output stops consuming it
+                let deadline = Instant::now() + BUFFER_DURATION * 2;
+                while !producer.is_empty() && Instant::now() < deadline {
+                    std::thread::sleep(POLL_INTERVAL);
+                }
+            }
+            self.stream = None;
+            Ok(())
+        }
+
+        fn write(&mut self, packet: AudioPacket, converter: &mut Converter) -> SinkResult<()> {
+            let Some((_, producer)) = &mut self.stream else {
+                return Err(SinkError::NotConnected(
+                    "output stream isn't open".to_string(),
+                ));
+            };
+            let samples = converter.f64_to_f32(packet_samples(&packet)?);
+            // waits while the buffer is full, so the audio is decoded at the output's pace
+            let mut written = 0;
+            loop {
+                written += producer.push_slice(&samples[written..]);
+                if written == samples.len() {
+                    return Ok(());
+                }
+                std::thread::sleep(POLL_INTERVAL);
+            }
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Routes serving the playing item's data and a minimal HTML overlay,
+//! e.g. for an OBS browser source
+
+use serde::Serialize;
+
+use crate::{
+    http_server::{Request, Response, Routes},
+    state::SharedState,
+    utils,
+};
+
+/// The overlay's page, which polls the playing item's data every second
+const OVERLAY_HTML: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<style>
+  body { margin: 0; font-family: sans-serif; color: white; background: transparent; }
+  #overlay { display: none; align-items: center; gap: 12px; padding: 12px; width: 480px; background: rgba(0, 0, 0, 0.6); border-radius: 8px; }
+  #cover { width: 80px; height: 80px; border-radius: 4px; object-fit: cover; }
+  #info { flex: 1; min-width: 0; }
+  #info div { overflow: hidden; white-space: nowrap; text-overflow: ellipsis; }
+  #track { font-size: 20px; font-weight: bold; }
+  #artists { font-size: 16px; opacity: 0.8; }
+  #bar { margin-top: 8px; height: 4px; background: rgba(255, 255, 255, 0.3); border-radius: 2px; }
+  #progress { height: 100%; width: 0; background: white; border-radius: 2px; }
+</style>
+</head>
+<body>
+<div id="overlay">
+  <img id="cover">
+  <div id="info">
+    <div id="track"></div>
+    <div id="artists"></div>
+    <div id="bar"><div id="progress"></div></div>
+  </div>
+</div>
+<script>
+  async function update() {
+    try {
+      const data = await (await fetch("/now-playing.json")).json();
+      const overlay = document.getElementById("overlay");
+      if (!data) {
+        overlay.style.display = "none";
+        return;
+      }
+      overlay.style.display = "flex";
+      document.getElementById("cover").src = data.cover_url || "";
+      document.getElementById("track").textContent = data.track;
+      document.getElementById("artists").textContent = data.artists;
+      const percent = data.duration_ms > 0 ? (100 * data.progress_ms) / data.duration_ms : 0;
+      document.getElementById("progress").style.width = percent + "%";
+    } catch (err) {}
+  }
+  update();
+  setInterval(update, 1000);
+</script>
+</body>
+</html>
+"#;
+
+/// The playing item's data served by the overlay server
+#[derive(Serialize)]
+struct NowPlaying {
+    uri: String,
+    track: String,
+    artists: String,
+    album: String,
+    cover_url: Option<String>,
+    is_playing: bool,
+    progress_ms: i64,
+    duration_ms: i64,
+}
+
+fn now_playing(state: &SharedState) -> Option<NowPlaying> {
+    let player = state.player.read();
+    let is_playing = player.playback.as_ref()?.is_playing;
+    let progress_ms = player.playback_progress()?.num_milliseconds();
+
+    Some(match player.currently_playing()? {
+        rspotify::model::PlayableItem::Track(track) => NowPlaying {
+            uri: track
+                .id
+                .as_ref()
+                .map(rspotify::prelude::Id::uri)
+                .unwrap_or_default(),
+            track: track.name.clone(),
+            artists: utils::map_join(&track.artists, |a| &a.name, ", "),
+            album: track.album.name.clone(),
+            cover_url: utils::get_track_album_image_url(track).map(ToString::to_string),
+            is_playing,
+            progress_ms,
+            duration_ms: track.duration.num_milliseconds(),
+        },
+        rspotify::model::PlayableItem::Episode(episode) => NowPlaying {
+            uri: rspotify::prelude::Id::uri(&episode.id),
+            track: episode.name.clone(),
+            artists: episode.show.publisher.clone(),
+            album: episode.show.name.clone(),
+            cover_url: utils::get_episode_show_image_url(episode).map(ToString::to_string),
+            is_playing,
+            progress_ms,
+            duration_ms: episode.duration.num_milliseconds(),
+        },
+    })
+}
+
+/// The overlay's routes mounted on the application's HTTP server, serving the overlay's page
+/// at the `/` path and the playing item's data at the `/now-playing.json` path
+pub struct Overlay {
+    state: SharedState,
+}
+
+impl Overlay {
+    pub fn new(state: SharedState) -> Self {
+        Self { state }
+    }
+}
+
+#[async_trait::async_trait]
+impl Routes for Overlay {
+    async fn handle(&self, request: &Request) -> Option<Response> {
+        let response = match request.path.as_str() {
+            "/" => Response::ok("text/html; charset=utf-8", OVERLAY_HTML),
+            "/now-playing.json" => match serde_json::to_vec(&now_playing(&self.state)) {
+                Ok(data) => Response::ok("application/json", data),
+                Err(err) => Response::error(500, format!("{err:#}")),
+            },
+            _ => return None,
+        };
+        Some(response.with_header("Cache-Control", "no-store"))
+    }
+}
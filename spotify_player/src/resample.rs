@@ -0,0 +1,149 @@
+//! Resampling of the integrated device's audio to a configured output sample rate,
+//! e.g. for the audio backends writing the raw samples into another program
+
+use librespot_playback::{
+    audio_backend::{Sink, SinkResult},
+    convert::Converter,
+    decoder::AudioPacket,
+    NUM_CHANNELS, SAMPLE_RATE,
+};
+
+use crate::config::ResamplerQuality;
+
+/// number of the precomputed filter weights per input frame
+const FILTER_RESOLUTION: usize = 256;
+
+/// A sink resampling the audio samples from `SAMPLE_RATE` to another sample rate
+/// before writing them into another sink
+pub struct ResampleSink {
+    sink: Box<dyn Sink>,
+    /// distance (in input frames) between two consecutive output frames
+    step: f64,
+    /// number of the input frames on each side of an output frame it's interpolated from
+    half_taps: usize,
+    /// weights of the input frames at the distances from `-half_taps` to `half_taps`
+    /// (in input frames) from an output frame, sampled `FILTER_RESOLUTION` times per frame
+    filter: Vec<f64>,
+    /// interleaved input samples not fully consumed yet
+    input: Vec<f64>,
+    /// position (in input frames) of the next output frame inside `input`
+    position: f64,
+}
+
+impl ResampleSink {
+    pub fn new(sink: Box<dyn Sink>, sample_rate: u32, quality: ResamplerQuality) -> Self {
+        let ratio = f64::from(sample_rate) / f64::from(SAMPLE_RATE);
+        let half_taps = match quality {
+            ResamplerQuality::Low => 1,
+            ResamplerQuality::Medium => 8,
+            ResamplerQuality::High => 32,
+        };
+        // frequencies above the output's Nyquist frequency are filtered out when downsampling
+        let cutoff = ratio.min(1.0) * 0.95;
+        // the weights are computed once for the resampler's quality and ratio,
+        // with an extra weight following the last one for the interpolation
+        let filter = (0..=2 * half_taps * FILTER_RESOLUTION + 1)
+            .map(|i| {
+                let distance = i as f64 / FILTER_RESOLUTION as f64 - half_taps as f64;
+                filter_weight(quality, half_taps, cutoff, distance)
+            })
+            .collect();
+        let mut sink = Self {
+            sink,
+            step: 1.0 / ratio,
+            half_taps,
+            filter,
+            input: Vec::new(),
+            position: 0.0,
+        };
+        sink.reset();
+        sink
+    }
+
+    /// Reset the resampler's state, padding the input with silence preceding the first frame
+    fn reset(&mut self) {
+        self.input = vec![0.0; (self.half_taps - 1) * NUM_CHANNELS as usize];
+        self.position = (self.half_taps - 1) as f64;
+    }
+
+    /// Get the weight of an input frame at a distance (in input frames) from an output frame,
+    /// interpolated between the two nearest precomputed weights
+    fn weight(&self, distance: f64) -> f64 {
+        let index = (distance + self.half_taps as f64) * FILTER_RESOLUTION as f64;
+        let i = index as usize;
+        let fraction = index - i as f64;
+        self.filter[i] * (1.0 - fraction) + self.filter[i + 1] * fraction
+    }
+}
+
+/// Compute the weight of an input frame at a distance (in input frames) from an output frame
+fn filter_weight(quality: ResamplerQuality, half_taps: usize, cutoff: f64, distance: f64) -> f64 {
+    match quality {
+        ResamplerQuality::Low => (1.0 - distance.abs()).max(0.0),
+        ResamplerQuality::Medium | ResamplerQuality::High => {
+            // a sinc function windowed by a Blackman window
+            let x = distance / half_taps as f64;
+            if x.abs() >= 1.0 {
+                return 0.0;
+            }
+            let window = 0.42
+                + 0.5 * (std::f64::consts::PI * x).cos()
+                + 0.08 * (2.0 * std::f64::consts::PI * x).cos();
+            let t = std::f64::consts::PI * cutoff * distance;
+            let sinc = if t.abs() < f64::EPSILON {
+                1.0
+            } else {
+                t.sin() / t
+            };
+            cutoff * sinc * window
+        }
+    }
+}
+
+impl Sink for ResampleSink {
+    fn start(&mut self) -> SinkResult<()> {
+        self.sink.start()
+    }
+
+    fn stop(&mut self) -> SinkResult<()> {
+        self.reset();
+        self.sink.stop()
+    }
+
+    fn write(&mut self, packet: AudioPacket, converter: &mut Converter) -> SinkResult<()> {
+        let AudioPacket::Samples(samples) = packet else {
+            return self.sink.write(packet, converter);
+        };
+
+        let channels = NUM_CHANNELS as usize;
+        self.input.extend(samples);
+        let frames = self.input.len() / channels;
+
+        // each output frame is interpolated from `half_taps` input frames on both of its sides
+        let mut output = Vec::new();
+        while (self.position as usize) + self.half_taps < frames {
+            let center = self.position as usize;
+            let first = center + 1 - self.half_taps;
+            let last = center + self.half_taps;
+            for channel in 0..channels {
+                let value: f64 = (first..=last)
+                    .map(|k| {
+                        self.input[k * channels + channel] * self.weight(k as f64 - self.position)
+                    })
+                    .sum();
+                output.push(value);
+            }
+            self.position += self.step;
+        }
+
+        // drop the input frames preceding the ones needed by the next output frame
+        let consumed = (self.position as usize + 1).saturating_sub(self.half_taps);
+        self.input.drain(..consumed * channels);
+        self.position -= consumed as f64;
+
+        if output.is_empty() {
+            return Ok(());
+        }
+        self.sink.write(AudioPacket::Samples(output), converter)
+    }
+}
@@ -0,0 +1,211 @@
+//! User scripts, which are [Rhai](https://rhai.rs) scripts in the `scripts` folder of the
+//! application's config folder. A script can read the player's and the library's state and
+//! send client requests, e.g. skipping the playing track if it's shorter than 90 seconds.
+
+use anyhow::Result;
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map};
+use rspotify::model::{PlayableItem, RepeatState};
+use rspotify::prelude::Id;
+
+use crate::{
+    client::{ClientRequest, PlayerRequest},
+    config,
+    state::{ContextId, Playback, PlaylistFolderItem, SharedState},
+};
+
+const SCRIPTS_FOLDER: &str = "scripts";
+const SCRIPT_FILE_EXTENSION: &str = "rhai";
+
+/// The maximum number of operations run by a script, which stops a script with an infinite loop
+const MAX_SCRIPT_OPERATIONS: u64 = 1_000_000;
+
+/// Runs the user script with the given `name` in a background thread, so that a long-running
+/// script doesn't block the application's event handler. The script's client requests are sent
+/// to `client_pub` in order once the script finishes.
+pub fn spawn_script(name: String, client_pub: flume::Sender<ClientRequest>, state: SharedState) {
+    let result = std::thread::Builder::new()
+        .name(format!("script-{name}"))
+        .spawn(move || {
+            let (script_pub, script_sub) = flume::unbounded();
+            if let Err(err) = run_script(&name, &script_pub, &state) {
+                tracing::error!("Failed to run script {name}: {err:#}");
+            }
+            let requests = script_sub.drain().collect::<Vec<_>>();
+            if !requests.is_empty() {
+                client_pub
+                    .send(ClientRequest::Sequence(requests))
+                    .unwrap_or_default();
+            }
+        });
+    if let Err(err) = result {
+        tracing::error!("Failed to spawn a thread running a script: {err:#}");
+    }
+}
+
+/// Runs the user script with the given `name`, whose client requests are sent to `client_pub`
+fn run_script(
+    name: &str,
+    client_pub: &flume::Sender<ClientRequest>,
+    state: &SharedState,
+) -> Result<()> {
+    tracing::info!("Running script {name}");
+
+    let path = config::get_config()
+        .config_folder
+        .join(SCRIPTS_FOLDER)
+        .join(name)
+        .with_extension(SCRIPT_FILE_EXTENSION);
+    if !path.exists() {
+        anyhow::bail!("script {name} is not found (path={path:?})");
+    }
+
+    new_engine(client_pub, state)
+        .run_file(path)
+        .map_err(|err| anyhow::anyhow!("script {name}: {err}"))
+}
+
+/// Sends a player request from a script
+fn send(
+    client_pub: &flume::Sender<ClientRequest>,
+    request: PlayerRequest,
+) -> Result<(), Box<EvalAltResult>> {
+    client_pub
+        .send(ClientRequest::Player(request))
+        .map_err(|err| err.to_string().into())
+}
+
+/// Creates a script engine with the functions available to the scripts
+fn new_engine(client_pub: &flume::Sender<ClientRequest>, state: &SharedState) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_SCRIPT_OPERATIONS);
+    engine.on_print(|text| tracing::info!("[script] {text}"));
+    engine.on_debug(|text, _, _| tracing::debug!("[script] {text}"));
+
+    // functions reading the application's state
+    let s = state.clone();
+    engine.register_fn("playback", move || playback(&s));
+    let s = state.clone();
+    engine.register_fn("playlists", move || -> Array {
+        s.data
+            .read()
+            .user_data
+            .playlists
+            .iter()
+            .filter_map(|item| match item {
+                PlaylistFolderItem::Playlist(p) => Some(Dynamic::from(p.name.clone())),
+                PlaylistFolderItem::Folder(_) => None,
+            })
+            .collect()
+    });
+
+    // functions sending player requests
+    let tx = client_pub.clone();
+    engine.register_fn("next_track", move || send(&tx, PlayerRequest::NextTrack));
+    let tx = client_pub.clone();
+    engine.register_fn("previous_track", move || {
+        send(&tx, PlayerRequest::PreviousTrack)
+    });
+    let tx = client_pub.clone();
+    engine.register_fn("resume", move || send(&tx, PlayerRequest::Resume));
+    let tx = client_pub.clone();
+    engine.register_fn("pause", move || send(&tx, PlayerRequest::Pause));
+    let tx = client_pub.clone();
+    engine.register_fn("resume_pause", move || {
+        send(&tx, PlayerRequest::ResumePause)
+    });
+    let tx = client_pub.clone();
+    engine.register_fn("seek", move |position_ms: i64| {
+        send(
+            &tx,
+            PlayerRequest::SeekTrack(chrono::Duration::milliseconds(position_ms.max(0))),
+        )
+    });
+    let tx = client_pub.clone();
+    engine.register_fn("set_volume", move |volume: i64| {
+        send(&tx, PlayerRequest::Volume(volume.clamp(0, 100) as u8))
+    });
+    let tx = client_pub.clone();
+    engine.register_fn("set_shuffle", move |shuffle: bool| {
+        send(&tx, PlayerRequest::SetShuffle(shuffle))
+    });
+    let tx = client_pub.clone();
+    let s = state.clone();
+    engine.register_fn(
+        "play_playlist",
+        move |name: &str| -> Result<(), Box<EvalAltResult>> {
+            let id = s
+                .data
+                .read()
+                .user_data
+                .playlists
+                .iter()
+                .find_map(|item| match item {
+                    PlaylistFolderItem::Playlist(p) if p.name == name => Some(p.id.clone()),
+                    _ => None,
+                })
+                .ok_or_else(|| format!("no playlist named {name}"))?;
+            send(
+                &tx,
+                PlayerRequest::StartPlayback(
+                    Playback::Context(ContextId::Playlist(id), None),
+                    None,
+                ),
+            )
+        },
+    );
+
+    engine
+}
+
+/// Gets the current playback as a script's object map, or `()` if nothing is playing
+fn playback(state: &SharedState) -> Dynamic {
+    let player = state.player.read();
+    let Some(item) = player.currently_playing() else {
+        return Dynamic::UNIT;
+    };
+
+    let mut map = Map::new();
+    let (uri, duration) = match item {
+        PlayableItem::Track(track) => {
+            let artists = track
+                .artists
+                .iter()
+                .map(|a| Dynamic::from(a.name.clone()))
+                .collect::<Array>();
+            map.insert("name".into(), track.name.clone().into());
+            map.insert("artists".into(), Dynamic::from_array(artists));
+            map.insert("album".into(), track.album.name.clone().into());
+            (track.id.as_ref().map(Id::uri), track.duration)
+        }
+        PlayableItem::Episode(episode) => {
+            map.insert("name".into(), episode.name.clone().into());
+            map.insert("show".into(), episode.show.name.clone().into());
+            (Some(episode.id.uri()), episode.duration)
+        }
+    };
+    let uri = uri.unwrap_or_default();
+    let is_liked = state.data.read().user_data.saved_tracks.contains_key(&uri);
+    map.insert("uri".into(), uri.into());
+    map.insert("liked".into(), is_liked.into());
+    map.insert("duration_ms".into(), duration.num_milliseconds().into());
+    if let Some(progress) = player.playback_progress() {
+        map.insert("progress_ms".into(), progress.num_milliseconds().into());
+    }
+
+    if let Some(metadata) = player.buffered_playback.as_ref() {
+        let repeat = match metadata.repeat_state {
+            RepeatState::Off => "off",
+            RepeatState::Track => "track",
+            RepeatState::Context => "context",
+        };
+        map.insert("is_playing".into(), metadata.is_playing.into());
+        map.insert("shuffle".into(), metadata.shuffle_state.into());
+        map.insert("repeat".into(), repeat.into());
+        map.insert("device".into(), metadata.device_name.clone().into());
+        if let Some(volume) = metadata.volume {
+            map.insert("volume".into(), i64::from(volume).into());
+        }
+    }
+
+    Dynamic::from_map(map)
+}
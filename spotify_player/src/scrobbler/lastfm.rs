@@ -0,0 +1,185 @@
+//! Last.fm scrobbling, see <https://www.last.fm/api/scrobbling>
+
+use anyhow::{Context as _, Result};
+
+use super::{Rejected, Scrobble, ScrobbleService};
+use crate::config::LastfmConfig;
+
+const API_URL: &str = "https://ws.audioscrobbler.com/2.0/";
+/// code of the API error of an invalid session key, e.g. after the user revokes the access
+const INVALID_SESSION_ERROR: u64 = 9;
+/// codes of the API errors of temporary failures, i.e. an operation failed on the server,
+/// the service is offline or temporarily unavailable, and the rate limit is exceeded
+const TEMPORARY_ERRORS: [u64; 4] = [8, 11, 16, 29];
+
+pub struct Lastfm {
+    config: LastfmConfig,
+    http: reqwest::Client,
+    /// key of the authenticated Last.fm session, retrieved upon the first request
+    session_key: Option<String>,
+}
+
+impl Lastfm {
+    pub fn new(config: LastfmConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+            session_key: None,
+        }
+    }
+
+    /// Sends a request signed with the API account's secret to the Last.fm API.
+    /// The error of a request rejected by the API has the [`Rejected`] context,
+    /// and the session key is cleared upon an invalid session to authenticate a new session.
+    async fn request(&mut self, mut params: Vec<(String, String)>) -> Result<serde_json::Value> {
+        params.push(("api_key".to_string(), self.config.api_key.clone()));
+        params.sort();
+
+        let mut signature = params
+            .iter()
+            .map(|(k, v)| format!("{k}{v}"))
+            .collect::<String>();
+        signature.push_str(&self.config.api_secret);
+        params.push((
+            "api_sig".to_string(),
+            format!("{:x}", md5::compute(signature)),
+        ));
+        params.push(("format".to_string(), "json".to_string()));
+
+        let response: serde_json::Value = self
+            .http
+            .post(API_URL)
+            .form(&params)
+            .send()
+            .await?
+            .json()
+            .await?;
+        if let Some(code) = response.get("error") {
+            let err = anyhow::anyhow!("Last.fm API error {code}: {}", response["message"]);
+            let code = code.as_u64().unwrap_or_default();
+            if code == INVALID_SESSION_ERROR {
+                self.session_key = None;
+                return Err(err);
+            }
+            if TEMPORARY_ERRORS.contains(&code) {
+                return Err(err);
+            }
+            return Err(err.context(Rejected));
+        }
+        Ok(response)
+    }
+
+    async fn session_key(&mut self) -> Result<String> {
+        if let Some(key) = &self.session_key {
+            return Ok(key.clone());
+        }
+
+        let response = self
+            .request(vec![
+                ("method".to_string(), "auth.getMobileSession".to_string()),
+                ("username".to_string(), self.config.username.clone()),
+                ("password".to_string(), self.config.password.clone()),
+            ])
+            .await
+            .context("authenticate Last.fm session")?;
+        let key = response["session"]["key"]
+            .as_str()
+            .context("missing Last.fm session key")?
+            .to_string();
+        self.session_key = Some(key.clone());
+        Ok(key)
+    }
+}
+
+/// A track loved by the Last.fm user
+pub struct LovedTrack {
+    pub artist: String,
+    pub track: String,
+}
+
+/// Gets the tracks loved by the configured Last.fm user, most recently loved first
+pub async fn get_loved_tracks(config: &LastfmConfig) -> Result<Vec<LovedTrack>> {
+    let http = reqwest::Client::new();
+    let mut tracks = Vec::new();
+    let mut page = 1;
+    loop {
+        let response: serde_json::Value = http
+            .get(API_URL)
+            .query(&[
+                ("method", "user.getLovedTracks"),
+                ("user", config.username.as_str()),
+                ("api_key", config.api_key.as_str()),
+                ("format", "json"),
+                ("limit", "1000"),
+                ("page", page.to_string().as_str()),
+            ])
+            .send()
+            .await?
+            .json()
+            .await?;
+        if let Some(code) = response.get("error") {
+            anyhow::bail!("Last.fm API error {code}: {}", response["message"]);
+        }
+
+        let loved_tracks = &response["lovedtracks"];
+        if let Some(items) = loved_tracks["track"].as_array() {
+            tracks.extend(items.iter().filter_map(|item| {
+                Some(LovedTrack {
+                    artist: item["artist"]["name"].as_str()?.to_string(),
+                    track: item["name"].as_str()?.to_string(),
+                })
+            }));
+        }
+
+        // the pagination's attributes are strings in Last.fm API responses
+        let total_pages = loved_tracks["@attr"]["totalPages"]
+            .as_str()
+            .and_then(|p| p.parse::<u32>().ok())
+            .unwrap_or_default();
+        if page >= total_pages {
+            break;
+        }
+        page += 1;
+    }
+    Ok(tracks)
+}
+
+#[async_trait::async_trait]
+impl ScrobbleService for Lastfm {
+    fn name(&self) -> &'static str {
+        "lastfm"
+    }
+
+    async fn update_now_playing(&mut self, track: &Scrobble) -> Result<()> {
+        let session_key = self.session_key().await?;
+        self.request(vec![
+            ("method".to_string(), "track.updateNowPlaying".to_string()),
+            ("sk".to_string(), session_key),
+            ("artist".to_string(), track.artist.clone()),
+            ("track".to_string(), track.track.clone()),
+            ("album".to_string(), track.album.clone()),
+            ("duration".to_string(), track.duration_secs.to_string()),
+        ])
+        .await?;
+        Ok(())
+    }
+
+    async fn scrobble(&mut self, scrobbles: &[Scrobble]) -> Result<()> {
+        let session_key = self.session_key().await?;
+        let mut params = vec![
+            ("method".to_string(), "track.scrobble".to_string()),
+            ("sk".to_string(), session_key),
+        ];
+        for (i, scrobble) in scrobbles.iter().enumerate() {
+            params.extend([
+                (format!("artist[{i}]"), scrobble.artist.clone()),
+                (format!("track[{i}]"), scrobble.track.clone()),
+                (format!("album[{i}]"), scrobble.album.clone()),
+                (format!("duration[{i}]"), scrobble.duration_secs.to_string()),
+                (format!("timestamp[{i}]"), scrobble.timestamp.to_string()),
+            ]);
+        }
+        self.request(params).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+//! `ListenBrainz` scrobbling, see <https://listenbrainz.readthedocs.io/en/latest/users/api/core.html>
+
+use anyhow::Result;
+
+use super::{Rejected, Scrobble, ScrobbleService};
+use crate::config::ListenBrainzConfig;
+
+const DEFAULT_API_URL: &str = "https://api.listenbrainz.org";
+
+pub struct ListenBrainz {
+    config: ListenBrainzConfig,
+    http: reqwest::Client,
+}
+
+impl ListenBrainz {
+    pub fn new(config: ListenBrainzConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Submits listens of a given type (`playing_now`, `single` or `import`).
+    /// The error of a submission rejected by the API has the [`Rejected`] context.
+    async fn submit_listens(
+        &self,
+        listen_type: &str,
+        payload: Vec<serde_json::Value>,
+    ) -> Result<()> {
+        let api_url = self.config.api_url.as_deref().unwrap_or(DEFAULT_API_URL);
+        let response = self
+            .http
+            .post(format!(
+                "{}/1/submit-listens",
+                api_url.trim_end_matches('/')
+            ))
+            .header(
+                reqwest::header::AUTHORIZATION,
+                format!("Token {}", self.config.token),
+            )
+            .json(&serde_json::json!({
+                "listen_type": listen_type,
+                "payload": payload,
+            }))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            let err = anyhow::anyhow!("ListenBrainz API error {status}: {body}");
+            // the server errors and the rate limit are temporary
+            if status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+                return Err(err.context(Rejected));
+            }
+            return Err(err);
+        }
+        Ok(())
+    }
+}
+
+fn track_metadata(track: &Scrobble) -> serde_json::Value {
+    serde_json::json!({
+        "artist_name": track.artist,
+        "track_name": track.track,
+        "release_name": track.album,
+        "additional_info": {
+            "duration_ms": track.duration_secs * 1000,
+            "media_player": "Spotify",
+            "submission_client": "spotify_player",
+            "submission_client_version": env!("CARGO_PKG_VERSION"),
+        },
+    })
+}
+
+#[async_trait::async_trait]
+impl ScrobbleService for ListenBrainz {
+    fn name(&self) -> &'static str {
+        "listenbrainz"
+    }
+
+    async fn update_now_playing(&mut self, track: &Scrobble) -> Result<()> {
+        self.submit_listens(
+            "playing_now",
+            vec![serde_json::json!({ "track_metadata": track_metadata(track) })],
+        )
+        .await
+    }
+
+    async fn scrobble(&mut self, scrobbles: &[Scrobble]) -> Result<()> {
+        let payload = scrobbles
+            .iter()
+            .map(|scrobble| {
+                serde_json::json!({
+                    "listened_at": scrobble.timestamp,
+                    "track_metadata": track_metadata(scrobble),
+                })
+            })
+            .collect();
+        // `single` listen type only accepts one listen per request
+        let listen_type = if scrobbles.len() == 1 {
+            "single"
+        } else {
+            "import"
+        };
+        self.submit_listens(listen_type, payload).await
+    }
+}
@@ -0,0 +1,567 @@
+//! Scrobbling of the played tracks to services like Last.fm and `ListenBrainz`,
+//! or to a local log file
+
+mod lastfm;
+mod listenbrainz;
+mod play_log;
+
+pub use lastfm::{get_loved_tracks, LovedTrack};
+
+use std::{
+    collections::{HashMap, VecDeque},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use rspotify::{model::PlayableItem, prelude::Id};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    config,
+    state::{PlaybackEvent, SharedState},
+};
+
+/// name of the file storing the scrobbles failed to be submitted inside the cache folder
+const SCROBBLE_QUEUE_FILE: &str = "scrobble_queue.json";
+/// maximum number of scrobbles submitted in a single request
+const MAX_SCROBBLES_PER_REQUEST: usize = 50;
+/// minimum duration of a track to be scrobbled
+const MIN_TRACK_DURATION: Duration = Duration::from_secs(30);
+/// play time after which a track is scrobbled, regardless of the track's duration
+const MAX_SCROBBLE_PLAY_TIME: Duration = Duration::from_secs(4 * 60);
+/// interval between retries of submitting the queued scrobbles,
+/// doubled upon every consecutive transient failure up to `MAX_RETRY_INTERVAL`
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+const MAX_RETRY_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// number of rejected submissions after which a queued scrobble is dropped,
+/// so that a scrobble rejected by a service doesn't block the following ones
+const MAX_SUBMISSION_ATTEMPTS: u32 = 10;
+
+/// The context of an error of a service rejecting a request, e.g. because of invalid
+/// parameters or credentials, as opposed to a transient error, e.g. a network failure
+#[derive(Debug)]
+struct Rejected;
+
+impl std::fmt::Display for Rejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "rejected by the service")
+    }
+}
+
+/// A played track submitted to scrobbling services
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scrobble {
+    pub artist: String,
+    pub track: String,
+    pub album: String,
+    pub duration_secs: u64,
+    /// UNIX timestamp of the time the track started playing
+    pub timestamp: i64,
+}
+
+/// A scrobble waiting in a service's queue to be submitted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueuedScrobble {
+    #[serde(flatten)]
+    scrobble: Scrobble,
+    /// number of rejected submissions of the scrobble
+    #[serde(default)]
+    attempts: u32,
+}
+
+/// A scrobbling service
+#[async_trait::async_trait]
+trait ScrobbleService: Send {
+    /// Name of the service, which identifies the service's queued scrobbles
+    fn name(&self) -> &'static str;
+
+    /// Notifies the service about the playing track
+    async fn update_now_playing(&mut self, track: &Scrobble) -> Result<()>;
+
+    /// Submits scrobbles, whose number doesn't exceed `MAX_SCROBBLES_PER_REQUEST`.
+    /// The error of a rejected submission has the [`Rejected`] context.
+    async fn scrobble(&mut self, scrobbles: &[Scrobble]) -> Result<()>;
+}
+
+/// The playing track tracked by the scrobbler
+struct PlayingTrack {
+    id: String,
+    scrobble: Scrobble,
+    /// play time of the track until it's last resumed
+    play_time: Duration,
+    /// time at which the track is last resumed, if it's playing
+    resumed_at: Option<Instant>,
+    is_now_playing_sent: bool,
+    is_scrobbled: bool,
+}
+
+struct Scrobbler {
+    services: Vec<Box<dyn ScrobbleService>>,
+    play_log: Option<play_log::PlayLog>,
+    /// scrobbles waiting to be submitted, keyed by the service's name
+    queue: HashMap<String, VecDeque<QueuedScrobble>>,
+    queue_path: PathBuf,
+    last_submission: Option<Instant>,
+    /// number of the consecutive submissions failed because of a transient error
+    transient_failures: u32,
+}
+
+/// Starts the scrobbler, which scrobbles the played tracks to the configured services
+pub async fn start_scrobbler(state: SharedState) {
+    let configs = config::get_config();
+
+    let mut services: Vec<Box<dyn ScrobbleService>> = Vec::new();
+    if let Some(config) = configs.app_config.lastfm.clone() {
+        services.push(Box::new(lastfm::Lastfm::new(config)));
+    }
+    if let Some(config) = configs.app_config.listenbrainz.clone() {
+        services.push(Box::new(listenbrainz::ListenBrainz::new(config)));
+    }
+    let play_log = configs
+        .app_config
+        .scrobble_log_file
+        .clone()
+        .map(|path| play_log::PlayLog::new(path.into()));
+    if services.is_empty() && play_log.is_none() {
+        return;
+    }
+
+    let queue_path = configs.cache_folder.join(SCROBBLE_QUEUE_FILE);
+    let mut queue: HashMap<String, VecDeque<QueuedScrobble>> = match std::fs::read(&queue_path) {
+        Ok(data) => serde_json::from_slice(&data).unwrap_or_else(|err| {
+            tracing::warn!("Failed to load the scrobble queue: {err:#}");
+            HashMap::new()
+        }),
+        Err(_) => HashMap::new(),
+    };
+    // drop the queued scrobbles of services that are no longer configured
+    queue.retain(|name, _| services.iter().any(|s| s.name() == name));
+    let mut scrobbler = Scrobbler {
+        services,
+        play_log,
+        queue,
+        queue_path,
+        last_submission: None,
+        transient_failures: 0,
+    };
+
+    if !scrobbler.queue.is_empty() {
+        scrobbler.submit().await;
+    }
+
+    let mut playback_events = state.playback_events.subscribe();
+    let mut playing: Option<PlayingTrack> = None;
+    loop {
+        let scrobble_deadline = playing.as_ref().and_then(PlayingTrack::scrobble_deadline);
+        let retry_deadline = scrobbler
+            .last_submission
+            .filter(|_| !scrobbler.queue.is_empty())
+            .map(|t| t + scrobbler.retry_interval());
+        let deadline = scrobble_deadline.into_iter().chain(retry_deadline).min();
+        let sleep = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now).into());
+
+        let event = tokio::select! {
+            event = playback_events.recv() => match event {
+                Ok(PlaybackEvent::QueueChange | PlaybackEvent::LikedTracksChange) => continue,
+                Ok(event) => Some(event),
+                // the playing track is updated from the playback if some events are missed
+                Err(RecvError::Lagged(_)) => None,
+                Err(RecvError::Closed) => break,
+            },
+            () = sleep, if deadline.is_some() => {
+                if !scrobbler.scrobble_if_played(playing.as_mut()).await
+                    && retry_deadline.is_some_and(|t| t <= Instant::now())
+                {
+                    scrobbler.submit().await;
+                }
+                continue;
+            }
+        };
+        // the playing track may be played long enough before the event, e.g. when it ends
+        scrobbler.scrobble_if_played(playing.as_mut()).await;
+
+        let (item, is_playing, progress) = {
+            let player = state.player.read();
+            (
+                // nothing is scrobbled in a private session
+                player
+                    .currently_playing()
+                    .filter(|_| !*state.private_session.lock())
+                    .cloned(),
+                player.playback.as_ref().is_some_and(|p| p.is_playing),
+                player.playback_progress().unwrap_or_default(),
+            )
+        };
+
+        let track = match item {
+            Some(PlayableItem::Track(track)) => Some(track),
+            // episodes aren't scrobbled
+            _ => None,
+        };
+        let id = track.as_ref().map(|track| {
+            track
+                .id
+                .as_ref()
+                .map(|id| id.id().to_string())
+                .unwrap_or_default()
+        });
+
+        // a track changing to itself is restarted, e.g. when it's repeated
+        match playing.as_mut() {
+            Some(p) if Some(&p.id) == id.as_ref() && event != Some(PlaybackEvent::TrackChange) => {
+                p.set_playing(is_playing);
+            }
+            _ => {
+                // the previously playing track has ended
+                if let Some(p) = playing.take() {
+                    scrobbler.record_play(&p);
+                }
+                playing = track.zip(id).map(|(track, id)| PlayingTrack {
+                    id,
+                    scrobble: Scrobble {
+                        artist: track
+                            .artists
+                            .first()
+                            .map(|a| a.name.clone())
+                            .unwrap_or_default(),
+                        track: track.name.clone(),
+                        album: track.album.name.clone(),
+                        duration_secs: track.duration.num_seconds().max(0) as u64,
+                        timestamp: (chrono::Utc::now() - progress).timestamp(),
+                    },
+                    play_time: Duration::ZERO,
+                    resumed_at: is_playing.then(Instant::now),
+                    is_now_playing_sent: false,
+                    is_scrobbled: false,
+                });
+            }
+        }
+
+        if let Some(p) = playing.as_mut() {
+            if is_playing && !p.is_now_playing_sent {
+                p.is_now_playing_sent = true;
+                scrobbler.update_now_playing(&p.scrobble).await;
+            }
+        }
+    }
+}
+
+impl PlayingTrack {
+    /// Get the track's play time, excluding the time it's paused
+    fn play_time(&self) -> Duration {
+        self.play_time + self.resumed_at.map(|t| t.elapsed()).unwrap_or_default()
+    }
+
+    fn set_playing(&mut self, is_playing: bool) {
+        self.play_time = self.play_time();
+        self.resumed_at = is_playing.then(Instant::now);
+    }
+
+    /// Get the play time after which the track is scrobbled, if the track can be scrobbled
+    fn scrobble_play_time(&self) -> Option<Duration> {
+        let duration = Duration::from_secs(self.scrobble.duration_secs);
+        (duration >= MIN_TRACK_DURATION)
+            .then(|| std::cmp::min(duration / 2, MAX_SCROBBLE_PLAY_TIME))
+    }
+
+    /// Check if the track is played long enough to be scrobbled
+    fn is_played(&self) -> bool {
+        self.scrobble_play_time()
+            .is_some_and(|t| self.play_time() >= t)
+    }
+
+    fn should_scrobble(&self) -> bool {
+        !self.is_scrobbled && self.is_played()
+    }
+
+    /// Get the time at which the playing track should be scrobbled, if it isn't scrobbled yet
+    fn scrobble_deadline(&self) -> Option<Instant> {
+        if self.is_scrobbled {
+            return None;
+        }
+        let resumed_at = self.resumed_at?;
+        let play_time = self.scrobble_play_time()?;
+        Some(resumed_at + play_time.saturating_sub(self.play_time))
+    }
+}
+
+impl Scrobbler {
+    /// Scrobbles the playing track if it's played long enough but not scrobbled yet,
+    /// returning whether the track is scrobbled
+    async fn scrobble_if_played(&mut self, playing: Option<&mut PlayingTrack>) -> bool {
+        let Some(p) = playing.filter(|p| p.should_scrobble()) else {
+            return false;
+        };
+        p.is_scrobbled = true;
+        self.enqueue(&p.scrobble);
+        self.submit().await;
+        true
+    }
+
+    /// Records the play of an ended track in the play log, if it's played long enough
+    fn record_play(&self, playing: &PlayingTrack) {
+        let Some(play_log) = self.play_log.as_ref().filter(|_| playing.is_played()) else {
+            return;
+        };
+        if let Err(err) = play_log.record(&playing.scrobble) {
+            tracing::warn!("Failed to record the play in the play log: {err:#}");
+        }
+    }
+
+    async fn update_now_playing(&mut self, track: &Scrobble) {
+        for service in &mut self.services {
+            if let Err(err) = service.update_now_playing(track).await {
+                tracing::warn!(
+                    "Failed to update the now playing track on {}: {err:#}",
+                    service.name()
+                );
+            }
+        }
+    }
+
+    /// Adds a scrobble to the queue of every service
+    fn enqueue(&mut self, scrobble: &Scrobble) {
+        tracing::info!("Scrobbling {} by {}", scrobble.track, scrobble.artist);
+        for service in &self.services {
+            self.queue
+                .entry(service.name().to_string())
+                .or_default()
+                .push_back(QueuedScrobble {
+                    scrobble: scrobble.clone(),
+                    attempts: 0,
+                });
+        }
+    }
+
+    /// Submits the queued scrobbles, keeping the ones failed to be submitted
+    /// in the queue to be retried later, unless they're rejected `MAX_SUBMISSION_ATTEMPTS` times.
+    /// The scrobbles failed to be submitted because of a transient error, e.g. while offline,
+    /// are kept until they're submitted, with an increasing interval between the retries.
+    async fn submit(&mut self) {
+        let mut is_transient_failure = false;
+        for service in &mut self.services {
+            let Some(queue) = self.queue.get_mut(service.name()) else {
+                continue;
+            };
+            while !queue.is_empty() {
+                let n = std::cmp::min(queue.len(), MAX_SCROBBLES_PER_REQUEST);
+                let scrobbles = queue
+                    .range(..n)
+                    .map(|q| q.scrobble.clone())
+                    .collect::<Vec<_>>();
+                if let Err(err) = service.scrobble(&scrobbles).await {
+                    tracing::warn!(
+                        "Failed to submit {n} scrobbles to {}, retrying later: {err:#}",
+                        service.name()
+                    );
+                    if err.downcast_ref::<Rejected>().is_none() {
+                        is_transient_failure = true;
+                        break;
+                    }
+                    for q in queue.range_mut(..n) {
+                        q.attempts += 1;
+                    }
+                    // drop the scrobbles failing permanently, which would block the following ones
+                    queue.retain(|q| {
+                        let is_dropped = q.attempts >= MAX_SUBMISSION_ATTEMPTS;
+                        if is_dropped {
+                            tracing::warn!(
+                                "Dropped the scrobble of {} by {} after {} failed submissions to {}",
+                                q.scrobble.track,
+                                q.scrobble.artist,
+                                q.attempts,
+                                service.name()
+                            );
+                        }
+                        !is_dropped
+                    });
+                    break;
+                }
+                queue.drain(..n);
+            }
+        }
+        self.queue.retain(|_, queue| !queue.is_empty());
+        self.last_submission = Some(Instant::now());
+        self.transient_failures = if is_transient_failure {
+            self.transient_failures + 1
+        } else {
+            0
+        };
+
+        if let Err(err) = self.store_queue() {
+            tracing::warn!("Failed to store the scrobble queue: {err:#}");
+        }
+    }
+
+    /// Get the interval between the last submission and the next retry
+    fn retry_interval(&self) -> Duration {
+        let backoff = 2_u32.saturating_pow(self.transient_failures.saturating_sub(1));
+        std::cmp::min(RETRY_INTERVAL.saturating_mul(backoff), MAX_RETRY_INTERVAL)
+    }
+
+    fn store_queue(&self) -> Result<()> {
+        if self.queue.is_empty() {
+            if self.queue_path.exists() {
+                std::fs::remove_file(&self.queue_path)?;
+            }
+        } else {
+            std::fs::write(&self.queue_path, serde_json::to_vec(&self.queue)?)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[derive(Clone, Copy)]
+    enum Outcome {
+        Submitted,
+        Rejected,
+        Failed,
+    }
+
+    /// A service returning the given outcomes of the submissions, then submitting the scrobbles
+    struct MockService {
+        outcomes: VecDeque<Outcome>,
+        /// numbers of the scrobbles of the submissions
+        submissions: Arc<parking_lot::Mutex<Vec<usize>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ScrobbleService for MockService {
+        fn name(&self) -> &'static str {
+            "mock"
+        }
+
+        async fn update_now_playing(&mut self, _track: &Scrobble) -> Result<()> {
+            Ok(())
+        }
+
+        async fn scrobble(&mut self, scrobbles: &[Scrobble]) -> Result<()> {
+            self.submissions.lock().push(scrobbles.len());
+            match self.outcomes.pop_front().unwrap_or(Outcome::Submitted) {
+                Outcome::Submitted => Ok(()),
+                Outcome::Rejected => Err(anyhow::anyhow!("invalid scrobble").context(Rejected)),
+                Outcome::Failed => Err(anyhow::anyhow!("connection refused")),
+            }
+        }
+    }
+
+    fn new_scrobbler(
+        name: &str,
+        outcomes: &[Outcome],
+    ) -> (Scrobbler, Arc<parking_lot::Mutex<Vec<usize>>>) {
+        let submissions = Arc::default();
+        let service = MockService {
+            outcomes: outcomes.iter().copied().collect(),
+            submissions: Arc::clone(&submissions),
+        };
+        let scrobbler = Scrobbler {
+            services: vec![Box::new(service)],
+            play_log: None,
+            queue: HashMap::new(),
+            queue_path: std::env::temp_dir()
+                .join(format!("spotify-player-{name}-{}.json", std::process::id())),
+            last_submission: None,
+            transient_failures: 0,
+        };
+        (scrobbler, submissions)
+    }
+
+    fn scrobble(i: usize) -> Scrobble {
+        Scrobble {
+            artist: "Artist".to_string(),
+            track: format!("Track {i}"),
+            album: "Album".to_string(),
+            duration_secs: 180,
+            timestamp: i as i64,
+        }
+    }
+
+    fn queue(scrobbler: &Scrobbler) -> Vec<u32> {
+        scrobbler
+            .queue
+            .get("mock")
+            .map(|queue| queue.iter().map(|q| q.attempts).collect())
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn submit_in_batches() {
+        let (mut scrobbler, submissions) = new_scrobbler("submit-in-batches", &[]);
+        for i in 0..120 {
+            scrobbler.enqueue(&scrobble(i));
+        }
+        scrobbler.submit().await;
+
+        assert_eq!(*submissions.lock(), [50, 50, 20]);
+        assert!(scrobbler.queue.is_empty());
+        assert!(!scrobbler.queue_path.exists());
+    }
+
+    #[tokio::test]
+    async fn submit_with_transient_failures() {
+        let outcomes = [Outcome::Submitted, Outcome::Failed, Outcome::Failed];
+        let (mut scrobbler, submissions) = new_scrobbler("transient-failures", &outcomes);
+        for i in 0..60 {
+            scrobbler.enqueue(&scrobble(i));
+        }
+
+        // the scrobbles failed to be submitted are kept without counting the attempts
+        scrobbler.submit().await;
+        assert_eq!(*submissions.lock(), [50, 10]);
+        assert_eq!(queue(&scrobbler), [0; 10]);
+        assert_eq!(scrobbler.transient_failures, 1);
+        assert!(scrobbler.queue_path.exists());
+
+        scrobbler.submit().await;
+        assert_eq!(queue(&scrobbler), [0; 10]);
+        assert_eq!(scrobbler.transient_failures, 2);
+
+        scrobbler.submit().await;
+        assert_eq!(*submissions.lock(), [50, 10, 10, 10]);
+        assert!(scrobbler.queue.is_empty());
+        assert_eq!(scrobbler.transient_failures, 0);
+        assert!(!scrobbler.queue_path.exists());
+    }
+
+    #[tokio::test]
+    async fn submit_rejected_scrobbles() {
+        let outcomes = [Outcome::Rejected; MAX_SUBMISSION_ATTEMPTS as usize];
+        let (mut scrobbler, submissions) = new_scrobbler("rejected-scrobbles", &outcomes);
+        scrobbler.enqueue(&scrobble(0));
+
+        for attempts in 1..MAX_SUBMISSION_ATTEMPTS {
+            scrobbler.submit().await;
+            assert_eq!(queue(&scrobbler), [attempts]);
+            assert_eq!(scrobbler.transient_failures, 0);
+        }
+        // the scrobble is dropped after being rejected `MAX_SUBMISSION_ATTEMPTS` times,
+        // so it doesn't block the following scrobbles
+        scrobbler.enqueue(&scrobble(1));
+        scrobbler.submit().await;
+        assert_eq!(queue(&scrobbler), [1]);
+        scrobbler.submit().await;
+        assert!(scrobbler.queue.is_empty());
+        assert_eq!(
+            submissions.lock().len(),
+            MAX_SUBMISSION_ATTEMPTS as usize + 1
+        );
+    }
+
+    #[test]
+    fn retry_interval_backoff() {
+        let (mut scrobbler, _) = new_scrobbler("retry-interval", &[]);
+        let intervals = [0, 1, 2, 3, 6, 7, 100].map(|failures| {
+            scrobbler.transient_failures = failures;
+            scrobbler.retry_interval().as_secs()
+        });
+        assert_eq!(intervals, [60, 60, 120, 240, 1920, 3600, 3600]);
+    }
+}
@@ -0,0 +1,68 @@
+//! A local log of the completed plays, which can be imported into scrobbling services later.
+//!
+//! A play is recorded once its track ends, if the track is played long enough to be scrobbled.
+//! The log is written in the Audioscrobbler portable player format (`.scrobbler.log`),
+//! or as JSON lines if the log file has a `.json` or `.jsonl` extension.
+
+use std::{io::Write, path::PathBuf};
+
+use anyhow::Result;
+
+use super::Scrobble;
+
+pub struct PlayLog {
+    path: PathBuf,
+}
+
+impl PlayLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn is_json(&self) -> bool {
+        matches!(
+            self.path.extension().and_then(|e| e.to_str()),
+            Some("json" | "jsonl")
+        )
+    }
+
+    /// Formats a play as a line of the Audioscrobbler portable player format:
+    /// `ARTIST`, `ALBUM`, `TITLE`, `TRACKNUM`, `LENGTH`, `RATING`, `TIMESTAMP` and
+    /// `MUSICBRAINZ_TRACKID` fields separated by tabs, where `L` rating means a listened track
+    fn audioscrobbler_line(play: &Scrobble) -> String {
+        let field = |s: &str| s.replace(['\t', '\n'], " ");
+        format!(
+            "{}\t{}\t{}\t\t{}\tL\t{}\t",
+            field(&play.artist),
+            field(&play.album),
+            field(&play.track),
+            play.duration_secs,
+            play.timestamp
+        )
+    }
+
+    /// Appends a completed play to the log
+    pub fn record(&self, play: &Scrobble) -> Result<()> {
+        let is_new = !std::fs::metadata(&self.path).is_ok_and(|m| m.len() > 0);
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+
+        let mut lines = String::new();
+        if self.is_json() {
+            lines.push_str(&serde_json::to_string(play)?);
+        } else {
+            if is_new {
+                lines.push_str(&format!(
+                    "#AUDIOSCROBBLER/1.1\n#TZ/UTC\n#CLIENT/spotify_player {}\n",
+                    env!("CARGO_PKG_VERSION")
+                ));
+            }
+            lines.push_str(&Self::audioscrobbler_line(play));
+        }
+        lines.push('\n');
+        file.write_all(lines.as_bytes())?;
+        Ok(())
+    }
+}
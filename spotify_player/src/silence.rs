@@ -0,0 +1,60 @@
+//! Skipping long silences of the integrated device's audio, e.g. the silence
+//! before a hidden track at the end of an album
+
+use librespot_playback::{
+    audio_backend::{Sink, SinkResult},
+    convert::Converter,
+    decoder::AudioPacket,
+    NUM_CHANNELS, SAMPLE_RATE,
+};
+
+/// level below which a sample is silent, i.e. -60 dB
+const SILENCE_LEVEL: f64 = 0.001;
+/// duration (in seconds) of a silence kept before skipping the rest of the silence
+const MAX_SILENCE: u32 = 1;
+
+/// A sink skipping long silences by not writing their audio samples into another sink.
+/// As the audio is decoded faster than it's played, the skipped silence shortens
+/// the playback time.
+pub struct SilenceSink {
+    sink: Box<dyn Sink>,
+    /// number of the consecutive silent samples
+    silent_samples: usize,
+}
+
+impl SilenceSink {
+    pub fn new(sink: Box<dyn Sink>) -> Self {
+        Self {
+            sink,
+            silent_samples: 0,
+        }
+    }
+}
+
+impl Sink for SilenceSink {
+    fn start(&mut self) -> SinkResult<()> {
+        self.silent_samples = 0;
+        self.sink.start()
+    }
+
+    fn stop(&mut self) -> SinkResult<()> {
+        self.sink.stop()
+    }
+
+    fn write(&mut self, packet: AudioPacket, converter: &mut Converter) -> SinkResult<()> {
+        let AudioPacket::Samples(samples) = &packet else {
+            return self.sink.write(packet, converter);
+        };
+
+        if samples.iter().all(|x| x.abs() < SILENCE_LEVEL) {
+            self.silent_samples += samples.len();
+            let max_samples = (MAX_SILENCE * SAMPLE_RATE * u32::from(NUM_CHANNELS)) as usize;
+            if self.silent_samples > max_samples {
+                return Ok(());
+            }
+        } else {
+            self.silent_samples = 0;
+        }
+        self.sink.write(packet, converter)
+    }
+}
@@ -1,27 +1,63 @@
-use std::io::{BufReader, BufWriter};
-use std::{collections::HashMap, path::Path};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 use serde::{de::DeserializeOwned, Serialize};
 use std::sync::LazyLock;
 
 use super::model::{
-    Album, Artist, Category, Context, ContextId, Id, Playlist, PlaylistFolderItem,
-    PlaylistFolderNode, SearchResults, Show, Track,
+    Album, Artist, Category, Context, ContextId, Id, ListeningHistoryEntry, Playlist,
+    PlaylistFolderItem, PlaylistFolderNode, SavedSearch, SearchResults, Show, Track,
 };
+use super::ui::fuzzy_match_items;
 use super::Lyrics;
+use crate::config;
 
 pub type DataReadGuard<'a> = parking_lot::RwLockReadGuard<'a, AppData>;
 
 #[derive(Debug, Copy, Clone)]
 pub enum FileCacheKey {
+    User,
     Playlists,
     PlaylistFolders,
     FollowedArtists,
     SavedShows,
     SavedAlbums,
     SavedTracks,
+    SearchHistory,
+    SavedSearches,
+    Devices,
+    Equalizer,
+}
+
+impl FileCacheKey {
+    /// file caches of the user's library data fetched from Spotify APIs,
+    /// which can be cleared and re-fetched at any time
+    pub const API_DATA: [FileCacheKey; 6] = [
+        FileCacheKey::Playlists,
+        FileCacheKey::PlaylistFolders,
+        FileCacheKey::FollowedArtists,
+        FileCacheKey::SavedShows,
+        FileCacheKey::SavedAlbums,
+        FileCacheKey::SavedTracks,
+    ];
+
+    /// Get the path of the key's file cache inside the cache folder
+    pub fn file_path(self, cache_folder: &Path) -> PathBuf {
+        cache_folder.join(format!("{self:?}_cache.json"))
+    }
 }
 
+/// name of the file storing the listening history inside the cache folder,
+/// in which each line is a JSON-serialized `ListeningHistoryEntry`
+const LISTENING_HISTORY_FILE: &str = "listening_history.jsonl";
+
+/// prefix of the keys of library search results, followed by the search query
+const LIBRARY_SEARCH_PREFIX: &str = "library:";
+
 /// default time-to-live cache duration
 pub static TTL_CACHE_DURATION: LazyLock<std::time::Duration> =
     LazyLock::new(|| std::time::Duration::from_secs(60 * 60));
@@ -31,6 +67,12 @@ pub struct AppData {
     pub user_data: UserData,
     pub caches: MemoryCaches,
     pub browse: BrowseData,
+    /// past search queries, ordered from the oldest to the most recent one
+    pub search_history: Vec<String>,
+    pub saved_searches: Vec<SavedSearch>,
+    /// the last library search's query and results, which are kept out of the search cache
+    /// so that searching as the user types doesn't evict the cached Spotify search results
+    library_search: Option<(String, SearchResults)>,
 }
 
 #[derive(Debug)]
@@ -79,26 +121,119 @@ impl AppData {
             user_data: UserData::new_from_file_caches(cache_folder),
             caches: MemoryCaches::new(),
             browse: BrowseData::default(),
+            search_history: load_data_from_file_cache(FileCacheKey::SearchHistory, cache_folder)
+                .unwrap_or_default(),
+            saved_searches: load_data_from_file_cache(FileCacheKey::SavedSearches, cache_folder)
+                .unwrap_or_default(),
+            library_search: None,
         }
     }
 
-    /// Get a list of tracks inside a given context
-    pub fn context_tracks_mut(&mut self, id: &ContextId) -> Option<&mut Vec<Track>> {
-        let c = self.caches.context.get_mut(&id.uri())?;
+    /// Search a query in the user's cached library, keeping the results as the last
+    /// library search's results.
+    ///
+    /// Returns the key of the results, which is passed to [`AppData::search_results`].
+    pub fn search_library(&mut self, query: &str) -> String {
+        if !matches!(&self.library_search, Some((q, _)) if q == query) {
+            self.library_search = Some((query.to_string(), self.user_data.search(query)));
+        }
+        format!("{LIBRARY_SEARCH_PREFIX}{query}")
+    }
 
-        Some(match c {
-            Context::Album { tracks, .. }
-            | Context::Playlist { tracks, .. }
-            | Context::Tracks { tracks, .. }
-            | Context::Artist {
-                top_tracks: tracks, ..
-            } => tracks,
-            Context::Show { .. } => {
-                return None;
-            }
+    /// Get the search results of a key, i.e. the cached Spotify search results of a query
+    /// or the results of a library search.
+    ///
+    /// If `library_only` is true, only results that are in the user's library are returned.
+    pub fn search_results(&self, key: &str, library_only: bool) -> Option<Cow<'_, SearchResults>> {
+        let results = match key.strip_prefix(LIBRARY_SEARCH_PREFIX) {
+            Some(query) => match &self.library_search {
+                Some((q, results)) if q == query => Cow::Borrowed(results),
+                // a previous library search, e.g. of a page in the history, is searched again
+                _ => Cow::Owned(self.user_data.search(query)),
+            },
+            None => Cow::Borrowed(self.caches.search.get(key)?),
+        };
+        Some(if library_only {
+            Cow::Owned(self.user_data.filter_library_items(&results))
+        } else {
+            results
         })
     }
 
+    /// Add a query to the search history and store the updated history into the file cache.
+    ///
+    /// A query that was searched before is moved to the most recent position.
+    pub fn add_search_query(&mut self, query: String) {
+        let configs = config::get_config();
+        let size = configs.app_config.search_history_size;
+        if size == 0 {
+            return;
+        }
+
+        self.search_history.retain(|q| *q != query);
+        self.search_history.push(query);
+        if self.search_history.len() > size {
+            self.search_history
+                .drain(..self.search_history.len() - size);
+        }
+
+        self.store_search_history(&configs.cache_folder);
+    }
+
+    /// Clear the search history, including its file cache
+    pub fn clear_search_history(&mut self) {
+        self.search_history.clear();
+        self.store_search_history(&config::get_config().cache_folder);
+    }
+
+    fn store_search_history(&self, cache_folder: &Path) {
+        if let Err(err) = store_data_into_file_cache(
+            FileCacheKey::SearchHistory,
+            cache_folder,
+            &self.search_history,
+        ) {
+            tracing::error!("Failed to store search history into the file cache: {err:#}");
+        }
+    }
+
+    /// Save a search and store the saved searches into the file cache.
+    ///
+    /// A saved search with the same name is replaced.
+    pub fn add_saved_search(&mut self, search: SavedSearch) {
+        match self
+            .saved_searches
+            .iter_mut()
+            .find(|s| s.name == search.name)
+        {
+            Some(s) => *s = search,
+            None => self.saved_searches.push(search),
+        }
+        self.store_saved_searches(&config::get_config().cache_folder);
+    }
+
+    /// Delete the `id`-th saved search and store the saved searches into the file cache
+    pub fn delete_saved_search(&mut self, id: usize) {
+        if id < self.saved_searches.len() {
+            self.saved_searches.remove(id);
+            self.store_saved_searches(&config::get_config().cache_folder);
+        }
+    }
+
+    fn store_saved_searches(&self, cache_folder: &Path) {
+        if let Err(err) = store_data_into_file_cache(
+            FileCacheKey::SavedSearches,
+            cache_folder,
+            &self.saved_searches,
+        ) {
+            tracing::error!("Failed to store saved searches into the file cache: {err:#}");
+        }
+    }
+
+    /// Get a list of tracks inside a given context
+    pub fn context_tracks_mut(&mut self, id: &ContextId) -> Option<&mut Vec<Track>> {
+        self.caches.context.get_mut(&id.uri())?.tracks_mut()
+    }
+
     pub fn context_tracks(&self, id: &ContextId) -> Option<&Vec<Track>> {
         let c = self.caches.context.get(&id.uri())?;
         Some(match c {
@@ -119,7 +254,7 @@ impl UserData {
     /// Construct a new user data based on file caches
     pub fn new_from_file_caches(cache_folder: &Path) -> Self {
         Self {
-            user: None,
+            user: load_data_from_file_cache(FileCacheKey::User, cache_folder),
             playlists: load_data_from_file_cache(FileCacheKey::Playlists, cache_folder)
                 .unwrap_or_default(),
             playlist_folder_node: load_data_from_file_cache(
@@ -181,11 +316,101 @@ impl UserData {
             .collect()
     }
 
+    /// Search a query in the user's library data, which includes playlists, saved albums,
+    /// liked tracks, followed artists and saved shows
+    pub fn search(&self, query: &str) -> SearchResults {
+        fn matched_items<T: std::fmt::Display + Clone>(items: &[T], query: &str) -> Vec<T> {
+            fuzzy_match_items(items, query)
+                .into_iter()
+                .cloned()
+                .collect()
+        }
+
+        let mut tracks = self.saved_tracks.values().collect::<Vec<_>>();
+        tracks.sort_by(|x, y| x.name.cmp(&y.name));
+        let playlists = self
+            .playlists
+            .iter()
+            .filter_map(|item| match item {
+                PlaylistFolderItem::Playlist(p) => Some(p.clone()),
+                PlaylistFolderItem::Folder(_) => None,
+            })
+            .collect::<Vec<_>>();
+
+        SearchResults {
+            tracks: matched_items(&tracks, query).into_iter().cloned().collect(),
+            artists: matched_items(&self.followed_artists, query),
+            albums: matched_items(&self.saved_albums, query),
+            playlists: matched_items(&playlists, query),
+            shows: matched_items(&self.saved_shows, query),
+            episodes: Vec::new(),
+            audiobooks: Vec::new(),
+            next_offsets: HashMap::new(),
+        }
+    }
+
     /// Check if a track is a liked track
     pub fn is_liked_track(&self, track: &Track) -> bool {
         self.saved_tracks.contains_key(&track.id.uri())
     }
 
+    /// Check if an album is saved in the user's library
+    pub fn is_saved_album(&self, album: &Album) -> bool {
+        self.saved_albums.iter().any(|a| a.id == album.id)
+    }
+
+    /// Check if an artist is followed
+    pub fn is_followed_artist(&self, artist: &Artist) -> bool {
+        self.followed_artists.iter().any(|a| a.id == artist.id)
+    }
+
+    /// Check if a show is saved in the user's library
+    pub fn is_saved_show(&self, show: &Show) -> bool {
+        self.saved_shows.iter().any(|s| s.id == show.id)
+    }
+
+    /// Filter search results to only include items in the user's library.
+    ///
+    /// Episodes and audiobooks are not part of the library data, so none of them is included.
+    pub fn filter_library_items(&self, results: &SearchResults) -> SearchResults {
+        SearchResults {
+            tracks: results
+                .tracks
+                .iter()
+                .filter(|t| self.is_liked_track(t))
+                .cloned()
+                .collect(),
+            artists: results
+                .artists
+                .iter()
+                .filter(|a| self.is_followed_artist(a))
+                .cloned()
+                .collect(),
+            albums: results
+                .albums
+                .iter()
+                .filter(|a| self.is_saved_album(a))
+                .cloned()
+                .collect(),
+            playlists: results
+                .playlists
+                .iter()
+                .filter(|p| self.is_followed_playlist(p))
+                .cloned()
+                .collect(),
+            shows: results
+                .shows
+                .iter()
+                .filter(|s| self.is_saved_show(s))
+                .cloned()
+                .collect(),
+            episodes: Vec::new(),
+            audiobooks: Vec::new(),
+            // library items aren't paginated
+            next_offsets: HashMap::new(),
+        }
+    }
+
     /// Check if a playlist is followed
     pub fn is_followed_playlist(&self, playlist: &Playlist) -> bool {
         self.playlists.iter().any(|x| match x {
@@ -200,17 +425,49 @@ pub fn store_data_into_file_cache<T: Serialize>(
     cache_folder: &Path,
     data: &T,
 ) -> std::io::Result<()> {
-    let path = cache_folder.join(format!("{key:?}_cache.json"));
+    let path = key.file_path(cache_folder);
     let f = BufWriter::new(std::fs::File::create(path)?);
     serde_json::to_writer(f, data)?;
     Ok(())
 }
 
+/// Appends an entry to the listening history stored in the cache folder
+pub fn append_listening_history_entry(
+    cache_folder: &Path,
+    entry: &ListeningHistoryEntry,
+) -> std::io::Result<()> {
+    let mut f = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(cache_folder.join(LISTENING_HISTORY_FILE))?;
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    f.write_all(&line)
+}
+
+/// Loads the listening history stored in the cache folder, ordered from the oldest entry
+pub fn load_listening_history(cache_folder: &Path) -> std::io::Result<Vec<ListeningHistoryEntry>> {
+    let path = cache_folder.join(LISTENING_HISTORY_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let f = BufReader::new(std::fs::File::open(path)?);
+    let mut entries = Vec::new();
+    for line in f.lines() {
+        match serde_json::from_str(&line?) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => tracing::warn!("Failed to parse a listening history entry: {err:#}"),
+        }
+    }
+    Ok(entries)
+}
+
 pub fn load_data_from_file_cache<T>(key: FileCacheKey, cache_folder: &Path) -> Option<T>
 where
     T: DeserializeOwned,
 {
-    let path = cache_folder.join(format!("{key:?}_cache.json"));
+    let path = key.file_path(cache_folder);
     if path.exists() {
         tracing::info!("Loading {key:?} data from {}...", path.display());
         let f = BufReader::new(std::fs::File::open(path).expect("path exists"));
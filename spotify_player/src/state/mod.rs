@@ -14,6 +14,9 @@ use crate::config;
 
 pub use parking_lot::{Mutex, RwLock};
 
+/// maximum number of the playback events buffered for a subscriber lagging behind
+const PLAYBACK_EVENTS_CAPACITY: usize = 64;
+
 /// Application's shared state (wrapped inside an `std::sync::Arc`)
 pub type SharedState = std::sync::Arc<State>;
 
@@ -22,6 +25,35 @@ pub struct State {
     pub ui: Mutex<UIState>,
     pub player: RwLock<PlayerState>,
     pub data: RwLock<AppData>,
+    /// sender of the playback events, to which the application's integrations subscribe
+    pub playback_events: tokio::sync::broadcast::Sender<PlaybackEvent>,
+
+    /// whether the current session is private, which suppresses the scrobbling,
+    /// the listening history and the notifications
+    pub private_session: Mutex<bool>,
+
+    /// streaming bitrate (in kbps) of the integrated device, which can be switched at runtime
+    #[cfg(feature = "streaming")]
+    pub bitrate: Mutex<u16>,
+    /// audio output of the integrated device, which is applied upon a new streaming connection
+    #[cfg(feature = "streaming")]
+    pub audio_output: Mutex<crate::streaming::AudioOutput>,
+    /// equalizer settings of the integrated device, which are shared with the equalizer's sink
+    #[cfg(feature = "streaming")]
+    pub equalizer: arc_swap::ArcSwap<crate::equalizer::EqualizerSettings>,
+    /// whether the night mode's compression is applied to the integrated device's audio
+    #[cfg(feature = "streaming")]
+    pub night_mode: Mutex<bool>,
+    /// whether the integrated device autoplays similar tracks when its playing context ends,
+    /// which is applied upon a new streaming connection
+    #[cfg(feature = "streaming")]
+    pub autoplay: Mutex<bool>,
+    /// whether the integrated device's audio is fading out before a playback transition
+    #[cfg(feature = "streaming")]
+    pub fading_out: Mutex<bool>,
+    /// profile of the integrated device (if any), which is applied upon a new streaming connection
+    #[cfg(feature = "streaming")]
+    pub device_profile: Mutex<Option<config::DeviceProfile>>,
 
     pub is_daemon: bool,
 }
@@ -31,21 +63,80 @@ impl State {
         let mut ui = UIState::default();
         let configs = config::get_config();
 
-        if let Some(theme) = configs.theme_config.find_theme(&configs.app_config.theme) {
+        if let Some(theme) = configs
+            .theme_config
+            .find_theme(configs.app_config.theme_name())
+        {
             // update the UI's theme based on the `theme` config option
             ui.theme = theme;
         }
 
         let app_data = AppData::new(&configs.cache_folder);
 
+        #[cfg(feature = "streaming")]
+        let device_profile = configs
+            .app_config
+            .device_profile
+            .as_deref()
+            .and_then(|name| configs.app_config.find_device_profile(name))
+            .cloned();
+
         Self {
             ui: Mutex::new(ui),
             player: RwLock::new(PlayerState::default()),
             data: RwLock::new(app_data),
+            playback_events: tokio::sync::broadcast::channel(PLAYBACK_EVENTS_CAPACITY).0,
+            private_session: Mutex::new(false),
+            #[cfg(feature = "streaming")]
+            bitrate: Mutex::new(configs.app_config.device.bitrate),
+            #[cfg(feature = "streaming")]
+            audio_output: Mutex::new(crate::streaming::AudioOutput::new(device_profile.as_ref())),
+            #[cfg(feature = "streaming")]
+            device_profile: Mutex::new(device_profile),
+            #[cfg(feature = "streaming")]
+            equalizer: arc_swap::ArcSwap::from_pointee(crate::equalizer::EqualizerSettings::load()),
+            #[cfg(feature = "streaming")]
+            night_mode: Mutex::new(configs.app_config.device.night_mode),
+            #[cfg(feature = "streaming")]
+            autoplay: Mutex::new(configs.app_config.device.autoplay),
+            #[cfg(feature = "streaming")]
+            fading_out: Mutex::new(false),
             is_daemon,
         }
     }
 
+    /// Publish the playback events happening since the last publication,
+    /// which is called upon every update of the player's playback
+    pub fn publish_playback_events(&self) {
+        let events = self.player.write().take_playback_events();
+        for event in events {
+            self.publish_event(event);
+        }
+    }
+
+    pub fn publish_event(&self, event: PlaybackEvent) {
+        tracing::debug!("Publishing a playback event: {event:?}");
+        // sending an event only fails if there is no subscriber
+        let _ = self.playback_events.send(event);
+    }
+
+    /// Get the integrated device's name and type, which are overridden by the device profile (if any)
+    #[cfg(feature = "streaming")]
+    pub fn device_name_and_type(&self) -> (String, String) {
+        let configs = config::get_config();
+        let device = &configs.app_config.device;
+        match &*self.device_profile.lock() {
+            Some(profile) => (
+                profile.name.clone(),
+                profile
+                    .device_type
+                    .clone()
+                    .unwrap_or_else(|| device.device_type.clone()),
+            ),
+            None => (device.name.clone(), device.device_type.clone()),
+        }
+    }
+
     #[cfg(feature = "streaming")]
     pub fn is_streaming_enabled(&self) -> bool {
         let configs = config::get_config();
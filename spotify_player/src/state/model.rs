@@ -1,3 +1,4 @@
+use crate::config::SearchCategory;
 use crate::ui::utils::to_bidi_string;
 use crate::utils::map_join;
 use html_escape::decode_html_entities;
@@ -81,9 +82,83 @@ pub struct SearchResults {
     pub playlists: Vec<Playlist>,
     pub shows: Vec<Show>,
     pub episodes: Vec<Episode>,
+    #[serde(default)]
+    pub audiobooks: Vec<Audiobook>,
+    /// offsets of the categories' next pages in the API's results, which can differ from
+    /// the numbers of the categories' items as the API may return `null` items.
+    /// A category without a next page has no offset.
+    #[serde(default)]
+    pub next_offsets: std::collections::HashMap<SearchCategory, u32>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+/// A named search query saved by the user
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+    /// whether the search only shows results that are in the user's library
+    pub library_only: bool,
+}
+
+impl std::fmt::Display for SavedSearch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.name, self.query)?;
+        if self.library_only {
+            write!(f, " (in library)")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+/// An entry of the listening history recorded by the application
+pub struct ListeningHistoryEntry {
+    /// when the item started playing, in RFC 3339 format
+    pub played_at: String,
+    pub uri: String,
+    pub name: String,
+    /// the track's artists or the episode's show publisher
+    pub artists: String,
+    /// the track's album or the episode's show
+    pub album: String,
+    pub duration_ms: i64,
+}
+
+impl ListeningHistoryEntry {
+    /// The maximum play time required for an item to be recorded, shorter items
+    /// are recorded once half of them is played
+    const MAX_REQUIRED_PLAY_TIME: chrono::Duration = chrono::Duration::minutes(4);
+
+    /// creates an entry of an item that stopped playing at the given progress,
+    /// or `None` if the item wasn't played long enough to be recorded
+    pub fn new(
+        playable: &rspotify::model::PlayableItem,
+        progress: chrono::Duration,
+    ) -> Option<Self> {
+        let value = |placeholder| {
+            crate::utils::get_playable_placeholder_value(placeholder, playable).unwrap_or_default()
+        };
+        let duration = match playable {
+            rspotify::model::PlayableItem::Track(track) => track.duration,
+            rspotify::model::PlayableItem::Episode(episode) => episode.duration,
+        };
+
+        if progress < std::cmp::min(duration / 2, Self::MAX_REQUIRED_PLAY_TIME) {
+            return None;
+        }
+
+        Some(Self {
+            played_at: (chrono::Utc::now() - progress).to_rfc3339(),
+            uri: playable.id().map(|id| id.uri()).unwrap_or_default(),
+            name: value("{track}"),
+            artists: value("{artists}"),
+            album: value("{album}"),
+            duration_ms: duration.num_milliseconds(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 /// A track order
 pub enum TrackOrder {
     AddedAt,
@@ -128,7 +203,7 @@ pub struct PlaybackMetadata {
     pub fake_track_repeat_state: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 /// A Spotify device
 pub struct Device {
     pub id: String,
@@ -198,6 +273,14 @@ pub struct Episode {
     pub release_date: String,
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+/// A Spotify audiobook
+pub struct Audiobook {
+    pub id: String,
+    pub name: String,
+    pub authors: Vec<String>,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 /// A playlist folder, not related to Spotify API yet
 pub struct PlaylistFolder {
@@ -236,6 +319,19 @@ pub struct Category {
 }
 
 impl Context {
+    /// gets the context's tracks, `None` for a show context
+    pub fn tracks_mut(&mut self) -> Option<&mut Vec<Track>> {
+        match self {
+            Context::Album { tracks, .. }
+            | Context::Playlist { tracks, .. }
+            | Context::Tracks { tracks, .. }
+            | Context::Artist {
+                top_tracks: tracks, ..
+            } => Some(tracks),
+            Context::Show { .. } => None,
+        }
+    }
+
     /// gets the context's description
     pub fn description(&self) -> String {
         match self {
@@ -245,7 +341,7 @@ impl Context {
             } => format!(
                 "{} | {} | {} songs | {}",
                 album.name,
-                album.release_date,
+                crate::utils::format_date(&album.release_date),
                 tracks.len(),
                 play_time(tracks),
             ),
@@ -320,6 +416,81 @@ impl TrackOrder {
     }
 }
 
+impl SearchResults {
+    /// appends items of other search results to the current results
+    pub fn extend(&mut self, other: SearchResults) {
+        self.tracks.extend(other.tracks);
+        self.artists.extend(other.artists);
+        self.albums.extend(other.albums);
+        self.playlists.extend(other.playlists);
+        self.shows.extend(other.shows);
+        self.episodes.extend(other.episodes);
+        self.audiobooks.extend(other.audiobooks);
+        self.next_offsets.extend(other.next_offsets);
+    }
+
+    /// appends the next page of a specific category's results to the current results
+    pub fn extend_category(&mut self, category: SearchCategory, other: SearchResults) {
+        if !other.next_offsets.contains_key(&category) {
+            self.next_offsets.remove(&category);
+        }
+        self.extend(other);
+    }
+}
+
+impl From<rspotify::model::SearchResult> for SearchResults {
+    fn from(result: rspotify::model::SearchResult) -> Self {
+        use rspotify::model::SearchResult;
+
+        /// gets the offset of the page following a page, if any
+        fn next_offset<T>(page: &rspotify::model::Page<T>) -> Option<u32> {
+            page.next.as_ref().map(|_| page.offset + page.limit)
+        }
+
+        let mut results = Self::default();
+        let (category, next_offset) = match &result {
+            SearchResult::Tracks(p) => (SearchCategory::Tracks, next_offset(p)),
+            SearchResult::Artists(p) => (SearchCategory::Artists, next_offset(p)),
+            SearchResult::Albums(p) => (SearchCategory::Albums, next_offset(p)),
+            SearchResult::Playlists(p) => (SearchCategory::Playlists, next_offset(p)),
+            SearchResult::Shows(p) => (SearchCategory::Shows, next_offset(p)),
+            SearchResult::Episodes(p) => (SearchCategory::Episodes, next_offset(p)),
+        };
+        if let Some(offset) = next_offset {
+            results.next_offsets.insert(category, offset);
+        }
+        match result {
+            SearchResult::Tracks(p) => {
+                results.tracks = p
+                    .items
+                    .into_iter()
+                    .filter_map(Track::try_from_full_track)
+                    .collect();
+            }
+            SearchResult::Artists(p) => {
+                results.artists = p.items.into_iter().map(std::convert::Into::into).collect();
+            }
+            SearchResult::Albums(p) => {
+                results.albums = p
+                    .items
+                    .into_iter()
+                    .filter_map(Album::try_from_simplified_album)
+                    .collect();
+            }
+            SearchResult::Playlists(p) => {
+                results.playlists = p.items.into_iter().map(std::convert::Into::into).collect();
+            }
+            SearchResult::Shows(p) => {
+                results.shows = p.items.into_iter().map(std::convert::Into::into).collect();
+            }
+            SearchResult::Episodes(p) => {
+                results.episodes = p.items.into_iter().map(std::convert::Into::into).collect();
+            }
+        }
+        results
+    }
+}
+
 impl Device {
     /// tries to convert from a `rspotify::model::Device` into `Device`
     pub fn try_from_device(device: rspotify::model::Device) -> Option<Self> {
@@ -612,6 +783,21 @@ impl std::fmt::Display for Show {
 
 impl BidiDisplay for Show {}
 
+impl Audiobook {
+    /// gets the audiobook's authors information
+    pub fn authors_info(&self) -> String {
+        self.authors.join(", ")
+    }
+}
+
+impl std::fmt::Display for Audiobook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} • {}", self.name, self.authors_info())
+    }
+}
+
+impl BidiDisplay for Audiobook {}
+
 impl From<rspotify::model::SimplifiedEpisode> for Episode {
     fn from(episode: rspotify::model::SimplifiedEpisode) -> Self {
         Self {
@@ -696,6 +882,21 @@ impl TracksId {
 }
 
 impl Playback {
+    /// gets the type of the playback's context
+    pub fn context_type(&self) -> crate::config::PlaybackContextType {
+        use crate::config::PlaybackContextType;
+
+        match self {
+            Playback::Context(ContextId::Playlist(_), _) => PlaybackContextType::Playlist,
+            Playback::Context(ContextId::Album(_), _) => PlaybackContextType::Album,
+            Playback::Context(ContextId::Artist(_), _) => PlaybackContextType::Artist,
+            Playback::Context(ContextId::Show(_), _) => PlaybackContextType::Show,
+            Playback::Context(ContextId::Tracks(_), _) | Playback::URIs(..) => {
+                PlaybackContextType::Tracks
+            }
+        }
+    }
+
     /// creates new playback with a specified offset based on the current playback
     pub fn uri_offset(&self, uri: String, limit: usize) -> Self {
         match self {
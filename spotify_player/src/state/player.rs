@@ -1,3 +1,5 @@
+use rspotify::prelude::Id;
+
 use super::model::{AlbumId, ArtistId, ContextId, Device, PlaybackMetadata, PlaylistId, ShowId};
 
 /// Player state
@@ -12,6 +14,114 @@ pub struct PlayerState {
     pub buffered_playback: Option<PlaybackMetadata>,
 
     pub queue: Option<rspotify::model::CurrentUserQueue>,
+    /// URIs of the items added to the queue by the user, which tell them apart
+    /// from the items added by autoplay
+    pub user_queued_items: std::collections::HashSet<String>,
+
+    /// whether the client is reconnecting after losing its session, e.g. after a network loss
+    pub reconnecting: bool,
+
+    /// snapshot of the player's state upon the last published playback events
+    published_snapshot: Option<PlaybackSnapshot>,
+}
+
+/// A change of the player's state, published to the subscribers of `State::playback_events`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlaybackEvent {
+    /// An item starts playing, including when the same item restarts, e.g. upon repeating it
+    TrackChange,
+    Play,
+    Pause,
+    DeviceChange,
+    VolumeChange,
+    /// The playback's shuffle state, repeat state or context changes
+    MetadataChange,
+    /// The playback's position changes unexpectedly, e.g. upon seeking
+    Seek,
+    QueueChange,
+    LikedTracksChange,
+}
+
+/// The player's state compared to detect the playback events
+#[derive(Debug)]
+struct PlaybackSnapshot {
+    item_uri: Option<String>,
+    is_playing: bool,
+    device_id: Option<String>,
+    volume: Option<u32>,
+    metadata: (bool, rspotify::model::RepeatState, Option<String>),
+    progress: chrono::Duration,
+    time: std::time::Instant,
+}
+
+impl PlaybackSnapshot {
+    fn new(playback: &rspotify::model::CurrentPlaybackContext) -> Self {
+        let item_uri = match playback.item {
+            Some(rspotify::model::PlayableItem::Track(ref track)) => track.id.as_ref().map(Id::uri),
+            Some(rspotify::model::PlayableItem::Episode(ref episode)) => Some(episode.id.uri()),
+            None => None,
+        };
+        Self {
+            item_uri,
+            is_playing: playback.is_playing,
+            device_id: playback.device.id.clone(),
+            volume: playback.device.volume_percent,
+            metadata: (
+                playback.shuffle_state,
+                playback.repeat_state,
+                playback.context.as_ref().map(|c| c.uri.clone()),
+            ),
+            progress: playback.progress.unwrap_or_default(),
+            time: std::time::Instant::now(),
+        }
+    }
+
+    /// Get the events happening since a previous snapshot
+    fn events(&self, prev: Option<&Self>) -> Vec<PlaybackEvent> {
+        let Some(prev) = prev else {
+            return if self.item_uri.is_some() {
+                vec![PlaybackEvent::TrackChange]
+            } else {
+                vec![]
+            };
+        };
+
+        let mut events = Vec::new();
+        // the expected position if the playback hasn't been changed since the previous snapshot
+        let expected_progress = if prev.is_playing {
+            prev.progress + chrono::Duration::from_std(prev.time.elapsed()).unwrap_or_default()
+        } else {
+            prev.progress
+        };
+        if self.item_uri != prev.item_uri {
+            if self.item_uri.is_some() {
+                events.push(PlaybackEvent::TrackChange);
+            }
+        } else if self.progress < chrono::Duration::seconds(10)
+            && self.progress + chrono::Duration::seconds(5) < expected_progress
+        {
+            events.push(PlaybackEvent::TrackChange);
+        } else if (self.progress - expected_progress).num_milliseconds().abs() > 2000 {
+            events.push(PlaybackEvent::Seek);
+        }
+        if self.is_playing != prev.is_playing {
+            events.push(if self.is_playing {
+                PlaybackEvent::Play
+            } else {
+                PlaybackEvent::Pause
+            });
+        }
+        if self.device_id != prev.device_id {
+            events.push(PlaybackEvent::DeviceChange);
+        }
+        if self.volume != prev.volume {
+            events.push(PlaybackEvent::VolumeChange);
+        }
+        if self.metadata != prev.metadata {
+            events.push(PlaybackEvent::MetadataChange);
+        }
+        events
+    }
 }
 
 impl PlayerState {
@@ -46,10 +156,31 @@ impl PlayerState {
         Some(playback)
     }
 
+    /// Get the playback events happening since the last call,
+    /// which is called upon every update of the playback
+    pub(super) fn take_playback_events(&mut self) -> Vec<PlaybackEvent> {
+        let snapshot = self.current_playback().as_ref().map(PlaybackSnapshot::new);
+        let events = match snapshot {
+            Some(ref snapshot) => snapshot.events(self.published_snapshot.as_ref()),
+            None => vec![],
+        };
+        self.published_snapshot = snapshot;
+        events
+    }
+
     pub fn currently_playing(&self) -> Option<&rspotify::model::PlayableItem> {
         self.playback.as_ref().and_then(|p| p.item.as_ref())
     }
 
+    /// Update the playback's progress upon seeking the playback, so the new position is used
+    /// without waiting for the next playback refresh, e.g. by consecutive seeks
+    pub fn seek_progress(&mut self, position: chrono::Duration) {
+        if let Some(ref mut playback) = self.playback {
+            playback.progress = Some(position);
+            self.playback_last_updated_time = Some(std::time::Instant::now());
+        }
+    }
+
     pub fn playback_progress(&self) -> Option<chrono::Duration> {
         match self.playback {
             None => None,
@@ -69,29 +200,29 @@ impl PlayerState {
     }
 
     pub fn playing_context_id(&self) -> Option<ContextId> {
-        match self.playback {
-            Some(ref playback) => match playback.context {
-                Some(ref context) => {
-                    let uri = crate::utils::parse_uri(&context.uri);
-                    match context._type {
-                        rspotify::model::Type::Playlist => Some(ContextId::Playlist(
-                            PlaylistId::from_uri(&uri).ok()?.into_static(),
-                        )),
-                        rspotify::model::Type::Album => Some(ContextId::Album(
-                            AlbumId::from_uri(&uri).ok()?.into_static(),
-                        )),
-                        rspotify::model::Type::Artist => Some(ContextId::Artist(
-                            ArtistId::from_uri(&uri).ok()?.into_static(),
-                        )),
-                        rspotify::model::Type::Show => {
-                            Some(ContextId::Show(ShowId::from_uri(&uri).ok()?.into_static()))
-                        }
-                        _ => None,
-                    }
-                }
-                None => None,
-            },
-            None => None,
+        self.playback.as_ref().and_then(playback_context_id)
+    }
+}
+
+/// Gets the ID of a playback's context, if any
+pub fn playback_context_id(
+    playback: &rspotify::model::CurrentPlaybackContext,
+) -> Option<ContextId> {
+    let context = playback.context.as_ref()?;
+    let uri = crate::utils::parse_uri(&context.uri);
+    match context._type {
+        rspotify::model::Type::Playlist => Some(ContextId::Playlist(
+            PlaylistId::from_uri(&uri).ok()?.into_static(),
+        )),
+        rspotify::model::Type::Album => Some(ContextId::Album(
+            AlbumId::from_uri(&uri).ok()?.into_static(),
+        )),
+        rspotify::model::Type::Artist => Some(ContextId::Artist(
+            ArtistId::from_uri(&uri).ok()?.into_static(),
+        )),
+        rspotify::model::Type::Show => {
+            Some(ContextId::Show(ShowId::from_uri(&uri).ok()?.into_static()))
         }
+        _ => None,
     }
 }
@@ -1,3 +1,5 @@
+use std::collections::{HashMap, HashSet};
+
 use crate::{
     config::{self, Theme},
     key,
@@ -9,7 +11,7 @@ pub type UIStateGuard<'a> = parking_lot::MutexGuard<'a, UIState>;
 mod page;
 mod popup;
 
-use super::TracksId;
+use super::{Track, TrackOrder, TracksId};
 
 pub use page::*;
 pub use popup::*;
@@ -27,8 +29,13 @@ pub struct ImageRenderInfo {
 #[derive(Debug)]
 pub struct UIState {
     pub is_running: bool,
+    /// whether the UI is stopped because the application failed, in which case the UI returns
+    /// after restoring the terminal instead of exiting the process, so the error can be reported
+    pub is_stopped_by_error: bool,
     pub theme: config::Theme,
     pub input_key_sequence: key::KeySequence,
+    /// time of the last key of the pending input key sequence
+    pub input_key_time: Option<std::time::Instant>,
     pub orientation: ui::Orientation,
 
     pub history: Vec<PageState>,
@@ -49,9 +56,52 @@ pub struct UIState {
 
     /// Track last click timestamp and position for double-click detection
     pub last_click: Option<LastClick>,
+
+    /// Remembered views of the visited context pages, keyed by the contexts' URIs
+    pub context_views: HashMap<String, ContextView>,
+
+    /// Destructive actions confirmed with "Yes to all", which are no longer confirmed
+    pub skipped_confirmations: HashSet<config::DestructiveAction>,
+}
+
+/// The view of a context page, restored when returning to the context
+#[derive(Default, Debug, Clone)]
+pub struct ContextView {
+    /// the order the context's tracks were last sorted by
+    pub order: Option<TrackOrder>,
+    /// whether the context's tracks were reversed after being sorted
+    pub reversed: bool,
+    /// the query filtering the context's tracks
+    pub filter: Option<String>,
+    /// the selected item's position in the page's focused window
+    pub selected: Option<usize>,
+}
+
+impl ContextView {
+    /// Re-apply the remembered order to the (re-)fetched tracks of the context
+    pub fn sort_tracks(&self, tracks: &mut [Track]) {
+        if let Some(order) = self.order {
+            tracks.sort_by(|x, y| order.compare(x, y));
+        }
+        if self.reversed {
+            tracks.reverse();
+        }
+    }
 }
 
 impl UIState {
+    /// Clear the pending input key sequence if no key is pressed within the key sequence timeout
+    pub fn expire_input_key_sequence(&mut self) {
+        let timeout = config::get_config().app_config.key_sequence_timeout_in_ms;
+        let is_expired = self.input_key_time.is_some_and(|time| {
+            timeout > 0 && time.elapsed() > std::time::Duration::from_millis(timeout)
+        });
+        if is_expired {
+            self.input_key_sequence.keys = vec![];
+            self.input_key_time = None;
+        }
+    }
+
     pub fn current_page(&self) -> &PageState {
         self.history.last().expect("non-empty history")
     }
@@ -64,14 +114,58 @@ impl UIState {
         self.current_page_mut().select(0);
         self.popup = Some(PopupState::Search {
             query: String::new(),
+            is_editing: true,
         });
     }
 
     pub fn new_page(&mut self, page: PageState) {
+        self.save_context_view();
         self.history.push(page);
         self.popup = None;
     }
 
+    /// Remember the filter and the selection of the current context page
+    pub fn save_context_view(&mut self) {
+        let uri = match self.current_page() {
+            PageState::Context {
+                id: Some(id),
+                state: Some(_),
+                ..
+            } => id.uri(),
+            _ => return,
+        };
+        let filter = match &self.popup {
+            Some(PopupState::Search { query, .. }) if !query.is_empty() => Some(query.clone()),
+            _ => None,
+        };
+        let selected = self.current_page_mut().selected();
+
+        let view = self.context_views.entry(uri).or_default();
+        view.filter = filter;
+        view.selected = selected;
+    }
+
+    /// Restore the remembered filter and selection of the current context page
+    pub fn restore_context_view(&mut self) {
+        let view = match self.current_page() {
+            PageState::Context { id: Some(id), .. } => self.context_views.get(&id.uri()).cloned(),
+            _ => None,
+        };
+        let Some(view) = view else {
+            return;
+        };
+
+        if let Some(query) = view.filter {
+            self.popup = Some(PopupState::Search {
+                query,
+                is_editing: false,
+            });
+        }
+        if let Some(selected) = view.selected {
+            self.current_page_mut().select(selected);
+        }
+    }
+
     pub fn new_radio_page(&mut self, uri: &str) {
         self.new_page(PageState::Context {
             id: None,
@@ -96,32 +190,89 @@ impl UIState {
     /// Get a list of items possibly filtered by a search query if exists a search popup
     pub fn search_filtered_items<'a, T: std::fmt::Display>(&self, items: &'a [T]) -> Vec<&'a T> {
         match self.popup {
-            Some(PopupState::Search { ref query }) => {
-                let query = query.to_lowercase();
-
-                #[cfg(feature = "fzf")]
-                return fuzzy_search_items(items, &query);
-
-                #[cfg(not(feature = "fzf"))]
-                items
-                    .iter()
-                    .filter(|t| {
-                        if query.is_empty() {
-                            true
-                        } else {
-                            let t = t.to_string().to_lowercase();
-                            query
-                                .split(' ')
-                                .filter(|q| !q.is_empty())
-                                .all(|q| t.contains(q))
-                        }
-                    })
-                    .collect::<Vec<_>>()
-            }
+            Some(PopupState::Search { ref query, .. }) => filter_items(items, query),
             _ => items.iter().collect::<Vec<_>>(),
         }
     }
 }
+
+/// Get a list of items matching a search query.
+///
+/// Items are fuzzy-matched if the `fzf` feature is enabled. Otherwise, an item matches
+/// if it contains all the query's words.
+pub fn filter_items<'a, T: std::fmt::Display>(items: &'a [T], query: &str) -> Vec<&'a T> {
+    let query = query.to_lowercase();
+
+    #[cfg(feature = "fzf")]
+    return fuzzy_search_items(items, &query);
+
+    #[cfg(not(feature = "fzf"))]
+    items
+        .iter()
+        .filter(|t| {
+            if query.is_empty() {
+                true
+            } else {
+                let t = t.to_string().to_lowercase();
+                query
+                    .split(' ')
+                    .filter(|q| !q.is_empty())
+                    .all(|q| t.contains(q))
+            }
+        })
+        .collect::<Vec<_>>()
+}
+
+/// Get a list of items fuzzy-matching a search query, the best matches first.
+///
+/// Items are matched by `fzf`'s algorithm if the `fzf` feature is enabled. Otherwise, an item
+/// matches if it contains the query's characters in order, see [`fuzzy_match_score`].
+pub fn fuzzy_match_items<'a, T: std::fmt::Display>(items: &'a [T], query: &str) -> Vec<&'a T> {
+    let query = query.to_lowercase();
+
+    #[cfg(feature = "fzf")]
+    return fuzzy_search_items(items, &query);
+
+    #[cfg(not(feature = "fzf"))]
+    {
+        let mut result = items
+            .iter()
+            .filter_map(|t| fuzzy_match_score(&t.to_string(), &query).map(|score| (t, score)))
+            .collect::<Vec<_>>();
+        // the sort is stable, so the equally matching items keep their order
+        result.sort_by(|(_, a), (_, b)| b.cmp(a));
+        result.into_iter().map(|(t, _)| t).collect::<Vec<_>>()
+    }
+}
+
+/// Get the score of a text matching a lowercase query, i.e. the text contains the query's
+/// characters (except whitespaces) in order, or `None` if the text doesn't match.
+///
+/// The characters at the start of the text's words and the consecutive characters
+/// score higher, while the characters far from the previous matched character score lower.
+#[cfg(not(feature = "fzf"))]
+fn fuzzy_match_score(text: &str, query: &str) -> Option<i64> {
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut start = 0;
+    let mut last_match: Option<usize> = None;
+    for c in query.chars().filter(|c| !c.is_whitespace()) {
+        let i = start + text[start..].iter().position(|t| *t == c)?;
+        score += 1;
+        if i == 0 || !text[i - 1].is_alphanumeric() {
+            score += 8;
+        }
+        match last_match {
+            Some(j) if j + 1 == i => score += 4,
+            Some(j) => score -= std::cmp::min(i - j - 1, 3) as i64,
+            None => {}
+        }
+        last_match = Some(i);
+        start = i + 1;
+    }
+    Some(score)
+}
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct UIRects {
     // Library page
@@ -137,6 +288,7 @@ pub struct UIRects {
     pub search_playlists: ratatui::layout::Rect,
     pub search_shows: ratatui::layout::Rect,
     pub search_episodes: ratatui::layout::Rect,
+    pub search_audiobooks: ratatui::layout::Rect,
 
     // Context pages (generic tracks table)
     pub context_tracks: ratatui::layout::Rect,
@@ -151,6 +303,9 @@ pub struct UIRects {
     // Other pages
     pub queue_rect: ratatui::layout::Rect,
     pub command_help_rect: ratatui::layout::Rect,
+
+    // List of the opened popup (if any)
+    pub popup_list: ratatui::layout::Rect,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -184,8 +339,10 @@ impl Default for UIState {
     fn default() -> Self {
         Self {
             is_running: true,
+            is_stopped_by_error: false,
             theme: Theme::default(),
             input_key_sequence: key::KeySequence { keys: vec![] },
+            input_key_time: None,
             orientation: match crossterm::terminal::size() {
                 Ok((columns, rows)) => ui::Orientation::from_size(columns, rows),
                 Err(err) => {
@@ -209,6 +366,10 @@ impl Default for UIState {
             rects: UIRects::default(),
 
             last_click: None,
+
+            context_views: HashMap::new(),
+
+            skipped_confirmations: HashSet::new(),
         }
     }
 }
@@ -1,4 +1,5 @@
 use crate::{
+    config::{self, LibraryWindow, SearchCategory},
     state::model::{Category, ContextId},
     ui::single_line_input::LineInput,
 };
@@ -33,6 +34,11 @@ pub enum PageState {
     CommandHelp {
         scroll_offset: usize,
     },
+    Diagnostics {
+        /// the diagnostics' names and values, collected when opening the page
+        entries: Vec<(String, String)>,
+        scroll_offset: usize,
+    },
 }
 
 #[derive(PartialEq, Eq, Clone, Copy)]
@@ -44,6 +50,7 @@ pub enum PageType {
     Lyrics,
     Queue,
     CommandHelp,
+    Diagnostics,
 }
 
 #[derive(Clone, Debug)]
@@ -63,7 +70,13 @@ pub struct SearchPageUIState {
     pub playlist_list: ListState,
     pub show_list: ListState,
     pub episode_list: ListState,
+    pub audiobook_list: ListState,
     pub focus: SearchFocusState,
+    /// position of the recalled query in the search history (if any)
+    pub history_index: Option<usize>,
+    pub mode: SearchMode,
+    /// whether to only show search results that are already in the user's library
+    pub library_only: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -108,6 +121,15 @@ pub enum ArtistFocusState {
     RelatedArtists,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Where a search query is looked up
+pub enum SearchMode {
+    /// search using Spotify APIs
+    Spotify,
+    /// search in the user's cached library, without making any API requests
+    Library,
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum SearchFocusState {
     Input,
@@ -117,6 +139,7 @@ pub enum SearchFocusState {
     Playlists,
     Shows,
     Episodes,
+    Audiobooks,
 }
 
 #[derive(Clone, Debug)]
@@ -147,6 +170,7 @@ impl PageState {
             PageState::Lyrics { .. } => PageType::Lyrics,
             PageState::Queue { .. } => PageType::Queue,
             PageState::CommandHelp { .. } => PageType::CommandHelp,
+            PageState::Diagnostics { .. } => PageType::Diagnostics,
         }
     }
 
@@ -191,7 +215,9 @@ impl PageState {
                         playlist_list,
                         show_list,
                         episode_list,
+                        audiobook_list,
                         focus,
+                        ..
                     },
                 ..
             } => match focus {
@@ -202,6 +228,7 @@ impl PageState {
                 SearchFocusState::Playlists => Some(MutableWindowState::List(playlist_list)),
                 SearchFocusState::Shows => Some(MutableWindowState::List(show_list)),
                 SearchFocusState::Episodes => Some(MutableWindowState::List(episode_list)),
+                SearchFocusState::Audiobooks => Some(MutableWindowState::List(audiobook_list)),
             },
             Self::Context { state, .. } => state.as_mut().map(|state| match state {
                 ContextPageUIState::Tracks { track_table }
@@ -232,7 +259,9 @@ impl PageState {
                 }
             },
             Self::Lyrics { .. } => None,
-            Self::CommandHelp { scroll_offset } | Self::Queue { scroll_offset } => {
+            Self::CommandHelp { scroll_offset }
+            | Self::Queue { scroll_offset }
+            | Self::Diagnostics { scroll_offset, .. } => {
                 Some(MutableWindowState::Scroll(scroll_offset))
             }
         }
@@ -245,14 +274,14 @@ impl LibraryPageUIState {
             playlist_list: ListState::default(),
             saved_album_list: ListState::default(),
             followed_artist_list: ListState::default(),
-            focus: LibraryFocusState::Playlists,
+            focus: LibraryFocusState::focus_order()[0],
             playlist_folder_id: 0,
         }
     }
 }
 
 impl SearchPageUIState {
-    pub fn new() -> Self {
+    pub fn new(mode: SearchMode) -> Self {
         Self {
             track_list: ListState::default(),
             album_list: ListState::default(),
@@ -260,7 +289,80 @@ impl SearchPageUIState {
             playlist_list: ListState::default(),
             show_list: ListState::default(),
             episode_list: ListState::default(),
+            audiobook_list: ListState::default(),
             focus: SearchFocusState::Input,
+            history_index: None,
+            mode,
+            library_only: false,
+        }
+    }
+}
+
+impl SearchFocusState {
+    /// gets the search category of the focused result window, or `None` for the search input
+    pub fn category(self) -> Option<SearchCategory> {
+        match self {
+            Self::Input => None,
+            Self::Tracks => Some(SearchCategory::Tracks),
+            Self::Albums => Some(SearchCategory::Albums),
+            Self::Artists => Some(SearchCategory::Artists),
+            Self::Playlists => Some(SearchCategory::Playlists),
+            Self::Shows => Some(SearchCategory::Shows),
+            Self::Episodes => Some(SearchCategory::Episodes),
+            Self::Audiobooks => Some(SearchCategory::Audiobooks),
+        }
+    }
+
+    /// gets the search page's windows in the focus order, which is the search input
+    /// followed by the result windows in the configured order
+    fn focus_order() -> Vec<Self> {
+        std::iter::once(Self::Input)
+            .chain(
+                config::get_config()
+                    .app_config
+                    .search
+                    .categories
+                    .iter()
+                    .map(|c| c.category.into()),
+            )
+            .collect()
+    }
+}
+
+impl From<SearchCategory> for SearchFocusState {
+    fn from(category: SearchCategory) -> Self {
+        match category {
+            SearchCategory::Tracks => Self::Tracks,
+            SearchCategory::Albums => Self::Albums,
+            SearchCategory::Artists => Self::Artists,
+            SearchCategory::Playlists => Self::Playlists,
+            SearchCategory::Shows => Self::Shows,
+            SearchCategory::Episodes => Self::Episodes,
+            SearchCategory::Audiobooks => Self::Audiobooks,
+        }
+    }
+}
+
+impl LibraryFocusState {
+    /// gets the library page's windows in the focus order, which is the configured display order
+    fn focus_order() -> Vec<Self> {
+        config::get_config()
+            .app_config
+            .layout
+            .library
+            .windows()
+            .iter()
+            .map(|w| w.window.into())
+            .collect()
+    }
+}
+
+impl From<LibraryWindow> for LibraryFocusState {
+    fn from(window: LibraryWindow) -> Self {
+        match window {
+            LibraryWindow::Playlists => Self::Playlists,
+            LibraryWindow::Albums => Self::SavedAlbums,
+            LibraryWindow::Artists => Self::FollowedArtists,
         }
     }
 }
@@ -410,13 +512,6 @@ macro_rules! impl_focusable {
 	};
 }
 
-impl_focusable!(
-    LibraryFocusState,
-    [Playlists, SavedAlbums],
-    [SavedAlbums, FollowedArtists],
-    [FollowedArtists, Playlists]
-);
-
 impl_focusable!(
     ArtistFocusState,
     [TopTracks, Albums],
@@ -424,13 +519,30 @@ impl_focusable!(
     [RelatedArtists, TopTracks]
 );
 
-impl_focusable!(
-    SearchFocusState,
-    [Input, Tracks],
-    [Tracks, Albums],
-    [Albums, Artists],
-    [Artists, Playlists],
-    [Playlists, Shows],
-    [Shows, Episodes],
-    [Episodes, Input]
-);
+impl Focusable for LibraryFocusState {
+    fn next(&mut self) {
+        let order = Self::focus_order();
+        let id = order.iter().position(|f| f == self).unwrap_or_default();
+        *self = order[(id + 1) % order.len()];
+    }
+
+    fn previous(&mut self) {
+        let order = Self::focus_order();
+        let id = order.iter().position(|f| f == self).unwrap_or_default();
+        *self = order[(id + order.len() - 1) % order.len()];
+    }
+}
+
+impl Focusable for SearchFocusState {
+    fn next(&mut self) {
+        let order = Self::focus_order();
+        let id = order.iter().position(|f| f == self).unwrap_or_default();
+        *self = order[(id + 1) % order.len()];
+    }
+
+    fn previous(&mut self) {
+        let order = Self::focus_order();
+        let id = order.iter().position(|f| f == self).unwrap_or_default();
+        *self = order[(id + order.len() - 1) % order.len()];
+    }
+}
@@ -1,6 +1,14 @@
 use crate::{
-    command,
-    state::model::{Album, Artist, Episode, EpisodeId, Playlist, Show, Track, TrackId},
+    command::{self, Command},
+    config,
+    key::KeySequence,
+    state::{
+        model::{
+            Album, Artist, Audiobook, Episode, EpisodeId, Playlist, PlaylistFolderItem, Show,
+            Track, TrackId,
+        },
+        AppData,
+    },
     ui::single_line_input::LineInput,
 };
 use ratatui::widgets::ListState;
@@ -15,11 +23,22 @@ pub enum PlaylistCreateCurrentField {
 pub enum PopupState {
     Search {
         query: String,
+        /// whether the query is being edited, otherwise
+        /// `n`/`N` can be used to move between the matched items
+        is_editing: bool,
     },
     UserPlaylistList(PlaylistPopupAction, ListState),
     UserFollowedArtistList(ListState),
     UserSavedAlbumList(ListState),
     DeviceList(ListState),
+    #[cfg(feature = "streaming")]
+    AudioOutputList(Vec<crate::streaming::AudioOutput>, ListState),
+    /// The device profiles, preceded by the default profile of the device configurations
+    #[cfg(feature = "streaming")]
+    DeviceProfileList(ListState),
+    /// The equalizer's bands, one of which is selected to adjust its gain
+    #[cfg(feature = "streaming")]
+    Equalizer(ListState),
     ArtistList(ArtistPopupAction, Vec<Artist>, ListState),
     ThemeList(Vec<crate::config::Theme>, ListState),
     ActionList(Box<ActionListItem>, ListState),
@@ -28,6 +47,40 @@ pub enum PopupState {
         desc: LineInput,
         current_field: PlaylistCreateCurrentField,
     },
+    /// A palette to jump to a page or a library context
+    JumpPalette(LineInput, ListState),
+    /// A palette to search and run a command
+    CommandPalette(LineInput, ListState),
+    /// A palette to search a command to rebind
+    KeymapEditor(LineInput, ListState),
+    /// An input recording the new key sequence of a command
+    KeymapRebind {
+        command: Command,
+        key_sequence: KeySequence,
+    },
+    SavedSearchCreate {
+        name: LineInput,
+        query: String,
+        library_only: bool,
+    },
+    SavedSearchList(ListState),
+    /// An input for a query whose top matched track is added to the queue
+    SearchAndQueue(LineInput),
+    /// An error of the reloaded configurations, which are invalid
+    ConfigError(String),
+    /// A confirmation of a destructive action, see [`CONFIRMATION_CHOICES`]
+    Confirmation(Box<PendingConfirmation>, ListState),
+}
+
+/// The choices of a confirmation popup
+pub const CONFIRMATION_CHOICES: [&str; 3] = ["No", "Yes", "Yes to all"];
+
+#[derive(Debug)]
+/// A destructive action's request waiting for the user's confirmation
+pub struct PendingConfirmation {
+    pub action: config::DestructiveAction,
+    pub prompt: String,
+    pub request: crate::client::ClientRequest,
 }
 
 #[derive(Debug, Clone)]
@@ -38,6 +91,40 @@ pub enum ActionListItem {
     Playlist(Playlist, Vec<command::Action>),
     Show(Show, Vec<command::Action>),
     Episode(Episode, Vec<command::Action>),
+    Audiobook(Audiobook, Vec<command::Action>),
+}
+
+/// An item in the jump palette popup
+#[derive(Debug, Clone)]
+pub enum JumpPaletteItem {
+    Page(&'static str, Command),
+    Playlist(Playlist),
+    Album(Album),
+    Artist(Artist),
+}
+
+/// Pages that can be jumped to from the jump palette
+const JUMP_PALETTE_PAGES: [(&str, Command); 12] = [
+    ("Library", Command::LibraryPage),
+    ("Search", Command::SearchPage),
+    ("Library Search", Command::LibrarySearchPage),
+    ("Browse", Command::BrowsePage),
+    ("Queue", Command::Queue),
+    ("Lyrics", Command::LyricsPage),
+    ("Currently Playing", Command::CurrentlyPlayingContextPage),
+    ("Liked Tracks", Command::LikedTrackPage),
+    ("Top Tracks", Command::TopTrackPage),
+    ("Recently Played Tracks", Command::RecentlyPlayedTrackPage),
+    ("Command Help", Command::OpenCommandHelp),
+    ("Diagnostics", Command::OpenDiagnostics),
+];
+
+/// An item in the command palette popup
+#[derive(Debug, Clone)]
+pub enum CommandPaletteItem {
+    /// a command with the key sequences mapped to it
+    Command(Command, Vec<String>),
+    Macro(config::Macro),
 }
 
 /// An action on an item in a playlist popup list
@@ -73,8 +160,22 @@ impl PopupState {
             | Self::UserSavedAlbumList(list_state)
             | Self::ArtistList(.., list_state)
             | Self::ThemeList(.., list_state)
-            | Self::ActionList(.., list_state) => Some(list_state),
-            Self::Search { .. } | Self::PlaylistCreate { .. } => None,
+            | Self::ActionList(.., list_state)
+            | Self::JumpPalette(.., list_state)
+            | Self::CommandPalette(.., list_state)
+            | Self::KeymapEditor(.., list_state)
+            | Self::SavedSearchList(list_state)
+            | Self::Confirmation(.., list_state) => Some(list_state),
+            #[cfg(feature = "streaming")]
+            Self::AudioOutputList(.., list_state)
+            | Self::DeviceProfileList(list_state)
+            | Self::Equalizer(list_state) => Some(list_state),
+            Self::Search { .. }
+            | Self::PlaylistCreate { .. }
+            | Self::SavedSearchCreate { .. }
+            | Self::SearchAndQueue(_)
+            | Self::KeymapRebind { .. }
+            | Self::ConfigError(_) => None,
         }
     }
 
@@ -87,8 +188,22 @@ impl PopupState {
             | Self::UserSavedAlbumList(list_state)
             | Self::ArtistList(.., list_state)
             | Self::ThemeList(.., list_state)
-            | Self::ActionList(.., list_state) => Some(list_state),
-            Self::Search { .. } | Self::PlaylistCreate { .. } => None,
+            | Self::ActionList(.., list_state)
+            | Self::JumpPalette(.., list_state)
+            | Self::CommandPalette(.., list_state)
+            | Self::KeymapEditor(.., list_state)
+            | Self::SavedSearchList(list_state)
+            | Self::Confirmation(.., list_state) => Some(list_state),
+            #[cfg(feature = "streaming")]
+            Self::AudioOutputList(.., list_state)
+            | Self::DeviceProfileList(list_state)
+            | Self::Equalizer(list_state) => Some(list_state),
+            Self::Search { .. }
+            | Self::PlaylistCreate { .. }
+            | Self::SavedSearchCreate { .. }
+            | Self::SearchAndQueue(_)
+            | Self::KeymapRebind { .. }
+            | Self::ConfigError(_) => None,
         }
     }
 
@@ -117,7 +232,8 @@ impl ActionListItem {
             | ActionListItem::Album(.., actions)
             | ActionListItem::Playlist(.., actions)
             | ActionListItem::Show(.., actions)
-            | ActionListItem::Episode(.., actions) => actions.len(),
+            | ActionListItem::Episode(.., actions)
+            | ActionListItem::Audiobook(.., actions) => actions.len(),
         }
     }
 
@@ -129,6 +245,7 @@ impl ActionListItem {
             ActionListItem::Playlist(playlist, ..) => &playlist.name,
             ActionListItem::Show(show, ..) => &show.name,
             ActionListItem::Episode(episode, ..) => &episode.name,
+            ActionListItem::Audiobook(audiobook, ..) => &audiobook.name,
         }
     }
 
@@ -139,9 +256,115 @@ impl ActionListItem {
             | ActionListItem::Album(.., actions)
             | ActionListItem::Playlist(.., actions)
             | ActionListItem::Show(.., actions)
-            | ActionListItem::Episode(.., actions) => {
+            | ActionListItem::Episode(.., actions)
+            | ActionListItem::Audiobook(.., actions) => {
                 actions.iter().map(|a| format!("{a:?}")).collect::<Vec<_>>()
             }
         }
     }
 }
+
+impl JumpPaletteItem {
+    /// gets the jump palette items matching a query, which include pages,
+    /// the user's playlists, saved albums and followed artists
+    pub fn matched_items(data: &AppData, query: &str) -> Vec<Self> {
+        let items = JUMP_PALETTE_PAGES
+            .iter()
+            .map(|&(name, command)| Self::Page(name, command))
+            .chain(
+                data.user_data
+                    .playlists
+                    .iter()
+                    .filter_map(|item| match item {
+                        PlaylistFolderItem::Playlist(p) => Some(Self::Playlist(p.clone())),
+                        PlaylistFolderItem::Folder(_) => None,
+                    }),
+            )
+            .chain(data.user_data.saved_albums.iter().cloned().map(Self::Album))
+            .chain(
+                data.user_data
+                    .followed_artists
+                    .iter()
+                    .cloned()
+                    .map(Self::Artist),
+            )
+            .collect::<Vec<_>>();
+
+        super::filter_items(&items, query)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+}
+
+impl CommandPaletteItem {
+    /// gets the command palette items matching a query, which include all the commands,
+    /// the commands with arguments mapped in the keymap configurations and the macros
+    pub fn matched_items(query: &str) -> Vec<Self> {
+        let configs = config::get_config();
+        let keymap_config = &configs.keymap_config;
+        let mut map = std::collections::BTreeMap::<Command, Vec<String>>::new();
+        for command in Command::all() {
+            map.insert(command, vec![]);
+        }
+        for keymap in &keymap_config.keymaps {
+            if keymap.include_in_help_screen() {
+                map.entry(keymap.command)
+                    .or_default()
+                    .push(keymap.key_sequence.to_string());
+            }
+        }
+
+        let items = map
+            .into_iter()
+            .map(|(command, keys)| Self::Command(command, keys))
+            .chain(keymap_config.macros.iter().cloned().map(Self::Macro))
+            .collect::<Vec<_>>();
+
+        super::filter_items(&items, query)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// gets the command palette items matching a query without the macros,
+    /// i.e. the commands that can be rebound
+    pub fn matched_commands(query: &str) -> Vec<Self> {
+        Self::matched_items(query)
+            .into_iter()
+            .filter(|item| matches!(item, Self::Command(..)))
+            .collect()
+    }
+}
+
+impl std::fmt::Display for CommandPaletteItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Command(command, keys) => {
+                write!(f, "{command:?}")?;
+                if !keys.is_empty() {
+                    write!(f, " [{}]", keys.join(", "))?;
+                }
+                write!(f, ": {}", command.desc())
+            }
+            Self::Macro(m) => {
+                write!(f, "[macro] {}", m.name)?;
+                if let Some(key_sequence) = &m.key_sequence {
+                    write!(f, " [{key_sequence}]")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for JumpPaletteItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Page(name, _) => write!(f, "[page] {name}"),
+            Self::Playlist(p) => write!(f, "[playlist] {p}"),
+            Self::Album(a) => write!(f, "[album] {a}"),
+            Self::Artist(a) => write!(f, "[artist] {a}"),
+        }
+    }
+}
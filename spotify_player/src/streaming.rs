@@ -1,19 +1,29 @@
-use crate::{client::Client, config, state::SharedState};
+use crate::{
+    client::Client,
+    config,
+    state::{PlaybackEvent, SharedState},
+};
 use anyhow::Context;
+use librespot_audio::AudioFetchParams;
 use librespot_connect::{ConnectConfig, Spirc};
 use librespot_core::authentication::Credentials;
 use librespot_core::Session;
 use librespot_core::{config::DeviceType, spotify_id};
 use librespot_playback::mixer::MixerConfig;
 use librespot_playback::{
-    audio_backend,
+    audio_backend::{self, Sink},
     config::{AudioFormat, Bitrate, PlayerConfig},
-    mixer::{self, Mixer},
+    mixer::{self, Mixer, VolumeGetter},
     player,
 };
 use rspotify::model::{EpisodeId, Id, PlayableId, TrackId};
+use rspotify::prelude::OAuthClient;
 use serde::Serialize;
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicU16, Ordering},
+    Arc, Weak,
+};
+use tokio::sync::broadcast::error::RecvError;
 
 #[cfg(not(any(
     feature = "rodio-backend",
@@ -138,6 +148,162 @@ fn execute_player_event_hook_command(
     Ok(())
 }
 
+/// Launch a zeroconf discovery service, which advertises the integrated device
+/// on the local network and yields the credentials of users connecting to it
+pub fn new_discovery(
+    device_id: &str,
+    state: &SharedState,
+) -> anyhow::Result<librespot_discovery::Discovery> {
+    let configs = config::get_config();
+    let device = &configs.app_config.device;
+    let (name, device_type) = state.device_name_and_type();
+
+    tracing::info!(
+        "Starting a zeroconf discovery service for device {} on port {}",
+        name,
+        device.zeroconf_port
+    );
+
+    let discovery =
+        librespot_discovery::Discovery::builder(device_id, crate::auth::SPOTIFY_CLIENT_ID)
+            .name(name)
+            .device_type(parse_device_type(&device_type))
+            // a zero port lets the OS choose a random port
+            .port(device.zeroconf_port)
+            .launch()
+            .context("launch zeroconf discovery")?;
+    Ok(discovery)
+}
+
+/// An audio output of the integrated device, i.e. an audio backend and one of its output devices
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioOutput {
+    /// name of the audio backend, `None` for the default backend
+    pub backend: Option<String>,
+    /// name of the backend's output device, `None` for the backend's default device
+    pub device: Option<String>,
+}
+
+impl AudioOutput {
+    /// Get the audio output of a device profile, falling back to the device configurations
+    pub fn new(profile: Option<&config::DeviceProfile>) -> Self {
+        let configs = config::get_config();
+        let device = &configs.app_config.device;
+        Self {
+            backend: profile
+                .and_then(|p| p.audio_backend.clone())
+                .or_else(|| device.audio_backend.clone()),
+            device: profile
+                .and_then(|p| p.audio_device.clone())
+                .or_else(|| device.audio_device.clone()),
+        }
+    }
+}
+
+impl AudioOutput {
+    /// Get the name of the output's audio backend
+    fn backend_name(&self) -> &str {
+        self.backend
+            .as_deref()
+            .unwrap_or(audio_backend::BACKENDS[0].0)
+    }
+
+    /// Get the builder of a sink opening the output device at the configured `sample_rate`,
+    /// if the backend's output device is opened by the application instead of `librespot`
+    fn sample_rate_sink(&self) -> Option<crate::output::SinkBuilder> {
+        crate::output::find(self.backend_name())
+    }
+
+    /// Get the sample rate (in Hz) of the audio written into the output.
+    ///
+    /// The configured `sample_rate` applies to the `pipe` and `subprocess` backends,
+    /// which write the raw samples into a file or another program, and to the backends
+    /// whose output device can be opened at that rate by the application, i.e. `alsa`,
+    /// `pulseaudio` and `rodio`. The other backends always open the output device
+    /// at `SAMPLE_RATE`.
+    pub fn sample_rate(&self) -> u32 {
+        let configs = config::get_config();
+        match configs.app_config.device.sample_rate {
+            Some(rate)
+                if matches!(self.backend_name(), "pipe" | "subprocess")
+                    || self.sample_rate_sink().is_some() =>
+            {
+                rate
+            }
+            _ => librespot_playback::SAMPLE_RATE,
+        }
+    }
+}
+
+impl std::fmt::Display for AudioOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let backend = self.backend.as_deref().unwrap_or("default backend");
+        match &self.device {
+            Some(device) => write!(f, "{backend} | {device}"),
+            None => write!(f, "{backend} | default device"),
+        }
+    }
+}
+
+/// Get the available audio outputs, i.e. the default device of each audio backend
+/// the application is built with, followed by the backend's listed output devices
+pub fn list_audio_outputs() -> Vec<AudioOutput> {
+    audio_backend::BACKENDS
+        .iter()
+        .flat_map(|(backend, _)| {
+            std::iter::once(None)
+                .chain(list_output_devices(backend).into_iter().map(Some))
+                .map(|device| AudioOutput {
+                    backend: Some((*backend).to_string()),
+                    device,
+                })
+        })
+        .collect()
+}
+
+/// List the output devices of an audio backend using the system's audio tools.
+/// The devices of backends without such a tool aren't listed.
+fn list_output_devices(backend: &str) -> Vec<String> {
+    let (program, args): (&str, &[&str]) = match backend {
+        // `PipeWire` is supported via its `PulseAudio` server
+        "pulseaudio" => ("pactl", &["list", "short", "sinks"]),
+        "alsa" => ("aplay", &["-L"]),
+        _ => return Vec::new(),
+    };
+    let output = match std::process::Command::new(program).args(args).output() {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            tracing::warn!(
+                "Failed to list the {backend} output devices: {program} exited with {}",
+                output.status
+            );
+            return Vec::new();
+        }
+        Err(err) => {
+            tracing::warn!("Failed to list the {backend} output devices: {err:#}");
+            return Vec::new();
+        }
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if backend == "pulseaudio" {
+        // each line consists of a sink's index, name, driver, format and state separated by tabs
+        stdout
+            .lines()
+            .filter_map(|line| line.split('\t').nth(1))
+            .map(ToString::to_string)
+            .collect()
+    } else {
+        // each device's name is followed by its indented description lines
+        stdout
+            .lines()
+            .filter(|line| !line.is_empty() && !line.starts_with(char::is_whitespace))
+            .filter(|line| *line != "null")
+            .map(ToString::to_string)
+            .collect()
+    }
+}
+
 /// Create a new streaming connection
 pub async fn new_connection(
     client: Client,
@@ -147,37 +313,102 @@ pub async fn new_connection(
 ) -> anyhow::Result<Spirc> {
     let configs = config::get_config();
     let device = &configs.app_config.device;
+    let (name, device_type) = state.device_name_and_type();
 
+    // the device's volume configurations (if any) override the initial volume
+    let volume = match configs.app_config.find_device_volume(&name) {
+        Some(volume_config) => volume_config.cap(volume_config.volume.unwrap_or(device.volume)),
+        None => device.volume,
+    };
     // `librespot` volume is a u16 number ranging from 0 to 65535,
     // while a percentage volume value (from 0 to 100) is used for the device configuration.
     // So we need to convert from one format to another
-    let volume = (f64::from(std::cmp::min(device.volume, 100_u8)) / 100.0 * 65535.0).round() as u16;
+    let volume = (f64::from(std::cmp::min(volume, 100_u8)) / 100.0 * 65535.0).round() as u16;
+
+    // in the bit-perfect mode, the decoded audio is passed untouched to the audio backend:
+    // the volume is left to the DAC, and the audio processing (normalization, equalizer,
+    // night mode, fades, mono downmix, balance and dithering) is disabled
+    let bit_perfect = device.bit_perfect;
+    let volume = if bit_perfect { u16::MAX } else { volume };
 
     let connect_config = ConnectConfig {
-        name: device.name.clone(),
-        device_type: device.device_type.parse::<DeviceType>().unwrap_or_default(),
+        name,
+        device_type: parse_device_type(&device_type),
         initial_volume: volume,
+        disable_volume: bit_perfect,
 
         // non-configurable fields, use default values.
         // We may allow users to configure these fields in a future release
         is_group: false,
-        disable_volume: false,
         volume_steps: 64,
     };
 
     tracing::info!("Application's connect configurations: {:?}", connect_config);
 
-    let mixer = Arc::new(mixer::softmixer::SoftMixer::open(MixerConfig::default()).context("opening softmixer")?);
-    mixer.set_volume(volume);
+    // with the system volume sync, the volume is applied to the application's sink input
+    // instead of being applied by a software mixer
+    let system_volume = (cfg!(all(target_os = "linux", feature = "system-volume-sync"))
+        && device.system_volume_sync
+        && !bit_perfect)
+        .then(|| Arc::new(AtomicU16::new(volume)));
+    let mixer: Arc<dyn Mixer> = match &system_volume {
+        Some(system_volume) => Arc::new(PassthroughMixer {
+            volume: system_volume.clone(),
+        }),
+        None if bit_perfect => Arc::new(PassthroughMixer {
+            volume: Arc::new(AtomicU16::new(volume)),
+        }),
+        None => {
+            let mixer = Arc::new(
+                mixer::softmixer::SoftMixer::open(MixerConfig::default())
+                    .context("opening softmixer")?,
+            );
+            mixer.set_volume(volume);
+            mixer
+        }
+    };
+    let device_id = session.device_id().to_string();
+
+    let audio_output = state.audio_output.lock().clone();
+    let backend = audio_backend::find(audio_output.backend.clone())
+        .with_context(|| format!("find the audio backend of {audio_output}"))?;
+    let sample_rate = audio_output.sample_rate();
+    if device.sample_rate.is_some_and(|rate| rate != sample_rate) {
+        tracing::warn!(
+            "The configured sample rate isn't applied to {audio_output}, which outputs at {sample_rate} Hz"
+        );
+    }
+    // the playing track's audio is downloaded ahead of the playback, so seeking within
+    // the downloaded audio is served from the local buffer without waiting for a new fetch.
+    // The fetch parameters are global, so they're only set by the first connection.
+    let read_ahead = std::time::Duration::from_secs(device.read_ahead_secs);
+    if AudioFetchParams::set(AudioFetchParams {
+        read_ahead_during_playback: read_ahead,
+        ..AudioFetchParams::default()
+    })
+    .is_err()
+        && AudioFetchParams::get().read_ahead_during_playback != read_ahead
+    {
+        tracing::warn!(
+            "The updated `read_ahead_secs` is only applied after restarting the application"
+        );
+    }
 
-    let backend = audio_backend::find(None).expect("should be able to find an audio backend");
     let player_config = PlayerConfig {
-        bitrate: device
+        bitrate: state
             .bitrate
+            .lock()
             .to_string()
             .parse::<Bitrate>()
             .unwrap_or_default(),
-        normalisation: device.normalization,
+        normalisation: device.normalization && !bit_perfect,
+        // toggles `librespot`'s gapless playback, which keeps the audio sink open between tracks
+        gapless: device.gapless,
+        ditherer: if bit_perfect {
+            None
+        } else {
+            PlayerConfig::default().ditherer
+        },
         ..Default::default()
     };
 
@@ -186,15 +417,61 @@ pub async fn new_connection(
         session.device_id()
     );
 
-    let player = player::Player::new(
-        player_config,
-        session.clone(),
-        mixer.get_soft_volume(),
-        move || backend(None, AudioFormat::default()),
-    );
+    let player = player::Player::new(player_config, session.clone(), mixer.get_soft_volume(), {
+        let configs = configs.clone();
+        let state = state.clone();
+        let volume = mixer.get_soft_volume();
+        move || {
+            let device = &configs.app_config.device;
+            let sink: Box<dyn Sink> = if sample_rate == librespot_playback::SAMPLE_RATE {
+                backend(audio_output.device.clone(), AudioFormat::default())
+            } else {
+                let sink = match audio_output.sample_rate_sink() {
+                    Some(build_sink) => build_sink(audio_output.device.clone(), sample_rate),
+                    None => backend(audio_output.device.clone(), AudioFormat::default()),
+                };
+                Box::new(crate::resample::ResampleSink::new(
+                    sink,
+                    sample_rate,
+                    device.resampler_quality,
+                ))
+            };
+            let sink = Box::new(crate::audio_stats::StatsSink::new(sink));
+            let sink = if bit_perfect {
+                sink
+            } else {
+                new_processing_sink(sink, &state, volume)
+            };
+            // cutting the silence alters the audio, so it's disabled in the bit-perfect mode
+            if device.skip_silence && !bit_perfect {
+                Box::new(crate::silence::SilenceSink::new(sink))
+            } else {
+                sink
+            }
+        }
+    });
+
+    if device.gapless {
+        tokio::task::spawn(preload_next_track(
+            state.clone(),
+            device_id.clone(),
+            Arc::downgrade(&player),
+        ));
+    }
+
+    #[cfg(feature = "system-volume-sync")]
+    if let Some(system_volume) = &system_volume {
+        tokio::task::spawn(sync_system_volume(
+            client.clone(),
+            device_id,
+            Arc::downgrade(system_volume),
+        ));
+    }
 
     let player_event_task = tokio::task::spawn({
         let mut channel = player.get_player_event_channel();
+        let client = client.clone();
+        let state = state.clone();
         async move {
             while let Some(event) = channel.recv().await {
                 match PlayerEvent::from_librespot_player_event(event) {
@@ -205,6 +482,8 @@ pub async fn new_connection(
                         tracing::info!("Got a new player event: {event:?}");
                         match event {
                             PlayerEvent::Playing { .. } => {
+                                // the playback transition after fading out is done
+                                *state.fading_out.lock() = false;
                                 let mut player = state.player.write();
                                 if let Some(playback) = player.buffered_playback.as_mut() {
                                     playback.is_playing = true;
@@ -218,6 +497,7 @@ pub async fn new_connection(
                             }
                             _ => {}
                         }
+                        state.publish_playback_events();
                         client.update_playback(&state);
 
                         // execute a player event hook command
@@ -246,9 +526,318 @@ pub async fn new_connection(
             () = spirc_task => {},
             _ = player_event_task => {}
         }
+        // the connection is closed upon a shutdown or a lost session,
+        // in which case the integrated device is recovered with a new session
+        if let Err(err) = client.check_valid_session(&state).await {
+            tracing::error!("{err:#}");
+        }
     });
 
     tracing::info!("New streaming connection has been established!");
 
     Ok(spirc)
 }
+
+/// Wrap an audio backend's sink with the sinks processing the integrated device's audio.
+/// The equalizer is applied first, followed by the night mode's compression,
+/// the fades, then the mono downmix and the balance.
+fn new_processing_sink(
+    sink: Box<dyn Sink>,
+    state: &SharedState,
+    volume: Box<dyn VolumeGetter + Send>,
+) -> Box<dyn Sink> {
+    let configs = config::get_config();
+    let device = &configs.app_config.device;
+    let sink: Box<dyn Sink> = if device.mono || device.balance.abs() > f32::EPSILON {
+        Box::new(crate::channels::ChannelSink::new(
+            sink,
+            device.mono,
+            device.balance,
+        ))
+    } else {
+        sink
+    };
+    let sink: Box<dyn Sink> = if device.fade_duration_ms > 0 {
+        Box::new(crate::fade::FadeSink::new(
+            sink,
+            state.clone(),
+            std::time::Duration::from_millis(device.fade_duration_ms),
+        ))
+    } else {
+        sink
+    };
+    // the night mode and the equalizer are only applied while they're enabled,
+    // toggling them restarts the integrated client
+    let sink: Box<dyn Sink> = if *state.night_mode.lock() {
+        Box::new(crate::night_mode::NightModeSink::new(sink, volume))
+    } else {
+        sink
+    };
+    if state.equalizer.load().enabled {
+        Box::new(crate::equalizer::EqualizerSink::new(sink, state.clone()))
+    } else {
+        sink
+    }
+}
+
+/// Parse a device type, e.g. `speaker`, `computer` or `avr`,
+/// falling back to the default device type if the device type is invalid
+fn parse_device_type(device_type: &str) -> DeviceType {
+    device_type.parse().unwrap_or_else(|()| {
+        tracing::warn!("Invalid device type {device_type}, using the default device type");
+        DeviceType::default()
+    })
+}
+
+/// Preload the next item of the playback's queue into the integrated device's player
+/// as soon as the queue is known, until the player is dropped.
+///
+/// `librespot` only preloads the next item of its own context shortly before the playing item
+/// ends. Preloading the application's queue, which also includes the items queued with
+/// the Web API, downloads the next item's audio ahead and lets the player start it
+/// right after the playing item without reopening the audio sink.
+async fn preload_next_track(state: SharedState, device_id: String, player: Weak<player::Player>) {
+    let mut playback_events = state.playback_events.subscribe();
+    // the last preloaded item, which isn't preloaded again upon the next queue changes
+    let mut preloaded: Option<String> = None;
+
+    loop {
+        match playback_events.recv().await {
+            Ok(PlaybackEvent::QueueChange | PlaybackEvent::DeviceChange)
+            | Err(RecvError::Lagged(_)) => {}
+            Ok(_) => continue,
+            Err(RecvError::Closed) => return,
+        }
+
+        let next_uri = {
+            let player_state = state.player.read();
+            let is_active = player_state.playback.as_ref().is_some_and(|p| {
+                p.device.id.as_deref() == Some(device_id.as_str())
+                    && p.repeat_state != rspotify::model::RepeatState::Track
+            });
+            player_state
+                .queue
+                .as_ref()
+                .filter(|_| is_active)
+                .and_then(|queue| queue.queue.first())
+                .and_then(|item| item.id())
+                .map(|id| id.uri())
+        };
+        let Some(next_uri) = next_uri else {
+            continue;
+        };
+        if preloaded.as_deref() == Some(next_uri.as_str()) {
+            continue;
+        }
+
+        let Some(player) = player.upgrade() else {
+            return;
+        };
+        match spotify_id::SpotifyId::from_uri(&next_uri) {
+            Ok(id) => {
+                tracing::info!("Preloading the next item {next_uri}");
+                player.preload(id);
+                preloaded = Some(next_uri);
+            }
+            Err(err) => tracing::warn!("Failed to preload the next item {next_uri}: {err}"),
+        }
+    }
+}
+
+/// A mixer which doesn't apply the volume to the audio. The volume is left either
+/// to the application's sink input, whose volume is synchronized with the mixer's volume
+/// by [`sync_system_volume`], or to the DAC in the bit-perfect mode
+struct PassthroughMixer {
+    volume: Arc<AtomicU16>,
+}
+
+impl Mixer for PassthroughMixer {
+    fn open(_: MixerConfig) -> Result<Self, librespot_core::Error> {
+        Ok(Self {
+            volume: Arc::new(AtomicU16::new(u16::MAX)),
+        })
+    }
+
+    fn volume(&self) -> u16 {
+        self.volume.load(Ordering::Relaxed)
+    }
+
+    fn set_volume(&self, volume: u16) {
+        self.volume.store(volume, Ordering::Relaxed);
+    }
+}
+
+/// Synchronize the integrated device's volume with the volume of the application's
+/// `PulseAudio`/`PipeWire` sink input in both directions, until the device's mixer is dropped.
+#[cfg(feature = "system-volume-sync")]
+async fn sync_system_volume(client: Client, device_id: String, volume: Weak<AtomicU16>) {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let sync = tokio::task::spawn_blocking(move || system_volume::sync(&volume, &tx));
+
+    // the sink input's volume is changed externally, e.g. with volume keys
+    while let Some(sink_volume) = rx.recv().await {
+        if let Err(err) = client.volume(sink_volume, Some(&device_id)).await {
+            tracing::warn!("Failed to update the device's volume: {err:#}");
+        }
+    }
+
+    match sync.await {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => tracing::warn!("Failed to synchronize the system volume: {err:#}"),
+        Err(err) => tracing::error!("Failed to run the system volume sync: {err:#}"),
+    }
+}
+
+#[cfg(feature = "system-volume-sync")]
+mod system_volume {
+    use super::{AtomicU16, Ordering, Weak};
+    use anyhow::Context as _;
+    use libpulse_binding::{
+        callbacks::ListResult,
+        context::{
+            introspect::SinkInputInfo,
+            subscribe::{Facility, InterestMaskSet, Operation},
+            Context, FlagSet, State,
+        },
+        mainloop::standard::{IterateResult, Mainloop},
+        time::MicroSeconds,
+        volume::{ChannelVolumes, Volume},
+    };
+    use std::{cell::RefCell, rc::Rc};
+
+    /// Interval between checks of the device's volume while waiting for `PulseAudio` events
+    const DEVICE_VOLUME_CHECK_INTERVAL: MicroSeconds = MicroSeconds(100_000);
+
+    /// The application's sink input, which only exists while the audio backend is running
+    #[derive(Clone, Copy)]
+    struct SinkInput {
+        index: u32,
+        channels: u8,
+        /// the volume in percentage, which can be boosted above the device's maximum volume (100%)
+        volume: u8,
+    }
+
+    /// Synchronize the device's volume with the application's sink input, whose volume changes are
+    /// subscribed to, until the device's mixer is dropped.
+    /// The sink input's volume (in percentage) is sent upon every external change.
+    pub fn sync(
+        volume: &Weak<AtomicU16>,
+        on_change: &tokio::sync::mpsc::UnboundedSender<u8>,
+    ) -> anyhow::Result<()> {
+        let mut mainloop = Mainloop::new().context("create a PulseAudio main loop")?;
+        let mut context =
+            Context::new(&mainloop, "spotify_player").context("create a PulseAudio context")?;
+        context.connect(None, FlagSet::NOFLAGS, None)?;
+        loop {
+            if !matches!(mainloop.iterate(true), IterateResult::Success(_)) {
+                anyhow::bail!("PulseAudio main loop stopped");
+            }
+            match context.get_state() {
+                State::Ready => break,
+                State::Failed | State::Terminated => {
+                    anyhow::bail!("failed to connect to the PulseAudio server")
+                }
+                _ => {}
+            }
+        }
+
+        let sink_input = Rc::new(RefCell::new(None));
+        context.set_subscribe_callback(Some(Box::new({
+            let introspect = context.introspect();
+            let sink_input = sink_input.clone();
+            move |facility, operation, index| {
+                if facility != Some(Facility::SinkInput) {
+                    return;
+                }
+                match operation {
+                    Some(Operation::New | Operation::Changed) => {
+                        introspect.get_sink_input_info(index, update_sink_input(&sink_input));
+                    }
+                    Some(Operation::Removed) => {
+                        let mut sink_input = sink_input.borrow_mut();
+                        if sink_input.is_some_and(|s: SinkInput| s.index == index) {
+                            *sink_input = None;
+                        }
+                    }
+                    None => {}
+                }
+            }
+        })));
+        context.subscribe(InterestMaskSet::SINK_INPUT, |_| {});
+        context
+            .introspect()
+            .get_sink_input_info_list(update_sink_input(&sink_input));
+
+        // the sink input after the last synchronization
+        let mut last_sink_input: Option<SinkInput> = None;
+        loop {
+            // wait for the subscribed events, waking up periodically to check the device's volume
+            mainloop.prepare(Some(DEVICE_VOLUME_CHECK_INTERVAL))?;
+            mainloop.poll()?;
+            mainloop.dispatch()?;
+
+            let Some(volume) = volume.upgrade() else {
+                return Ok(());
+            };
+            let device_volume =
+                (f64::from(volume.load(Ordering::Relaxed)) / 65535.0 * 100.0).round() as u8;
+            let Some(mut current) = *sink_input.borrow() else {
+                last_sink_input = None;
+                continue;
+            };
+
+            match last_sink_input {
+                Some(last) if last.index == current.index && last.volume != current.volume => {
+                    // the sink input's volume is changed externally, e.g. with volume keys
+                    volume.store(
+                        (f64::from(current.volume) / 100.0 * 65535.0).round() as u16,
+                        Ordering::Relaxed,
+                    );
+                    if on_change.send(current.volume).is_err() {
+                        return Ok(());
+                    }
+                }
+                _ if current.volume != device_volume => {
+                    // the device's volume is changed or a new sink input is created
+                    let mut volumes = ChannelVolumes::default();
+                    volumes.set(current.channels, percent_to_volume(device_volume));
+                    context
+                        .introspect()
+                        .set_sink_input_volume(current.index, &volumes, None);
+                    current.volume = device_volume;
+                    *sink_input.borrow_mut() = Some(current);
+                }
+                _ => {}
+            }
+            last_sink_input = Some(current);
+        }
+    }
+
+    /// A callback updating the sink input from the info of a sink input if it's the application's
+    fn update_sink_input(
+        sink_input: &Rc<RefCell<Option<SinkInput>>>,
+    ) -> impl FnMut(ListResult<&SinkInputInfo>) + 'static {
+        let sink_input = sink_input.clone();
+        let pid = std::process::id().to_string();
+        move |result| {
+            let ListResult::Item(info) = result else {
+                return;
+            };
+            if info.proplist.get_str("application.process.id").as_deref() == Some(pid.as_str()) {
+                *sink_input.borrow_mut() = Some(SinkInput {
+                    index: info.index,
+                    channels: info.volume.len(),
+                    volume: std::cmp::min(volume_to_percent(info.volume.avg()), 100) as u8,
+                });
+            }
+        }
+    }
+
+    fn volume_to_percent(volume: Volume) -> u32 {
+        (f64::from(volume.0) / f64::from(Volume::NORMAL.0) * 100.0).round() as u32
+    }
+
+    fn percent_to_volume(percent: u8) -> Volume {
+        Volume((f64::from(percent) / 100.0 * f64::from(Volume::NORMAL.0)).round() as u32)
+    }
+}
@@ -0,0 +1,101 @@
+//! Detection of the terminal's background color, used to choose between the light
+//! and dark themes with `theme_mode = "Auto"`
+
+use std::{sync::OnceLock, time::Duration};
+
+/// whether the terminal's background is light, detected at startup
+static IS_LIGHT: OnceLock<Option<bool>> = OnceLock::new();
+
+/// Detects the terminal's background by querying its color (OSC 11),
+/// falling back to the `COLORFGBG` environment variable.
+///
+/// As the terminal responds to the query via the standard input, this function
+/// must be called before starting the terminal event handler.
+pub fn detect() {
+    let is_light = query_background().or_else(background_from_env);
+    tracing::info!("Detected the terminal's background: is_light={is_light:?}");
+    IS_LIGHT.set(is_light).ok();
+}
+
+/// Gets whether the terminal's background is light, `None` if it is unknown
+pub fn is_light() -> Option<bool> {
+    match IS_LIGHT.get() {
+        Some(is_light) => *is_light,
+        None => background_from_env(),
+    }
+}
+
+/// Queries the terminal's background color, returning whether it is light
+fn query_background() -> Option<bool> {
+    use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+    use std::io::{IsTerminal, Write};
+
+    if cfg!(windows) || !std::io::stdout().is_terminal() {
+        return None;
+    }
+
+    crossterm::terminal::enable_raw_mode().ok()?;
+    let response = (|| -> std::io::Result<Option<String>> {
+        let mut stdout = std::io::stdout();
+        write!(stdout, "\x1b]11;?\x1b\\")?;
+        stdout.flush()?;
+
+        // The response `ESC ] 11;rgb:RRRR/GGGG/BBBB ST`, in which `ST` is either `ESC \` or `BEL`,
+        // is parsed by `crossterm` as key events: `Alt+]`, the response's characters,
+        // then `Alt+\` or `Ctrl+g`. Terminals not supporting the query don't respond.
+        let mut response = None;
+        while event::poll(Duration::from_millis(100))? {
+            let Event::Key(KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers,
+                ..
+            }) = event::read()?
+            else {
+                continue;
+            };
+            match response.as_mut() {
+                None if c == ']' && modifiers.contains(KeyModifiers::ALT) => {
+                    response = Some(String::new());
+                }
+                Some(_) if c == '\\' && modifiers.contains(KeyModifiers::ALT) => break,
+                Some(_) if c == 'g' && modifiers.contains(KeyModifiers::CONTROL) => break,
+                Some(response) => response.push(c),
+                None => {}
+            }
+        }
+        Ok(response)
+    })();
+    crossterm::terminal::disable_raw_mode().ok();
+
+    parse_background(&response.ok()??)
+}
+
+/// Parses the response to a background color query, e.g. `11;rgb:1e1e/1f1f/2929`,
+/// returning whether the color is light
+fn parse_background(response: &str) -> Option<bool> {
+    let channels = response
+        .strip_prefix("11;rgb:")?
+        .split('/')
+        .map(|c| {
+            // each channel has 1 to 4 hex digits
+            if c.is_empty() || c.len() > 4 {
+                return None;
+            }
+            let max = 16_f64.powi(c.len() as i32) - 1.0;
+            u32::from_str_radix(c, 16).ok().map(|v| f64::from(v) / max)
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let [r, g, b] = channels[..] else {
+        return None;
+    };
+    // a background is light if its relative luminance is more than a half
+    Some(0.2126 * r + 0.7152 * g + 0.0722 * b > 0.5)
+}
+
+/// Gets whether the terminal's background is light from the `COLORFGBG` environment variable,
+/// e.g. `15;0`, whose last field is the ANSI color of the background
+fn background_from_env() -> Option<bool> {
+    let colors = std::env::var("COLORFGBG").ok()?;
+    let background = colors.rsplit(';').next()?.parse::<u8>().ok()?;
+    Some(matches!(background, 7 | 9..=15))
+}
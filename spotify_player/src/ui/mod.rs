@@ -4,7 +4,7 @@ use crate::{
         Album, Artist, ArtistFocusState, BrowsePageUIState, Context, ContextPageUIState,
         DataReadGuard, Id, LibraryFocusState, MutableWindowState, PageState, PageType,
         PlaybackMetadata, PlaylistCreateCurrentField, PlaylistFolderItem, PlaylistPopupAction,
-        PopupState, SearchFocusState, SharedState, Track, UIStateGuard,
+        PopupState, SearchFocusState, SearchMode, SharedState, Track, UIStateGuard,
     },
 };
 use anyhow::{Context as AnyhowContext, Result};
@@ -25,6 +25,7 @@ use crate::state::ImageRenderInfo;
 type Terminal = ratatui::Terminal<ratatui::backend::CrosstermBackend<std::io::Stdout>>;
 
 mod page;
+pub mod picker;
 mod playback;
 mod popup;
 pub mod single_line_input;
@@ -38,15 +39,25 @@ pub fn run(state: &SharedState) -> Result<()> {
         config::get_config().app_config.app_refresh_duration_in_ms,
     );
     let mut last_terminal_size = None;
+    let mut last_title = None;
 
     loop {
         {
             let mut ui = state.ui.lock();
             if !ui.is_running {
                 clean_up(terminal).context("clean up UI resources")?;
+                if ui.is_stopped_by_error {
+                    return Ok(());
+                }
+                #[cfg(unix)]
+                crate::cli::remove_unix_socket_file();
                 std::process::exit(0);
             }
 
+            if let Err(err) = update_terminal_title(&mut terminal, state, &mut last_title) {
+                tracing::error!("Failed to update the terminal's title: {err:#}");
+            }
+
             let terminal_size = terminal.size()?;
             if Some(terminal_size) != last_terminal_size {
                 last_terminal_size = Some(terminal_size);
@@ -77,19 +88,97 @@ pub fn run(state: &SharedState) -> Result<()> {
 fn init_ui() -> Result<Terminal> {
     let mut stdout = std::io::stdout();
     crossterm::terminal::enable_raw_mode()?;
-    crossterm::execute!(
-        stdout,
-        crossterm::terminal::EnterAlternateScreen,
-        crossterm::event::EnableMouseCapture
-    )?;
+    crossterm::execute!(stdout, crossterm::terminal::EnterAlternateScreen)?;
+    if config::get_config().app_config.mouse.enabled {
+        crossterm::execute!(stdout, crossterm::event::EnableMouseCapture)?;
+    }
     let backend = ratatui::backend::CrosstermBackend::new(stdout);
     let mut terminal = ratatui::Terminal::new(backend)?;
     terminal.clear()?;
     Ok(terminal)
 }
 
+/// Restore the terminal used by the UI without cleaning up the UI's other resources,
+/// e.g. to report an error after the UI fails without restoring the terminal
+pub fn restore_terminal() -> Result<()> {
+    crossterm::terminal::disable_raw_mode()?;
+    crossterm::execute!(
+        std::io::stdout(),
+        crossterm::terminal::LeaveAlternateScreen,
+        crossterm::event::DisableMouseCapture,
+        crossterm::cursor::Show,
+    )?;
+    Ok(())
+}
+
+/// Update the terminal's title and the tmux status to the playing track upon every change
+fn update_terminal_title(
+    terminal: &mut Terminal,
+    state: &SharedState,
+    last_title: &mut Option<String>,
+) -> Result<()> {
+    let configs = config::get_config();
+    if !configs.app_config.set_terminal_title && !configs.app_config.tmux_status {
+        return Ok(());
+    }
+
+    let title = state
+        .player
+        .read()
+        .current_playback()
+        .map(|p| crate::utils::format_playback(&configs.app_config.terminal_title_format, &p))
+        .unwrap_or_default();
+    if last_title.as_ref() == Some(&title) {
+        return Ok(());
+    }
+
+    if configs.app_config.set_terminal_title {
+        crossterm::execute!(
+            terminal.backend_mut(),
+            crossterm::terminal::SetTitle(&title)
+        )?;
+    }
+    if configs.app_config.tmux_status {
+        set_tmux_status(Some(&title))?;
+    }
+    *last_title = Some(title);
+    Ok(())
+}
+
+/// Set (or unset if `None`) the global `@spotify_player` tmux option
+fn set_tmux_status(status: Option<&str>) -> Result<()> {
+    if std::env::var_os("TMUX").is_none() {
+        return Ok(());
+    }
+
+    let mut cmd = std::process::Command::new("tmux");
+    match status {
+        Some(status) => cmd.args(["set-option", "-gq", "@spotify_player", status]),
+        None => cmd.args(["set-option", "-gqu", "@spotify_player"]),
+    };
+    let output = cmd.output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "tmux exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
 /// Clean up UI resources before quitting the application
 fn clean_up(mut terminal: Terminal) -> Result<()> {
+    let configs = config::get_config();
+    if configs.app_config.set_terminal_title {
+        crossterm::execute!(terminal.backend_mut(), crossterm::terminal::SetTitle(""))?;
+    }
+    if configs.app_config.tmux_status {
+        if let Err(err) = set_tmux_status(None) {
+            tracing::warn!("Failed to unset the tmux status: {err:#}");
+        }
+    }
+
     crossterm::terminal::disable_raw_mode()?;
     crossterm::execute!(
         terminal.backend_mut(),
@@ -133,6 +222,7 @@ fn render_main_layout(
         PageType::Lyrics => page::render_lyrics_page(is_active, frame, state, ui, rect),
         PageType::Queue => page::render_queue_page(frame, state, ui, rect),
         PageType::CommandHelp => page::render_commands_help_page(frame, ui, rect),
+        PageType::Diagnostics => page::render_diagnostics_page(frame, state, ui, rect),
     }
 }
 
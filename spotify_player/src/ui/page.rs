@@ -1,5 +1,5 @@
 use std::{
-    collections::{btree_map::Entry, BTreeMap},
+    collections::{btree_map::Entry, BTreeMap, HashSet},
     fmt::Display,
 };
 
@@ -7,12 +7,16 @@ use super::{
     config, utils, utils::construct_and_render_block, Album, Artist, ArtistFocusState, Borders,
     BrowsePageUIState, Cell, Constraint, Context, ContextPageUIState, DataReadGuard, Frame, Id,
     Layout, LibraryFocusState, MutableWindowState, Orientation, PageState, Paragraph,
-    PlaylistFolderItem, Rect, Row, SearchFocusState, SharedState, Style, Table, Track,
+    PlaylistFolderItem, Rect, Row, SearchFocusState, SearchMode, SharedState, Style, Table, Track,
     UIStateGuard,
 };
+use crate::config::SearchCategory;
 use crate::state::BidiDisplay;
 use crate::ui::utils::to_bidi_string;
-use crate::{state::Episode, utils::format_duration};
+use crate::{
+    state::Episode,
+    utils::{format_date, format_duration},
+};
 use ratatui::text::Line;
 
 const COMMAND_TABLE_CONSTRAINTS: [Constraint; 3] = [
@@ -45,159 +49,115 @@ pub fn render_search_page(
     // 1. Get data
     let data = state.data.read();
 
-    let (focus_state, current_query, line_input) = match ui.current_page() {
+    let (focus_state, mode, library_only, current_query, line_input) = match ui.current_page() {
         PageState::Search {
             state,
             current_query,
             line_input,
-        } => (state.focus, current_query, line_input),
+        } => (
+            state.focus,
+            state.mode,
+            state.library_only,
+            current_query,
+            line_input,
+        ),
         _ => return,
     };
 
-    let search_results = data.caches.search.get(current_query);
+    let search_results = data.search_results(current_query, library_only);
+    let search_results = search_results.as_deref();
 
     // 2. Construct the page's layout
-    let rect = construct_and_render_block("Search", &ui.theme, Borders::ALL, frame, rect);
+    let title = match (mode, library_only) {
+        (SearchMode::Spotify, false) => "Search",
+        (SearchMode::Spotify, true) => "Search (In Library)",
+        (SearchMode::Library, _) => "Search (Library)",
+    };
+    let rect = construct_and_render_block(title, &ui.theme, Borders::ALL, frame, rect);
 
     // search input's layout
     let chunks = Layout::vertical([Constraint::Length(1), Constraint::Fill(0)]).split(rect);
     let search_input_rect = chunks[0];
     let rect = chunks[1];
 
-    // track/album/artist/playlist/show/episode search results layout
+    // search results layout, in which each category shown on the search page has its own window
+    let categories = config::get_config()
+        .app_config
+        .search
+        .categories
+        .iter()
+        .map(|c| c.category)
+        .collect::<Vec<_>>();
     let chunks = match ui.orientation {
-        // 1x6
+        // 1xN
         Orientation::Vertical => {
-            let constraints = if focus_state == SearchFocusState::Input {
-                [Constraint::Ratio(1, 6); 6]
-            } else {
-                let mut constraints = [Constraint::Percentage(15); 6];
-                constraints[focus_state as usize - 1] = Constraint::Percentage(25);
-                constraints
-            };
+            // the focused window is larger than the other windows
+            let constraints = categories.iter().map(|&category| {
+                if focus_state.category() == Some(category) {
+                    Constraint::Fill(5)
+                } else {
+                    Constraint::Fill(3)
+                }
+            });
 
-            Layout::vertical(constraints).split(rect)
+            Layout::vertical(constraints).split(rect).to_vec()
+        }
+        // 2 columns grid
+        Orientation::Horizontal => {
+            let n_rows = categories.len().div_ceil(2);
+            Layout::vertical(vec![Constraint::Fill(1); n_rows])
+                .split(rect)
+                .iter()
+                .enumerate()
+                .flat_map(|(i, rect)| {
+                    let n_columns = std::cmp::min(2, categories.len() - 2 * i);
+                    Layout::horizontal(vec![Constraint::Fill(1); n_columns])
+                        .split(*rect)
+                        .to_vec()
+                })
+                .collect()
         }
-        // 2x3
-        Orientation::Horizontal => Layout::vertical([Constraint::Ratio(1, 3); 3])
-            .split(rect)
-            .iter()
-            .flat_map(|rect| {
-                Layout::horizontal([Constraint::Ratio(1, 2); 2])
-                    .split(*rect)
-                    .to_vec()
-            })
-            .collect(),
     };
 
-    let track_rect = construct_and_render_block(
-        "Tracks",
-        &ui.theme,
-        if ui.orientation == Orientation::Horizontal {
-            Borders::TOP | Borders::RIGHT
-        } else {
-            Borders::TOP
-        },
-        frame,
-        chunks[0],
-    );
-    let album_rect =
-        construct_and_render_block("Albums", &ui.theme, Borders::TOP, frame, chunks[1]);
-    let artist_rect = construct_and_render_block(
-        "Artists",
-        &ui.theme,
-        if ui.orientation == Orientation::Horizontal {
-            Borders::TOP | Borders::RIGHT
-        } else {
-            Borders::TOP
-        },
-        frame,
-        chunks[2],
-    );
-    let playlist_rect =
-        construct_and_render_block("Playlists", &ui.theme, Borders::TOP, frame, chunks[3]);
-    let show_rect = construct_and_render_block(
-        "Shows",
-        &ui.theme,
-        if ui.orientation == Orientation::Horizontal {
-            Borders::TOP | Borders::RIGHT
-        } else {
-            Borders::TOP
-        },
-        frame,
-        chunks[4],
-    );
-    let episode_rect =
-        construct_and_render_block("Episodes", &ui.theme, Borders::TOP, frame, chunks[5]);
-
-    // Store rects for mouse hit-testing (defer writing until after immutable borrows)
-    let rects_search_input = search_input_rect;
-    let rects_search_tracks = track_rect;
-    let rects_search_albums = album_rect;
-    let rects_search_artists = artist_rect;
-    let rects_search_playlists = playlist_rect;
-    let rects_search_shows = show_rect;
-    let rects_search_episodes = episode_rect;
-
     // 3. Construct the page's widgets
-    let (track_list, n_tracks) = {
-        let track_items = search_results
-            .map(|s| search_items(&s.tracks))
-            .unwrap_or_default();
-
-        let is_active = is_active && focus_state == SearchFocusState::Tracks;
-
-        utils::construct_list_widget(&ui.theme, track_items, is_active)
-    };
-
-    let (album_list, n_albums) = {
-        let album_items = search_results
-            .map(|s| search_items(&s.albums))
-            .unwrap_or_default();
-
-        let is_active = is_active && focus_state == SearchFocusState::Albums;
-
-        utils::construct_list_widget(&ui.theme, album_items, is_active)
-    };
-
-    let (artist_list, n_artists) = {
-        let artist_items = search_results
-            .map(|s| search_items(&s.artists))
-            .unwrap_or_default();
-
-        let is_active = is_active && focus_state == SearchFocusState::Artists;
-
-        utils::construct_list_widget(&ui.theme, artist_items, is_active)
-    };
-
-    let (playlist_list, n_playlists) = {
-        let playlist_items = search_results
-            .map(|s| search_items(&s.playlists))
-            .unwrap_or_default();
-
-        let is_active = is_active && focus_state == SearchFocusState::Playlists;
-
-        utils::construct_list_widget(&ui.theme, playlist_items, is_active)
-    };
-
-    let (show_list, n_shows) = {
-        let show_items = search_results
-            .map(|s| search_items(&s.shows))
-            .unwrap_or_default();
-        let is_active = is_active && focus_state == SearchFocusState::Shows;
-
-        utils::construct_list_widget(&ui.theme, show_items, is_active)
-    };
-
-    let (episode_list, n_episodes) = {
-        let episode_items = search_results
-            .map(|s| search_items(&s.episodes))
-            .unwrap_or_default();
+    let windows = categories
+        .iter()
+        .zip(chunks)
+        .enumerate()
+        .map(|(i, (&category, chunk))| {
+            let borders = if ui.orientation == Orientation::Horizontal
+                && i % 2 == 0
+                && i + 1 < categories.len()
+            {
+                Borders::TOP | Borders::RIGHT
+            } else {
+                Borders::TOP
+            };
+            let rect = construct_and_render_block(
+                &format!("{category:?}"),
+                &ui.theme,
+                borders,
+                frame,
+                chunk,
+            );
 
-        let is_active = is_active && focus_state == SearchFocusState::Episodes;
+            let items = search_results
+                .map(|s| match category {
+                    SearchCategory::Tracks => search_items(&s.tracks),
+                    SearchCategory::Albums => search_items(&s.albums),
+                    SearchCategory::Artists => search_items(&s.artists),
+                    SearchCategory::Playlists => search_items(&s.playlists),
+                    SearchCategory::Shows => search_items(&s.shows),
+                    SearchCategory::Episodes => search_items(&s.episodes),
+                    SearchCategory::Audiobooks => search_items(&s.audiobooks),
+                })
+                .unwrap_or_default();
+            let is_active = is_active && focus_state.category() == Some(category);
+            let (list, len) = utils::construct_list_widget(&ui.theme, items, is_active);
 
-        utils::construct_list_widget(&ui.theme, episode_items, is_active)
-    };
+            (category, rect, list, len)
+        })
+        .collect::<Vec<_>>();
 
     // 4. Render the page's widgets
     // Render the query input box
@@ -206,16 +166,29 @@ pub fn render_search_page(
         search_input_rect,
     );
 
-    // Apply rects after input render to avoid borrow conflicts
+    // Store rects for mouse hit-testing, windows of the hidden categories can't be clicked
     {
         let rects = &mut ui.rects;
-        rects.search_input = rects_search_input;
-        rects.search_tracks = rects_search_tracks;
-        rects.search_albums = rects_search_albums;
-        rects.search_artists = rects_search_artists;
-        rects.search_playlists = rects_search_playlists;
-        rects.search_shows = rects_search_shows;
-        rects.search_episodes = rects_search_episodes;
+        rects.search_input = search_input_rect;
+        rects.search_tracks = Rect::default();
+        rects.search_albums = Rect::default();
+        rects.search_artists = Rect::default();
+        rects.search_playlists = Rect::default();
+        rects.search_shows = Rect::default();
+        rects.search_episodes = Rect::default();
+        rects.search_audiobooks = Rect::default();
+        for (category, rect, ..) in &windows {
+            let window_rect = match category {
+                SearchCategory::Tracks => &mut rects.search_tracks,
+                SearchCategory::Albums => &mut rects.search_albums,
+                SearchCategory::Artists => &mut rects.search_artists,
+                SearchCategory::Playlists => &mut rects.search_playlists,
+                SearchCategory::Shows => &mut rects.search_shows,
+                SearchCategory::Episodes => &mut rects.search_episodes,
+                SearchCategory::Audiobooks => &mut rects.search_audiobooks,
+            };
+            *window_rect = *rect;
+        }
     }
 
     // Render the search result windows.
@@ -226,48 +199,18 @@ pub fn render_search_page(
     else {
         return;
     };
-    utils::render_list_window(
-        frame,
-        track_list,
-        track_rect,
-        n_tracks,
-        &mut page_state.track_list,
-    );
-    utils::render_list_window(
-        frame,
-        album_list,
-        album_rect,
-        n_albums,
-        &mut page_state.album_list,
-    );
-    utils::render_list_window(
-        frame,
-        artist_list,
-        artist_rect,
-        n_artists,
-        &mut page_state.artist_list,
-    );
-    utils::render_list_window(
-        frame,
-        playlist_list,
-        playlist_rect,
-        n_playlists,
-        &mut page_state.playlist_list,
-    );
-    utils::render_list_window(
-        frame,
-        show_list,
-        show_rect,
-        n_shows,
-        &mut page_state.show_list,
-    );
-    utils::render_list_window(
-        frame,
-        episode_list,
-        episode_rect,
-        n_episodes,
-        &mut page_state.episode_list,
-    );
+    for (category, rect, list, len) in windows {
+        let list_state = match category {
+            SearchCategory::Tracks => &mut page_state.track_list,
+            SearchCategory::Albums => &mut page_state.album_list,
+            SearchCategory::Artists => &mut page_state.artist_list,
+            SearchCategory::Playlists => &mut page_state.playlist_list,
+            SearchCategory::Shows => &mut page_state.show_list,
+            SearchCategory::Episodes => &mut page_state.episode_list,
+            SearchCategory::Audiobooks => &mut page_state.audiobook_list,
+        };
+        utils::render_list_window(frame, list, rect, len, list_state);
+    }
 }
 
 pub fn render_context_page(
@@ -436,45 +379,43 @@ pub fn render_library_page(
     };
 
     // 2. Construct the page's layout
-    // Split the library page into 3 windows:
+    // Split the library page into the configured windows, which can be:
     // - a playlists window
     // - a saved albums window
     // - a followed artists window
+    // Windows not in the layout configurations are not rendered.
 
+    let windows = configs.app_config.layout.library.windows();
     let chunks = ui
         .orientation
-        .layout([
-            Constraint::Percentage(configs.app_config.layout.library.playlist_percent),
-            Constraint::Percentage(configs.app_config.layout.library.album_percent),
-            Constraint::Percentage(
-                100 - (configs.app_config.layout.library.album_percent
-                    + configs.app_config.layout.library.playlist_percent),
-            ),
-        ])
+        .layout(windows.iter().map(|w| w.size))
         .split(rect);
 
-    let playlist_rect = construct_and_render_block(
-        "Playlists",
-        &ui.theme,
-        match ui.orientation {
-            Orientation::Horizontal => Borders::TOP | Borders::LEFT | Borders::BOTTOM,
-            Orientation::Vertical => Borders::ALL,
-        },
-        frame,
-        chunks[0],
-    );
-    let album_rect = construct_and_render_block(
-        "Albums",
-        &ui.theme,
-        match ui.orientation {
-            Orientation::Horizontal => Borders::TOP | Borders::LEFT | Borders::BOTTOM,
-            Orientation::Vertical => Borders::ALL,
-        },
-        frame,
-        chunks[1],
-    );
-    let artist_rect =
-        construct_and_render_block("Artists", &ui.theme, Borders::ALL, frame, chunks[2]);
+    let mut playlist_rect = Rect::default();
+    let mut album_rect = Rect::default();
+    let mut artist_rect = Rect::default();
+    for (i, w) in windows.iter().enumerate() {
+        let (title, window_rect) = match w.window {
+            config::LibraryWindow::Playlists => ("Playlists", &mut playlist_rect),
+            config::LibraryWindow::Albums => ("Albums", &mut album_rect),
+            config::LibraryWindow::Artists => ("Artists", &mut artist_rect),
+        };
+        // in the horizontal orientation, adjacent windows share their borders
+        let borders = match ui.orientation {
+            Orientation::Horizontal if i + 1 < windows.len() => {
+                Borders::TOP | Borders::LEFT | Borders::BOTTOM
+            }
+            _ => Borders::ALL,
+        };
+        *window_rect = utils::construct_and_render_window_block(
+            title,
+            &ui.theme,
+            config::Window::Library,
+            borders,
+            frame,
+            chunks[i],
+        );
+    }
 
     // Store rects for mouse hit-testing
     {
@@ -764,6 +705,46 @@ pub fn render_commands_help_page(frame: &mut Frame, ui: &mut UIStateGuard, rect:
     frame.render_widget(help_table, rect);
 }
 
+pub fn render_diagnostics_page(
+    frame: &mut Frame,
+    state: &SharedState,
+    ui: &mut UIStateGuard,
+    rect: Rect,
+) {
+    // 1. Get data
+    let (entries, scroll_offset) = match ui.current_page_mut() {
+        PageState::Diagnostics {
+            entries,
+            ref mut scroll_offset,
+        } => {
+            let mut entries = entries.clone();
+            entries.extend(crate::utils::get_live_diagnostics(state));
+            if !entries.is_empty() && *scroll_offset >= entries.len() {
+                *scroll_offset = entries.len() - 1;
+            }
+            (entries, *scroll_offset)
+        }
+        _ => return,
+    };
+
+    // 2. Construct the page's layout
+    let rect = construct_and_render_block("Diagnostics", &ui.theme, Borders::ALL, frame, rect);
+
+    // 3. Construct the page's widget
+    let diagnostics_table = Table::new(
+        entries
+            .into_iter()
+            .skip(scroll_offset)
+            .map(|(name, value)| Row::new(vec![Cell::from(name), Cell::from(value)]))
+            .collect::<Vec<_>>(),
+        [Constraint::Percentage(30), Constraint::Percentage(70)],
+    )
+    .header(Row::new(vec![Cell::from("Name"), Cell::from("Value")]).style(ui.theme.table_header()));
+
+    // 4. Render the page's widget
+    frame.render_widget(diagnostics_table, rect);
+}
+
 pub fn render_queue_page(
     frame: &mut Frame,
     state: &SharedState,
@@ -801,6 +782,15 @@ pub fn render_queue_page(
         Some(ref q) => &q.queue,
         None => return,
     };
+    let context_uris = autoplay_context_uris(state, &player);
+    // an item added by autoplay is neither in the playing context nor queued by the user
+    let is_autoplay_item = |item: &PlayableItem| match (&context_uris, item.id()) {
+        (Some(uris), Some(id)) => {
+            let uri = id.uri();
+            !uris.contains(&uri) && !player.user_queued_items.contains(&uri)
+        }
+        _ => false,
+    };
     let scroll_offset = match ui.current_page_mut() {
         PageState::Queue {
             ref mut scroll_offset,
@@ -825,7 +815,11 @@ pub fn render_queue_page(
             .map(|(i, x)| {
                 Row::new(vec![
                     Cell::from(format!("{}", i + 1)),
-                    Cell::from(get_playable_name(x)),
+                    Cell::from(if is_autoplay_item(x) {
+                        format!("{} (autoplay)", get_playable_name(x))
+                    } else {
+                        get_playable_name(x)
+                    }),
                     Cell::from(get_playable_artists(x)),
                     Cell::from(get_playable_duration(x)),
                 ])
@@ -854,6 +848,34 @@ pub fn render_queue_page(
     frame.render_widget(queue_table, rect);
 }
 
+/// Get the URIs of the playing context's tracks if the integrated device's autoplay is enabled,
+/// which tell apart the queue's items added by autoplay
+#[cfg(feature = "streaming")]
+fn autoplay_context_uris(
+    state: &SharedState,
+    player: &crate::state::PlayerState,
+) -> Option<HashSet<String>> {
+    if !*state.autoplay.lock() {
+        return None;
+    }
+    let id = player.playing_context_id()?;
+    let data = state.data.read();
+    match data.caches.context.get(&id.uri())? {
+        Context::Playlist { tracks, .. }
+        | Context::Album { tracks, .. }
+        | Context::Tracks { tracks, .. } => Some(tracks.iter().map(|t| t.id.uri()).collect()),
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "streaming"))]
+fn autoplay_context_uris(
+    _state: &SharedState,
+    _player: &crate::state::PlayerState,
+) -> Option<HashSet<String>> {
+    None
+}
+
 /// Render windows for an artist context page, which includes
 /// - A top track table
 /// - An album table
@@ -915,7 +937,7 @@ fn render_artist_context_page_windows(
         .into_iter()
         .map(|a| {
             Row::new(vec![
-                Cell::from(a.release_date.clone()),
+                Cell::from(format_date(&a.release_date)),
                 Cell::from(a.album_type()),
                 Cell::from(a.name.clone()),
             ])
@@ -1044,11 +1066,9 @@ fn render_track_table(
                 Cell::from(to_bidi_string(&t.display_name())),
                 Cell::from(to_bidi_string(&t.artists_info())),
                 Cell::from(to_bidi_string(&t.album_info())),
-                Cell::from(format!(
-                    "{}:{:02}",
-                    t.duration.as_secs() / 60,
-                    t.duration.as_secs() % 60,
-                )),
+                Cell::from(format_duration(&chrono::Duration::seconds(
+                    t.duration.as_secs() as i64,
+                ))),
             ])
             .style(style)
         })
@@ -1134,12 +1154,10 @@ fn render_episode_table(
             Row::new(vec![
                 Cell::from(id),
                 Cell::from(to_bidi_string(&e.name)),
-                Cell::from(e.release_date.clone()),
-                Cell::from(format!(
-                    "{}:{:02}",
-                    e.duration.as_secs() / 60,
-                    e.duration.as_secs() % 60,
-                )),
+                Cell::from(format_date(&e.release_date)),
+                Cell::from(format_duration(&chrono::Duration::seconds(
+                    e.duration.as_secs() as i64,
+                ))),
             ])
             .style(style)
         })
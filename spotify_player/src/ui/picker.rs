@@ -0,0 +1,133 @@
+//! A minimal fuzzy picker used by the `pick` CLI command to choose an item
+//! from the user's data, e.g. in shell scripts
+
+use super::{Block, Borders, Constraint, Layout, Line, List, ListItem, ListState, Paragraph, Span};
+use crate::{config, state::filter_items};
+use anyhow::{Context as _, Result};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+type Terminal = ratatui::Terminal<ratatui::backend::CrosstermBackend<std::io::Stderr>>;
+
+/// An item of the picker
+pub struct PickerItem {
+    pub uri: String,
+    pub label: String,
+}
+
+impl std::fmt::Display for PickerItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label)
+    }
+}
+
+/// Run the picker, returning the chosen item's URI or `None` if the picker is cancelled.
+/// The `prompt` is shown in the picker's title.
+///
+/// The picker is rendered to stderr, so the command's stdout can be captured by shell scripts.
+pub fn pick(items: &[PickerItem], query: &str, prompt: &str) -> Result<Option<String>> {
+    let mut terminal = init_picker().context("failed to initialize the picker's UI")?;
+    let result = run_picker(&mut terminal, items, query.to_string(), prompt);
+    clean_up(terminal).context("clean up the picker's UI resources")?;
+    result
+}
+
+fn init_picker() -> Result<Terminal> {
+    let mut stderr = std::io::stderr();
+    crossterm::terminal::enable_raw_mode()?;
+    crossterm::execute!(stderr, crossterm::terminal::EnterAlternateScreen)?;
+    let backend = ratatui::backend::CrosstermBackend::new(stderr);
+    let mut terminal = ratatui::Terminal::new(backend)?;
+    terminal.clear()?;
+    Ok(terminal)
+}
+
+fn clean_up(mut terminal: Terminal) -> Result<()> {
+    crossterm::terminal::disable_raw_mode()?;
+    crossterm::execute!(
+        terminal.backend_mut(),
+        crossterm::terminal::LeaveAlternateScreen
+    )?;
+    terminal.show_cursor()?;
+    Ok(())
+}
+
+fn run_picker(
+    terminal: &mut Terminal,
+    items: &[PickerItem],
+    mut query: String,
+    prompt: &str,
+) -> Result<Option<String>> {
+    let configs = config::get_config();
+    let theme = configs
+        .theme_config
+        .find_theme(configs.app_config.theme_name())
+        .unwrap_or_default();
+    let mut selected = 0;
+
+    loop {
+        let filtered_items = filter_items(items, &query);
+        selected = std::cmp::min(selected, filtered_items.len().saturating_sub(1));
+
+        terminal.draw(|frame| {
+            let rect = frame.area();
+            frame.render_widget(Block::default().style(theme.app()), rect);
+
+            let [input_rect, list_rect] =
+                Layout::vertical([Constraint::Length(3), Constraint::Fill(1)]).areas(rect);
+
+            let title = format!("{prompt} ({}/{})", filtered_items.len(), items.len());
+            let input = Paragraph::new(Line::from(format!("> {query}"))).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border())
+                    .title(Span::styled(title, theme.block_title())),
+            );
+            frame.render_widget(input, input_rect);
+
+            let list = List::new(
+                filtered_items
+                    .iter()
+                    .map(|item| ListItem::new(item.label.as_str())),
+            )
+            .highlight_style(theme.selection(true))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border()),
+            );
+            let mut list_state = ListState::default()
+                .with_selected((!filtered_items.is_empty()).then_some(selected));
+            frame.render_stateful_widget(list, list_rect, &mut list_state);
+        })?;
+
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = crossterm::event::read()?
+        else {
+            continue;
+        };
+
+        match (code, modifiers.contains(KeyModifiers::CONTROL)) {
+            (KeyCode::Esc, _) | (KeyCode::Char('c'), true) => return Ok(None),
+            (KeyCode::Enter, _) => {
+                return Ok(filtered_items.get(selected).map(|item| item.uri.clone()))
+            }
+            (KeyCode::Up, _) | (KeyCode::Char('p'), true) => {
+                selected = selected.saturating_sub(1);
+            }
+            (KeyCode::Down, _) | (KeyCode::Char('n'), true) => selected += 1,
+            (KeyCode::Backspace, _) => {
+                query.pop();
+                selected = 0;
+            }
+            (KeyCode::Char(c), false) => {
+                query.push(c);
+                selected = 0;
+            }
+            _ => {}
+        }
+    }
+}
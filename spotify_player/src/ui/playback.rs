@@ -1,6 +1,6 @@
 use super::{
-    config, utils::construct_and_render_block, Borders, Constraint, Frame, Gauge, Layout, Line,
-    LineGauge, Modifier, Paragraph, PlaybackMetadata, Rect, SharedState, Span, Style, Text,
+    config, utils::construct_and_render_window_block, Borders, Constraint, Frame, Gauge, Layout,
+    Line, LineGauge, Modifier, Paragraph, PlaybackMetadata, Rect, SharedState, Span, Style, Text,
     UIStateGuard, Wrap,
 };
 #[cfg(feature = "image")]
@@ -22,7 +22,19 @@ pub fn render_playback_window(
     rect: Rect,
 ) -> Rect {
     let (rect, other_rect) = split_rect_for_playback_window(rect);
-    let rect = construct_and_render_block("Playback", &ui.theme, Borders::ALL, frame, rect);
+    // indicate the selected profile, if any, in the window's title
+    let title = match &config::get_config().profile {
+        Some(profile) => format!("Playback ({profile})"),
+        None => "Playback".to_string(),
+    };
+    let rect = construct_and_render_window_block(
+        &title,
+        &ui.theme,
+        config::Window::Playback,
+        Borders::ALL,
+        frame,
+        rect,
+    );
 
     let player = state.player.read();
     if let Some(ref playback) = player.playback {
@@ -139,7 +151,8 @@ pub fn render_playback_window(
             };
 
             if let Some(ref playback) = player.buffered_playback {
-                let playback_text = construct_playback_text(ui, state, item, playback);
+                let playback_text =
+                    construct_playback_text(ui, state, item, playback, player.reconnecting);
                 let playback_desc = Paragraph::new(playback_text);
                 frame.render_widget(playback_desc, metadata_rect);
             }
@@ -204,45 +217,35 @@ fn construct_playback_text(
     state: &SharedState,
     playable: &rspotify::model::PlayableItem,
     playback: &PlaybackMetadata,
+    is_reconnecting: bool,
 ) -> Text<'static> {
     // Construct a "styled" text (`playback_text`) from playback's data
-    // based on a user-configurable format string (app_config.playback_format)
+    // based on a user-configurable format template (app_config.playback_format),
+    // which is parsed into tokens upon loading the configurations
     let configs = config::get_config();
-    let format_str = &configs.app_config.playback_format;
     let data = state.data.read();
 
     let mut playback_text = Text::default();
     let mut spans = vec![];
 
-    // this regex is to handle a format argument or a newline
-    let re = regex::Regex::new(r"\{.*?\}|\n").unwrap();
-
-    let mut ptr = 0;
-    for m in re.find_iter(format_str) {
-        let s = m.start();
-        let e = m.end();
-        if ptr < s {
-            spans.push(Span::raw(format_str[ptr..s].to_string()));
-        }
-        ptr = e;
-
-        let (text, style) = match m.as_str() {
+    for token in configs.app_config.playback_format.tokens() {
+        let placeholder = match token {
+            config::FormatToken::Text(text) => {
+                spans.push(Span::raw(text.clone()));
+                continue;
+            }
             // upon encountering a newline, create a new `Spans`
-            "\n" => {
-                let mut tmp = vec![];
-                std::mem::swap(&mut tmp, &mut spans);
-                playback_text.lines.push(Line::from(tmp));
+            config::FormatToken::Newline => {
+                playback_text.lines.push(Line::from(std::mem::take(&mut spans)));
                 continue;
             }
-            "{status}" => (
-                if playback.is_playing {
-                    &configs.app_config.play_icon
-                } else {
-                    &configs.app_config.pause_icon
-                }
-                .to_owned(),
-                ui.theme.playback_status(),
-            ),
+            config::FormatToken::Placeholder(placeholder) => placeholder,
+        };
+
+        let (text, style) = match placeholder.as_str() {
+            "{status}" if is_reconnecting => {
+                ("reconnecting…".to_string(), ui.theme.playback_status())
+            }
             "{liked}" => match playable {
                 rspotify::model::PlayableItem::Track(track) => match &track.id {
                     Some(id) => {
@@ -256,84 +259,54 @@ fn construct_playback_text(
                 },
                 rspotify::model::PlayableItem::Episode(_) => continue,
             },
-            "{track}" => match playable {
-                rspotify::model::PlayableItem::Track(track) => (
-                    {
-                        let bidi_string = to_bidi_string(&track.name);
-                        if track.explicit {
-                            format!("{bidi_string} (E)")
-                        } else {
-                            bidi_string
-                        }
-                    },
-                    ui.theme.playback_track(),
-                ),
-                rspotify::model::PlayableItem::Episode(episode) => (
-                    {
-                        let bidi_string = to_bidi_string(&episode.name);
-                        if episode.explicit {
-                            format!("{bidi_string} (E)")
-                        } else {
-                            bidi_string
-                        }
-                    },
-                    ui.theme.playback_track(),
-                ),
-            },
-            "{artists}" => match playable {
-                rspotify::model::PlayableItem::Track(track) => (
-                    to_bidi_string(&crate::utils::map_join(&track.artists, |a| &a.name, ", ")),
-                    ui.theme.playback_artists(),
-                ),
-                rspotify::model::PlayableItem::Episode(episode) => {
-                    (episode.show.publisher.clone(), ui.theme.playback_artists())
+            "{private}" => {
+                if !*state.private_session.lock() {
+                    continue;
                 }
-            },
-            "{album}" => match playable {
-                rspotify::model::PlayableItem::Track(track) => {
-                    (to_bidi_string(&track.album.name), ui.theme.playback_album())
+                ("private".to_string(), ui.theme.playback_metadata())
+            }
+            #[cfg(feature = "streaming")]
+            "{bitrate}" => {
+                if !state.is_streaming_enabled() {
+                    continue;
                 }
-                rspotify::model::PlayableItem::Episode(episode) => (
-                    to_bidi_string(&episode.show.name),
-                    ui.theme.playback_album(),
-                ),
-            },
-            "{metadata}" => {
-                let repeat_value = if playback.fake_track_repeat_state {
-                    "track (fake)".to_string()
-                } else {
-                    <&'static str>::from(playback.repeat_state).to_string()
-                };
-
-                let volume_value = if let Some(volume) = playback.mute_state {
-                    format!("{volume}% (muted)")
-                } else {
-                    format!("{}%", playback.volume.unwrap_or_default())
+                (format!("{} kbps", state.bitrate.lock()), ui.theme.playback_metadata())
+            }
+            // the placeholders shared with the CLI's playback formats
+            placeholder => {
+                let Some(value) = crate::utils::get_playback_placeholder_value(
+                    placeholder,
+                    Some(playable),
+                    playback,
+                ) else {
+                    continue;
                 };
-
-                let mut parts = vec![];
-
-                for field in &configs.app_config.playback_metadata_fields {
-                    match field.as_str() {
-                        "repeat" => parts.push(format!("repeat: {repeat_value}")),
-                        "shuffle" => parts.push(format!("shuffle: {}", playback.shuffle_state)),
-                        "volume" => parts.push(format!("volume: {volume_value}")),
-                        "device" => parts.push(format!("device: {}", playback.device_name)),
-                        _ => {}
+                match placeholder {
+                    "{status}" => (value, ui.theme.playback_status()),
+                    "{track}" => {
+                        let explicit = match playable {
+                            rspotify::model::PlayableItem::Track(track) => track.explicit,
+                            rspotify::model::PlayableItem::Episode(episode) => episode.explicit,
+                        };
+                        let bidi_string = to_bidi_string(&value);
+                        (
+                            if explicit {
+                                format!("{bidi_string} (E)")
+                            } else {
+                                bidi_string
+                            },
+                            ui.theme.playback_track(),
+                        )
                     }
+                    "{artists}" => (to_bidi_string(&value), ui.theme.playback_artists()),
+                    "{album}" => (to_bidi_string(&value), ui.theme.playback_album()),
+                    _ => (value, ui.theme.playback_metadata()),
                 }
-
-                let metadata_str = parts.join(" | ");
-                (metadata_str, ui.theme.playback_metadata())
             }
-            _ => continue,
         };
 
         spans.push(Span::styled(text, style));
     }
-    if ptr < format_str.len() {
-        spans.push(Span::raw(format_str[ptr..].to_string()));
-    }
     if !spans.is_empty() {
         playback_text.lines.push(Line::from(spans));
     }
@@ -423,7 +396,8 @@ fn render_playback_cover_image(state: &SharedState, ui: &mut UIStateGuard) -> Re
         };
 
         // Scale image to fill the allocated rectangle as a square
-        let cfg = &config::get_config().app_config;
+        let configs = config::get_config();
+        let cfg = &configs.app_config;
         
         // Use configured dimensions directly
         let width = (cfg.cover_img_width as u16).min(rect.width) as u32;
@@ -1,8 +1,11 @@
 use super::{
-    config, utils, utils::construct_and_render_block, Borders, Cell, Constraint, Frame, Layout,
-    Paragraph, PlaylistCreateCurrentField, PlaylistPopupAction, PopupState, Rect, Row, SharedState,
-    Table, UIStateGuard,
+    config, utils, utils::construct_and_render_popup_block, Borders, Cell, Constraint, Frame,
+    Layout, Paragraph, PlaylistCreateCurrentField, PlaylistPopupAction, PopupState, Rect, Row,
+    SharedState, Table, UIStateGuard, Wrap,
 };
+use crate::config::PopupAnchor;
+use crate::state::{CommandPaletteItem, JumpPaletteItem, CONFIRMATION_CHOICES};
+use ratatui::widgets::Widget;
 
 const SHORTCUT_TABLE_N_COLUMNS: usize = 3;
 const SHORTCUT_TABLE_CONSTRAINS: [Constraint; SHORTCUT_TABLE_N_COLUMNS] =
@@ -19,6 +22,7 @@ pub fn render_popup(
     ui: &mut UIStateGuard,
     rect: Rect,
 ) -> (Rect, bool) {
+    ui.rects.popup_list = Rect::default();
     match ui.popup {
         None => (rect, true),
         Some(ref popup) => match popup {
@@ -27,14 +31,13 @@ pub fn render_popup(
                 desc,
                 current_field,
             } => {
-                let chunks =
-                    Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).split(rect);
+                let chunks = split_popup_area(rect, 3, popup);
 
                 let popup_chunks =
                     Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
                         .split(chunks[1]);
 
-                let name_input = construct_and_render_block(
+                let name_input = construct_and_render_popup_block(
                     "Enter Name for New Playlist:",
                     &ui.theme,
                     Borders::ALL,
@@ -42,7 +45,7 @@ pub fn render_popup(
                     popup_chunks[0],
                 );
 
-                let desc_input = construct_and_render_block(
+                let desc_input = construct_and_render_popup_block(
                     "Enter Description for New Playlist:",
                     &ui.theme,
                     Borders::ALL,
@@ -60,16 +63,141 @@ pub fn render_popup(
                 );
                 (chunks[0], true)
             }
-            PopupState::Search { query } => {
-                let chunks =
-                    Layout::vertical([Constraint::Fill(0), Constraint::Length(3)]).split(rect);
+            PopupState::Search { query, is_editing } => {
+                let chunks = split_popup_area(rect, 3, popup);
 
-                let rect =
-                    construct_and_render_block("Search", &ui.theme, Borders::ALL, frame, chunks[1]);
+                let title = if *is_editing {
+                    "Search"
+                } else {
+                    "Search (n/N: next/previous match)"
+                };
+                let rect = construct_and_render_popup_block(
+                    title,
+                    &ui.theme,
+                    Borders::ALL,
+                    frame,
+                    chunks[1],
+                );
 
                 frame.render_widget(Paragraph::new(format!("/{query}")), rect);
                 (chunks[0], true)
             }
+            PopupState::JumpPalette(input, _) => {
+                let items = JumpPaletteItem::matched_items(&state.data.read(), &input.get_text())
+                    .into_iter()
+                    .map(|item| item.to_string())
+                    .collect();
+                let input = input.widget(true);
+                render_palette_popup(frame, ui, rect, "Go To", input, items)
+            }
+            PopupState::CommandPalette(input, _) => {
+                let items = CommandPaletteItem::matched_items(&input.get_text())
+                    .into_iter()
+                    .map(|item| item.to_string())
+                    .collect();
+                let input = input.widget(true);
+                render_palette_popup(frame, ui, rect, "Commands", input, items)
+            }
+            PopupState::KeymapEditor(input, _) => {
+                let items = CommandPaletteItem::matched_commands(&input.get_text())
+                    .into_iter()
+                    .map(|item| item.to_string())
+                    .collect();
+                let input = input.widget(true);
+                render_palette_popup(frame, ui, rect, "Rebind Command", input, items)
+            }
+            PopupState::KeymapRebind {
+                command,
+                key_sequence,
+            } => {
+                let chunks = split_popup_area(rect, 4, popup);
+
+                // `enter`, `backspace` and `esc` control the popup, so they can't be recorded
+                let title = format!(
+                    "Press the new keys of {command:?} (enter: save, backspace: delete, esc: cancel)"
+                );
+                let rect = construct_and_render_popup_block(
+                    &title,
+                    &ui.theme,
+                    Borders::ALL,
+                    frame,
+                    chunks[1],
+                );
+
+                let (replaced, shadowed) = config::get_config()
+                    .keymap_config
+                    .find_conflicts_with(key_sequence, *command);
+                let conflicts = if key_sequence.keys.is_empty() {
+                    String::new()
+                } else if !shadowed.is_empty() {
+                    format!("can't be saved, conflicts with {}", shadowed.join(", "))
+                } else if !replaced.is_empty() {
+                    format!("replaces {}", replaced.join(", "))
+                } else {
+                    String::new()
+                };
+                frame.render_widget(Paragraph::new(format!("{key_sequence}\n{conflicts}")), rect);
+                (chunks[0], false)
+            }
+            PopupState::SavedSearchCreate { name, .. } => {
+                let chunks = split_popup_area(rect, 3, popup);
+
+                let name_input = construct_and_render_popup_block(
+                    "Enter Name for Saved Search:",
+                    &ui.theme,
+                    Borders::ALL,
+                    frame,
+                    chunks[1],
+                );
+
+                frame.render_widget(name.widget(true), name_input);
+                (chunks[0], true)
+            }
+            PopupState::SearchAndQueue(query) => {
+                let chunks = split_popup_area(rect, 3, popup);
+
+                let query_input = construct_and_render_popup_block(
+                    "Search and Queue the Top Track:",
+                    &ui.theme,
+                    Borders::ALL,
+                    frame,
+                    chunks[1],
+                );
+
+                frame.render_widget(query.widget(true), query_input);
+                (chunks[0], true)
+            }
+            PopupState::ConfigError(err) => {
+                // fit the error's lines inside the popup, whose height is limited
+                let height = std::cmp::min(err.lines().count() + 2, 10) as u16;
+                let chunks = split_popup_area(rect, height, popup);
+
+                let error_rect = construct_and_render_popup_block(
+                    "Invalid Configurations (the previous configurations are kept)",
+                    &ui.theme,
+                    Borders::ALL,
+                    frame,
+                    chunks[1],
+                );
+
+                frame.render_widget(
+                    Paragraph::new(err.clone()).wrap(Wrap { trim: false }),
+                    error_rect,
+                );
+                (chunks[0], true)
+            }
+            PopupState::SavedSearchList(_) => {
+                let items = state
+                    .data
+                    .read()
+                    .saved_searches
+                    .iter()
+                    .map(|s| (s.to_string(), false))
+                    .collect();
+
+                let rect = render_list_popup(frame, rect, "Saved Searches", items, 7, ui);
+                (rect, false)
+            }
             PopupState::ActionList(item, _) => {
                 let rect = render_list_popup(
                     frame,
@@ -85,6 +213,16 @@ pub fn render_popup(
                 );
                 (rect, false)
             }
+            PopupState::Confirmation(confirmation, _) => {
+                let title = confirmation.prompt.clone();
+                let items = CONFIRMATION_CHOICES
+                    .iter()
+                    .map(|choice| ((*choice).to_string(), false))
+                    .collect();
+
+                let rect = render_list_popup(frame, rect, &title, items, 5, ui);
+                (rect, false)
+            }
             PopupState::DeviceList { .. } => {
                 let player = state.player.read();
 
@@ -101,6 +239,53 @@ pub fn render_popup(
                 let rect = render_list_popup(frame, rect, "Devices", items, 5, ui);
                 (rect, false)
             }
+            #[cfg(feature = "streaming")]
+            PopupState::AudioOutputList(outputs, ..) => {
+                let current_output = state.audio_output.lock().clone();
+                let items = outputs
+                    .iter()
+                    .map(|o| (o.to_string(), *o == current_output))
+                    .collect();
+
+                let rect = render_list_popup(frame, rect, "Audio Outputs", items, 10, ui);
+                (rect, false)
+            }
+            #[cfg(feature = "streaming")]
+            PopupState::DeviceProfileList(..) => {
+                let configs = config::get_config();
+                let current_profile = state.device_profile.lock().clone();
+                let items = std::iter::once((
+                    format!("{} (default)", configs.app_config.device.name),
+                    current_profile.is_none(),
+                ))
+                .chain(configs.app_config.device_profiles.iter().map(|p| {
+                    (
+                        p.name.clone(),
+                        current_profile.as_ref().is_some_and(|c| c.name == p.name),
+                    )
+                }))
+                .collect();
+
+                let rect = render_list_popup(frame, rect, "Device Profiles", items, 5, ui);
+                (rect, false)
+            }
+            #[cfg(feature = "streaming")]
+            PopupState::Equalizer(..) => {
+                let settings = state.equalizer.load();
+                let items = crate::equalizer::BANDS
+                    .iter()
+                    .zip(settings.gains)
+                    .map(|(frequency, gain)| (format_equalizer_band(*frequency, gain), false))
+                    .collect();
+                let title = format!(
+                    "Equalizer: {}{} (←/→: adjust, 0: reset, p: preset, e: toggle)",
+                    settings.preset.map_or("Custom", |p| p.name()),
+                    if settings.enabled { "" } else { " [off]" },
+                );
+
+                let rect = render_list_popup(frame, rect, &title, items, 12, ui);
+                (rect, false)
+            }
             PopupState::ThemeList(themes, ..) => {
                 let items = themes.iter().map(|t| (t.name.clone(), false)).collect();
 
@@ -159,6 +344,99 @@ pub fn render_popup(
     }
 }
 
+/// Format an equalizer's band as its frequency followed by a slider of its gain,
+/// e.g. `  1kHz ──────────────●────────── +2 dB`
+#[cfg(feature = "streaming")]
+fn format_equalizer_band(frequency: f64, gain: f32) -> String {
+    const SLIDER_WIDTH: usize = 25;
+
+    let max_gain = crate::equalizer::MAX_GAIN;
+    let label = if frequency >= 1000.0 {
+        format!("{}kHz", frequency / 1000.0)
+    } else {
+        format!("{frequency}Hz")
+    };
+    let position =
+        ((gain + max_gain) / (2.0 * max_gain) * (SLIDER_WIDTH - 1) as f32).round() as usize;
+    let slider = (0..SLIDER_WIDTH)
+        .map(|i| if i == position { '●' } else { '─' })
+        .collect::<String>();
+    format!("{label:>6} {slider} {gain:+.0} dB")
+}
+
+/// Render a palette popup, whose input is shown above the list of the items matching the input
+fn render_palette_popup(
+    frame: &mut Frame,
+    ui: &mut UIStateGuard,
+    rect: Rect,
+    title: &str,
+    input: impl Widget,
+    items: Vec<String>,
+) -> (Rect, bool) {
+    let Some(popup) = ui.popup.as_ref() else {
+        return (rect, true);
+    };
+    let chunks = split_popup_area(rect, 12, popup);
+    let rect = construct_and_render_popup_block(title, &ui.theme, Borders::ALL, frame, chunks[1]);
+    let popup_chunks = Layout::vertical([Constraint::Length(1), Constraint::Fill(0)]).split(rect);
+
+    frame.render_widget(input, popup_chunks[0]);
+    let items = items.into_iter().map(|item| (item, false)).collect();
+    let (list, len) = utils::construct_list_widget(&ui.theme, items, true);
+    utils::render_list_window(
+        frame,
+        list,
+        popup_chunks[1],
+        len,
+        ui.popup.as_mut().unwrap().list_state_mut().unwrap(),
+    );
+    ui.rects.popup_list = popup_chunks[1];
+    (chunks[0], false)
+}
+
+/// Split `rect` into the main layout's area and the area of `popup` with `height` rows,
+/// which is placed based on the popup's configured geometry
+fn split_popup_area(rect: Rect, height: u16, popup: &PopupState) -> [Rect; 2] {
+    let configs = config::get_config();
+    let popup_configs = &configs.app_config.layout.popup;
+    let geometry = match popup {
+        PopupState::ActionList(..) => popup_configs.action_list.as_ref(),
+        PopupState::Search { .. } => popup_configs.search.as_ref(),
+        PopupState::DeviceList(..) => popup_configs.device_list.as_ref(),
+        _ => None,
+    }
+    .unwrap_or(&popup_configs.default);
+    let height = geometry.height.unwrap_or(height);
+
+    let (main_rect, rect) = match geometry.anchor {
+        PopupAnchor::Top | PopupAnchor::TopLeft | PopupAnchor::TopRight => {
+            let chunks =
+                Layout::vertical([Constraint::Length(height), Constraint::Fill(0)]).split(rect);
+            (chunks[1], chunks[0])
+        }
+        PopupAnchor::Bottom | PopupAnchor::BottomLeft | PopupAnchor::BottomRight => {
+            let chunks =
+                Layout::vertical([Constraint::Fill(0), Constraint::Length(height)]).split(rect);
+            (chunks[0], chunks[1])
+        }
+    };
+
+    let width = Constraint::from(geometry.width);
+    let rect = match geometry.anchor {
+        PopupAnchor::TopLeft | PopupAnchor::BottomLeft => {
+            Layout::horizontal([width, Constraint::Fill(1)]).split(rect)[0]
+        }
+        PopupAnchor::TopRight | PopupAnchor::BottomRight => {
+            Layout::horizontal([Constraint::Fill(1), width]).split(rect)[1]
+        }
+        PopupAnchor::Top | PopupAnchor::Bottom => {
+            Layout::horizontal([Constraint::Fill(1), width, Constraint::Fill(1)]).split(rect)[1]
+        }
+    };
+
+    [main_rect, rect]
+}
+
 /// A helper function to render a list popup
 fn render_list_popup(
     frame: &mut Frame,
@@ -168,9 +446,9 @@ fn render_list_popup(
     length: u16,
     ui: &mut UIStateGuard,
 ) -> Rect {
-    let chunks = Layout::vertical([Constraint::Fill(0), Constraint::Length(length)]).split(rect);
+    let chunks = split_popup_area(rect, length, ui.popup.as_ref().unwrap());
 
-    let rect = construct_and_render_block(title, &ui.theme, Borders::ALL, frame, chunks[1]);
+    let rect = construct_and_render_popup_block(title, &ui.theme, Borders::ALL, frame, chunks[1]);
     let (list, len) = utils::construct_list_widget(&ui.theme, items, true);
 
     utils::render_list_window(
@@ -180,12 +458,14 @@ fn render_list_popup(
         len,
         ui.popup.as_mut().unwrap().list_state_mut().unwrap(),
     );
+    ui.rects.popup_list = rect;
 
     chunks[0]
 }
 
 /// Render a shortcut help popup to show the available shortcuts based on user's inputs
 pub fn render_shortcut_help_popup(frame: &mut Frame, ui: &mut UIStateGuard, rect: Rect) -> Rect {
+    ui.expire_input_key_sequence();
     let input = &ui.input_key_sequence;
 
     // get the matches (keymaps) from the current key sequence input,
@@ -213,8 +493,14 @@ pub fn render_shortcut_help_popup(frame: &mut Frame, ui: &mut UIStateGuard, rect
     } else {
         let chunks = Layout::vertical([Constraint::Fill(0), Constraint::Length(7)]).split(rect);
 
-        let rect =
-            construct_and_render_block("Shortcuts", &ui.theme, Borders::ALL, frame, chunks[1]);
+        // the title shows the pending keys of the input key sequence
+        let rect = construct_and_render_popup_block(
+            &format!("Shortcuts ({input})"),
+            &ui.theme,
+            Borders::ALL,
+            frame,
+            chunks[1],
+        );
 
         let help_table = Table::new(
             matches
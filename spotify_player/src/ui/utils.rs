@@ -4,7 +4,7 @@ use super::{
 };
 use unicode_bidi::BidiInfo;
 
-/// Construct and render a block.
+/// Construct and render a block of a page's window.
 ///
 /// This function should only be used to render a window's borders and its title.
 /// It returns the rectangle to render the inner widgets inside the block.
@@ -15,11 +15,38 @@ pub fn construct_and_render_block(
     frame: &mut Frame,
     rect: Rect,
 ) -> Rect {
-    let mut title = title.to_string();
+    construct_and_render_window_block(title, theme, config::Window::Page, borders, frame, rect)
+}
 
-    let configs = config::get_config();
+/// Construct and render a block of a popup
+pub fn construct_and_render_popup_block(
+    title: &str,
+    theme: &config::Theme,
+    borders: Borders,
+    frame: &mut Frame,
+    rect: Rect,
+) -> Rect {
+    construct_and_render_window_block(title, theme, config::Window::Popup, borders, frame, rect)
+}
 
-    let (borders, border_type) = match configs.app_config.border_type {
+/// Construct and render a block of a window, styled with the theme's styles of the window
+pub fn construct_and_render_window_block(
+    title: &str,
+    theme: &config::Theme,
+    window: config::Window,
+    borders: Borders,
+    frame: &mut Frame,
+    rect: Rect,
+) -> Rect {
+    let (title_style, border_style) = match window {
+        config::Window::Popup => (theme.popup_block_title(), theme.popup_border()),
+        config::Window::Playback => (theme.block_title(), theme.playback_border()),
+        config::Window::Library | config::Window::Page => (theme.block_title(), theme.border()),
+    };
+    let mut title = title.to_string();
+
+    let window_border_type = theme.border_type(window);
+    let (borders, border_type) = match window_border_type {
         config::BorderType::Hidden | config::BorderType::Plain => (borders, BorderType::Plain),
         config::BorderType::Rounded => (borders, BorderType::Rounded),
         config::BorderType::Double => (borders, BorderType::Double),
@@ -28,7 +55,7 @@ pub fn construct_and_render_block(
 
     let mut block = Block::default()
         .borders(borders)
-        .border_style(theme.border())
+        .border_style(border_style)
         .border_type(border_type);
 
     let inner_rect = block.inner(rect);
@@ -37,14 +64,16 @@ pub fn construct_and_render_block(
     // `Hidden` border can be done by setting the borders to be `NONE`.
     // NOTE: we want to handle the border after the inner rectangle computation,
     // so that paddings between windows are properly determined.
-    if configs.app_config.border_type == config::BorderType::Hidden {
+    if window_border_type == config::BorderType::Hidden {
         block = block.borders(Borders::NONE);
         // add padding to the title to ensure the inner text is aligned with the title
         title = format!(" {title}");
     }
 
     // Set `title` for the block
-    block = block.title(Span::styled(title, theme.block_title()));
+    if theme.show_title(window) {
+        block = block.title(Span::styled(title, title_style));
+    }
 
     frame.render_widget(block, rect);
     inner_rect
@@ -95,6 +124,10 @@ pub fn render_list_window(
     state: &mut ListState,
 ) {
     adjust_list_state(state, len);
+    if let Some(selected) = state.selected() {
+        *state.offset_mut() =
+            scroll_off_offset(state.offset(), selected, usize::from(rect.height), len);
+    }
     frame.render_stateful_widget(widget, rect, state);
 }
 
@@ -117,9 +150,34 @@ pub fn render_table_window(
     state: &mut TableState,
 ) {
     adjust_table_state(state, len);
+    if let Some(selected) = state.selected() {
+        // exclude the table's header row
+        let n_rows = usize::from(rect.height.saturating_sub(1));
+        *state.offset_mut() = scroll_off_offset(state.offset(), selected, n_rows, len);
+    }
     frame.render_stateful_widget(widget, rect, state);
 }
 
+/// gets the offset of a window with `n_rows` visible rows, which keeps the `scroll_off`
+/// config option's number of items visible above and below the selected item
+fn scroll_off_offset(offset: usize, selected: usize, n_rows: usize, len: usize) -> usize {
+    let scroll_off = config::get_config().app_config.scrolling.scroll_off;
+    if scroll_off == 0 || n_rows == 0 {
+        return offset;
+    }
+    // the selected item is kept at the window's center if the window is too small
+    let scroll_off = std::cmp::min(scroll_off, (n_rows - 1) / 2);
+
+    let offset = if selected < offset + scroll_off {
+        selected.saturating_sub(scroll_off)
+    } else if selected + scroll_off >= offset + n_rows {
+        selected + scroll_off + 1 - n_rows
+    } else {
+        offset
+    };
+    std::cmp::min(offset, len.saturating_sub(n_rows))
+}
+
 /// Convert a string to a bidirectional string.
 /// Used to handle RTL text properly in the UI.
 pub fn to_bidi_string(s: &str) -> String {
@@ -1,9 +1,59 @@
 use std::borrow::Cow;
+use std::fmt::Write;
+use std::sync::LazyLock;
 
-/// formats a time duration into a "{minutes}:{seconds}" format
+use rspotify::model::{AlbumId, ArtistId, EpisodeId, PlaylistId, ShowId, TrackId};
+use rspotify::prelude::Id;
+
+use crate::config::{FormatTemplate, FormatToken};
+use crate::state::PlaybackMetadata;
+
+/// formats a time duration based on the `duration_format` config option, whose
+/// specifiers are `%H` (hours), `%M` (minutes of the hour), `%m` (total minutes),
+/// `%S` (seconds of the minute), `%s` (unpadded seconds of the minute) and `%%`
 pub fn format_duration(duration: &chrono::Duration) -> String {
-    let secs = duration.num_seconds();
-    format!("{}:{:02}", secs / 60, secs % 60)
+    let configs = crate::config::get_config();
+    format_duration_with(duration, &configs.app_config.duration_format)
+}
+
+/// formats a time duration based on a `duration_format`
+fn format_duration_with(duration: &chrono::Duration, format: &str) -> String {
+    let secs = duration.num_seconds().max(0);
+
+    let mut output = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('H') => write!(output, "{}", secs / 3600).unwrap(),
+            Some('M') => write!(output, "{:02}", secs / 60 % 60).unwrap(),
+            Some('m') => write!(output, "{}", secs / 60).unwrap(),
+            Some('S') => write!(output, "{:02}", secs % 60).unwrap(),
+            Some('s') => write!(output, "{}", secs % 60).unwrap(),
+            Some('%') => output.push('%'),
+            // unknown specifiers are kept as is
+            Some(c) => {
+                output.push('%');
+                output.push(c);
+            }
+            None => output.push('%'),
+        }
+    }
+    output
+}
+
+/// formats a `YYYY-MM-DD` date based on the `date_format` config option.
+/// Dates with a lower precision, e.g. a release year, are kept as is.
+pub fn format_date(date: &str) -> String {
+    match chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        Ok(date) => date
+            .format(&crate::config::get_config().app_config.date_format)
+            .to_string(),
+        Err(_) => date.to_string(),
+    }
 }
 
 pub fn map_join<T, F>(v: &[T], f: F, sep: &str) -> String
@@ -19,6 +69,103 @@ where
     })
 }
 
+/// Replaces each `{placeholder}` in a format template with its value returned by `f`.
+/// Placeholders without a value are removed.
+pub fn format_with_placeholders<F>(template: &FormatTemplate, f: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut output = String::new();
+    for token in template.tokens() {
+        match token {
+            FormatToken::Text(text) => output.push_str(text),
+            FormatToken::Placeholder(placeholder) => {
+                output.push_str(&f(placeholder).unwrap_or_default());
+            }
+            FormatToken::Newline => output.push('\n'),
+        }
+    }
+    output
+}
+
+/// Gets the value of a `{track}`, `{artists}` or `{album}` placeholder for a playable item
+pub fn get_playable_placeholder_value(
+    placeholder: &str,
+    playable: &rspotify::model::PlayableItem,
+) -> Option<String> {
+    use rspotify::model::PlayableItem;
+
+    match (placeholder, playable) {
+        ("{track}", PlayableItem::Track(track)) => Some(track.name.clone()),
+        ("{track}", PlayableItem::Episode(episode)) => Some(episode.name.clone()),
+        ("{artists}", PlayableItem::Track(track)) => {
+            Some(map_join(&track.artists, |a| &a.name, ", "))
+        }
+        ("{artists}", PlayableItem::Episode(episode)) => Some(episode.show.publisher.clone()),
+        ("{album}", PlayableItem::Track(track)) => Some(track.album.name.clone()),
+        ("{album}", PlayableItem::Episode(episode)) => Some(episode.show.name.clone()),
+        _ => None,
+    }
+}
+
+/// Gets the value of a `{status}`, `{track}`, `{artists}`, `{album}` or `{metadata}`
+/// placeholder for a playback, which is shared by the `playback_format` config option
+/// and the CLI's playback formats
+pub fn get_playback_placeholder_value(
+    placeholder: &str,
+    playable: Option<&rspotify::model::PlayableItem>,
+    playback: &PlaybackMetadata,
+) -> Option<String> {
+    let configs = crate::config::get_config();
+
+    match placeholder {
+        "{status}" => Some(if playback.is_playing {
+            configs.app_config.play_icon.clone()
+        } else {
+            configs.app_config.pause_icon.clone()
+        }),
+        "{metadata}" => {
+            let repeat_value = if playback.fake_track_repeat_state {
+                "track (fake)".to_string()
+            } else {
+                <&'static str>::from(playback.repeat_state).to_string()
+            };
+            let volume_value = if let Some(volume) = playback.mute_state {
+                format!("{volume}% (muted)")
+            } else {
+                format!("{}%", playback.volume.unwrap_or_default())
+            };
+
+            let parts = configs
+                .app_config
+                .playback_metadata_fields
+                .iter()
+                .filter_map(|field| match field.as_str() {
+                    "repeat" => Some(format!("repeat: {repeat_value}")),
+                    "shuffle" => Some(format!("shuffle: {}", playback.shuffle_state)),
+                    "volume" => Some(format!("volume: {volume_value}")),
+                    "device" => Some(format!("device: {}", playback.device_name)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            Some(parts.join(" | "))
+        }
+        _ => playable.and_then(|item| get_playable_placeholder_value(placeholder, item)),
+    }
+}
+
+/// Formats a playback based on a format template, which supports the placeholders
+/// of [`get_playback_placeholder_value`]
+pub fn format_playback(
+    template: &FormatTemplate,
+    playback: &rspotify::model::CurrentPlaybackContext,
+) -> String {
+    let metadata = PlaybackMetadata::from_playback(playback);
+    format_with_placeholders(template, |placeholder| {
+        get_playback_placeholder_value(placeholder, playback.item.as_ref(), &metadata)
+    })
+}
+
 #[allow(dead_code)]
 pub fn get_track_album_image_url(track: &rspotify::model::FullTrack) -> Option<&str> {
     if track.album.images.is_empty() {
@@ -37,6 +184,165 @@ pub fn get_episode_show_image_url(episode: &rspotify::model::FullEpisode) -> Opt
     }
 }
 
+/// Get the path of a playable item's cover image inside the cache folder,
+/// or `None` if the item doesn't have enough data to name the cover image
+pub fn get_cover_image_path(item: &rspotify::model::PlayableItem) -> Option<std::path::PathBuf> {
+    let filename = (match item {
+        rspotify::model::PlayableItem::Track(track) => {
+            format!(
+                "{}-{}-cover-{}.jpg",
+                track.album.name,
+                track.album.artists.first()?.name,
+                // first 6 characters of the album's id
+                track.album.id.as_ref()?.id().get(..6)?
+            )
+        }
+        rspotify::model::PlayableItem::Episode(episode) => {
+            format!(
+                "{}-{}-cover-{}.jpg",
+                episode.show.name,
+                episode.show.publisher,
+                // first 6 characters of the show's id
+                episode.show.id.as_ref().id().get(..6)?
+            )
+        }
+    })
+    .replace('/', ""); // remove invalid characters from the file's name
+    Some(
+        crate::config::get_config()
+            .cache_folder
+            .join("image")
+            .join(filename),
+    )
+}
+
+/// Get the application's diagnostics, each of which consists of a name and a value
+pub fn get_diagnostics() -> Vec<(String, String)> {
+    let configs = crate::config::get_config();
+    let mut entries = vec![
+        ("Version".to_string(), env!("CARGO_PKG_VERSION").to_string()),
+        (
+            "Cache folder".to_string(),
+            configs.cache_folder.display().to_string(),
+        ),
+    ];
+
+    match configs.audio_cache_folder() {
+        None => entries.push(("Audio cache".to_string(), "disabled".to_string())),
+        Some(folder) => {
+            let usage = match get_folder_usage(&folder) {
+                Ok((size, count)) => format!("{} ({count} files)", format_size(size)),
+                // the audio cache folder is created upon caching the first audio file
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    format!("{} (0 files)", format_size(0))
+                }
+                Err(err) => format!("unavailable: {err}"),
+            };
+            let limit = match configs.app_config.device.audio_cache_size_limit {
+                Some(limit) => format_size(limit * 1024 * 1024),
+                None => "unlimited".to_string(),
+            };
+            entries.extend([
+                (
+                    "Audio cache folder".to_string(),
+                    folder.display().to_string(),
+                ),
+                ("Audio cache usage".to_string(), usage),
+                ("Audio cache size limit".to_string(), limit),
+            ]);
+        }
+    }
+
+    entries
+}
+
+/// Get the application's diagnostics changing over time, e.g. the integrated device's
+/// streaming statistics, which are refreshed upon rendering the diagnostics page
+pub fn get_live_diagnostics(state: &crate::state::SharedState) -> Vec<(String, String)> {
+    let mut entries = vec![];
+
+    #[cfg(feature = "streaming")]
+    if state.is_streaming_enabled() {
+        entries.push((
+            "Streaming bitrate".to_string(),
+            format!("{} kbps", state.bitrate.lock()),
+        ));
+        entries.push((
+            "Output sample rate".to_string(),
+            format!("{} Hz", state.audio_output.lock().sample_rate()),
+        ));
+        entries.extend(crate::metrics::audio_output_diagnostics());
+    }
+
+    entries.extend(crate::metrics::diagnostics());
+    entries
+}
+
+/// Get the total size (in bytes) and the number of the files inside a folder, including its sub-folders
+fn get_folder_usage(folder: &std::path::Path) -> std::io::Result<(u64, usize)> {
+    let mut usage = (0, 0);
+    for entry in std::fs::read_dir(folder)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            let (size, count) = get_folder_usage(&entry.path())?;
+            usage.0 += size;
+            usage.1 += count;
+        } else {
+            usage.0 += metadata.len();
+            usage.1 += 1;
+        }
+    }
+    Ok(usage)
+}
+
+/// Format a size in bytes into a human-readable string
+fn format_size(size: u64) -> String {
+    format!("{:.1} MB", size as f64 / 1024.0 / 1024.0)
+}
+
+/// A Spotify item referred to by a Spotify link
+pub enum SpotifyLink {
+    Track(TrackId<'static>),
+    Episode(EpisodeId<'static>),
+    Album(AlbumId<'static>),
+    Artist(ArtistId<'static>),
+    Playlist(PlaylistId<'static>),
+    Show(ShowId<'static>),
+}
+
+/// Matches a `https://open.spotify.com` URL or a `spotify:` URI, optionally with a locale
+/// segment (e.g. `intl-de`) or a user segment (e.g. `spotify:user:{user_id}:playlist:{id}`)
+static SPOTIFY_LINK_RE: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(
+        r"(https?://open\.spotify\.com/(intl-[[:alpha:]-]+/)?(user/[^/?#]+/)?|spotify:(user:[^:]+:)?)(?P<type>[[:alpha:]]+)[/:](?P<id>[[:alnum:]]+)",
+    )
+    .expect("valid Spotify link regex")
+});
+
+/// Finds and parses a Spotify link, which can be either a `https://open.spotify.com` URL
+/// or a `spotify:` URI, in a text.
+///
+/// Returns `None` if the text doesn't contain any Spotify link.
+pub fn parse_spotify_link(text: &str) -> anyhow::Result<Option<SpotifyLink>> {
+    let Some(cap) = SPOTIFY_LINK_RE.captures(text) else {
+        return Ok(None);
+    };
+
+    let typ = cap.name("type").expect("valid capture").as_str();
+    let id = cap.name("id").expect("valid capture").as_str();
+    let link = match typ {
+        "track" => SpotifyLink::Track(TrackId::from_id(id)?.into_static()),
+        "episode" => SpotifyLink::Episode(EpisodeId::from_id(id)?.into_static()),
+        "album" => SpotifyLink::Album(AlbumId::from_id(id)?.into_static()),
+        "artist" => SpotifyLink::Artist(ArtistId::from_id(id)?.into_static()),
+        "playlist" => SpotifyLink::Playlist(PlaylistId::from_id(id)?.into_static()),
+        "show" => SpotifyLink::Show(ShowId::from_id(id)?.into_static()),
+        _ => anyhow::bail!("unsupported Spotify link type '{typ}'"),
+    };
+    Ok(Some(link))
+}
+
 pub fn parse_uri(uri: &str) -> Cow<str> {
     let parts = uri.split(':').collect::<Vec<_>>();
     // The below URI probably has a format of `spotify:user:{user_id}:{type}:{id}`,
@@ -49,3 +355,33 @@ pub fn parse_uri(uri: &str) -> Cow<str> {
         Cow::Borrowed(uri)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(secs: i64, format: &str) -> String {
+        format_duration_with(&chrono::Duration::seconds(secs), format)
+    }
+
+    #[test]
+    fn format_duration_specifiers() {
+        assert_eq!(format(3725, "%H:%M:%S"), "1:02:05");
+        assert_eq!(format(3725, "%m:%S"), "62:05");
+        assert_eq!(format(65, "%m:%s"), "1:5");
+        assert_eq!(format(0, "%m:%S"), "0:00");
+        assert_eq!(format(65, "%m min %S sec"), "1 min 05 sec");
+    }
+
+    #[test]
+    fn format_duration_literal_percents() {
+        assert_eq!(format(65, "%%%m"), "%1");
+        assert_eq!(format(65, "%x %m"), "%x 1");
+        assert_eq!(format(65, "%m%"), "1%");
+    }
+
+    #[test]
+    fn format_duration_negative() {
+        assert_eq!(format(-5, "%m:%S"), "0:00");
+    }
+}
@@ -0,0 +1,102 @@
+//! Webhooks sending playback events as JSON to user-defined URLs,
+//! e.g. for Home Assistant automations
+
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    config::{self, WebhookConfig, WebhookEvent},
+    state::{PlaybackEvent, SharedState},
+};
+
+/// maximum number of attempts to send an event to a webhook
+const MAX_ATTEMPTS: u32 = 4;
+/// timeout of a webhook request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Starts the webhook sender, which sends the playback events to the configured webhooks
+pub async fn start_webhook_sender(state: SharedState) {
+    let http = match reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build() {
+        Ok(http) => http,
+        Err(err) => {
+            tracing::error!("Failed to create the webhooks' HTTP client: {err:#}");
+            return;
+        }
+    };
+
+    let mut playback_events = state.playback_events.subscribe();
+    loop {
+        let event = match playback_events.recv().await {
+            Ok(PlaybackEvent::TrackChange) => WebhookEvent::TrackChange,
+            Ok(PlaybackEvent::Play) => WebhookEvent::Play,
+            Ok(PlaybackEvent::Pause) => WebhookEvent::Pause,
+            Ok(PlaybackEvent::DeviceChange) => WebhookEvent::DeviceChange,
+            Ok(PlaybackEvent::VolumeChange) => WebhookEvent::VolumeChange,
+            Ok(_) => continue,
+            Err(RecvError::Lagged(n)) => {
+                tracing::warn!("Missed {n} playback events to send to the webhooks");
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+
+        // the webhooks are read upon every event to apply the reloaded configurations
+        let configs = config::get_config();
+        let webhooks = configs
+            .app_config
+            .webhooks
+            .iter()
+            .filter(|webhook| webhook.events.contains(&event))
+            .collect::<Vec<_>>();
+        if webhooks.is_empty() {
+            continue;
+        }
+
+        let playback =
+            serde_json::to_value(state.player.read().current_playback()).unwrap_or_default();
+        let payload = serde_json::json!({
+            "event": event.name(),
+            "timestamp": chrono::Utc::now().timestamp(),
+            "playback": playback,
+        });
+        for webhook in webhooks {
+            tokio::task::spawn(send_event(http.clone(), webhook.clone(), payload.clone()));
+        }
+    }
+}
+
+/// Sends an event's payload to a webhook, retrying with an exponential backoff upon failures
+async fn send_event(http: reqwest::Client, webhook: WebhookConfig, payload: serde_json::Value) {
+    let event = payload["event"].as_str().unwrap_or_default();
+    let mut delay = Duration::from_secs(1);
+    for attempt in 1..=MAX_ATTEMPTS {
+        match post(&http, &webhook.url, &payload).await {
+            Ok(()) => return,
+            Err(err) if attempt < MAX_ATTEMPTS => {
+                tracing::warn!(
+                    "Failed to send the {event} event to {} (attempt {attempt}), retrying: {err:#}",
+                    webhook.url
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            Err(err) => {
+                tracing::error!(
+                    "Failed to send the {event} event to {}: {err:#}",
+                    webhook.url
+                );
+            }
+        }
+    }
+}
+
+async fn post(http: &reqwest::Client, url: &str, payload: &serde_json::Value) -> Result<()> {
+    http.post(url)
+        .json(payload)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}